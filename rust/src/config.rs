@@ -5,6 +5,18 @@ use std::fmt;
 use std::io::IsTerminal;
 use std::path::PathBuf;
 
+use regex::Regex;
+
+use crate::support::child_framing::DEFAULT_MAX_FRAME_BYTES;
+use crate::support::child_stderr::{
+    StderrLevel, StderrOptions, StderrPattern, DEFAULT_STDERR_BUFFER_LINES,
+};
+use crate::support::child_swap::{ReplacementOptions, RestartStrategy, DEFAULT_DRAIN_TIMEOUT_MS};
+use crate::support::launch::{LaunchOptions, ResourceLimits};
+use crate::support::log_store::{parse_time_bound, unix_timestamp_secs};
+use crate::support::redact::Redactor;
+use crate::support::sandbox::SandboxPolicy;
+use crate::support::secrets::validate_secret_refs;
 use crate::types::HeadersMap;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -16,6 +28,35 @@ pub enum OutputTransport {
     Grpc,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WsIsolation {
+    Shared,
+    PerConnection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackpressurePolicy {
+    Block,
+    Disconnect,
+    DropNotifications,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChildFraming {
+    Ndjson,
+    ContentLength,
+    JsonStream,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ChildEnvMode {
+    #[default]
+    Inherit,
+    Allowlist,
+    Clear,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum LogLevel {
     Debug,
@@ -23,7 +64,7 @@ pub enum LogLevel {
     None,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CorsConfig {
     Disabled,
     AllowAll,
@@ -33,6 +74,9 @@ pub enum CorsConfig {
 #[derive(Debug, Clone)]
 pub struct Config {
     pub stdio: Option<String>,
+    pub server_name: Option<String>,
+    pub instance_name: Option<String>,
+    pub log_per_instance: bool,
     pub sse: Option<String>,
     pub streamable_http: Option<String>,
     pub output_transport: OutputTransport,
@@ -42,14 +86,27 @@ pub struct Config {
     pub message_path: String,
     pub streamable_http_path: String,
     pub log_level: LogLevel,
+    pub redactor: Redactor,
     pub cors: CorsConfig,
     pub health_endpoints: Vec<String>,
     pub headers: HeadersMap,
     pub env: HashMap<String, String>,
     pub stateful: bool,
     pub session_timeout: Option<u64>,
+    pub ws_isolation: WsIsolation,
+    pub ws_idle_grace_ms: Option<u64>,
+    pub backpressure: BackpressurePolicy,
+    pub child_framing: ChildFraming,
+    pub max_frame_bytes: usize,
+    pub child_launch: LaunchOptions,
+    pub child_stderr: StderrOptions,
+    pub child_replacement: ReplacementOptions,
+    pub managed_initialize: bool,
     pub protocol_version: String,
     pub runtime_prompt: bool,
+    pub control_socket: Option<PathBuf>,
+    pub config_file: Option<PathBuf>,
+    pub watch_config: bool,
     pub runtime_admin_port: Option<u16>,
     pub runtime_admin_host: String,
     pub runtime_admin_token: Option<String>,
@@ -201,6 +258,7 @@ pub struct DiscoverConfig {
     pub order: SortOrder,
     pub offset: usize,
     pub limit: Option<usize>,
+    pub reveal_secrets: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -213,6 +271,7 @@ pub struct ImportConfig {
     pub save_profiles_dir: Option<PathBuf>,
     pub bundle_mcpway: bool,
     pub compile_wrapper: bool,
+    pub reveal_secrets: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
@@ -253,12 +312,67 @@ pub struct LogsTailConfig {
     pub lines: usize,
     pub level: Option<LogsLevel>,
     pub transport: Option<LogsTransport>,
+    pub instance: Option<String>,
+    pub session: Option<String>,
+    pub request_id: Option<String>,
+    pub method: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
     pub json: bool,
+    pub reveal_secrets: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PredicateOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+}
+
+/// A `--where KEY OP VALUE` clause of `logs query`, e.g. `level>=warn` or `fields.tool=search`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogPredicate {
+    pub key: String,
+    pub op: PredicateOp,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogsOutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogsQueryConfig {
+    pub file: Option<PathBuf>,
+    pub predicates: Vec<LogPredicate>,
+    pub text: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub limit: Option<usize>,
+    pub format: LogsOutputFormat,
+    pub count_by: Option<String>,
+    pub percentiles: Option<String>,
+    pub no_index: bool,
+    pub reveal_secrets: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum LogsConfig {
     Tail(LogsTailConfig),
+    Query(LogsQueryConfig),
+}
+
+#[derive(Debug, Clone)]
+pub struct CtlConfig {
+    pub socket: PathBuf,
+    pub command: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -268,7 +382,15 @@ pub struct WebConfig {
     pub log_file: Option<PathBuf>,
     pub admin_base_url: Option<String>,
     pub admin_token: Option<String>,
+    pub admin_targets: Vec<AdminTargetSpec>,
+    pub instance_dir: Option<PathBuf>,
+    pub no_instance_index: bool,
+    pub poll_interval_seconds: u64,
     pub auth_token: Option<String>,
+    pub tool_endpoint: Option<String>,
+    pub tool_protocol: Option<ConnectProtocol>,
+    pub tool_headers: HeadersMap,
+    pub registry_path: Option<PathBuf>,
     pub theme_catalog_url: String,
     pub theme_cache_ttl_seconds: u64,
     pub theme_cache_file: Option<PathBuf>,
@@ -276,6 +398,12 @@ pub struct WebConfig {
     pub log_level: LogLevel,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminTargetSpec {
+    pub name: String,
+    pub base_url: String,
+}
+
 #[derive(Debug, Clone)]
 pub enum CliCommand {
     Run(Box<Config>),
@@ -285,7 +413,8 @@ pub enum CliCommand {
     Discover(DiscoverConfig),
     Import(ImportConfig),
     Logs(LogsConfig),
-    Web(WebConfig),
+    Web(Box<WebConfig>),
+    Ctl(CtlConfig),
 }
 
 #[derive(Debug)]
@@ -334,7 +463,8 @@ fn parse_cli_command_from(raw_args: Vec<String>) -> Result<CliCommand, ConfigErr
         Some("discover") => parse_discover_config_from(raw_args).map(CliCommand::Discover),
         Some("import") => parse_import_config_from(raw_args).map(CliCommand::Import),
         Some("logs") => parse_logs_config_from(raw_args).map(CliCommand::Logs),
-        Some("web") => parse_web_config_from(raw_args).map(CliCommand::Web),
+        Some("web") => parse_web_config_from(raw_args).map(|cfg| CliCommand::Web(Box::new(cfg))),
+        Some("ctl") => parse_ctl_config_from(raw_args).map(CliCommand::Ctl),
         _ => {
             if raw_args.len() <= 1 {
                 eprintln!("{}", no_args_banner_text());
@@ -351,6 +481,9 @@ fn parse_config_from(raw_args: Vec<String>) -> Result<Config, ConfigError> {
     let matches = build_cli().get_matches_from(raw_args);
 
     let stdio = matches.get_one::<String>("stdio").cloned();
+    let server_name = matches.get_one::<String>("server-name").cloned();
+    let instance_name = matches.get_one::<String>("instance-name").cloned();
+    let log_per_instance = matches.get_flag("log-per-instance");
     let sse = matches.get_one::<String>("sse").cloned();
     let streamable_http = matches.get_one::<String>("streamable-http").cloned();
 
@@ -401,6 +534,20 @@ fn parse_config_from(raw_args: Vec<String>) -> Result<Config, ConfigError> {
         .get_one::<LogLevel>("log-level")
         .copied()
         .unwrap_or(LogLevel::Info);
+    let redact_keys: Vec<String> = matches
+        .get_many::<String>("redact-key")
+        .map(|values| values.cloned().collect())
+        .unwrap_or_default();
+    let redact_values: Vec<String> = matches
+        .get_many::<String>("redact-value")
+        .map(|values| values.cloned().collect())
+        .unwrap_or_default();
+    let redactor = Redactor::new(
+        &redact_keys,
+        &redact_values,
+        matches.get_flag("reveal-secrets"),
+    )
+    .map_err(ConfigError::InvalidArg)?;
 
     let health_endpoints: Vec<String> = matches
         .get_many::<String>("health-endpoint")
@@ -423,7 +570,7 @@ fn parse_config_from(raw_args: Vec<String>) -> Result<Config, ConfigError> {
 
     let oauth2_bearer = matches.get_one::<String>("oauth2-bearer").cloned();
     let headers = parse_headers(&header_values, oauth2_bearer.as_deref())?;
-    let env = parse_env_values(&env_values);
+    let env = parse_env_values(&env_values, "--env")?;
 
     let cors = if cors_input.present {
         if cors_input.allow_all {
@@ -456,12 +603,58 @@ fn parse_config_from(raw_args: Vec<String>) -> Result<Config, ConfigError> {
         None
     };
 
+    let ws_isolation = matches
+        .get_one::<WsIsolation>("ws-isolation")
+        .copied()
+        .unwrap_or(WsIsolation::Shared);
+    let ws_idle_grace_ms = matches.get_one::<u64>("ws-idle-grace-ms").copied();
+    let backpressure = matches
+        .get_one::<BackpressurePolicy>("backpressure")
+        .copied()
+        .unwrap_or(BackpressurePolicy::Block);
+    let child_framing = matches
+        .get_one::<ChildFraming>("child-framing")
+        .copied()
+        .unwrap_or(ChildFraming::Ndjson);
+    let max_frame_bytes = matches
+        .get_one::<u64>("max-frame-bytes")
+        .map(|value| *value as usize)
+        .unwrap_or(DEFAULT_MAX_FRAME_BYTES);
+    let child_launch = parse_child_launch(&matches)?;
+    let child_stderr = parse_child_stderr(&matches)?;
+    if ws_idle_grace_ms.is_some() && ws_isolation != WsIsolation::PerConnection {
+        return Err(ConfigError::InvalidArg(
+            "ws-idle-grace-ms requires --ws-isolation per-connection".to_string(),
+        ));
+    }
+
     let protocol_version = matches
         .get_one::<String>("protocol-version")
         .cloned()
         .unwrap_or_else(|| "2024-11-05".to_string());
+    let child_replacement = ReplacementOptions {
+        strategy: matches
+            .get_one::<RestartStrategy>("restart-strategy")
+            .copied()
+            .unwrap_or_default(),
+        drain_timeout: std::time::Duration::from_millis(
+            matches
+                .get_one::<u64>("drain-timeout-ms")
+                .copied()
+                .unwrap_or(DEFAULT_DRAIN_TIMEOUT_MS),
+        ),
+    };
+    let managed_initialize = matches.get_flag("managed-initialize");
 
     let runtime_prompt = matches.get_flag("runtime-prompt");
+    let control_socket = matches.get_one::<PathBuf>("control-socket").cloned();
+    let config_file = matches.get_one::<PathBuf>("config").cloned();
+    let watch_config = matches.get_flag("watch-config");
+    if watch_config && config_file.is_none() {
+        return Err(ConfigError::InvalidArg(
+            "--watch-config requires --config".to_string(),
+        ));
+    }
     let runtime_admin_port = if let Some(raw) = matches.get_one::<String>("runtime-admin-port") {
         let val: i64 = raw.parse().map_err(|_| {
             ConfigError::InvalidRuntimePort(format!(
@@ -511,6 +704,9 @@ fn parse_config_from(raw_args: Vec<String>) -> Result<Config, ConfigError> {
 
     Ok(Config {
         stdio,
+        server_name,
+        instance_name,
+        log_per_instance,
         sse,
         streamable_http,
         output_transport,
@@ -520,14 +716,27 @@ fn parse_config_from(raw_args: Vec<String>) -> Result<Config, ConfigError> {
         message_path,
         streamable_http_path,
         log_level,
+        redactor,
         cors,
         health_endpoints,
         headers,
         env,
         stateful,
         session_timeout,
+        ws_isolation,
+        ws_idle_grace_ms,
+        backpressure,
+        child_framing,
+        max_frame_bytes,
+        child_launch,
+        child_stderr,
+        child_replacement,
+        managed_initialize,
         protocol_version,
         runtime_prompt,
+        control_socket,
+        config_file,
+        watch_config,
         runtime_admin_port,
         runtime_admin_host,
         runtime_admin_token,
@@ -643,7 +852,7 @@ fn parse_connect_config_from(raw_args: Vec<String>) -> Result<ConnectConfig, Con
         .unwrap_or_default();
     let oauth2_bearer = sub.get_one::<String>("oauth2-bearer").cloned();
     let headers = parse_headers(&header_values, oauth2_bearer.as_deref())?;
-    let stdio_env = parse_env_values(&stdio_env_values);
+    let stdio_env = parse_env_values(&stdio_env_values, "--stdio-env")?;
 
     let registry_path = sub.get_one::<String>("registry").map(PathBuf::from);
     let save_profile_dir = sub.get_one::<String>("save-profile").map(PathBuf::from);
@@ -779,6 +988,7 @@ fn parse_discover_config_from(raw_args: Vec<String>) -> Result<DiscoverConfig, C
         .unwrap_or(SortOrder::Asc);
     let offset = sub.get_one::<usize>("offset").copied().unwrap_or(0);
     let limit = sub.get_one::<usize>("limit").copied();
+    let reveal_secrets = sub.get_flag("reveal-secrets");
 
     Ok(DiscoverConfig {
         from,
@@ -793,6 +1003,7 @@ fn parse_discover_config_from(raw_args: Vec<String>) -> Result<DiscoverConfig, C
         order,
         offset,
         limit,
+        reveal_secrets,
     })
 }
 
@@ -815,6 +1026,7 @@ fn parse_import_config_from(raw_args: Vec<String>) -> Result<ImportConfig, Confi
     let save_profiles_dir = sub.get_one::<String>("save-profiles").map(PathBuf::from);
     let bundle_mcpway = sub.get_flag("bundle-mcpway");
     let compile_wrapper = sub.get_flag("compile-wrapper");
+    let reveal_secrets = sub.get_flag("reveal-secrets");
 
     Ok(ImportConfig {
         from,
@@ -825,6 +1037,7 @@ fn parse_import_config_from(raw_args: Vec<String>) -> Result<ImportConfig, Confi
         save_profiles_dir,
         bundle_mcpway,
         compile_wrapper,
+        reveal_secrets,
     })
 }
 
@@ -835,9 +1048,12 @@ fn parse_logs_config_from(raw_args: Vec<String>) -> Result<LogsConfig, ConfigErr
             "Failed to parse logs command".to_string(),
         ));
     };
+    if let Some(query) = sub.subcommand_matches("query") {
+        return parse_logs_query_config(query);
+    }
     let Some(tail) = sub.subcommand_matches("tail") else {
         return Err(ConfigError::InvalidArg(
-            "logs currently supports only the 'tail' subcommand".to_string(),
+            "logs supports the 'tail' and 'query' subcommands".to_string(),
         ));
     };
 
@@ -845,7 +1061,23 @@ fn parse_logs_config_from(raw_args: Vec<String>) -> Result<LogsConfig, ConfigErr
     let lines = tail.get_one::<usize>("lines").copied().unwrap_or(200);
     let level = tail.get_one::<LogsLevel>("level").copied();
     let transport = tail.get_one::<LogsTransport>("transport").copied();
+    let instance = tail.get_one::<String>("instance").cloned();
+    let session = tail.get_one::<String>("session").cloned();
+    let request_id = tail.get_one::<String>("request-id").cloned();
+    let method = tail.get_one::<String>("method").cloned();
+    let now = unix_timestamp_secs();
+    let since = tail
+        .get_one::<String>("since")
+        .map(|value| parse_time_bound(value, now))
+        .transpose()
+        .map_err(ConfigError::InvalidArg)?;
+    let until = tail
+        .get_one::<String>("until")
+        .map(|value| parse_time_bound(value, now))
+        .transpose()
+        .map_err(ConfigError::InvalidArg)?;
     let json = tail.get_flag("json");
+    let reveal_secrets = tail.get_flag("reveal-secrets");
     let no_follow = tail.get_flag("no-follow");
     let follow = !no_follow;
 
@@ -855,10 +1087,111 @@ fn parse_logs_config_from(raw_args: Vec<String>) -> Result<LogsConfig, ConfigErr
         lines,
         level,
         transport,
+        instance,
+        session,
+        request_id,
+        method,
+        since,
+        until,
         json,
+        reveal_secrets,
+    }))
+}
+
+fn parse_logs_query_config(query: &ArgMatches) -> Result<LogsConfig, ConfigError> {
+    let predicates = query
+        .get_many::<String>("where")
+        .map(|values| values.map(|raw| parse_log_predicate(raw)).collect())
+        .transpose()?
+        .unwrap_or_default();
+    let now = unix_timestamp_secs();
+    let time_bound = |name: &str| {
+        query
+            .get_one::<String>(name)
+            .map(|value| parse_time_bound(value, now))
+            .transpose()
+            .map_err(ConfigError::InvalidArg)
+    };
+
+    Ok(LogsConfig::Query(LogsQueryConfig {
+        file: query.get_one::<String>("file").map(PathBuf::from),
+        predicates,
+        text: query.get_one::<String>("match").cloned(),
+        since: time_bound("since")?,
+        until: time_bound("until")?,
+        limit: query.get_one::<usize>("limit").copied(),
+        format: query
+            .get_one::<LogsOutputFormat>("format")
+            .copied()
+            .unwrap_or(LogsOutputFormat::Table),
+        count_by: query.get_one::<String>("count-by").cloned(),
+        percentiles: query.get_one::<String>("percentiles").cloned(),
+        no_index: query.get_flag("no-index"),
+        reveal_secrets: query.get_flag("reveal-secrets"),
     }))
 }
 
+fn parse_log_predicate(raw: &str) -> Result<LogPredicate, ConfigError> {
+    let invalid = || {
+        ConfigError::InvalidArg(format!(
+            "Invalid --where '{raw}', expected KEY OP VALUE with OP one of = != > >= < <= ~"
+        ))
+    };
+    let raw_trimmed = raw.trim();
+    let key_len = raw_trimmed
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '_' | '.' | '-')))
+        .ok_or_else(invalid)?;
+    let (key, rest) = raw_trimmed.split_at(key_len);
+    let rest = rest.trim_start();
+    // Two-character operators first so `>=` is not read as `>` followed by `=value`.
+    let operators = [
+        (">=", PredicateOp::Ge),
+        ("<=", PredicateOp::Le),
+        ("!=", PredicateOp::Ne),
+        ("=", PredicateOp::Eq),
+        (">", PredicateOp::Gt),
+        ("<", PredicateOp::Lt),
+        ("~", PredicateOp::Contains),
+    ];
+    let (op, value) = operators
+        .iter()
+        .find_map(|(token, op)| rest.strip_prefix(token).map(|value| (*op, value)))
+        .ok_or_else(invalid)?;
+    if key.is_empty() {
+        return Err(invalid());
+    }
+    Ok(LogPredicate {
+        key: key.to_string(),
+        op,
+        value: value.trim().to_string(),
+    })
+}
+
+fn parse_ctl_config_from(raw_args: Vec<String>) -> Result<CtlConfig, ConfigError> {
+    let matches = build_ctl_root_cli().get_matches_from(raw_args);
+    let Some(sub) = matches.subcommand_matches("ctl") else {
+        return Err(ConfigError::InvalidArg(
+            "Failed to parse ctl command".to_string(),
+        ));
+    };
+
+    let socket = sub
+        .get_one::<PathBuf>("socket")
+        .cloned()
+        .or_else(|| env::var_os("MCPWAY_CONTROL_SOCKET").map(PathBuf::from))
+        .ok_or_else(|| {
+            ConfigError::InvalidArg(
+                "ctl requires --socket <PATH> or MCPWAY_CONTROL_SOCKET".to_string(),
+            )
+        })?;
+    let command = sub
+        .get_many::<String>("command")
+        .map(|vals| vals.cloned().collect())
+        .unwrap_or_default();
+
+    Ok(CtlConfig { socket, command })
+}
+
 fn parse_web_config_from(raw_args: Vec<String>) -> Result<WebConfig, ConfigError> {
     let matches = build_web_root_cli().get_matches_from(raw_args);
     let Some(sub) = matches.subcommand_matches("web") else {
@@ -880,12 +1213,31 @@ fn parse_web_config_from(raw_args: Vec<String>) -> Result<WebConfig, ConfigError
         .or_else(|| env::var("MCPWAY_RUNTIME_ADMIN_TOKEN").ok())
         .map(|value| value.trim().to_string())
         .filter(|value| !value.is_empty());
+    let admin_target_values: Vec<String> = sub
+        .get_many::<String>("admin-target")
+        .map(|vals| vals.map(|v| v.to_string()).collect())
+        .unwrap_or_default();
+    let admin_targets = parse_admin_targets(&admin_target_values)?;
+    let instance_dir = sub.get_one::<String>("instance-dir").map(PathBuf::from);
+    let no_instance_index = sub.get_flag("no-instance-index");
+    let poll_interval_seconds = sub
+        .get_one::<u64>("poll-interval-seconds")
+        .copied()
+        .unwrap_or(5);
     let auth_token = sub
         .get_one::<String>("auth-token")
         .cloned()
         .or_else(|| env::var("MCPWAY_WEB_AUTH_TOKEN").ok())
         .map(|value| value.trim().to_string())
         .filter(|value| !value.is_empty());
+    let tool_endpoint = sub.get_one::<String>("tool-endpoint").cloned();
+    let tool_protocol = sub.get_one::<ConnectProtocol>("tool-protocol").copied();
+    let tool_header_values: Vec<String> = sub
+        .get_many::<String>("tool-header")
+        .map(|vals| vals.map(|v| v.to_string()).collect())
+        .unwrap_or_default();
+    let tool_headers = parse_headers(&tool_header_values, None)?;
+    let registry_path = sub.get_one::<String>("registry").map(PathBuf::from);
     let theme_catalog_url = sub
         .get_one::<String>("theme-catalog-url")
         .cloned()
@@ -910,7 +1262,15 @@ fn parse_web_config_from(raw_args: Vec<String>) -> Result<WebConfig, ConfigError
         log_file,
         admin_base_url,
         admin_token,
+        admin_targets,
+        instance_dir,
+        no_instance_index,
+        poll_interval_seconds,
         auth_token,
+        tool_endpoint,
+        tool_protocol,
+        tool_headers,
+        registry_path,
         theme_catalog_url,
         theme_cache_ttl_seconds,
         theme_cache_file,
@@ -922,6 +1282,21 @@ fn parse_web_config_from(raw_args: Vec<String>) -> Result<WebConfig, ConfigError
 fn build_cli() -> Command {
     Command::new("mcpway")
         .arg(Arg::new("stdio").long("stdio").value_name("CMD"))
+        .arg(
+            Arg::new("server-name")
+                .long("server-name")
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("instance-name")
+                .long("instance-name")
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("log-per-instance")
+                .long("log-per-instance")
+                .action(ArgAction::SetTrue),
+        )
         .arg(Arg::new("sse").long("sse").value_name("URL"))
         .arg(
             Arg::new("streamable-http")
@@ -965,6 +1340,23 @@ fn build_cli() -> Command {
                 .value_parser(clap::builder::EnumValueParser::<LogLevel>::new())
                 .default_value("info"),
         )
+        .arg(
+            Arg::new("redact-key")
+                .long("redact-key")
+                .action(ArgAction::Append)
+                .value_name("PATTERN"),
+        )
+        .arg(
+            Arg::new("redact-value")
+                .long("redact-value")
+                .action(ArgAction::Append)
+                .value_name("REGEX"),
+        )
+        .arg(
+            Arg::new("reveal-secrets")
+                .long("reveal-secrets")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("cors")
                 .long("cors")
@@ -1005,16 +1397,189 @@ fn build_cli() -> Command {
                 .long("session-timeout")
                 .value_name("MILLISECONDS"),
         )
+        .arg(
+            Arg::new("ws-isolation")
+                .long("ws-isolation")
+                .value_parser(clap::builder::EnumValueParser::<WsIsolation>::new())
+                .default_value("shared"),
+        )
+        .arg(
+            Arg::new("ws-idle-grace-ms")
+                .long("ws-idle-grace-ms")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .value_name("MILLISECONDS"),
+        )
+        .arg(
+            Arg::new("backpressure")
+                .long("backpressure")
+                .value_parser(clap::builder::EnumValueParser::<BackpressurePolicy>::new())
+                .default_value("block"),
+        )
+        .arg(
+            Arg::new("child-framing")
+                .long("child-framing")
+                .value_parser(clap::builder::EnumValueParser::<ChildFraming>::new())
+                .default_value("ndjson"),
+        )
+        .arg(
+            Arg::new("max-frame-bytes")
+                .long("max-frame-bytes")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .value_name("BYTES"),
+        )
+        .arg(
+            Arg::new("child-stderr-level")
+                .long("child-stderr-level")
+                .value_parser(clap::builder::EnumValueParser::<StderrLevel>::new())
+                .default_value("info"),
+        )
+        .arg(
+            Arg::new("child-stderr-buffer")
+                .long("child-stderr-buffer")
+                .value_parser(clap::value_parser!(u64))
+                .value_name("LINES"),
+        )
+        .arg(
+            Arg::new("child-stderr-pattern")
+                .long("child-stderr-pattern")
+                .action(ArgAction::Append)
+                .value_name("REGEX=LEVEL"),
+        )
+        .arg(
+            Arg::new("restart-strategy")
+                .long("restart-strategy")
+                .value_parser(clap::builder::EnumValueParser::<RestartStrategy>::new())
+                .default_value("kill"),
+        )
+        .arg(
+            Arg::new("drain-timeout-ms")
+                .long("drain-timeout-ms")
+                .value_parser(clap::value_parser!(u64))
+                .value_name("MILLISECONDS"),
+        )
+        .arg(
+            Arg::new("managed-initialize")
+                .long("managed-initialize")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("child-cwd")
+                .long("child-cwd")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("DIR"),
+        )
+        .arg(
+            Arg::new("child-env-mode")
+                .long("child-env-mode")
+                .value_parser(clap::builder::EnumValueParser::<ChildEnvMode>::new())
+                .default_value("inherit"),
+        )
+        .arg(
+            Arg::new("child-env-allow")
+                .long("child-env-allow")
+                .action(ArgAction::Append)
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("child-path")
+                .long("child-path")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("DIR"),
+        )
+        .arg(
+            Arg::new("child-umask")
+                .long("child-umask")
+                .value_name("OCTAL"),
+        )
+        .arg(
+            Arg::new("child-uid")
+                .long("child-uid")
+                .value_parser(clap::value_parser!(u32))
+                .value_name("UID"),
+        )
+        .arg(
+            Arg::new("child-gid")
+                .long("child-gid")
+                .value_parser(clap::value_parser!(u32))
+                .value_name("GID"),
+        )
+        .arg(
+            Arg::new("child-rlimit-as")
+                .long("child-rlimit-as")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .value_name("BYTES"),
+        )
+        .arg(
+            Arg::new("child-rlimit-cpu")
+                .long("child-rlimit-cpu")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .value_name("SECONDS"),
+        )
+        .arg(
+            Arg::new("child-rlimit-nofile")
+                .long("child-rlimit-nofile")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .value_name("COUNT"),
+        )
+        .arg(
+            Arg::new("child-rlimit-nproc")
+                .long("child-rlimit-nproc")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .value_name("COUNT"),
+        )
+        .arg(
+            Arg::new("child-max-rss")
+                .long("child-max-rss")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .value_name("BYTES"),
+        )
+        .arg(
+            Arg::new("sandbox-ro")
+                .long("sandbox-ro")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH"),
+        )
+        .arg(
+            Arg::new("sandbox-rw")
+                .long("sandbox-rw")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH"),
+        )
+        .arg(
+            Arg::new("sandbox-deny-network")
+                .long("sandbox-deny-network")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("protocol-version")
                 .long("protocol-version")
                 .default_value("2024-11-05"),
         )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH"),
+        )
+        .arg(
+            Arg::new("watch-config")
+                .long("watch-config")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("runtime-prompt")
                 .long("runtime-prompt")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("control-socket")
+                .long("control-socket")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH"),
+        )
         .arg(
             Arg::new("runtime-admin-port")
                 .long("runtime-admin-port")
@@ -1104,6 +1669,30 @@ fn build_logs_root_cli() -> Command {
         .subcommand(build_logs_subcommand())
 }
 
+fn build_ctl_root_cli() -> Command {
+    Command::new("mcpway")
+        .subcommand_required(true)
+        .subcommand(build_ctl_subcommand())
+}
+
+fn build_ctl_subcommand() -> Command {
+    Command::new("ctl")
+        .about("Send runtime commands to a gateway's --control-socket")
+        .arg(
+            Arg::new("socket")
+                .long("socket")
+                .value_parser(clap::value_parser!(PathBuf))
+                .value_name("PATH"),
+        )
+        .arg(
+            Arg::new("command")
+                .num_args(0..)
+                .trailing_var_arg(true)
+                .allow_hyphen_values(true)
+                .value_name("COMMAND"),
+        )
+}
+
 fn build_web_root_cli() -> Command {
     Command::new("mcpway")
         .subcommand_required(true)
@@ -1442,6 +2031,11 @@ fn build_discover_subcommand() -> Command {
                 .value_parser(clap::value_parser!(usize))
                 .value_name("N"),
         )
+        .arg(
+            Arg::new("reveal-secrets")
+                .long("reveal-secrets")
+                .action(ArgAction::SetTrue),
+        )
 }
 
 fn build_import_subcommand() -> Command {
@@ -1484,11 +2078,16 @@ fn build_import_subcommand() -> Command {
                 .long("compile-wrapper")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("reveal-secrets")
+                .long("reveal-secrets")
+                .action(ArgAction::SetTrue),
+        )
 }
 
 fn build_logs_subcommand() -> Command {
     Command::new("logs")
-        .about("Read and tail local mcpway logs")
+        .about("Read, tail and query local mcpway logs")
         .subcommand_required(true)
         .subcommand(
             Command::new("tail")
@@ -1513,11 +2112,65 @@ fn build_logs_subcommand() -> Command {
                         .value_parser(clap::builder::EnumValueParser::<LogsTransport>::new())
                         .value_name("stdio|sse|ws|streamable-http|grpc|connect"),
                 )
+                .arg(Arg::new("instance").long("instance").value_name("ID"))
+                .arg(Arg::new("session").long("session").value_name("ID"))
+                .arg(Arg::new("request-id").long("request-id").value_name("ID"))
+                .arg(Arg::new("method").long("method").value_name("METHOD"))
+                .arg(Arg::new("since").long("since").value_name("TIME"))
+                .arg(Arg::new("until").long("until").value_name("TIME"))
                 .arg(Arg::new("json").long("json").action(ArgAction::SetTrue))
                 .arg(
                     Arg::new("no-follow")
                         .long("no-follow")
                         .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("reveal-secrets")
+                        .long("reveal-secrets")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("query")
+                .about("Search all log segments with predicates and aggregations")
+                .arg(Arg::new("file").long("file").value_name("PATH"))
+                .arg(
+                    Arg::new("where")
+                        .long("where")
+                        .value_name("KEY OP VALUE")
+                        .action(ArgAction::Append),
+                )
+                .arg(Arg::new("match").long("match").value_name("TEXT"))
+                .arg(Arg::new("since").long("since").value_name("TIME"))
+                .arg(Arg::new("until").long("until").value_name("TIME"))
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(clap::builder::EnumValueParser::<LogsOutputFormat>::new())
+                        .value_name("table|json|csv")
+                        .default_value("table"),
+                )
+                .arg(Arg::new("count-by").long("count-by").value_name("KEY"))
+                .arg(
+                    Arg::new("percentiles")
+                        .long("percentiles")
+                        .value_name("FIELD"),
+                )
+                .arg(
+                    Arg::new("no-index")
+                        .long("no-index")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("reveal-secrets")
+                        .long("reveal-secrets")
+                        .action(ArgAction::SetTrue),
                 ),
         )
 }
@@ -1549,11 +2202,52 @@ fn build_web_subcommand() -> Command {
                 .long("admin-token")
                 .value_name("TOKEN"),
         )
+        .arg(
+            Arg::new("admin-target")
+                .long("admin-target")
+                .action(ArgAction::Append)
+                .value_name("NAME=URL"),
+        )
+        .arg(
+            Arg::new("instance-dir")
+                .long("instance-dir")
+                .value_name("PATH"),
+        )
+        .arg(
+            Arg::new("no-instance-index")
+                .long("no-instance-index")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("poll-interval-seconds")
+                .long("poll-interval-seconds")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .value_name("SECONDS")
+                .default_value("5"),
+        )
         .arg(
             Arg::new("auth-token")
                 .long("auth-token")
                 .value_name("TOKEN"),
         )
+        .arg(
+            Arg::new("tool-endpoint")
+                .long("tool-endpoint")
+                .value_name("URL"),
+        )
+        .arg(
+            Arg::new("tool-protocol")
+                .long("tool-protocol")
+                .value_parser(clap::builder::EnumValueParser::<ConnectProtocol>::new())
+                .value_name("sse|streamable-http|ws|grpc"),
+        )
+        .arg(
+            Arg::new("tool-header")
+                .long("tool-header")
+                .action(ArgAction::Append)
+                .value_name("KEY:VALUE"),
+        )
+        .arg(Arg::new("registry").long("registry").value_name("PATH"))
         .arg(
             Arg::new("theme-catalog-url")
                 .long("theme-catalog-url")
@@ -1642,6 +2336,8 @@ fn no_args_banner_text_with_style(use_ansi: bool) -> String {
     output.push_str("  mcpway import --from auto --save-profiles ./profiles\n\n");
     output.push_str(&format!("{}\n", maybe_bold("Web Inspector", use_ansi)));
     output.push_str("  mcpway web --port 5173\n\n");
+    output.push_str(&format!("{}\n", maybe_bold("Runtime Control", use_ansi)));
+    output.push_str("  mcpway ctl --socket /run/mcpway.sock status\n\n");
     output.push_str(&format!("{}\n", maybe_bold("Quick Start", use_ansi)));
     output.push_str("  mcpway --stdio \"npx -y @modelcontextprotocol/server-everything\"\n");
     output.push_str("  mcpway --sse http://127.0.0.1:9000/sse\n");
@@ -1726,6 +2422,8 @@ fn parse_headers(
             tracing::error!("Invalid header format: {raw}, ignoring");
             continue;
         }
+        validate_secret_refs(value)
+            .map_err(|err| ConfigError::InvalidArg(format!("--header {key}: {err}")))?;
         headers.insert(key.to_string(), value.to_string());
     }
     if let Some(token) = oauth2_bearer {
@@ -1737,7 +2435,35 @@ fn parse_headers(
     Ok(headers)
 }
 
-fn parse_env_values(values: &[String]) -> HashMap<String, String> {
+fn parse_admin_targets(values: &[String]) -> Result<Vec<AdminTargetSpec>, ConfigError> {
+    let mut targets: Vec<AdminTargetSpec> = Vec::new();
+    for raw in values {
+        let Some((name, base_url)) = raw.split_once('=') else {
+            return Err(ConfigError::InvalidArg(format!(
+                "Invalid --admin-target '{raw}', expected NAME=URL"
+            )));
+        };
+        let name = name.trim();
+        let base_url = base_url.trim();
+        if name.is_empty() || base_url.is_empty() {
+            return Err(ConfigError::InvalidArg(format!(
+                "Invalid --admin-target '{raw}', expected NAME=URL"
+            )));
+        }
+        if targets.iter().any(|target| target.name == name) {
+            return Err(ConfigError::InvalidArg(format!(
+                "Duplicate --admin-target name '{name}'"
+            )));
+        }
+        targets.push(AdminTargetSpec {
+            name: name.to_string(),
+            base_url: base_url.to_string(),
+        });
+    }
+    Ok(targets)
+}
+
+fn parse_env_values(values: &[String], flag: &str) -> Result<HashMap<String, String>, ConfigError> {
     let mut env_map = HashMap::new();
     for raw in values {
         let Some((key, value)) = raw.split_once('=') else {
@@ -1749,9 +2475,110 @@ fn parse_env_values(values: &[String]) -> HashMap<String, String> {
             tracing::error!("Invalid env format: {raw}, expected KEY=VALUE, ignoring");
             continue;
         }
+        validate_secret_refs(value)
+            .map_err(|err| ConfigError::InvalidArg(format!("{flag} {key}: {err}")))?;
         env_map.insert(key.to_string(), value.to_string());
     }
-    env_map
+    Ok(env_map)
+}
+
+fn parse_child_launch(matches: &ArgMatches) -> Result<LaunchOptions, ConfigError> {
+    let env_mode = matches
+        .get_one::<ChildEnvMode>("child-env-mode")
+        .copied()
+        .unwrap_or_default();
+    let env_allow: Vec<String> = matches
+        .get_many::<String>("child-env-allow")
+        .map(|vals| vals.map(|v| v.trim().to_string()).collect())
+        .unwrap_or_default();
+    if !env_allow.is_empty() && env_mode != ChildEnvMode::Allowlist {
+        return Err(ConfigError::InvalidArg(
+            "child-env-allow requires --child-env-mode allowlist".to_string(),
+        ));
+    }
+
+    let umask = match matches.get_one::<String>("child-umask") {
+        Some(raw) => {
+            let digits = raw.trim().trim_start_matches("0o");
+            match u32::from_str_radix(digits, 8) {
+                Ok(mask) if mask <= 0o777 => Some(mask),
+                _ => {
+                    return Err(ConfigError::InvalidArg(format!(
+                        "child-umask must be an octal mode between 000 and 777, received: {raw}"
+                    )))
+                }
+            }
+        }
+        None => None,
+    };
+
+    Ok(LaunchOptions {
+        cwd: matches.get_one::<PathBuf>("child-cwd").cloned(),
+        env_mode,
+        env_allow,
+        path_prepend: matches
+            .get_many::<PathBuf>("child-path")
+            .map(|vals| vals.cloned().collect())
+            .unwrap_or_default(),
+        umask,
+        uid: matches.get_one::<u32>("child-uid").copied(),
+        gid: matches.get_one::<u32>("child-gid").copied(),
+        rlimits: ResourceLimits {
+            address_space: matches.get_one::<u64>("child-rlimit-as").copied(),
+            cpu_seconds: matches.get_one::<u64>("child-rlimit-cpu").copied(),
+            open_files: matches.get_one::<u64>("child-rlimit-nofile").copied(),
+            processes: matches.get_one::<u64>("child-rlimit-nproc").copied(),
+        },
+        max_rss_bytes: matches.get_one::<u64>("child-max-rss").copied(),
+        sandbox: SandboxPolicy {
+            read_only: matches
+                .get_many::<PathBuf>("sandbox-ro")
+                .map(|vals| vals.cloned().collect())
+                .unwrap_or_default(),
+            read_write: matches
+                .get_many::<PathBuf>("sandbox-rw")
+                .map(|vals| vals.cloned().collect())
+                .unwrap_or_default(),
+            deny_network: matches.get_flag("sandbox-deny-network"),
+        },
+    })
+}
+
+fn parse_child_stderr(matches: &ArgMatches) -> Result<StderrOptions, ConfigError> {
+    let mut patterns = Vec::new();
+    for raw in matches
+        .get_many::<String>("child-stderr-pattern")
+        .into_iter()
+        .flatten()
+    {
+        // The level comes after the last `=` so patterns may contain `=` themselves.
+        let Some((regex, level)) = raw.rsplit_once('=') else {
+            return Err(ConfigError::InvalidArg(format!(
+                "child-stderr-pattern must be REGEX=LEVEL, received: {raw}"
+            )));
+        };
+        let level = StderrLevel::from_str(level.trim(), true).map_err(|_| {
+            ConfigError::InvalidArg(format!(
+                "child-stderr-pattern level must be trace, debug, info, warn or error, received: {level}"
+            ))
+        })?;
+        let regex = Regex::new(regex).map_err(|err| {
+            ConfigError::InvalidArg(format!("child-stderr-pattern {regex}: {err}"))
+        })?;
+        patterns.push(StderrPattern { regex, level });
+    }
+
+    Ok(StderrOptions {
+        default_level: matches
+            .get_one::<StderrLevel>("child-stderr-level")
+            .copied()
+            .unwrap_or(StderrLevel::Info),
+        buffer_lines: matches
+            .get_one::<u64>("child-stderr-buffer")
+            .map(|value| *value as usize)
+            .unwrap_or(DEFAULT_STDERR_BUFFER_LINES),
+        patterns,
+    })
 }
 
 #[cfg(test)]
@@ -1841,6 +2668,278 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_accepts_ws_per_connection_isolation() {
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "cat",
+            "--output-transport",
+            "ws",
+            "--ws-isolation",
+            "per-connection",
+            "--ws-idle-grace-ms",
+            "500",
+        ])
+        .expect("per-connection isolation should parse");
+        assert_eq!(cfg.ws_isolation, WsIsolation::PerConnection);
+        assert_eq!(cfg.ws_idle_grace_ms, Some(500));
+
+        let err = parse(&["mcpway", "--stdio", "cat", "--ws-idle-grace-ms", "500"])
+            .expect_err("grace period without isolation should fail");
+        assert!(err.to_string().contains("--ws-isolation per-connection"));
+    }
+
+    #[test]
+    fn parse_backpressure_policy_defaults_to_block() {
+        let cfg = parse(&["mcpway", "--stdio", "cat"]).expect("default config should parse");
+        assert_eq!(cfg.backpressure, BackpressurePolicy::Block);
+
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "cat",
+            "--backpressure",
+            "drop-notifications",
+        ])
+        .expect("drop-notifications should parse");
+        assert_eq!(cfg.backpressure, BackpressurePolicy::DropNotifications);
+    }
+
+    #[test]
+    fn parse_accepts_child_framing_and_max_frame_bytes() {
+        let cfg = parse(&["mcpway", "--stdio", "cat"]).expect("default config should parse");
+        assert_eq!(cfg.child_framing, ChildFraming::Ndjson);
+        assert_eq!(cfg.max_frame_bytes, DEFAULT_MAX_FRAME_BYTES);
+
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "cat",
+            "--child-framing",
+            "content-length",
+            "--max-frame-bytes",
+            "4096",
+        ])
+        .expect("content-length framing should parse");
+        assert_eq!(cfg.child_framing, ChildFraming::ContentLength);
+        assert_eq!(cfg.max_frame_bytes, 4096);
+    }
+
+    #[test]
+    fn parse_collects_child_stderr_options() {
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "cat",
+            "--child-stderr-level",
+            "warn",
+            "--child-stderr-buffer",
+            "50",
+            "--child-stderr-pattern",
+            "running on (stdio|port=\\d+)=debug",
+        ])
+        .expect("child stderr options should parse");
+        assert_eq!(cfg.child_stderr.default_level, StderrLevel::Warn);
+        assert_eq!(cfg.child_stderr.buffer_lines, 50);
+        assert_eq!(
+            cfg.child_stderr.classify("running on port=8080"),
+            StderrLevel::Debug
+        );
+
+        let err = parse(&[
+            "mcpway",
+            "--stdio",
+            "cat",
+            "--child-stderr-pattern",
+            "ready=loud",
+        ])
+        .expect_err("unknown level should be rejected");
+        assert!(err.to_string().contains("child-stderr-pattern"));
+    }
+
+    #[test]
+    fn parse_collects_restart_strategy_and_drain_timeout() {
+        let cfg = parse(&["mcpway", "--stdio", "cat"]).expect("defaults should parse");
+        assert_eq!(cfg.child_replacement.strategy, RestartStrategy::Kill);
+
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "cat",
+            "--restart-strategy",
+            "blue-green",
+            "--drain-timeout-ms",
+            "2500",
+        ])
+        .expect("blue/green options should parse");
+        assert_eq!(cfg.child_replacement.strategy, RestartStrategy::BlueGreen);
+        assert_eq!(
+            cfg.child_replacement.drain_timeout,
+            std::time::Duration::from_millis(2500)
+        );
+    }
+
+    #[test]
+    fn parse_collects_child_launch_options() {
+        let cfg = parse(&["mcpway", "--stdio", "cat"]).expect("default config should parse");
+        assert_eq!(cfg.child_launch, LaunchOptions::default());
+
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "cat",
+            "--child-cwd",
+            "/srv/mcp",
+            "--child-env-mode",
+            "allowlist",
+            "--child-env-allow",
+            "HOME",
+            "--child-env-allow",
+            "LANG",
+            "--child-path",
+            "/opt/mcp/bin",
+            "--child-umask",
+            "027",
+            "--child-rlimit-nofile",
+            "256",
+            "--child-max-rss",
+            "1048576",
+        ])
+        .expect("child launch options should parse");
+        let launch = cfg.child_launch;
+        assert_eq!(launch.cwd, Some(PathBuf::from("/srv/mcp")));
+        assert_eq!(launch.env_mode, ChildEnvMode::Allowlist);
+        assert_eq!(
+            launch.env_allow,
+            vec!["HOME".to_string(), "LANG".to_string()]
+        );
+        assert_eq!(launch.path_prepend, vec![PathBuf::from("/opt/mcp/bin")]);
+        assert_eq!(launch.umask, Some(0o027));
+        assert_eq!(launch.rlimits.open_files, Some(256));
+        assert_eq!(launch.max_rss_bytes, Some(1048576));
+    }
+
+    #[test]
+    fn parse_collects_instance_name_and_per_instance_logging() {
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "npx server",
+            "--instance-name",
+            "docs-prod",
+            "--log-per-instance",
+        ])
+        .expect("instance options should parse");
+        assert_eq!(cfg.instance_name, Some("docs-prod".to_string()));
+        assert!(cfg.log_per_instance);
+
+        let cfg = parse(&["mcpway", "--stdio", "npx server"]).expect("defaults should parse");
+        assert_eq!(cfg.instance_name, None);
+        assert!(!cfg.log_per_instance);
+    }
+
+    #[test]
+    fn parse_collects_server_name_and_sandbox_policy() {
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "npx server",
+            "--server-name",
+            "files",
+            "--sandbox-ro",
+            "/usr",
+            "--sandbox-rw",
+            "/srv/data",
+            "--sandbox-deny-network",
+        ])
+        .expect("sandbox options should parse");
+        assert_eq!(cfg.server_name, Some("files".to_string()));
+        let sandbox = cfg.child_launch.sandbox;
+        assert_eq!(sandbox.read_only, vec![PathBuf::from("/usr")]);
+        assert_eq!(sandbox.read_write, vec![PathBuf::from("/srv/data")]);
+        assert!(sandbox.deny_network);
+    }
+
+    #[test]
+    fn parse_keeps_secret_references_and_rejects_malformed_ones() {
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "cat",
+            "--env",
+            "GITHUB_TOKEN=${env:GITHUB_TOKEN}",
+            "--header",
+            "Authorization: Bearer ${file:/run/secrets/gh}",
+        ])
+        .expect("secret references should parse");
+        assert_eq!(
+            cfg.env.get("GITHUB_TOKEN"),
+            Some(&"${env:GITHUB_TOKEN}".to_string())
+        );
+        assert_eq!(
+            cfg.headers.get("Authorization"),
+            Some(&"Bearer ${file:/run/secrets/gh}".to_string())
+        );
+
+        let err = parse(&["mcpway", "--stdio", "cat", "--env", "GH=${dotenv:.env}"])
+            .expect_err("dotenv reference without a key should be rejected");
+        assert!(matches!(err, ConfigError::InvalidArg(msg) if msg.starts_with("--env GH:")));
+    }
+
+    #[test]
+    fn parse_requires_config_file_for_watch_config() {
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "cat",
+            "--config",
+            "/etc/mcpway/gateway.toml",
+            "--watch-config",
+        ])
+        .expect("config flags should parse");
+        assert_eq!(
+            cfg.config_file,
+            Some(PathBuf::from("/etc/mcpway/gateway.toml"))
+        );
+        assert!(cfg.watch_config);
+
+        let err = parse(&["mcpway", "--stdio", "cat", "--watch-config"])
+            .expect_err("--watch-config without --config should be rejected");
+        assert!(matches!(err, ConfigError::InvalidArg(_)));
+    }
+
+    #[test]
+    fn parse_builds_redactor_from_redaction_flags() {
+        let cfg = parse(&[
+            "mcpway",
+            "--stdio",
+            "cat",
+            "--redact-key",
+            "x-tenant",
+            "--redact-value",
+            r"acct-\d+",
+        ])
+        .expect("redaction flags should parse");
+        assert_eq!(cfg.redactor.redact_value("X-Tenant", "acme"), "[REDACTED]");
+        assert_eq!(cfg.redactor.redact_text("for acct-42"), "for [REDACTED]");
+
+        let err = parse(&["mcpway", "--stdio", "cat", "--redact-value", "("])
+            .expect_err("invalid redaction regex should be rejected");
+        assert!(matches!(err, ConfigError::InvalidArg(_)));
+    }
+
+    #[test]
+    fn parse_rejects_child_env_allow_without_allowlist_mode() {
+        let err = parse(&["mcpway", "--stdio", "cat", "--child-env-allow", "HOME"])
+            .expect_err("expected child-env-allow to require allowlist mode");
+        assert!(err.to_string().contains("--child-env-mode allowlist"));
+
+        let err = parse(&["mcpway", "--stdio", "cat", "--child-umask", "999"])
+            .expect_err("expected invalid umask");
+        assert!(err.to_string().contains("child-umask"));
+    }
+
     #[test]
     fn parse_rejects_invalid_runtime_admin_port() {
         let err = parse(&["mcpway", "--stdio", "cat", "--runtime-admin-port", "70000"])
@@ -2218,12 +3317,100 @@ mod tests {
                 assert_eq!(cfg.lines, 200);
                 assert_eq!(cfg.level, None);
                 assert_eq!(cfg.transport, None);
+                assert_eq!(cfg.instance, None);
+                assert_eq!(cfg.session, None);
+                assert_eq!(cfg.since, None);
                 assert!(!cfg.json);
             }
             other => panic!("unexpected command: {other:?}"),
         }
     }
 
+    #[test]
+    fn parse_logs_tail_correlation_and_time_filters() {
+        let cmd = parse_cli(&[
+            "mcpway",
+            "logs",
+            "tail",
+            "--session",
+            "abc",
+            "--request-id",
+            "7",
+            "--method",
+            "tools/call",
+            "--since",
+            "1700000000",
+            "--until",
+            "2026-01-01T00:00:00Z",
+        ])
+        .expect("logs tail parse failed");
+        match cmd {
+            CliCommand::Logs(LogsConfig::Tail(cfg)) => {
+                assert_eq!(cfg.session, Some("abc".to_string()));
+                assert_eq!(cfg.request_id, Some("7".to_string()));
+                assert_eq!(cfg.method, Some("tools/call".to_string()));
+                assert_eq!(cfg.since, Some(1_700_000_000));
+                assert_eq!(cfg.until, Some(1_767_225_600));
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        assert!(parse_cli(&["mcpway", "logs", "tail", "--since", "yesterday"]).is_err());
+    }
+
+    #[test]
+    fn parse_logs_query_subcommand() {
+        let cmd = parse_cli(&[
+            "mcpway",
+            "logs",
+            "query",
+            "--where",
+            "level>=warn",
+            "--where",
+            "fields.tool = search",
+            "--match",
+            "timeout",
+            "--since",
+            "1700000000",
+            "--format",
+            "csv",
+            "--count-by",
+            "session",
+            "--percentiles",
+            "duration_ms",
+        ])
+        .expect("logs query parse failed");
+        match cmd {
+            CliCommand::Logs(LogsConfig::Query(cfg)) => {
+                assert_eq!(
+                    cfg.predicates,
+                    vec![
+                        LogPredicate {
+                            key: "level".to_string(),
+                            op: PredicateOp::Ge,
+                            value: "warn".to_string(),
+                        },
+                        LogPredicate {
+                            key: "fields.tool".to_string(),
+                            op: PredicateOp::Eq,
+                            value: "search".to_string(),
+                        },
+                    ]
+                );
+                assert_eq!(cfg.text, Some("timeout".to_string()));
+                assert_eq!(cfg.since, Some(1_700_000_000));
+                assert_eq!(cfg.format, LogsOutputFormat::Csv);
+                assert_eq!(cfg.count_by, Some("session".to_string()));
+                assert_eq!(cfg.percentiles, Some("duration_ms".to_string()));
+                assert!(!cfg.no_index);
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        assert!(parse_cli(&["mcpway", "logs", "query", "--where", "level"]).is_err());
+        assert!(parse_cli(&["mcpway", "logs", "query", "--where", "=warn"]).is_err());
+    }
+
     #[test]
     fn parse_web_subcommand_defaults() {
         let cmd = parse_cli(&["mcpway", "web"]).expect("web parse failed");
@@ -2234,7 +3421,15 @@ mod tests {
                 assert_eq!(cfg.log_file, None);
                 assert_eq!(cfg.admin_base_url, None);
                 assert_eq!(cfg.admin_token, None);
+                assert!(cfg.admin_targets.is_empty());
+                assert_eq!(cfg.instance_dir, None);
+                assert!(!cfg.no_instance_index);
+                assert_eq!(cfg.poll_interval_seconds, 5);
                 assert_eq!(cfg.auth_token, None);
+                assert_eq!(cfg.tool_endpoint, None);
+                assert_eq!(cfg.tool_protocol, None);
+                assert!(cfg.tool_headers.is_empty());
+                assert_eq!(cfg.registry_path, None);
                 assert_eq!(cfg.theme_cache_ttl_seconds, 86_400);
                 assert_eq!(cfg.theme_cache_file, None);
                 assert!(!cfg.no_open_browser);
@@ -2259,8 +3454,25 @@ mod tests {
             "http://127.0.0.1:9101",
             "--admin-token",
             "secret",
+            "--admin-target",
+            "docs=http://127.0.0.1:9102",
+            "--admin-target",
+            "search=http://127.0.0.1:9103",
+            "--instance-dir",
+            "./instances",
+            "--no-instance-index",
+            "--poll-interval-seconds",
+            "2",
             "--auth-token",
             "ui-secret",
+            "--tool-endpoint",
+            "http://127.0.0.1:8000/mcp",
+            "--tool-protocol",
+            "streamable-http",
+            "--tool-header",
+            "X-Team: core",
+            "--registry",
+            "./registry.json",
             "--theme-catalog-url",
             "https://example.com/themes.json",
             "--theme-cache-ttl-seconds",
@@ -2280,7 +3492,30 @@ mod tests {
                 assert_eq!(cfg.log_file, Some(PathBuf::from("./mcpway.ndjson")));
                 assert_eq!(cfg.admin_base_url, Some("http://127.0.0.1:9101".to_string()));
                 assert_eq!(cfg.admin_token, Some("secret".to_string()));
+                assert_eq!(
+                    cfg.admin_targets,
+                    vec![
+                        AdminTargetSpec {
+                            name: "docs".to_string(),
+                            base_url: "http://127.0.0.1:9102".to_string(),
+                        },
+                        AdminTargetSpec {
+                            name: "search".to_string(),
+                            base_url: "http://127.0.0.1:9103".to_string(),
+                        },
+                    ]
+                );
+                assert_eq!(cfg.instance_dir, Some(PathBuf::from("./instances")));
+                assert!(cfg.no_instance_index);
+                assert_eq!(cfg.poll_interval_seconds, 2);
                 assert_eq!(cfg.auth_token, Some("ui-secret".to_string()));
+                assert_eq!(
+                    cfg.tool_endpoint,
+                    Some("http://127.0.0.1:8000/mcp".to_string())
+                );
+                assert_eq!(cfg.tool_protocol, Some(ConnectProtocol::StreamableHttp));
+                assert_eq!(cfg.tool_headers["X-Team"], "core");
+                assert_eq!(cfg.registry_path, Some(PathBuf::from("./registry.json")));
                 assert_eq!(
                     cfg.theme_catalog_url,
                     "https://example.com/themes.json".to_string()
@@ -2294,6 +3529,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_web_subcommand_rejects_malformed_admin_target() {
+        let err = parse_cli(&["mcpway", "web", "--admin-target", "http://127.0.0.1:9101"])
+            .expect_err("admin target without a name should fail");
+        assert!(err.to_string().contains("expected NAME=URL"));
+    }
+
+    #[test]
+    fn parse_ctl_subcommand_keeps_the_command_words() {
+        let command = parse_cli(&[
+            "mcpway",
+            "ctl",
+            "--socket",
+            "/tmp/mcpway.sock",
+            "set",
+            "env",
+            "API_KEY=abc",
+        ])
+        .expect("ctl should parse");
+        match command {
+            CliCommand::Ctl(cfg) => {
+                assert_eq!(cfg.socket, PathBuf::from("/tmp/mcpway.sock"));
+                assert_eq!(cfg.command, vec!["set", "env", "API_KEY=abc"]);
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
     #[test]
     fn cli_help_contains_usage_and_transport_flags() {
         let help = cli_help_text();
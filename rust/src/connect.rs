@@ -7,14 +7,23 @@ use tokio::process::Command;
 use tokio::sync::mpsc;
 use url::Url;
 
-use crate::config::{Config, ConnectConfig, ConnectProtocol, CorsConfig, OutputTransport};
+use crate::config::{
+    BackpressurePolicy, ChildFraming, Config, ConnectConfig, ConnectProtocol, CorsConfig,
+    OutputTransport, WsIsolation,
+};
 use crate::discovery::registry::{resolve_server, ResolvedImportedServer};
 use crate::gateways::{grpc_to_stdio, sse_to_stdio, streamable_http_to_stdio, ws_to_stdio};
 use crate::generator;
 use crate::oauth;
 use crate::runtime::store::RuntimeArgsStore;
 use crate::runtime::RuntimeUpdateRequest;
+use crate::support::child_framing::DEFAULT_MAX_FRAME_BYTES;
+use crate::support::child_stderr::StderrOptions;
+use crate::support::child_swap::ReplacementOptions;
 use crate::support::command_spec::parse_command_spec;
+use crate::support::launch::LaunchOptions;
+use crate::support::redact::Redactor;
+use crate::support::secrets::{resolve_secret_map, resolve_secret_refs};
 use crate::support::telemetry::init_telemetry;
 use crate::transport::reliability::{
     run_with_retry, CircuitBreaker, CircuitBreakerPolicy, RetryPolicy,
@@ -126,6 +135,8 @@ async fn run_remote_mode(
         profile_config.endpoint = Some(endpoint.clone());
         generator::save_connect_profile(&profile_config, protocol)?;
     }
+    // Resolved only after the profile is saved so it keeps the references, not the values.
+    config.headers = resolve_secret_map(&config.headers, "header").await?;
 
     let _telemetry = init_telemetry(
         config.log_level,
@@ -216,6 +227,14 @@ async fn run_stdio_mode(
     tracing::info!("  - server: {name}");
     tracing::info!("  - command: {command}");
 
+    let mut resolved_env = HashMap::with_capacity(env.len());
+    for (key, value) in env {
+        let resolved = resolve_env_template_value(&value)
+            .await
+            .map_err(|err| format!("env {key}: {err}"))?;
+        resolved_env.insert(key, resolved);
+    }
+    let env = resolved_env;
     let status = Command::new(&command)
         .args(&args)
         .envs(&env)
@@ -321,10 +340,7 @@ fn resolve_from_wrapper_json(
         .map(|obj| {
             obj.iter()
                 .map(|(key, raw)| {
-                    let value = raw
-                        .as_str()
-                        .map(resolve_env_template_value)
-                        .unwrap_or_default();
+                    let value = raw.as_str().unwrap_or_default().to_string();
                     (key.clone(), value)
                 })
                 .collect::<HashMap<_, _>>()
@@ -342,15 +358,17 @@ fn resolve_from_wrapper_json(
     Ok((name, command, args, env))
 }
 
-fn resolve_env_template_value(value: &str) -> String {
+// A bare `${KEY}` template is filled from the environment when set; `${env:..}`,
+// `${file:..}`, `${cmd:..}` and `${dotenv:..}` references must resolve or the launch fails.
+async fn resolve_env_template_value(value: &str) -> Result<String, String> {
     let trimmed = value.trim();
     if trimmed.starts_with("${") && trimmed.ends_with('}') && trimmed.len() > 3 {
         let key = &trimmed[2..trimmed.len() - 1];
         if let Ok(found) = std::env::var(key) {
-            return found;
+            return Ok(found);
         }
     }
-    value.to_string()
+    resolve_secret_refs(value).await
 }
 
 fn save_stdio_wrapper(
@@ -442,6 +460,9 @@ fn to_gateway_config(config: &ConnectConfig, endpoint: &str, protocol: ConnectPr
 
     Config {
         stdio: None,
+        server_name: None,
+        instance_name: None,
+        log_per_instance: false,
         sse,
         streamable_http,
         output_transport: OutputTransport::Stdio,
@@ -451,14 +472,27 @@ fn to_gateway_config(config: &ConnectConfig, endpoint: &str, protocol: ConnectPr
         message_path: "/message".to_string(),
         streamable_http_path: "/mcp".to_string(),
         log_level: config.log_level,
+        redactor: Redactor::default(),
         cors: CorsConfig::Disabled,
         health_endpoints: Vec::new(),
         headers: config.headers.clone(),
         env: HashMap::new(),
         stateful: false,
         session_timeout: None,
+        ws_isolation: WsIsolation::Shared,
+        ws_idle_grace_ms: None,
+        backpressure: BackpressurePolicy::Block,
+        child_framing: ChildFraming::Ndjson,
+        max_frame_bytes: DEFAULT_MAX_FRAME_BYTES,
+        child_launch: LaunchOptions::default(),
+        child_stderr: StderrOptions::default(),
+        child_replacement: ReplacementOptions::default(),
+        managed_initialize: false,
         protocol_version: config.protocol_version.clone(),
         runtime_prompt: false,
+        control_socket: None,
+        config_file: None,
+        watch_config: false,
         runtime_admin_port: None,
         runtime_admin_host: "127.0.0.1".to_string(),
         runtime_admin_token: None,
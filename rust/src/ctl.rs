@@ -0,0 +1,80 @@
+use crate::config::CtlConfig;
+
+/// `mcpway ctl`: runs one command given on the command line, or reads commands from
+/// stdin, against a gateway's `--control-socket`.
+#[cfg(unix)]
+pub async fn run(config: CtlConfig) -> Result<(), String> {
+    use std::io::{IsTerminal, Write};
+
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+
+    use crate::runtime::control::ControlReply;
+
+    let stream = UnixStream::connect(&config.socket).await.map_err(|err| {
+        format!(
+            "Failed to connect to control socket {}: {err}",
+            config.socket.display()
+        )
+    })?;
+    let (read, mut write) = stream.into_split();
+    let mut replies = BufReader::new(read).lines();
+
+    let mut exchange = async |line: &str| -> Result<ControlReply, String> {
+        write
+            .write_all(format!("{line}\n").as_bytes())
+            .await
+            .map_err(|err| format!("Failed to send command: {err}"))?;
+        let reply = replies
+            .next_line()
+            .await
+            .map_err(|err| format!("Failed to read reply: {err}"))?
+            .ok_or("Gateway closed the control socket")?;
+        serde_json::from_str(&reply).map_err(|err| format!("Invalid reply from gateway: {err}"))
+    };
+
+    if !config.command.is_empty() {
+        let reply = exchange(&shell_words::join(&config.command)).await?;
+        if !reply.ok {
+            return Err(reply.output);
+        }
+        if !reply.output.is_empty() {
+            println!("{}", reply.output);
+        }
+        return Ok(());
+    }
+
+    let interactive = std::io::stdin().is_terminal();
+    let mut input = BufReader::new(tokio::io::stdin()).lines();
+    loop {
+        if interactive {
+            print!("mcpway> ");
+            let _ = std::io::stdout().flush();
+        }
+        let Some(line) = input
+            .next_line()
+            .await
+            .map_err(|err| format!("Failed to read stdin: {err}"))?
+        else {
+            break;
+        };
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+        let reply = exchange(&line).await?;
+        if reply.output.is_empty() {
+            continue;
+        }
+        if reply.ok {
+            println!("{}", reply.output);
+        } else {
+            eprintln!("error: {}", reply.output);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn run(_config: CtlConfig) -> Result<(), String> {
+    Err("mcpway ctl needs Unix domain sockets, which this platform lacks".to_string())
+}
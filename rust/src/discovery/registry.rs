@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::config::ConnectProtocol;
 use crate::discovery::{DiscoveredServer, DiscoveredTransport};
+use crate::support::secrets::validate_secret_refs;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportedRegistry {
@@ -84,6 +85,15 @@ pub fn resolve_server(
         .find(|server| server.name == name)
         .ok_or_else(|| format!("Server '{name}' not found in registry {}", path.display()))?;
 
+    // Secret references stay unresolved here; they are resolved when the child is spawned
+    // or the connection is made, so the registry never has to hold real tokens.
+    for (field, values) in [("env", &server.env), ("header", &server.headers)] {
+        for (key, value) in values {
+            validate_secret_refs(value)
+                .map_err(|err| format!("Server '{name}' {field} {key}: {err}"))?;
+        }
+    }
+
     match server.transport {
         DiscoveredTransport::Stdio => {
             let command = server
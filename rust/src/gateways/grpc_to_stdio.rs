@@ -14,8 +14,10 @@ use url::Url;
 use crate::grpc_proto::bridge::mcp_bridge_client::McpBridgeClient;
 use crate::grpc_proto::bridge::Envelope;
 use crate::runtime::store::RuntimeArgsStore;
-use crate::runtime::{RuntimeApplyResult, RuntimeScope, RuntimeUpdateRequest};
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdateRequest};
 use crate::support::signals::install_signal_handlers;
+use crate::support::trace_context::{begin_request, RequestSpans, SpanRole, TraceCarrier};
+use crate::support::traffic::{self, TrafficDirection};
 use crate::transport::pool::{global_pool, transport_fingerprint};
 
 const GRPC_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
@@ -62,21 +64,29 @@ pub async fn run(
     let runtime_store = runtime.clone();
     tokio::spawn(async move {
         while let Some(req) = updates.recv().await {
-            let result = match req.update.scope {
-                RuntimeScope::Global => {
-                    let update_result = runtime_store.update_global(req.update.update).await;
-                    if update_result.restart_needed || update_result.headers_changed {
-                        RuntimeApplyResult::ok(
-                            "Updated runtime args/headers; reconnect required for gRPC endpoint",
-                            true,
-                        )
-                    } else {
-                        RuntimeApplyResult::ok("No runtime changes applied", false)
+            let result = match req.command {
+                RuntimeCommand::Update(update) => match update.scope {
+                    RuntimeScope::Global => {
+                        let update_result = runtime_store.update_global(update.update).await;
+                        if update_result.restart_needed || update_result.headers_changed {
+                            RuntimeApplyResult::ok(
+                                "Updated runtime args/headers; reconnect required for gRPC endpoint",
+                                true,
+                            )
+                        } else {
+                            RuntimeApplyResult::ok("No runtime changes applied", false)
+                        }
                     }
+                    RuntimeScope::Session(_) => RuntimeApplyResult::error(
+                        "Per-session runtime overrides are not supported for gRPC→stdio",
+                    ),
+                },
+                RuntimeCommand::RestartChild => {
+                    RuntimeApplyResult::error("gRPC→stdio has no local child to restart")
+                }
+                RuntimeCommand::RestartSession(_) | RuntimeCommand::EvictSession(_) => {
+                    RuntimeApplyResult::error("gRPC→stdio does not track gateway sessions")
                 }
-                RuntimeScope::Session(_) => RuntimeApplyResult::error(
-                    "Per-session runtime overrides are not supported for gRPC→stdio",
-                ),
             };
             let _ = req.respond_to.send(result);
         }
@@ -84,6 +94,7 @@ pub async fn run(
 
     let envelope_headers = Arc::new(initial_runtime.headers.clone());
     let seq = AtomicU64::new(0);
+    let spans = RequestSpans::default();
     let mut stdin_lines = FramedRead::new(tokio::io::stdin(), LinesCodec::new());
 
     loop {
@@ -97,7 +108,7 @@ pub async fn run(
                     continue;
                 }
 
-                let Ok(message_json) = serde_json::from_str::<serde_json::Value>(&line) else {
+                let Ok(mut message_json) = serde_json::from_str::<serde_json::Value>(&line) else {
                     tracing::error!("Invalid JSON from stdin: {line}");
                     continue;
                 };
@@ -107,9 +118,24 @@ pub async fn run(
                     continue;
                 }
 
+                traffic::record(TrafficDirection::ClientToServer, "grpc", None, &message_json);
+                let mut metadata = envelope_headers.as_ref().clone();
+                if let Some((trace, carrier)) = begin_request(
+                    SpanRole::Client,
+                    "grpc",
+                    &mut message_json,
+                    None,
+                    TraceCarrier::new(),
+                ) {
+                    metadata.extend(carrier);
+                    if let Some(id) = message_json.get("id") {
+                        spans.track(None, id, trace);
+                    }
+                }
+
                 let envelope = Envelope {
                     json_rpc: message_json.to_string(),
-                    metadata: envelope_headers.as_ref().clone(),
+                    metadata,
                     session_id: String::new(),
                     seq: seq.fetch_add(1, Ordering::SeqCst) + 1,
                 };
@@ -126,6 +152,8 @@ pub async fn run(
                         }
                         let payload: serde_json::Value = serde_json::from_str(&envelope.json_rpc)
                             .map_err(|err| format!("gRPC envelope payload was not valid JSON: {err}"))?;
+                        spans.finish(None, &payload);
+                        traffic::record(TrafficDirection::ServerToClient, "grpc", None, &payload);
                         println!("{}", payload);
                     }
                     Ok(None) => return Err("gRPC connection closed".to_string()),
@@ -6,12 +6,16 @@ use futures::StreamExt;
 use reqwest::Url;
 use tokio::sync::{mpsc, RwLock};
 use tokio_util::codec::{FramedRead, LinesCodec};
+use tracing::{Instrument, Span};
 use uuid::Uuid;
 
 use crate::config::Config;
 use crate::runtime::store::RuntimeArgsStore;
-use crate::runtime::{RuntimeApplyResult, RuntimeScope, RuntimeUpdateRequest};
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdateRequest};
+use crate::support::redact::redacted_json;
 use crate::support::signals::install_signal_handlers;
+use crate::support::trace_context::{begin_request, SpanRole, TraceCarrier};
+use crate::support::traffic::{self, TrafficDirection};
 use crate::transport::pool::{global_pool, transport_fingerprint, TransportPool};
 use crate::transport::reliability::{
     run_with_retry, CircuitBreaker, CircuitBreakerPolicy, RetryPolicy,
@@ -29,16 +33,15 @@ pub async fn run(
 ) -> Result<(), String> {
     let sse_url = config.sse.clone().ok_or("sse url is required")?;
     tracing::info!("  - sse: {sse_url}");
-    tracing::info!(
-        "  - Headers: {}",
-        serde_json::to_string(&config.headers).unwrap_or_else(|_| "(none)".into())
-    );
+    tracing::info!("  - Headers: {}", redacted_json(&config.headers));
     tracing::info!("Connecting to SSE...");
 
     install_signal_handlers(None);
 
     let message_endpoint: Arc<RwLock<Option<Url>>> = Arc::new(RwLock::new(None));
-    let headers = config.headers.clone();
+    // The event stream opens once, with the startup runtime headers: the command-line set
+    // plus any from the config file.
+    let headers = runtime.get_effective(None).await.headers;
     let protocol_version = config.protocol_version.clone();
     let sse_url_clone = sse_url.clone();
     let message_endpoint_clone = message_endpoint.clone();
@@ -90,6 +93,7 @@ pub async fn run(
                         continue;
                     }
                     if let Ok(json) = serde_json::from_str::<serde_json::Value>(&event.data) {
+                        traffic::record(TrafficDirection::ServerToClient, "sse", None, &json);
                         println!("{}", json);
                     }
                 }
@@ -104,21 +108,29 @@ pub async fn run(
     let runtime_store = runtime.clone();
     tokio::spawn(async move {
         while let Some(req) = updates.recv().await {
-            let result = match req.update.scope {
-                RuntimeScope::Global => {
-                    let update_result = runtime_store.update_global(req.update.update).await;
-                    if update_result.restart_needed {
-                        RuntimeApplyResult::ok(
-                            "Updated runtime args; env/CLI changes require restart of remote server",
-                            false,
-                        )
-                    } else {
-                        RuntimeApplyResult::ok("Updated runtime headers", false)
+            let result = match req.command {
+                RuntimeCommand::Update(update) => match update.scope {
+                    RuntimeScope::Global => {
+                        let update_result = runtime_store.update_global(update.update).await;
+                        if update_result.restart_needed {
+                            RuntimeApplyResult::ok(
+                                "Updated runtime args; env/CLI changes require restart of remote server",
+                                false,
+                            )
+                        } else {
+                            RuntimeApplyResult::ok("Updated runtime headers", false)
+                        }
                     }
+                    RuntimeScope::Session(_) => RuntimeApplyResult::error(
+                        "Per-session runtime overrides are not supported for SSE→stdio",
+                    ),
+                },
+                RuntimeCommand::RestartChild => {
+                    RuntimeApplyResult::error("SSE→stdio has no local child to restart")
+                }
+                RuntimeCommand::RestartSession(_) | RuntimeCommand::EvictSession(_) => {
+                    RuntimeApplyResult::error("SSE→stdio does not track gateway sessions")
                 }
-                RuntimeScope::Session(_) => RuntimeApplyResult::error(
-                    "Per-session runtime overrides are not supported for SSE→stdio",
-                ),
             };
             let _ = req.respond_to.send(result);
         }
@@ -149,7 +161,7 @@ pub async fn run(
         if line.trim().is_empty() {
             continue;
         }
-        let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) else {
+        let Ok(mut message) = serde_json::from_str::<serde_json::Value>(&line) else {
             tracing::error!("Invalid JSON from stdin: {line}");
             continue;
         };
@@ -158,6 +170,7 @@ pub async fn run(
             println!("{}", message);
             continue;
         }
+        traffic::record(TrafficDirection::ClientToServer, "sse", None, &message);
 
         let endpoint = match wait_for_message_endpoint(&message_endpoint).await {
             Ok(endpoint) => endpoint,
@@ -168,11 +181,23 @@ pub async fn run(
             }
         };
 
+        let (trace, carrier) = match begin_request(
+            SpanRole::Client,
+            "sse",
+            &mut message,
+            None,
+            TraceCarrier::new(),
+        ) {
+            Some((trace, carrier)) => (Some(trace), carrier),
+            None => (None, TraceCarrier::new()),
+        };
         let runtime_args = runtime_clone.get_effective(None).await;
+        let mut request_headers = runtime_args.headers.clone();
+        request_headers.extend(carrier);
         let request_context = SseRequestContext {
             http: &http,
             endpoint: &endpoint,
-            headers: &runtime_args.headers,
+            headers: &request_headers,
             pool: &pool,
             pool_key: &request_key,
         };
@@ -188,6 +213,7 @@ pub async fn run(
             .await;
             if init_payload.get("error").is_some() {
                 let response = wrap_response(&message, init_payload);
+                traffic::record(TrafficDirection::ServerToClient, "sse", None, &response);
                 println!("{}", response);
                 continue;
             }
@@ -207,7 +233,16 @@ pub async fn run(
             retry_policy,
             &mut circuit_breaker,
         )
+        .instrument(
+            trace
+                .as_ref()
+                .map(|trace| trace.span().clone())
+                .unwrap_or_else(Span::none),
+        )
         .await;
+        if let Some(trace) = trace {
+            trace.finish(&payload);
+        }
 
         if is_initialize_request(&message) && payload.get("error").is_none() && !initialized {
             if let Err(err) =
@@ -221,6 +256,7 @@ pub async fn run(
         }
 
         let response = wrap_response(&message, payload);
+        traffic::record(TrafficDirection::ServerToClient, "sse", None, &response);
         println!("{}", response);
     }
 
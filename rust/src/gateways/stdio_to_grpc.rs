@@ -16,10 +16,20 @@ use crate::config::Config;
 use crate::grpc_proto::bridge::mcp_bridge_server::{McpBridge, McpBridgeServer};
 use crate::grpc_proto::bridge::{Envelope, HealthRequest, HealthResponse};
 use crate::runtime::store::RuntimeArgsStore;
-use crate::runtime::{RuntimeApplyResult, RuntimeScope, RuntimeUpdateRequest};
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdateRequest};
+use crate::support::child_swap::SwapReport;
 use crate::support::command_spec::parse_command_spec;
+use crate::support::resource_subscriptions::{ChildRoute, ClientRoute, ResourceSubscriptions};
+use crate::support::session_children::SessionChildren;
+use crate::support::session_queues::{send_disconnect_notice, Delivery, SessionQueues};
+use crate::support::session_registry::SessionRegistry;
 use crate::support::signals::install_signal_handlers;
 use crate::support::stdio_child::StdioChild;
+use crate::support::trace_context::{
+    begin_request, carrier_from_map, carrier_from_metadata, RequestSpans, SpanRole,
+};
+use crate::support::traffic::{self, TrafficDirection};
+use crate::types::RuntimeArgs;
 
 const GRPC_CLIENT_BUFFER: usize = 256;
 
@@ -30,8 +40,14 @@ type GrpcClientMap = Arc<Mutex<HashMap<String, GrpcEnvelopeSender>>>;
 struct AppState {
     clients: GrpcClientMap,
     child: Arc<StdioChild>,
+    session_children: SessionChildren,
+    runtime: RuntimeArgsStore,
+    queues: SessionQueues,
+    registry: SessionRegistry,
     seq: Arc<AtomicU64>,
     bearer_token: Option<String>,
+    spans: RequestSpans,
+    subscriptions: ResourceSubscriptions,
 }
 
 #[derive(Clone)]
@@ -50,18 +66,35 @@ impl McpBridge for BridgeService {
         authorize(request.metadata(), self.state.bearer_token.as_deref())?;
 
         let client_id = Uuid::new_v4().to_string();
+        let session_id = client_id.clone();
         let (tx, rx) = mpsc::channel::<Result<Envelope, Status>>(GRPC_CLIENT_BUFFER);
+        self.state.queues.register(&client_id, "grpc", &tx).await;
         {
             let mut clients = self.state.clients.lock().await;
             clients.insert(client_id.clone(), tx);
         }
 
         let child = self.state.child.clone();
+        let evicted = self
+            .state
+            .registry
+            .register(&client_id, "grpc", Some(child.clone()))
+            .await;
+        let session_children = self.state.session_children.clone();
         let clients = self.state.clients.clone();
+        let queues = self.state.queues.clone();
+        let registry = self.state.registry.clone();
+        let spans = self.state.spans.clone();
+        let subscriptions = self.state.subscriptions.clone();
+        let stream_carrier = carrier_from_metadata(request.metadata());
         let mut incoming = request.into_inner();
         tokio::spawn(async move {
             loop {
-                match incoming.message().await {
+                let message = tokio::select! {
+                    message = incoming.message() => message,
+                    _ = evicted.cancelled() => break,
+                };
+                match message {
                     Ok(Some(envelope)) => {
                         if envelope.json_rpc.trim().is_empty() {
                             continue;
@@ -74,15 +107,53 @@ impl McpBridge for BridgeService {
                             continue;
                         };
 
-                        if let Some(id) = json.get("id").cloned() {
-                            let prefixed = prefix_id(&client_id, &id);
+                        registry.record_request(&client_id, &json).await;
+                        traffic::record(
+                            TrafficDirection::ClientToServer,
+                            "grpc",
+                            Some(&client_id),
+                            &json,
+                        );
+                        let mut carrier = stream_carrier.clone();
+                        carrier.extend(carrier_from_map(&envelope.metadata));
+                        let trace = begin_request(
+                            SpanRole::Server,
+                            "grpc",
+                            &mut json,
+                            Some(&client_id),
+                            carrier,
+                        );
+                        let request_id = json.get("id").cloned();
+                        if let Some(id) = &request_id {
+                            if let Some((trace, _)) = trace {
+                                spans.track(Some(&client_id), id, trace);
+                            }
+                            let prefixed = prefix_id(&client_id, id);
                             if let Some(obj) = json.as_object_mut() {
                                 obj.insert("id".to_string(), prefixed);
                             }
                         }
 
-                        if let Err(err) = child.send(&json).await {
-                            tracing::error!("Failed to write gRPC message to stdio child: {err}");
+                        let dedicated = session_children.get(&client_id).await;
+                        if dedicated.is_none() {
+                            session_children.record_initialize(&client_id, &json).await;
+                            if subscriptions
+                                .on_client_message(&client_id, &json, &child)
+                                .await
+                                == ClientRoute::Handled
+                            {
+                                continue;
+                            }
+                        }
+                        let target = dedicated.unwrap_or_else(|| child.clone());
+                        if let Err(err) = target.send(&json).await {
+                            tracing::error!(
+                                session_id = %client_id,
+                                "Failed to write gRPC message to stdio child: {err}"
+                            );
+                            if let Some(id) = &request_id {
+                                spans.abandon(Some(&client_id), id, &err);
+                            }
                             break;
                         }
                     }
@@ -94,11 +165,20 @@ impl McpBridge for BridgeService {
                 }
             }
 
-            let mut clients = clients.lock().await;
-            clients.remove(&client_id);
+            clients.lock().await.remove(&client_id);
+            queues.unregister(&client_id).await;
+            registry.unregister(&client_id).await;
+            spans.abandon_session(Some(&client_id), "Session closed");
+            session_children.release(&client_id).await;
+            subscriptions.release(&client_id, &child).await;
         });
 
-        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+        let mut response: Response<Self::StreamStream> =
+            Response::new(Box::pin(ReceiverStream::new(rx)));
+        if let Ok(value) = session_id.parse() {
+            response.metadata_mut().insert("mcp-session-id", value);
+        }
+        Ok(response)
     }
 
     async fn health(
@@ -117,13 +197,23 @@ pub async fn run(
     config: Config,
     runtime: RuntimeArgsStore,
     mut updates: mpsc::Receiver<RuntimeUpdateRequest>,
+    queues: SessionQueues,
+    registry: SessionRegistry,
 ) -> Result<(), String> {
     let stdio_cmd = config.stdio.clone().ok_or("stdio command is required")?;
 
     tracing::info!("  - port: {}", config.port);
     tracing::info!("  - stdio: {}", stdio_cmd);
 
-    let spec = parse_command_spec(&stdio_cmd)?;
+    let spec = parse_command_spec(&stdio_cmd)?
+        .with_framing(config.child_framing, config.max_frame_bytes)
+        .with_launch(config.child_launch.clone())
+        .with_stderr(config.child_stderr.clone())
+        .with_replacement(config.child_replacement.clone())
+        .with_protocol_version(&config.protocol_version)
+        .with_managed_initialize(config.managed_initialize)
+        .with_server_name(config.server_name.as_deref());
+    let session_children = SessionChildren::new(spec.clone(), runtime.clone());
     let child = Arc::new(StdioChild::new(spec, true));
     let initial_args = runtime.get_effective(None).await;
     child.spawn(&initial_args).await?;
@@ -131,116 +221,265 @@ pub async fn run(
     let state = AppState {
         clients: Arc::new(Mutex::new(HashMap::new())),
         child: child.clone(),
+        session_children: session_children.clone(),
+        runtime: runtime.clone(),
+        queues,
+        registry,
         seq: Arc::new(AtomicU64::new(0)),
         bearer_token: config.runtime_admin_token.clone(),
+        spans: RequestSpans::default(),
+        subscriptions: ResourceSubscriptions::default(),
     };
 
-    let runtime_child = child.clone();
     let runtime_store = runtime.clone();
+    let runtime_state = state.clone();
     tokio::spawn(async move {
         while let Some(req) = updates.recv().await {
-            let result = match req.update.scope {
-                RuntimeScope::Global => {
-                    let update_result = runtime_store.update_global(req.update.update).await;
-                    if update_result.restart_needed {
-                        let args = runtime_store.get_effective(None).await;
-                        if runtime_child.restart(&args).await.is_err() {
-                            RuntimeApplyResult::error("Failed to restart child")
+            let result = match req.command {
+                RuntimeCommand::Update(update) => match update.scope {
+                    RuntimeScope::Global => {
+                        let update_result = runtime_store.update_global(update.update).await;
+                        if update_result.restart_needed {
+                            let args = runtime_store.get_effective(None).await;
+                            match replace_all_children(&runtime_state, &args).await {
+                                Ok(swaps) => RuntimeApplyResult::replaced(swaps),
+                                Err(err) => {
+                                    runtime_store.revert_global(update_result.previous).await;
+                                    RuntimeApplyResult::error(format!(
+                                        "Failed to restart child: {err}"
+                                    ))
+                                }
+                            }
                         } else {
-                            RuntimeApplyResult::ok("Restarted child with new runtime args", true)
+                            RuntimeApplyResult::ok("Updated runtime args", false)
                         }
+                    }
+                    RuntimeScope::Session(session_id) => {
+                        if !runtime_state.clients.lock().await.contains_key(&session_id) {
+                            RuntimeApplyResult::error(format!("Unknown session {session_id}"))
+                        } else {
+                            let update_result = runtime_store
+                                .update_session(&session_id, update.update)
+                                .await;
+                            if update_result.restart_needed {
+                                match restart_session_child(&runtime_state, &session_id).await {
+                                    Ok(true) => RuntimeApplyResult::ok(
+                                        "Moved session onto a dedicated child",
+                                        true,
+                                    ),
+                                    Ok(false) => RuntimeApplyResult::ok(
+                                        "Restarted session child with new runtime args",
+                                        true,
+                                    ),
+                                    Err(err) => RuntimeApplyResult::error(format!(
+                                        "Failed to start session child: {err}"
+                                    )),
+                                }
+                            } else {
+                                RuntimeApplyResult::ok("Updated session runtime args", false)
+                            }
+                        }
+                    }
+                },
+                RuntimeCommand::RestartChild => {
+                    let args = runtime_store.get_effective(None).await;
+                    match restart_all_children(&runtime_state, &args).await {
+                        Ok(()) => RuntimeApplyResult::ok("Restarted child", true),
+                        Err(err) => {
+                            RuntimeApplyResult::error(format!("Failed to restart child: {err}"))
+                        }
+                    }
+                }
+                RuntimeCommand::RestartSession(session_id) => {
+                    if !runtime_state.clients.lock().await.contains_key(&session_id) {
+                        RuntimeApplyResult::error(format!("Unknown session {session_id}"))
                     } else {
-                        RuntimeApplyResult::ok("Updated runtime args", false)
+                        match restart_session_child(&runtime_state, &session_id).await {
+                            Ok(true) => RuntimeApplyResult::ok(
+                                "Moved session onto a fresh dedicated child",
+                                true,
+                            ),
+                            Ok(false) => RuntimeApplyResult::ok("Restarted session child", true),
+                            Err(err) => RuntimeApplyResult::error(format!(
+                                "Failed to restart session child: {err}"
+                            )),
+                        }
+                    }
+                }
+                RuntimeCommand::EvictSession(session_id) => {
+                    let removed = runtime_state.clients.lock().await.remove(&session_id);
+                    match removed {
+                        Some(sender) => {
+                            let notice =
+                                Status::aborted("Session evicted by the runtime admin API");
+                            send_disconnect_notice(sender, Err(notice));
+                            // Firing the eviction token ends the stream's reader, which releases the rest.
+                            runtime_state.registry.evict(&session_id).await;
+                            RuntimeApplyResult::ok("Evicted session", false)
+                        }
+                        None => RuntimeApplyResult::error(format!("Unknown session {session_id}")),
                     }
                 }
-                RuntimeScope::Session(_) => RuntimeApplyResult::error(
-                    "Per-session runtime overrides are not supported for stdio→gRPC",
-                ),
             };
             let _ = req.respond_to.send(result);
         }
     });
 
-    let clients = state.clients.clone();
-    let seq = state.seq.clone();
-    let mut rx = child.subscribe();
+    spawn_output_router(child, None, state.clone()).await;
+
+    install_signal_handlers(None);
+
+    let addr: std::net::SocketAddr = ([0, 0, 0, 0], config.port).into();
+    tracing::info!("Listening on port {}", config.port);
+    tracing::info!("gRPC endpoint: grpc://localhost:{}", config.port);
+
+    Server::builder()
+        .add_service(McpBridgeServer::new(BridgeService { state }))
+        .serve(addr)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+// Moves every child onto new runtime args with the configured restart strategy. Children
+// swapped blue/green still answer their in-flight requests, so those are only cleared
+// after a plain restart.
+async fn replace_all_children(
+    state: &AppState,
+    args: &RuntimeArgs,
+) -> Result<Vec<SwapReport>, String> {
+    let mut swaps: Vec<SwapReport> = state.child.apply_runtime(args).await?.into_iter().collect();
+    swaps.extend(state.session_children.apply_runtime_all().await?);
+    if swaps.is_empty() {
+        state.registry.reset_in_flight(None).await;
+        state.spans.abandon_session(None, "Child restarted");
+    }
+    state.subscriptions.resubscribe(&state.child).await;
+    Ok(swaps)
+}
+
+async fn restart_all_children(state: &AppState, args: &RuntimeArgs) -> Result<(), String> {
+    state.child.restart(args).await?;
+    state.session_children.restart_all().await?;
+    state.registry.reset_in_flight(None).await;
+    state.spans.abandon_session(None, "Child restarted");
+    state.subscriptions.resubscribe(&state.child).await;
+    Ok(())
+}
+
+// Gives the session a fresh child of its own; returns true when it was newly moved off the
+// shared child.
+async fn restart_session_child(state: &AppState, session_id: &str) -> Result<bool, String> {
+    let moved = match state.session_children.isolate(session_id).await? {
+        Some(dedicated) => {
+            state
+                .registry
+                .set_child(session_id, dedicated.clone())
+                .await;
+            state
+                .subscriptions
+                .move_session(session_id, &state.child, &dedicated)
+                .await;
+            spawn_output_router(dedicated, Some(session_id.to_string()), state.clone()).await;
+            true
+        }
+        None => false,
+    };
+    state.registry.reset_in_flight(Some(session_id)).await;
+    state
+        .spans
+        .abandon_session(Some(session_id), "Session child restarted");
+    Ok(moved)
+}
+
+// Routes child output back to streams by their id prefix. Output of a session's dedicated
+// child (`origin`) never reaches other streams, and resource updates from the shared child
+// reach only the sessions subscribed to that resource. Envelopes carry the target session
+// id and its effective header overrides as metadata.
+async fn spawn_output_router(child: Arc<StdioChild>, origin: Option<String>, state: AppState) {
+    let mut rx = child.subscribe().await;
+    drop(child);
     tokio::spawn(async move {
-        loop {
-            let msg = match rx.recv().await {
-                Ok(msg) => msg,
-                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
-                    tracing::warn!(
-                        "stdio->grpc child output receiver lagged by {skipped} messages; continuing"
-                    );
-                    continue;
-                }
-                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        while let Some(msg) = rx.recv().await {
+            let route = match origin {
+                Some(_) if state.subscriptions.owns(&msg) => ChildRoute::Drop,
+                Some(_) => ChildRoute::Everyone,
+                None => state.subscriptions.on_child_message(&msg).await,
             };
+            if route == ChildRoute::Drop {
+                continue;
+            }
 
-            let mut target_id: Option<String> = None;
+            let mut target_id = origin.clone();
             let mut outgoing = msg.clone();
             if let Some((client_id, raw_id)) = strip_prefixed_id(&msg) {
-                target_id = Some(client_id);
+                if origin.is_none() {
+                    target_id = Some(client_id);
+                }
                 if let Some(obj) = outgoing.as_object_mut() {
                     obj.insert("id".to_string(), raw_id);
                 }
             }
-
-            let envelope = Envelope {
-                json_rpc: outgoing.to_string(),
-                metadata: HashMap::new(),
-                session_id: String::new(),
-                seq: seq.fetch_add(1, Ordering::SeqCst) + 1,
-            };
-
-            if let Some(target) = target_id {
-                let sender = {
-                    let clients_guard = clients.lock().await;
-                    clients_guard.get(&target).cloned()
-                };
-                if let Some(sender) = sender {
-                    if sender.send(Ok(envelope)).await.is_err() {
-                        let mut clients_guard = clients.lock().await;
-                        clients_guard.remove(&target);
-                    }
-                }
-                continue;
-            }
+            let json_rpc = outgoing.to_string();
 
             let senders: Vec<(String, GrpcEnvelopeSender)> = {
-                let clients_guard = clients.lock().await;
-                clients_guard
-                    .iter()
-                    .map(|(id, sender)| (id.clone(), sender.clone()))
-                    .collect()
+                let clients_guard = state.clients.lock().await;
+                match &target_id {
+                    Some(target) => clients_guard
+                        .get(target)
+                        .map(|sender| vec![(target.clone(), sender.clone())])
+                        .unwrap_or_default(),
+                    None => clients_guard
+                        .iter()
+                        .filter(|(id, _)| match &route {
+                            ChildRoute::Sessions(subscribed) => subscribed.contains(*id),
+                            _ => true,
+                        })
+                        .map(|(id, sender)| (id.clone(), sender.clone()))
+                        .collect(),
+                }
             };
+            if origin.is_some() && senders.is_empty() {
+                break;
+            }
 
             let mut dead = Vec::new();
             for (id, sender) in senders {
-                if sender.send(Ok(envelope.clone())).await.is_err() {
-                    dead.push(id);
+                state.spans.finish(Some(&id), &outgoing);
+                state.registry.record_response(&id, &outgoing).await;
+                traffic::record(
+                    TrafficDirection::ServerToClient,
+                    "grpc",
+                    Some(&id),
+                    &outgoing,
+                );
+                let envelope = build_envelope(&state, &id, json_rpc.clone()).await;
+                match state.queues.deliver(&id, &sender, Ok(envelope), &msg).await {
+                    Delivery::Delivered | Delivery::Dropped => {}
+                    Delivery::Closed => dead.push(id),
+                    Delivery::Disconnect => {
+                        let notice = Status::resource_exhausted("Session outbound queue is full");
+                        send_disconnect_notice(sender, Err(notice));
+                        dead.push(id);
+                    }
                 }
             }
             if !dead.is_empty() {
-                let mut clients_guard = clients.lock().await;
+                let mut clients_guard = state.clients.lock().await;
                 for id in dead {
                     clients_guard.remove(&id);
                 }
             }
         }
     });
+}
 
-    install_signal_handlers(None);
-
-    let addr: std::net::SocketAddr = ([0, 0, 0, 0], config.port).into();
-    tracing::info!("Listening on port {}", config.port);
-    tracing::info!("gRPC endpoint: grpc://localhost:{}", config.port);
-
-    Server::builder()
-        .add_service(McpBridgeServer::new(BridgeService { state }))
-        .serve(addr)
-        .await
-        .map_err(|err| err.to_string())
+async fn build_envelope(state: &AppState, session_id: &str, json_rpc: String) -> Envelope {
+    Envelope {
+        json_rpc,
+        metadata: state.runtime.get_effective(Some(session_id)).await.headers,
+        session_id: session_id.to_string(),
+        seq: state.seq.fetch_add(1, Ordering::SeqCst) + 1,
+    }
 }
 
 fn authorize(metadata: &tonic::metadata::MetadataMap, token: Option<&str>) -> Result<(), Status> {
@@ -14,11 +14,20 @@ use uuid::Uuid;
 
 use crate::config::Config;
 use crate::runtime::store::RuntimeArgsStore;
-use crate::runtime::{RuntimeApplyResult, RuntimeScope, RuntimeUpdateRequest};
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdateRequest};
+use crate::support::child_swap::SwapReport;
 use crate::support::command_spec::parse_command_spec;
 use crate::support::cors::build_cors_layer;
+use crate::support::redact::redacted_json;
+use crate::support::resource_subscriptions::{ChildRoute, ClientRoute, ResourceSubscriptions};
+use crate::support::session_children::SessionChildren;
+use crate::support::session_queues::{send_disconnect_notice, Delivery, SessionQueues};
+use crate::support::session_registry::SessionRegistry;
 use crate::support::signals::install_signal_handlers;
 use crate::support::stdio_child::StdioChild;
+use crate::support::trace_context::{begin_request, carrier_from_headers, RequestSpans, SpanRole};
+use crate::support::traffic::{self, TrafficDirection};
+use crate::types::RuntimeArgs;
 
 // Keep enough per-session queue headroom for high-volume bursts observed in CI.
 const SESSION_EVENT_BUFFER: usize = 1024;
@@ -27,10 +36,15 @@ const SESSION_EVENT_BUFFER: usize = 1024;
 struct AppState {
     sessions: Arc<Mutex<HashMap<String, mpsc::Sender<Event>>>>,
     child: Arc<StdioChild>,
+    session_children: SessionChildren,
+    queues: SessionQueues,
+    registry: SessionRegistry,
     runtime: RuntimeArgsStore,
     base_headers: HeaderMap,
     message_path: String,
     base_url: String,
+    spans: RequestSpans,
+    subscriptions: ResourceSubscriptions,
 }
 
 #[derive(serde::Deserialize)]
@@ -43,12 +57,11 @@ pub async fn run(
     config: Config,
     runtime: RuntimeArgsStore,
     mut updates: mpsc::Receiver<RuntimeUpdateRequest>,
+    queues: SessionQueues,
+    registry: SessionRegistry,
 ) -> Result<(), String> {
     let stdio_cmd = config.stdio.clone().ok_or("stdio command is required")?;
-    tracing::info!(
-        "  - Headers: {}",
-        serde_json::to_string(&config.headers).unwrap_or_else(|_| "(none)".into())
-    );
+    tracing::info!("  - Headers: {}", redacted_json(&config.headers));
     tracing::info!("  - port: {}", config.port);
     tracing::info!("  - stdio: {}", stdio_cmd);
     if !config.base_url.is_empty() {
@@ -57,44 +70,137 @@ pub async fn run(
     tracing::info!("  - ssePath: {}", config.sse_path);
     tracing::info!("  - messagePath: {}", config.message_path);
 
-    let spec = parse_command_spec(&stdio_cmd)?;
+    let spec = parse_command_spec(&stdio_cmd)?
+        .with_framing(config.child_framing, config.max_frame_bytes)
+        .with_launch(config.child_launch.clone())
+        .with_stderr(config.child_stderr.clone())
+        .with_replacement(config.child_replacement.clone())
+        .with_protocol_version(&config.protocol_version)
+        .with_managed_initialize(config.managed_initialize)
+        .with_server_name(config.server_name.as_deref());
+    let session_children = SessionChildren::new(spec.clone(), runtime.clone());
     let child = Arc::new(StdioChild::new(spec, true));
     let initial_args = runtime.get_effective(None).await;
     child.spawn(&initial_args).await?;
 
     let sessions: Arc<Mutex<HashMap<String, mpsc::Sender<Event>>>> =
         Arc::new(Mutex::new(HashMap::new()));
+    let spans = RequestSpans::default();
 
     let state = AppState {
         sessions: sessions.clone(),
         child: child.clone(),
+        session_children: session_children.clone(),
+        queues,
+        registry,
         runtime: runtime.clone(),
         base_headers: header_map_from(&config.headers),
         message_path: config.message_path.clone(),
         base_url: config.base_url.clone(),
+        spans: spans.clone(),
+        subscriptions: ResourceSubscriptions::default(),
     };
 
-    let runtime_child = child.clone();
     let runtime_store = runtime.clone();
+    let runtime_state = state.clone();
     tokio::spawn(async move {
         while let Some(req) = updates.recv().await {
-            let result = match req.update.scope {
-                RuntimeScope::Global => {
-                    let update_result = runtime_store.update_global(req.update.update).await;
-                    if update_result.restart_needed {
-                        let args = runtime_store.get_effective(None).await;
-                        if runtime_child.restart(&args).await.is_err() {
-                            RuntimeApplyResult::error("Failed to restart child")
+            let result = match req.command {
+                RuntimeCommand::Update(update) => match update.scope {
+                    RuntimeScope::Global => {
+                        let update_result = runtime_store.update_global(update.update).await;
+                        if update_result.restart_needed {
+                            let args = runtime_store.get_effective(None).await;
+                            match replace_all_children(&runtime_state, &args).await {
+                                Ok(swaps) => RuntimeApplyResult::replaced(swaps),
+                                Err(err) => {
+                                    runtime_store.revert_global(update_result.previous).await;
+                                    RuntimeApplyResult::error(format!(
+                                        "Failed to restart child: {err}"
+                                    ))
+                                }
+                            }
+                        } else {
+                            RuntimeApplyResult::ok("Updated runtime args", false)
+                        }
+                    }
+                    RuntimeScope::Session(session_id) => {
+                        if !runtime_state
+                            .sessions
+                            .lock()
+                            .await
+                            .contains_key(&session_id)
+                        {
+                            RuntimeApplyResult::error(format!("Unknown session {session_id}"))
                         } else {
-                            RuntimeApplyResult::ok("Restarted child with new runtime args", true)
+                            let update_result = runtime_store
+                                .update_session(&session_id, update.update)
+                                .await;
+                            if update_result.restart_needed {
+                                match restart_session_child(&runtime_state, &session_id).await {
+                                    Ok(true) => RuntimeApplyResult::ok(
+                                        "Moved session onto a dedicated child",
+                                        true,
+                                    ),
+                                    Ok(false) => RuntimeApplyResult::ok(
+                                        "Restarted session child with new runtime args",
+                                        true,
+                                    ),
+                                    Err(err) => RuntimeApplyResult::error(format!(
+                                        "Failed to start session child: {err}"
+                                    )),
+                                }
+                            } else {
+                                RuntimeApplyResult::ok("Updated session runtime args", false)
+                            }
+                        }
+                    }
+                },
+                RuntimeCommand::RestartChild => {
+                    let args = runtime_store.get_effective(None).await;
+                    match restart_all_children(&runtime_state, &args).await {
+                        Ok(()) => RuntimeApplyResult::ok("Restarted child", true),
+                        Err(err) => {
+                            RuntimeApplyResult::error(format!("Failed to restart child: {err}"))
                         }
+                    }
+                }
+                RuntimeCommand::RestartSession(session_id) => {
+                    if !runtime_state
+                        .sessions
+                        .lock()
+                        .await
+                        .contains_key(&session_id)
+                    {
+                        RuntimeApplyResult::error(format!("Unknown session {session_id}"))
                     } else {
-                        RuntimeApplyResult::ok("Updated runtime args", false)
+                        match restart_session_child(&runtime_state, &session_id).await {
+                            Ok(true) => RuntimeApplyResult::ok(
+                                "Moved session onto a fresh dedicated child",
+                                true,
+                            ),
+                            Ok(false) => RuntimeApplyResult::ok("Restarted session child", true),
+                            Err(err) => RuntimeApplyResult::error(format!(
+                                "Failed to restart session child: {err}"
+                            )),
+                        }
+                    }
+                }
+                RuntimeCommand::EvictSession(session_id) => {
+                    let removed = runtime_state.sessions.lock().await.remove(&session_id);
+                    match removed {
+                        Some(sender) => {
+                            let notice = Event::default()
+                                .event("error")
+                                .data("Session evicted by the runtime admin API");
+                            send_disconnect_notice(sender, notice);
+                            runtime_state.registry.evict(&session_id).await;
+                            runtime_state.session_children.release(&session_id).await;
+                            RuntimeApplyResult::ok("Evicted session", false)
+                        }
+                        None => RuntimeApplyResult::error(format!("Unknown session {session_id}")),
                     }
                 }
-                RuntimeScope::Session(_) => RuntimeApplyResult::error(
-                    "Per-session runtime overrides are not supported for stdio→SSE",
-                ),
             };
             let _ = req.respond_to.send(result);
         }
@@ -120,69 +226,194 @@ pub async fn run(
 
     install_signal_handlers(None);
 
-    let mut rx = child.subscribe();
+    spawn_output_router(child, None, state).await;
+
+    let addr: std::net::SocketAddr = ([0, 0, 0, 0], config.port).into();
+    tracing::info!("Listening on port {}", config.port);
+    tracing::info!(
+        "SSE endpoint: http://localhost:{}{}",
+        config.port,
+        config.sse_path
+    );
+    tracing::info!(
+        "POST messages: http://localhost:{}{}",
+        config.port,
+        config.message_path
+    );
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|err| err.to_string())?;
+    axum::serve(listener, router.into_make_service())
+        .await
+        .map_err(|err| err.to_string())
+}
+
+// Moves every child onto new runtime args with the configured restart strategy. Children
+// swapped blue/green still answer their in-flight requests, so those are only cleared
+// after a plain restart.
+async fn replace_all_children(
+    state: &AppState,
+    args: &RuntimeArgs,
+) -> Result<Vec<SwapReport>, String> {
+    let mut swaps: Vec<SwapReport> = state.child.apply_runtime(args).await?.into_iter().collect();
+    swaps.extend(state.session_children.apply_runtime_all().await?);
+    if swaps.is_empty() {
+        state.registry.reset_in_flight(None).await;
+        state.spans.abandon_session(None, "Child restarted");
+    }
+    state.subscriptions.resubscribe(&state.child).await;
+    Ok(swaps)
+}
+
+async fn restart_all_children(state: &AppState, args: &RuntimeArgs) -> Result<(), String> {
+    state.child.restart(args).await?;
+    state.session_children.restart_all().await?;
+    state.registry.reset_in_flight(None).await;
+    state.spans.abandon_session(None, "Child restarted");
+    state.subscriptions.resubscribe(&state.child).await;
+    Ok(())
+}
+
+// Gives the session a fresh child of its own; returns true when it was newly moved off the
+// shared child.
+async fn restart_session_child(state: &AppState, session_id: &str) -> Result<bool, String> {
+    let moved = match state.session_children.isolate(session_id).await? {
+        Some(dedicated) => {
+            state
+                .registry
+                .set_child(session_id, dedicated.clone())
+                .await;
+            state
+                .subscriptions
+                .move_session(session_id, &state.child, &dedicated)
+                .await;
+            spawn_output_router(dedicated, Some(session_id.to_string()), state.clone()).await;
+            true
+        }
+        None => false,
+    };
+    state.registry.reset_in_flight(Some(session_id)).await;
+    state
+        .spans
+        .abandon_session(Some(session_id), "Session child restarted");
+    Ok(moved)
+}
+
+// Sends messages back to the session whose id prefix they carry and other child output to
+// every session. Output of a session's dedicated child (`origin`) never reaches other
+// sessions. Resource updates from the shared child go to the sessions subscribed to that
+// resource.
+async fn spawn_output_router(child: Arc<StdioChild>, origin: Option<String>, state: AppState) {
+    let mut rx = child.subscribe().await;
+    drop(child);
     tokio::spawn(async move {
-        loop {
-            let msg = match rx.recv().await {
-                Ok(msg) => msg,
-                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
-                    tracing::warn!(
-                        "stdio->sse child output receiver lagged by {skipped} messages; continuing"
-                    );
-                    continue;
-                }
-                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        while let Some(msg) = rx.recv().await {
+            let route = match origin {
+                Some(_) if state.subscriptions.owns(&msg) => ChildRoute::Drop,
+                Some(_) => ChildRoute::Everyone,
+                None => state.subscriptions.on_child_message(&msg).await,
             };
+            if route == ChildRoute::Drop {
+                continue;
+            }
 
-            let json = match serde_json::to_string(&msg) {
-                Ok(val) => val,
-                Err(_) => continue,
+            let mut target_id = origin.clone();
+            let mut outgoing = msg.clone();
+            if let Some((session_id, raw_id)) = strip_prefixed_id(&msg) {
+                if origin.is_none() {
+                    target_id = Some(session_id);
+                }
+                if let Some(obj) = outgoing.as_object_mut() {
+                    obj.insert("id".to_string(), raw_id);
+                }
+            }
+
+            let Ok(json) = serde_json::to_string(&outgoing) else {
+                continue;
             };
             let event = Event::default().data(json);
 
             let senders: Vec<(String, mpsc::Sender<Event>)> = {
-                let sessions_guard = sessions.lock().await;
-                sessions_guard
-                    .iter()
-                    .map(|(id, sender)| (id.clone(), sender.clone()))
-                    .collect()
+                let sessions_guard = state.sessions.lock().await;
+                match &target_id {
+                    Some(target) => sessions_guard
+                        .get(target)
+                        .map(|sender| vec![(target.clone(), sender.clone())])
+                        .unwrap_or_default(),
+                    None => sessions_guard
+                        .iter()
+                        .filter(|(id, _)| match &route {
+                            ChildRoute::Sessions(subscribed) => subscribed.contains(*id),
+                            _ => true,
+                        })
+                        .map(|(id, sender)| (id.clone(), sender.clone()))
+                        .collect(),
+                }
             };
+            if origin.is_some() && senders.is_empty() {
+                break;
+            }
 
             let mut dead = Vec::new();
             for (id, sender) in senders {
-                if sender.send(event.clone()).await.is_err() {
-                    dead.push(id);
+                state.spans.finish(Some(&id), &outgoing);
+                state.registry.record_response(&id, &outgoing).await;
+                traffic::record(
+                    TrafficDirection::ServerToClient,
+                    "sse",
+                    Some(&id),
+                    &outgoing,
+                );
+                match state
+                    .queues
+                    .deliver(&id, &sender, event.clone(), &msg)
+                    .await
+                {
+                    Delivery::Delivered | Delivery::Dropped => {}
+                    Delivery::Closed => dead.push(id),
+                    Delivery::Disconnect => {
+                        let notice = Event::default()
+                            .event("error")
+                            .data("Session outbound queue is full");
+                        send_disconnect_notice(sender, notice);
+                        dead.push(id);
+                    }
                 }
             }
 
             if !dead.is_empty() {
-                let mut sessions_guard = sessions.lock().await;
+                let mut sessions_guard = state.sessions.lock().await;
                 for id in dead {
                     sessions_guard.remove(&id);
                 }
             }
         }
     });
+}
 
-    let addr: std::net::SocketAddr = ([0, 0, 0, 0], config.port).into();
-    tracing::info!("Listening on port {}", config.port);
-    tracing::info!(
-        "SSE endpoint: http://localhost:{}{}",
-        config.port,
-        config.sse_path
-    );
-    tracing::info!(
-        "POST messages: http://localhost:{}{}",
-        config.port,
-        config.message_path
-    );
+// Cleans up a session once its SSE stream is dropped, whether the client went away or the
+// session was disconnected by the backpressure policy.
+struct SessionGuard {
+    state: AppState,
+    session_id: String,
+}
 
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .map_err(|err| err.to_string())?;
-    axum::serve(listener, router.into_make_service())
-        .await
-        .map_err(|err| err.to_string())
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        let state = self.state.clone();
+        let session_id = std::mem::take(&mut self.session_id);
+        tokio::spawn(async move {
+            state.sessions.lock().await.remove(&session_id);
+            state.queues.unregister(&session_id).await;
+            state.registry.unregister(&session_id).await;
+            state
+                .spans
+                .abandon_session(Some(&session_id), "Session closed");
+            state.session_children.release(&session_id).await;
+            state.subscriptions.release(&session_id, &state.child).await;
+        });
+    }
 }
 
 async fn sse_handler(State(state): State<AppState>) -> Response {
@@ -205,21 +436,35 @@ async fn sse_handler(State(state): State<AppState>) -> Response {
         .send(Event::default().event("endpoint").data(endpoint))
         .await;
 
-    let stream = ReceiverStream::new(rx).map(Ok::<Event, std::convert::Infallible>);
+    state.queues.register(&session_id, "sse", &tx).await;
+    state
+        .registry
+        .register(&session_id, "sse", Some(state.child.clone()))
+        .await;
+
+    let guard = SessionGuard {
+        state: state.clone(),
+        session_id: session_id.clone(),
+    };
+    let stream = ReceiverStream::new(rx).map(move |event| {
+        let _ = &guard;
+        Ok::<Event, std::convert::Infallible>(event)
+    });
     let sse = Sse::new(stream).keep_alive(axum::response::sse::KeepAlive::default());
     let mut response = sse.into_response();
-    apply_headers(&state, &mut response).await;
+    apply_headers(&state, Some(&session_id), &mut response).await;
     response
 }
 
 async fn message_handler(
     State(state): State<AppState>,
     Query(query): Query<MessageQuery>,
-    Json(payload): Json<serde_json::Value>,
+    headers: HeaderMap,
+    Json(mut payload): Json<serde_json::Value>,
 ) -> impl IntoResponse {
     if query.session_id.is_empty() {
         let mut response = (StatusCode::BAD_REQUEST, "Missing sessionId parameter").into_response();
-        apply_headers(&state, &mut response).await;
+        apply_headers(&state, None, &mut response).await;
         return response;
     }
 
@@ -240,29 +485,85 @@ async fn message_handler(
             format!("No active SSE connection for session {}", query.session_id),
         )
             .into_response();
-        apply_headers(&state, &mut response).await;
+        apply_headers(&state, None, &mut response).await;
         return response;
     }
 
-    if state.child.send(&payload).await.is_err() {
+    state
+        .registry
+        .record_request(&query.session_id, &payload)
+        .await;
+    traffic::record(
+        TrafficDirection::ClientToServer,
+        "sse",
+        Some(&query.session_id),
+        &payload,
+    );
+    if let Some((trace, _)) = begin_request(
+        SpanRole::Server,
+        "sse",
+        &mut payload,
+        Some(&query.session_id),
+        carrier_from_headers(&headers),
+    ) {
+        if let Some(id) = payload.get("id") {
+            state.spans.track(Some(&query.session_id), id, trace);
+        }
+    }
+    // Sessions share the child, so request ids carry the session to route the response back.
+    let request_id = payload.get("id").cloned();
+    if payload.get("method").is_some() {
+        if let Some(id) = &request_id {
+            let prefixed = prefix_id(&query.session_id, id);
+            if let Some(obj) = payload.as_object_mut() {
+                obj.insert("id".to_string(), prefixed);
+            }
+        }
+    }
+
+    let dedicated = state.session_children.get(&query.session_id).await;
+    if dedicated.is_none() {
+        state
+            .session_children
+            .record_initialize(&query.session_id, &payload)
+            .await;
+        if state
+            .subscriptions
+            .on_client_message(&query.session_id, &payload, &state.child)
+            .await
+            == ClientRoute::Handled
+        {
+            let mut response = StatusCode::OK.into_response();
+            apply_headers(&state, Some(&query.session_id), &mut response).await;
+            return response;
+        }
+    }
+    let child = dedicated.unwrap_or_else(|| state.child.clone());
+    if child.send(&payload).await.is_err() {
+        if let Some(id) = &request_id {
+            state
+                .spans
+                .abandon(Some(&query.session_id), id, "Failed to write to child");
+            state.registry.abandon(&query.session_id, id).await;
+        }
         let mut response = (StatusCode::BAD_GATEWAY, "Failed to write to child").into_response();
-        apply_headers(&state, &mut response).await;
+        apply_headers(&state, Some(&query.session_id), &mut response).await;
         return response;
     }
 
     let mut response = StatusCode::OK.into_response();
-    apply_headers(&state, &mut response).await;
+    apply_headers(&state, Some(&query.session_id), &mut response).await;
     response
 }
 
 async fn health_handler(state: AppState) -> impl IntoResponse {
     let mut response = (StatusCode::OK, "ok").into_response();
-    apply_headers(&state, &mut response).await;
+    apply_headers(&state, None, &mut response).await;
     response
 }
 
-async fn apply_headers(state: &AppState, response: &mut Response) {
-    let runtime = state.runtime.get_effective(None).await;
+async fn apply_headers(state: &AppState, session_id: Option<&str>, response: &mut Response) {
+    let runtime = state.runtime.get_effective(session_id).await;
     let headers = merge_headers(&state.base_headers, &runtime.headers);
     let header_map = response.headers_mut();
     for (key, value) in headers.iter() {
@@ -298,3 +599,24 @@ fn merge_headers(
     }
     merged
 }
+
+fn prefix_id(client_id: &str, id: &serde_json::Value) -> serde_json::Value {
+    match id {
+        serde_json::Value::String(s) => serde_json::Value::String(format!("{client_id}:{s}")),
+        serde_json::Value::Number(n) => serde_json::Value::String(format!("{client_id}:{n}")),
+        _ => serde_json::Value::String(format!("{client_id}:{id}")),
+    }
+}
+
+fn strip_prefixed_id(message: &serde_json::Value) -> Option<(String, serde_json::Value)> {
+    let id_val = message.get("id")?;
+    let id_str = id_val.as_str()?;
+    let mut parts = id_str.splitn(2, ':');
+    let client = parts.next()?.to_string();
+    let raw = parts.next()?.to_string();
+    let raw_id = raw
+        .parse::<i64>()
+        .map(|num| serde_json::Value::Number(num.into()))
+        .unwrap_or_else(|_| serde_json::Value::String(raw));
+    Some((client, raw_id))
+}
@@ -1,15 +1,17 @@
 use std::sync::Arc;
 
 use futures::StreamExt;
-use tokio::sync::{broadcast, mpsc};
+use tokio::sync::mpsc;
 use tokio_util::codec::{FramedRead, LinesCodec};
 
 use crate::config::Config;
 use crate::runtime::store::RuntimeArgsStore;
-use crate::runtime::{RuntimeApplyResult, RuntimeScope, RuntimeUpdateRequest};
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdateRequest};
 use crate::support::command_spec::parse_command_spec;
 use crate::support::signals::install_signal_handlers;
 use crate::support::stdio_child::StdioChild;
+use crate::support::trace_context::{begin_request, RequestSpans, SpanRole, TraceCarrier};
+use crate::support::traffic::{self, TrafficDirection};
 
 pub async fn run(
     config: Config,
@@ -23,52 +25,81 @@ pub async fn run(
 
     install_signal_handlers(None);
 
-    let spec = parse_command_spec(&stdio_cmd)?;
+    let spec = parse_command_spec(&stdio_cmd)?
+        .with_framing(config.child_framing, config.max_frame_bytes)
+        .with_launch(config.child_launch.clone())
+        .with_stderr(config.child_stderr.clone())
+        .with_replacement(config.child_replacement.clone())
+        .with_protocol_version(&config.protocol_version)
+        .with_server_name(config.server_name.as_deref());
     let child = Arc::new(StdioChild::new(spec, true));
     let initial_args = runtime.get_effective(None).await;
     child.spawn(&initial_args).await?;
 
+    let spans = RequestSpans::default();
     let runtime_child = child.clone();
     let runtime_store = runtime.clone();
+    let runtime_spans = spans.clone();
     tokio::spawn(async move {
         while let Some(req) = updates.recv().await {
-            let result = match req.update.scope {
-                RuntimeScope::Global => {
-                    let update_result = runtime_store.update_global(req.update.update).await;
-                    if update_result.restart_needed {
-                        let args = runtime_store.get_effective(None).await;
-                        if runtime_child.restart(&args).await.is_err() {
-                            RuntimeApplyResult::error("Failed to restart child")
+            let result = match req.command {
+                RuntimeCommand::Update(update) => match update.scope {
+                    RuntimeScope::Global => {
+                        let update_result = runtime_store.update_global(update.update).await;
+                        if update_result.restart_needed {
+                            let args = runtime_store.get_effective(None).await;
+                            match runtime_child.apply_runtime(&args).await {
+                                Ok(swap) => {
+                                    // A swapped child still answers its in-flight requests.
+                                    if swap.is_none() {
+                                        runtime_spans.abandon_session(None, "Child restarted");
+                                    }
+                                    RuntimeApplyResult::replaced(swap.into_iter().collect())
+                                }
+                                Err(err) => {
+                                    runtime_store.revert_global(update_result.previous).await;
+                                    RuntimeApplyResult::error(format!(
+                                        "Failed to restart child: {err}"
+                                    ))
+                                }
+                            }
+                        } else if update_result.headers_changed {
+                            RuntimeApplyResult::ok("Updated runtime headers", false)
                         } else {
-                            RuntimeApplyResult::ok("Restarted child with new runtime args", true)
+                            RuntimeApplyResult::ok("No runtime changes applied", false)
                         }
-                    } else if update_result.headers_changed {
-                        RuntimeApplyResult::ok("Updated runtime headers", false)
-                    } else {
-                        RuntimeApplyResult::ok("No runtime changes applied", false)
                     }
+                    RuntimeScope::Session(_) => RuntimeApplyResult::error(
+                        "Per-session runtime overrides are not supported for stdio→stdio",
+                    ),
+                },
+                RuntimeCommand::RestartChild => {
+                    let args = runtime_store.get_effective(None).await;
+                    match runtime_child.restart(&args).await {
+                        Ok(()) => {
+                            runtime_spans.abandon_session(None, "Child restarted");
+                            RuntimeApplyResult::ok("Restarted child", true)
+                        }
+                        Err(err) => {
+                            RuntimeApplyResult::error(format!("Failed to restart child: {err}"))
+                        }
+                    }
+                }
+                RuntimeCommand::RestartSession(_) | RuntimeCommand::EvictSession(_) => {
+                    RuntimeApplyResult::error("stdio→stdio does not track gateway sessions")
                 }
-                RuntimeScope::Session(_) => RuntimeApplyResult::error(
-                    "Per-session runtime overrides are not supported for stdio→stdio",
-                ),
             };
             let _ = req.respond_to.send(result);
         }
     });
 
-    let outbound_child = child.clone();
+    let outbound_spans = spans.clone();
+    let mut rx = child.subscribe().await;
     tokio::spawn(async move {
-        let mut rx = outbound_child.subscribe();
-        loop {
-            match rx.recv().await {
-                Ok(message) => println!("{}", message),
-                Err(broadcast::error::RecvError::Lagged(skipped)) => {
-                    tracing::warn!(
-                        "stdio->stdio child output receiver lagged by {skipped} messages; continuing"
-                    );
-                }
-                Err(broadcast::error::RecvError::Closed) => break,
-            }
+        while let Some(message) = rx.recv().await {
+            outbound_spans.finish(None, &message);
+            traffic::record(TrafficDirection::ServerToClient, "stdio", None, &message);
+            println!("{}", message);
         }
     });
 
@@ -79,11 +110,28 @@ pub async fn run(
             continue;
         }
 
-        let Ok(message_json) = serde_json::from_str::<serde_json::Value>(&line) else {
+        let Ok(mut message_json) = serde_json::from_str::<serde_json::Value>(&line) else {
             tracing::error!("Invalid JSON from stdin: {line}");
             continue;
         };
 
+        traffic::record(
+            TrafficDirection::ClientToServer,
+            "stdio",
+            None,
+            &message_json,
+        );
+        if let Some((trace, _)) = begin_request(
+            SpanRole::Server,
+            "stdio",
+            &mut message_json,
+            None,
+            TraceCarrier::new(),
+        ) {
+            if let Some(id) = message_json.get("id") {
+                spans.track(None, id, trace);
+            }
+        }
         child.send(&message_json).await?;
     }
 
@@ -12,16 +12,24 @@ use axum::{Json, Router};
 use futures::StreamExt;
 use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
 use tokio_stream::wrappers::BroadcastStream;
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, Span};
 use uuid::Uuid;
 
 use crate::config::Config;
 use crate::runtime::store::RuntimeArgsStore;
-use crate::runtime::{RuntimeApplyResult, RuntimeScope, RuntimeUpdateRequest};
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdateRequest};
+use crate::support::child_framing::{write_frame, ChildFrame, ChildFrameCodec};
+use crate::support::child_stderr;
 use crate::support::command_spec::parse_command_spec;
 use crate::support::cors::build_cors_layer;
+use crate::support::redact::redacted_json;
 use crate::support::session_access_counter::SessionAccessCounter;
+use crate::support::session_registry::SessionRegistry;
 use crate::support::signals::install_signal_handlers;
 use crate::support::stdio_child::{CommandSpec, StdioChild};
+use crate::support::trace_context::{begin_request, carrier_from_headers, SpanRole};
+use crate::support::traffic::{self, TrafficDirection};
 use crate::types::RuntimeArgs;
 
 const SESSION_NOTIFICATION_BUFFER: usize = 256;
@@ -34,7 +42,7 @@ struct AppState {
     base_headers: HeaderMap,
     manager: Arc<SessionManager>,
     protocol_version: String,
-    stdio_cmd: String,
+    spec: CommandSpec,
 }
 
 #[derive(Clone)]
@@ -83,13 +91,11 @@ pub async fn run(
     config: Config,
     runtime: RuntimeArgsStore,
     mut updates: mpsc::Receiver<RuntimeUpdateRequest>,
+    registry: SessionRegistry,
 ) -> Result<(), String> {
     let stdio_cmd = config.stdio.clone().ok_or("stdio command is required")?;
 
-    tracing::info!(
-        "  - Headers: {}",
-        serde_json::to_string(&config.headers).unwrap_or_else(|_| "(none)".into())
-    );
+    tracing::info!("  - Headers: {}", redacted_json(&config.headers));
     tracing::info!("  - port: {}", config.port);
     tracing::info!("  - stdio: {}", stdio_cmd);
     tracing::info!("  - streamable-http-path: {}", config.streamable_http_path);
@@ -103,10 +109,15 @@ pub async fn run(
         );
     }
 
-    let spec = parse_command_spec(&stdio_cmd)?;
+    let spec = parse_command_spec(&stdio_cmd)?
+        .with_framing(config.child_framing, config.max_frame_bytes)
+        .with_launch(config.child_launch.clone())
+        .with_stderr(config.child_stderr.clone())
+        .with_server_name(config.server_name.as_deref());
     let manager = Arc::new(SessionManager::new(
-        spec,
+        spec.clone(),
         runtime.clone(),
+        registry,
         config.session_timeout,
     ));
 
@@ -115,7 +126,7 @@ pub async fn run(
         base_headers: header_map_from(&config.headers),
         manager: manager.clone(),
         protocol_version: config.protocol_version.clone(),
-        stdio_cmd,
+        spec,
     };
 
     let runtime_store = runtime.clone();
@@ -123,48 +134,75 @@ pub async fn run(
     let stateful = config.stateful;
     tokio::spawn(async move {
         while let Some(req) = updates.recv().await {
-            let result = match req.update.scope {
-                RuntimeScope::Global => {
-                    let update_result = runtime_store.update_global(req.update.update).await;
-                    if update_result.restart_needed {
-                        if stateful {
-                            manager_clone.restart_all().await;
-                            RuntimeApplyResult::ok(
-                                "Restarted all sessions with new runtime args",
-                                true,
-                            )
-                        } else {
-                            RuntimeApplyResult::ok(
-                                "Updated runtime args for future requests",
-                                false,
-                            )
-                        }
-                    } else {
-                        RuntimeApplyResult::ok("Updated runtime args", false)
-                    }
-                }
-                RuntimeScope::Session(session_id) => {
-                    if !stateful {
-                        RuntimeApplyResult::error(
-                            "Per-session overrides require stateful Streamable HTTP",
-                        )
-                    } else {
-                        let update_result = runtime_store
-                            .update_session(&session_id, req.update.update)
-                            .await;
+            let result = match req.command {
+                RuntimeCommand::Update(update) => match update.scope {
+                    RuntimeScope::Global => {
+                        let update_result = runtime_store.update_global(update.update).await;
                         if update_result.restart_needed {
-                            if manager_clone.restart_session(&session_id).await.is_err() {
-                                RuntimeApplyResult::error("Failed to restart session")
-                            } else {
+                            if stateful {
+                                manager_clone.restart_all().await;
                                 RuntimeApplyResult::ok(
-                                    "Restarted session with new runtime args",
+                                    "Restarted all sessions with new runtime args",
                                     true,
                                 )
+                            } else {
+                                RuntimeApplyResult::ok(
+                                    "Updated runtime args for future requests",
+                                    false,
+                                )
                             }
                         } else {
-                            RuntimeApplyResult::ok("Updated session runtime args", false)
+                            RuntimeApplyResult::ok("Updated runtime args", false)
                         }
                     }
+                    RuntimeScope::Session(session_id) => {
+                        if !stateful {
+                            RuntimeApplyResult::error(
+                                "Per-session overrides require stateful Streamable HTTP",
+                            )
+                        } else {
+                            let update_result = runtime_store
+                                .update_session(&session_id, update.update)
+                                .await;
+                            if update_result.restart_needed {
+                                if manager_clone.restart_session(&session_id).await.is_err() {
+                                    RuntimeApplyResult::error("Failed to restart session")
+                                } else {
+                                    RuntimeApplyResult::ok(
+                                        "Restarted session with new runtime args",
+                                        true,
+                                    )
+                                }
+                            } else {
+                                RuntimeApplyResult::ok("Updated session runtime args", false)
+                            }
+                        }
+                    }
+                },
+                RuntimeCommand::RestartChild => {
+                    if stateful {
+                        manager_clone.restart_all().await;
+                        RuntimeApplyResult::ok("Restarted all session children", true)
+                    } else {
+                        RuntimeApplyResult::error("Stateless Streamable HTTP spawns a child per request; there is no child to restart")
+                    }
+                }
+                RuntimeCommand::RestartSession(session_id) => {
+                    if manager_clone.get_session(&session_id).await.is_none() {
+                        RuntimeApplyResult::error(format!("Unknown session {session_id}"))
+                    } else if let Err(err) = manager_clone.restart_session(&session_id).await {
+                        RuntimeApplyResult::error(format!("Failed to restart session child: {err}"))
+                    } else {
+                        RuntimeApplyResult::ok("Restarted session child", true)
+                    }
+                }
+                RuntimeCommand::EvictSession(session_id) => {
+                    if manager_clone.remove_session(&session_id).await {
+                        runtime_store.remove_session(&session_id).await;
+                        RuntimeApplyResult::ok("Evicted session", false)
+                    } else {
+                        RuntimeApplyResult::error(format!("Unknown session {session_id}"))
+                    }
                 }
             };
             let _ = req.respond_to.send(result);
@@ -232,12 +270,47 @@ async fn health_handler(state: AppState) -> impl IntoResponse {
 
 async fn stateless_post(
     State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
+    headers: HeaderMap,
+    Json(mut payload): Json<serde_json::Value>,
 ) -> impl IntoResponse {
     let runtime = state.runtime.get_effective(None).await;
-    match handle_stateless_request(&state.stdio_cmd, &state.protocol_version, runtime, payload)
-        .await
-    {
+    traffic::record(
+        TrafficDirection::ClientToServer,
+        "streamable-http",
+        None,
+        &payload,
+    );
+    let trace = begin_request(
+        SpanRole::Server,
+        "streamable-http",
+        &mut payload,
+        None,
+        carrier_from_headers(&headers),
+    )
+    .map(|(trace, _)| trace);
+    let span = trace
+        .as_ref()
+        .map(|trace| trace.span().clone())
+        .unwrap_or_else(Span::none);
+    let result = handle_stateless_request(&state.spec, &state.protocol_version, runtime, payload)
+        .instrument(span)
+        .await;
+    if let Some(trace) = trace {
+        match &result {
+            Ok(Some(resp)) => trace.finish(resp),
+            Ok(None) => {}
+            Err(err) => trace.fail(err),
+        }
+    }
+    if let Ok(Some(resp)) = &result {
+        traffic::record(
+            TrafficDirection::ServerToClient,
+            "streamable-http",
+            None,
+            resp,
+        );
+    }
+    match result {
         Ok(Some(resp)) => {
             let mut response = Json(resp).into_response();
             apply_headers(&state, None, &mut response).await;
@@ -278,7 +351,7 @@ async fn stateless_method_not_allowed() -> impl IntoResponse {
 async fn stateful_post(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(payload): Json<serde_json::Value>,
+    Json(mut payload): Json<serde_json::Value>,
 ) -> impl IntoResponse {
     let session_header = headers
         .get("Mcp-Session-Id")
@@ -337,8 +410,54 @@ async fn stateful_post(
             .await;
     }
 
+    state
+        .manager
+        .registry
+        .record_request(&session_id, &payload)
+        .await;
+    traffic::record(
+        TrafficDirection::ClientToServer,
+        "streamable-http",
+        Some(&session_id),
+        &payload,
+    );
+    let trace = begin_request(
+        SpanRole::Server,
+        "streamable-http",
+        &mut payload,
+        Some(&session_id),
+        carrier_from_headers(&headers),
+    )
+    .map(|(trace, _)| trace);
     let response = if let Some(id) = payload.get("id").cloned() {
-        match session.request(payload).await {
+        let span = trace
+            .as_ref()
+            .map(|trace| trace.span().clone())
+            .unwrap_or_else(Span::none);
+        let result = session.request(payload).instrument(span).await;
+        if let Some(trace) = trace {
+            match &result {
+                Ok(resp) => trace.finish(resp),
+                Err(err) => trace.fail(err),
+            }
+        }
+        match &result {
+            Ok(resp) => {
+                state
+                    .manager
+                    .registry
+                    .record_response(&session_id, resp)
+                    .await;
+                traffic::record(
+                    TrafficDirection::ServerToClient,
+                    "streamable-http",
+                    Some(&session_id),
+                    resp,
+                );
+            }
+            Err(_) => state.manager.registry.abandon(&session_id, &id).await,
+        }
+        match result {
             Ok(resp) => Json(resp).into_response(),
             Err(err) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -398,17 +517,20 @@ async fn stateful_get(State(state): State<AppState>, headers: HeaderMap) -> impl
         .await;
 
     let rx = session.notifications.subscribe();
-    let stream = BroadcastStream::new(rx).filter_map(|msg| async move {
-        match msg {
-            Ok(value) => {
-                let data = serde_json::to_string(&value).ok()?;
-                Some(Ok::<Event, std::convert::Infallible>(
-                    Event::default().data(data),
-                ))
+    let evicted = session.evicted.clone().cancelled_owned();
+    let stream = BroadcastStream::new(rx)
+        .take_until(evicted)
+        .filter_map(|msg| async move {
+            match msg {
+                Ok(value) => {
+                    let data = serde_json::to_string(&value).ok()?;
+                    Some(Ok::<Event, std::convert::Infallible>(
+                        Event::default().data(data),
+                    ))
+                }
+                Err(_) => None,
             }
-            Err(_) => None,
-        }
-    });
+        });
     let sse = Sse::new(stream).keep_alive(axum::response::sse::KeepAlive::default());
     let mut response = sse.into_response();
     response.headers_mut().insert(
@@ -473,37 +595,36 @@ struct Session {
     child: Arc<StdioChild>,
     pending: Arc<Mutex<HashMap<String, oneshot::Sender<serde_json::Value>>>>,
     notifications: broadcast::Sender<serde_json::Value>,
+    evicted: CancellationToken,
 }
 
 impl Session {
-    async fn new(id: String, spec: CommandSpec, runtime: RuntimeArgs) -> Result<Self, String> {
+    async fn new(
+        id: String,
+        spec: CommandSpec,
+        runtime: RuntimeArgs,
+        registry: &SessionRegistry,
+    ) -> Result<Self, String> {
         let child = Arc::new(StdioChild::new(spec, false));
         child.spawn(&runtime).await?;
+        let evicted = registry
+            .register(&id, "streamable-http", Some(child.clone()))
+            .await;
         let (tx, _) = broadcast::channel(SESSION_NOTIFICATION_BUFFER);
         Ok(Session {
             id,
             child,
             pending: Arc::new(Mutex::new(HashMap::new())),
             notifications: tx,
+            evicted,
         })
     }
 
     async fn start_routing(self: Arc<Self>) {
-        let mut rx = self.child.subscribe();
+        let mut rx = self.child.subscribe().await;
         let this = self.clone();
         tokio::spawn(async move {
-            loop {
-                let msg = match rx.recv().await {
-                    Ok(msg) => msg,
-                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
-                        tracing::warn!(
-                            "stdio->streamable-http child output receiver lagged by {skipped} messages; continuing"
-                        );
-                        continue;
-                    }
-                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
-                };
-
+            while let Some(msg) = rx.recv().await {
                 if let Some(id) = msg.get("id").and_then(|v| v.as_str()) {
                     let sender = {
                         let mut pending = this.pending.lock().await;
@@ -514,6 +635,12 @@ impl Session {
                         continue;
                     }
                 }
+                traffic::record(
+                    TrafficDirection::ServerToClient,
+                    "streamable-http",
+                    Some(&this.id),
+                    &msg,
+                );
                 let _ = this.notifications.send(msg);
             }
         });
@@ -588,22 +715,34 @@ impl Session {
 struct SessionManager {
     spec: CommandSpec,
     runtime: RuntimeArgsStore,
+    registry: SessionRegistry,
     sessions: Arc<Mutex<HashMap<String, Arc<Session>>>>,
     session_counter: Option<Arc<SessionAccessCounter>>,
 }
 
 impl SessionManager {
-    fn new(spec: CommandSpec, runtime: RuntimeArgsStore, session_timeout: Option<u64>) -> Self {
+    fn new(
+        spec: CommandSpec,
+        runtime: RuntimeArgsStore,
+        registry: SessionRegistry,
+        session_timeout: Option<u64>,
+    ) -> Self {
         let sessions: Arc<Mutex<HashMap<String, Arc<Session>>>> =
             Arc::new(Mutex::new(HashMap::new()));
         let session_counter = session_timeout.map(|timeout| {
             let sessions_clone = sessions.clone();
+            let registry_clone = registry.clone();
             Arc::new(SessionAccessCounter::new(
                 timeout,
                 Arc::new(move |session_id| {
-                    tracing::info!("Session {session_id} timed out, cleaning up");
+                    tracing::info!(
+                        session_id = %session_id,
+                        "Session {session_id} timed out, cleaning up"
+                    );
                     let sessions_inner = sessions_clone.clone();
+                    let registry_inner = registry_clone.clone();
                     tokio::spawn(async move {
+                        registry_inner.unregister(&session_id).await;
                         if let Some(session) = sessions_inner.lock().await.remove(&session_id) {
                             session.child.shutdown().await;
                         }
@@ -614,6 +753,7 @@ impl SessionManager {
         Self {
             spec,
             runtime,
+            registry,
             sessions,
             session_counter,
         }
@@ -622,7 +762,15 @@ impl SessionManager {
     async fn create_session(&self) -> Result<Arc<Session>, String> {
         let session_id = Uuid::new_v4().to_string();
         let runtime = self.runtime.get_effective(Some(&session_id)).await;
-        let session = Arc::new(Session::new(session_id.clone(), self.spec.clone(), runtime).await?);
+        let session = Arc::new(
+            Session::new(
+                session_id.clone(),
+                self.spec.clone(),
+                runtime,
+                &self.registry,
+            )
+            .await?,
+        );
         session.clone().start_routing().await;
         let mut sessions = self.sessions.lock().await;
         sessions.insert(session_id.clone(), session.clone());
@@ -641,6 +789,8 @@ impl SessionManager {
         let mut sessions = self.sessions.lock().await;
         let removed = sessions.remove(session_id);
         if let Some(session) = removed {
+            // Evicting also ends any open GET stream for the session.
+            self.registry.evict(session_id).await;
             session.child.shutdown().await;
             true
         } else {
@@ -665,6 +815,7 @@ impl SessionManager {
         let sessions = self.sessions.lock().await;
         if let Some(session) = sessions.get(session_id) {
             session.restart(&runtime).await?;
+            self.registry.reset_in_flight(Some(session_id)).await;
         }
         Ok(())
     }
@@ -681,44 +832,31 @@ impl SessionManager {
 }
 
 async fn handle_stateless_request(
-    stdio_cmd: &str,
+    spec: &CommandSpec,
     protocol_version: &str,
     runtime: RuntimeArgs,
     payload: serde_json::Value,
 ) -> Result<Option<serde_json::Value>, String> {
-    use tokio::io::AsyncWriteExt;
-    use tokio_util::codec::{FramedRead, LinesCodec};
+    use tokio_util::codec::FramedRead;
 
-    let spec = parse_command_spec(stdio_cmd)?;
-    let mut cmd = spec.build_command(&runtime);
+    let mut cmd = spec.build_command(&runtime).await?;
     cmd.stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped());
     let mut child = cmd.spawn().map_err(|err| err.to_string())?;
+    spec.launch.watch_memory(child.id());
     let mut stdin = child.stdin.take().ok_or("Missing child stdin")?;
     let stdout = child.stdout.take().ok_or("Missing child stdout")?;
     let stderr = child.stderr.take().ok_or("Missing child stderr")?;
 
-    tokio::spawn(async move {
-        let mut lines = FramedRead::new(stderr, LinesCodec::new());
-        while let Some(Ok(line)) = lines.next().await {
-            if !line.trim().is_empty() {
-                eprintln!("[mcpway] Child stderr: {line}");
-            }
-        }
-    });
+    // Each stateless child gets its own buffer; it is marked exited when the request ends.
+    let stderr_buffer = child_stderr::register(&spec.name, &spec.stderr);
+    stderr_buffer.set_pid(child.id());
+    child_stderr::capture(stderr, stderr_buffer.buffer(), spec.stderr.clone());
 
     let original_id = payload.get("id").map(|v| v.to_string());
     if original_id.is_none() {
-        let line = serde_json::to_string(&payload).map_err(|err| err.to_string())?;
-        stdin
-            .write_all(line.as_bytes())
-            .await
-            .map_err(|err| err.to_string())?;
-        stdin
-            .write_all(b"\n")
-            .await
-            .map_err(|err| err.to_string())?;
+        write_frame(&mut stdin, spec.framing, &payload).await?;
         let _ = child.kill().await;
         return Ok(None);
     }
@@ -738,64 +876,29 @@ async fn handle_stateless_request(
         );
         auto_init_id = Some(init_id.clone());
         let init = create_initialize_request(&init_id, protocol_version);
-        let init_line = serde_json::to_string(&init).map_err(|err| err.to_string())?;
-        stdin
-            .write_all(init_line.as_bytes())
-            .await
-            .map_err(|err| err.to_string())?;
-        stdin
-            .write_all(b"\n")
-            .await
-            .map_err(|err| err.to_string())?;
+        write_frame(&mut stdin, spec.framing, &init).await?;
         pending_original = Some(payload.clone());
     } else {
-        let line = serde_json::to_string(&payload).map_err(|err| err.to_string())?;
-        stdin
-            .write_all(line.as_bytes())
-            .await
-            .map_err(|err| err.to_string())?;
-        stdin
-            .write_all(b"\n")
-            .await
-            .map_err(|err| err.to_string())?;
+        write_frame(&mut stdin, spec.framing, &payload).await?;
     }
 
-    let mut lines = FramedRead::new(stdout, LinesCodec::new());
-    while let Some(line) = lines.next().await {
-        match line {
-            Ok(line) => {
-                if line.trim().is_empty() {
+    let mut frames = FramedRead::new(
+        stdout,
+        ChildFrameCodec::new(spec.framing, spec.max_frame_bytes),
+    );
+    while let Some(frame) = frames.next().await {
+        match frame {
+            Ok(frame) => {
+                let ChildFrame::Message(msg) = frame else {
                     continue;
-                }
-                let msg: serde_json::Value = match serde_json::from_str(&line) {
-                    Ok(val) => val,
-                    Err(_) => continue,
                 };
                 if let Some(id) = msg.get("id").map(|v| v.to_string()) {
                     if let Some(auto_id) = auto_init_id.clone() {
                         if id.trim_matches('"') == auto_id {
                             let initialized = create_initialized_notification();
-                            let init_line = serde_json::to_string(&initialized)
-                                .map_err(|err| err.to_string())?;
-                            stdin
-                                .write_all(init_line.as_bytes())
-                                .await
-                                .map_err(|err| err.to_string())?;
-                            stdin
-                                .write_all(b"\n")
-                                .await
-                                .map_err(|err| err.to_string())?;
+                            write_frame(&mut stdin, spec.framing, &initialized).await?;
                             if let Some(original) = pending_original.take() {
-                                let line = serde_json::to_string(&original)
-                                    .map_err(|err| err.to_string())?;
-                                stdin
-                                    .write_all(line.as_bytes())
-                                    .await
-                                    .map_err(|err| err.to_string())?;
-                                stdin
-                                    .write_all(b"\n")
-                                    .await
-                                    .map_err(|err| err.to_string())?;
+                                write_frame(&mut stdin, spec.framing, &original).await?;
                             }
                             auto_init_id = None;
                             continue;
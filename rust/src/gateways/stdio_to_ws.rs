@@ -3,171 +3,536 @@ use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc,
 };
+use std::time::Duration;
 
-use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
-use axum::extract::State;
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
 use axum::http::{HeaderMap, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::routing::get;
 use axum::Router;
 use futures::{SinkExt, StreamExt};
 use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-use crate::config::Config;
+use crate::config::{Config, WsIsolation};
 use crate::runtime::store::RuntimeArgsStore;
-use crate::runtime::{RuntimeApplyResult, RuntimeScope, RuntimeUpdateRequest};
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdateRequest};
+use crate::support::child_swap::SwapReport;
 use crate::support::command_spec::parse_command_spec;
 use crate::support::cors::build_cors_layer;
+use crate::support::resource_subscriptions::{ChildRoute, ClientRoute, ResourceSubscriptions};
+use crate::support::session_children::SessionChildren;
+use crate::support::session_queues::{Delivery, SessionQueues};
+use crate::support::session_registry::SessionRegistry;
 use crate::support::signals::install_signal_handlers;
-use crate::support::stdio_child::StdioChild;
+use crate::support::stdio_child::{CommandSpec, StdioChild};
+use crate::support::trace_context::{
+    begin_request, carrier_from_headers, RequestSpans, SpanRole, TraceCarrier,
+};
+use crate::support::traffic::{self, TrafficDirection};
+use crate::types::RuntimeArgs;
 
 const WS_CLIENT_BUFFER: usize = 256;
+// Response headers only go out with the upgrade response, which an open session has already had.
+const SESSION_HEADERS_UNSUPPORTED: &str =
+    "Session headers cannot change on an open WebSocket; update the global headers instead";
 
 #[derive(Clone)]
 struct AppState {
-    clients: Arc<Mutex<HashMap<String, mpsc::Sender<serde_json::Value>>>>,
-    child: Arc<StdioChild>,
+    clients: ClientMap,
+    backend: Backend,
     runtime: RuntimeArgsStore,
+    queues: SessionQueues,
+    registry: SessionRegistry,
     base_headers: HeaderMap,
     ready: Arc<AtomicBool>,
+    spans: RequestSpans,
+    subscriptions: ResourceSubscriptions,
+}
+
+type ClientMap = Arc<Mutex<HashMap<String, mpsc::Sender<serde_json::Value>>>>;
+
+#[derive(Clone)]
+enum Backend {
+    Shared {
+        child: Arc<StdioChild>,
+        session_children: SessionChildren,
+    },
+    PerConnection(Arc<ConnectionChildren>),
+}
+
+#[derive(serde::Deserialize)]
+struct ConnectQuery {
+    #[serde(rename = "sessionId")]
+    session_id: Option<String>,
 }
 
 pub async fn run(
     config: Config,
     runtime: RuntimeArgsStore,
     mut updates: mpsc::Receiver<RuntimeUpdateRequest>,
+    queues: SessionQueues,
+    registry: SessionRegistry,
 ) -> Result<(), String> {
     let stdio_cmd = config.stdio.clone().ok_or("stdio command is required")?;
 
     tracing::info!("  - port: {}", config.port);
     tracing::info!("  - stdio: {}", stdio_cmd);
     tracing::info!("  - messagePath: {}", config.message_path);
+    tracing::info!(
+        "  - isolation: {}",
+        match config.ws_isolation {
+            WsIsolation::Shared => "shared",
+            WsIsolation::PerConnection => "per-connection",
+        }
+    );
 
-    let spec = parse_command_spec(&stdio_cmd)?;
+    let spec = parse_command_spec(&stdio_cmd)?
+        .with_framing(config.child_framing, config.max_frame_bytes)
+        .with_launch(config.child_launch.clone())
+        .with_stderr(config.child_stderr.clone())
+        .with_replacement(config.child_replacement.clone())
+        .with_protocol_version(&config.protocol_version)
+        .with_managed_initialize(
+            config.managed_initialize && config.ws_isolation == WsIsolation::Shared,
+        )
+        .with_server_name(config.server_name.as_deref());
+    if config.ws_isolation == WsIsolation::PerConnection {
+        let children = Arc::new(ConnectionChildren::new(
+            spec,
+            runtime.clone(),
+            registry.clone(),
+            config.ws_idle_grace_ms.map(Duration::from_millis),
+        ));
+        return run_per_connection(config, runtime, updates, queues, registry, children).await;
+    }
+    let session_children = SessionChildren::new(spec.clone(), runtime.clone());
     let child = Arc::new(StdioChild::new(spec, true));
     let initial_args = runtime.get_effective(None).await;
     child.spawn(&initial_args).await?;
 
-    let clients: Arc<Mutex<HashMap<String, mpsc::Sender<serde_json::Value>>>> =
-        Arc::new(Mutex::new(HashMap::new()));
+    let clients: ClientMap = Arc::new(Mutex::new(HashMap::new()));
 
     let ready = Arc::new(AtomicBool::new(false));
+    let spans = RequestSpans::default();
     let state = AppState {
         clients: clients.clone(),
-        child: child.clone(),
+        backend: Backend::Shared {
+            child: child.clone(),
+            session_children: session_children.clone(),
+        },
         runtime: runtime.clone(),
+        queues: queues.clone(),
+        registry,
         base_headers: header_map_from(&config.headers),
         ready: ready.clone(),
+        spans: spans.clone(),
+        subscriptions: ResourceSubscriptions::default(),
     };
 
     let runtime_child = child.clone();
     let runtime_store = runtime.clone();
+    let runtime_state = state.clone();
     tokio::spawn(async move {
         while let Some(req) = updates.recv().await {
-            let result = match req.update.scope {
-                RuntimeScope::Global => {
-                    let update_result = runtime_store.update_global(req.update.update).await;
-                    if update_result.restart_needed {
-                        let args = runtime_store.get_effective(None).await;
-                        if runtime_child.restart(&args).await.is_err() {
-                            RuntimeApplyResult::error("Failed to restart child")
+            let result = match req.command {
+                RuntimeCommand::Update(update) => match update.scope {
+                    RuntimeScope::Global => {
+                        let update_result = runtime_store.update_global(update.update).await;
+                        if update_result.restart_needed {
+                            let args = runtime_store.get_effective(None).await;
+                            match replace_shared_children(
+                                &runtime_state,
+                                &runtime_child,
+                                &session_children,
+                                &args,
+                            )
+                            .await
+                            {
+                                Ok(swaps) => RuntimeApplyResult::replaced(swaps),
+                                Err(err) => {
+                                    runtime_store.revert_global(update_result.previous).await;
+                                    RuntimeApplyResult::error(format!(
+                                        "Failed to restart child: {err}"
+                                    ))
+                                }
+                            }
                         } else {
-                            RuntimeApplyResult::ok("Restarted child with new runtime args", true)
+                            RuntimeApplyResult::ok("Updated runtime args", false)
                         }
+                    }
+                    RuntimeScope::Session(session_id) => {
+                        if !runtime_state.clients.lock().await.contains_key(&session_id) {
+                            RuntimeApplyResult::error(format!("Unknown session {session_id}"))
+                        } else if update.update.changes_headers() {
+                            RuntimeApplyResult::error(SESSION_HEADERS_UNSUPPORTED)
+                        } else {
+                            let update_result = runtime_store
+                                .update_session(&session_id, update.update)
+                                .await;
+                            if update_result.restart_needed {
+                                match restart_session_child(
+                                    &runtime_state,
+                                    &runtime_child,
+                                    &session_children,
+                                    &session_id,
+                                )
+                                .await
+                                {
+                                    Ok(true) => RuntimeApplyResult::ok(
+                                        "Moved session onto a dedicated child",
+                                        true,
+                                    ),
+                                    Ok(false) => RuntimeApplyResult::ok(
+                                        "Restarted session child with new runtime args",
+                                        true,
+                                    ),
+                                    Err(err) => RuntimeApplyResult::error(format!(
+                                        "Failed to start session child: {err}"
+                                    )),
+                                }
+                            } else {
+                                RuntimeApplyResult::ok("Updated session runtime args", false)
+                            }
+                        }
+                    }
+                },
+                RuntimeCommand::RestartChild => {
+                    let args = runtime_store.get_effective(None).await;
+                    match restart_shared_children(
+                        &runtime_state,
+                        &runtime_child,
+                        &session_children,
+                        &args,
+                    )
+                    .await
+                    {
+                        Ok(()) => RuntimeApplyResult::ok("Restarted child", true),
+                        Err(err) => {
+                            RuntimeApplyResult::error(format!("Failed to restart child: {err}"))
+                        }
+                    }
+                }
+                RuntimeCommand::RestartSession(session_id) => {
+                    if !runtime_state.clients.lock().await.contains_key(&session_id) {
+                        RuntimeApplyResult::error(format!("Unknown session {session_id}"))
                     } else {
-                        RuntimeApplyResult::ok("Updated runtime args", false)
+                        match restart_session_child(
+                            &runtime_state,
+                            &runtime_child,
+                            &session_children,
+                            &session_id,
+                        )
+                        .await
+                        {
+                            Ok(true) => RuntimeApplyResult::ok(
+                                "Moved session onto a fresh dedicated child",
+                                true,
+                            ),
+                            Ok(false) => RuntimeApplyResult::ok("Restarted session child", true),
+                            Err(err) => RuntimeApplyResult::error(format!(
+                                "Failed to restart session child: {err}"
+                            )),
+                        }
+                    }
+                }
+                RuntimeCommand::EvictSession(session_id) => {
+                    // The socket closes itself once its eviction token fires; its cleanup releases the rest.
+                    if runtime_state.registry.evict(&session_id).await {
+                        RuntimeApplyResult::ok("Evicted session", false)
+                    } else {
+                        RuntimeApplyResult::error(format!("Unknown session {session_id}"))
                     }
                 }
-                RuntimeScope::Session(_) => RuntimeApplyResult::error(
-                    "Per-session runtime overrides are not supported for stdio→WS",
-                ),
             };
             let _ = req.respond_to.send(result);
         }
     });
 
-    let mut router = Router::new()
-        .route(&config.message_path, get(ws_handler))
-        .with_state(state.clone());
+    let router = build_router(&config, state.clone());
 
-    for ep in &config.health_endpoints {
-        let state = state.clone();
-        let path = ep.clone();
-        router = router.route(
-            &path,
-            get(move || async move { health_handler(state.clone()).await }),
-        );
-    }
+    install_signal_handlers(None);
 
-    if let Some(cors) = build_cors_layer(&config.cors) {
-        router = router.layer(cors);
-    }
+    spawn_output_router(child, None, state).await;
 
-    install_signal_handlers(None);
+    ready.store(true, Ordering::SeqCst);
 
-    let mut rx = child.subscribe();
+    serve(&config, router).await
+}
+
+async fn run_per_connection(
+    config: Config,
+    runtime: RuntimeArgsStore,
+    mut updates: mpsc::Receiver<RuntimeUpdateRequest>,
+    queues: SessionQueues,
+    registry: SessionRegistry,
+    children: Arc<ConnectionChildren>,
+) -> Result<(), String> {
+    let state = AppState {
+        clients: Arc::new(Mutex::new(HashMap::new())),
+        backend: Backend::PerConnection(children.clone()),
+        runtime: runtime.clone(),
+        queues: queues.clone(),
+        registry: registry.clone(),
+        base_headers: header_map_from(&config.headers),
+        ready: Arc::new(AtomicBool::new(true)),
+        spans: RequestSpans::default(),
+        subscriptions: ResourceSubscriptions::default(),
+    };
+
+    let spans = state.spans.clone();
     tokio::spawn(async move {
-        loop {
-            let msg = match rx.recv().await {
-                Ok(msg) => msg,
-                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
-                    tracing::warn!(
-                        "stdio->ws child output receiver lagged by {skipped} messages; continuing"
-                    );
-                    continue;
+        while let Some(req) = updates.recv().await {
+            let result = match req.command {
+                RuntimeCommand::Update(update) => match update.scope {
+                    RuntimeScope::Global => {
+                        let update_result = runtime.update_global(update.update).await;
+                        if update_result.restart_needed {
+                            match children.apply_runtime_all().await {
+                                Ok(swaps) => {
+                                    // Swapped children still answer their in-flight requests.
+                                    if swaps.is_empty() {
+                                        registry.reset_in_flight(None).await;
+                                        spans.abandon_session(None, "Child restarted");
+                                    }
+                                    RuntimeApplyResult::replaced(swaps)
+                                }
+                                Err(err) => {
+                                    runtime.revert_global(update_result.previous).await;
+                                    RuntimeApplyResult::error(format!(
+                                        "Failed to restart connection children: {err}"
+                                    ))
+                                }
+                            }
+                        } else {
+                            RuntimeApplyResult::ok("Updated runtime args", false)
+                        }
+                    }
+                    RuntimeScope::Session(session_id) => {
+                        if !registry.contains(&session_id).await {
+                            RuntimeApplyResult::error(format!("Unknown session {session_id}"))
+                        } else if update.update.changes_headers() {
+                            RuntimeApplyResult::error(SESSION_HEADERS_UNSUPPORTED)
+                        } else {
+                            let update_result =
+                                runtime.update_session(&session_id, update.update).await;
+                            if !update_result.restart_needed {
+                                RuntimeApplyResult::ok("Updated session runtime args", false)
+                            } else if let Err(err) = children.restart(&session_id).await {
+                                RuntimeApplyResult::error(format!(
+                                    "Failed to restart session: {err}"
+                                ))
+                            } else {
+                                registry.reset_in_flight(Some(&session_id)).await;
+                                spans.abandon_session(Some(&session_id), "Child restarted");
+                                RuntimeApplyResult::ok(
+                                    "Restarted session with new runtime args",
+                                    true,
+                                )
+                            }
+                        }
+                    }
+                },
+                RuntimeCommand::RestartChild => match children.restart_all().await {
+                    Ok(()) => {
+                        registry.reset_in_flight(None).await;
+                        spans.abandon_session(None, "Child restarted");
+                        RuntimeApplyResult::ok("Restarted all connection children", true)
+                    }
+                    Err(err) => RuntimeApplyResult::error(format!(
+                        "Failed to restart connection children: {err}"
+                    )),
+                },
+                RuntimeCommand::RestartSession(session_id) => {
+                    if !registry.contains(&session_id).await {
+                        RuntimeApplyResult::error(format!("Unknown session {session_id}"))
+                    } else if let Err(err) = children.restart(&session_id).await {
+                        RuntimeApplyResult::error(format!("Failed to restart session child: {err}"))
+                    } else {
+                        registry.reset_in_flight(Some(&session_id)).await;
+                        spans.abandon_session(Some(&session_id), "Child restarted");
+                        RuntimeApplyResult::ok("Restarted session child", true)
+                    }
+                }
+                RuntimeCommand::EvictSession(session_id) => {
+                    if registry.evict(&session_id).await {
+                        children.remove(&session_id).await;
+                        RuntimeApplyResult::ok("Evicted session", false)
+                    } else {
+                        RuntimeApplyResult::error(format!("Unknown session {session_id}"))
+                    }
                 }
-                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
             };
+            let _ = req.respond_to.send(result);
+        }
+    });
+
+    let router = build_router(&config, state);
+    install_signal_handlers(None);
+    serve(&config, router).await
+}
+
+// Moves the shared children onto new runtime args with the configured restart strategy.
+// Children swapped blue/green still answer their in-flight requests, so those are only
+// cleared after a plain restart.
+async fn replace_shared_children(
+    state: &AppState,
+    child: &StdioChild,
+    session_children: &SessionChildren,
+    args: &RuntimeArgs,
+) -> Result<Vec<SwapReport>, String> {
+    let mut swaps: Vec<SwapReport> = child.apply_runtime(args).await?.into_iter().collect();
+    swaps.extend(session_children.apply_runtime_all().await?);
+    if swaps.is_empty() {
+        state.registry.reset_in_flight(None).await;
+        state.spans.abandon_session(None, "Child restarted");
+    }
+    state.subscriptions.resubscribe(child).await;
+    Ok(swaps)
+}
 
-            let mut target_id: Option<String> = None;
+async fn restart_shared_children(
+    state: &AppState,
+    child: &StdioChild,
+    session_children: &SessionChildren,
+    args: &RuntimeArgs,
+) -> Result<(), String> {
+    child.restart(args).await?;
+    session_children.restart_all().await?;
+    state.registry.reset_in_flight(None).await;
+    state.spans.abandon_session(None, "Child restarted");
+    state.subscriptions.resubscribe(child).await;
+    Ok(())
+}
+
+// Gives the session a fresh child of its own; returns true when it was newly moved off the
+// shared child.
+async fn restart_session_child(
+    state: &AppState,
+    child: &StdioChild,
+    session_children: &SessionChildren,
+    session_id: &str,
+) -> Result<bool, String> {
+    let moved = match session_children.isolate(session_id).await? {
+        Some(dedicated) => {
+            state
+                .registry
+                .set_child(session_id, dedicated.clone())
+                .await;
+            state
+                .subscriptions
+                .move_session(session_id, child, &dedicated)
+                .await;
+            spawn_output_router(dedicated, Some(session_id.to_string()), state.clone()).await;
+            true
+        }
+        None => false,
+    };
+    state.registry.reset_in_flight(Some(session_id)).await;
+    state
+        .spans
+        .abandon_session(Some(session_id), "Session child restarted");
+    Ok(moved)
+}
+
+// Routes child output back to clients by their id prefix. Output of a session's dedicated
+// child (`origin`) never reaches other clients. Resource updates from the shared child go
+// to the sessions subscribed to that resource.
+async fn spawn_output_router(child: Arc<StdioChild>, origin: Option<String>, state: AppState) {
+    let mut rx = child.subscribe().await;
+    drop(child);
+    tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            let route = match origin {
+                Some(_) if state.subscriptions.owns(&msg) => ChildRoute::Drop,
+                Some(_) => ChildRoute::Everyone,
+                None => state.subscriptions.on_child_message(&msg).await,
+            };
+            if route == ChildRoute::Drop {
+                continue;
+            }
+
+            let mut target_id = origin.clone();
             let mut outgoing = msg.clone();
             if let Some((client_id, raw_id)) = strip_prefixed_id(&msg) {
-                target_id = Some(client_id);
+                if origin.is_none() {
+                    target_id = Some(client_id);
+                }
                 if let Some(obj) = outgoing.as_object_mut() {
                     obj.insert("id".to_string(), raw_id);
                 }
             }
 
-            if let Some(target) = target_id {
-                let sender = {
-                    let clients_guard = clients.lock().await;
-                    clients_guard.get(&target).cloned()
-                };
-                if let Some(sender) = sender {
-                    if sender.send(outgoing).await.is_err() {
-                        let mut clients_guard = clients.lock().await;
-                        clients_guard.remove(&target);
-                    }
-                }
-                continue;
-            }
-
             let senders: Vec<(String, mpsc::Sender<serde_json::Value>)> = {
-                let clients_guard = clients.lock().await;
-                clients_guard
-                    .iter()
-                    .map(|(id, sender)| (id.clone(), sender.clone()))
-                    .collect()
+                let clients_guard = state.clients.lock().await;
+                match &target_id {
+                    Some(target) => clients_guard
+                        .get(target)
+                        .map(|sender| vec![(target.clone(), sender.clone())])
+                        .unwrap_or_default(),
+                    None => clients_guard
+                        .iter()
+                        .filter(|(id, _)| match &route {
+                            ChildRoute::Sessions(subscribed) => subscribed.contains(*id),
+                            _ => true,
+                        })
+                        .map(|(id, sender)| (id.clone(), sender.clone()))
+                        .collect(),
+                }
             };
+            if origin.is_some() && senders.is_empty() {
+                break;
+            }
 
+            // Dropping a client's sender ends its socket's send task, which closes the socket.
             let mut dead = Vec::new();
             for (id, sender) in senders {
-                if sender.send(outgoing.clone()).await.is_err() {
-                    dead.push(id);
+                state.spans.finish(Some(&id), &outgoing);
+                state.registry.record_response(&id, &outgoing).await;
+                traffic::record(TrafficDirection::ServerToClient, "ws", Some(&id), &outgoing);
+                match state
+                    .queues
+                    .deliver(&id, &sender, outgoing.clone(), &msg)
+                    .await
+                {
+                    Delivery::Delivered | Delivery::Dropped => {}
+                    Delivery::Closed | Delivery::Disconnect => dead.push(id),
                 }
             }
             if !dead.is_empty() {
-                let mut clients_guard = clients.lock().await;
+                let mut clients_guard = state.clients.lock().await;
                 for id in dead {
                     clients_guard.remove(&id);
                 }
             }
         }
     });
+}
 
-    ready.store(true, Ordering::SeqCst);
+fn build_router(config: &Config, state: AppState) -> Router {
+    let mut router = Router::new()
+        .route(&config.message_path, get(ws_handler))
+        .with_state(state.clone());
 
+    for ep in &config.health_endpoints {
+        let state = state.clone();
+        let path = ep.clone();
+        router = router.route(
+            &path,
+            get(move || async move { health_handler(state.clone()).await }),
+        );
+    }
+
+    if let Some(cors) = build_cors_layer(&config.cors) {
+        router = router.layer(cors);
+    }
+    router
+}
+
+async fn serve(config: &Config, router: Router) -> Result<(), String> {
     let addr: std::net::SocketAddr = ([0, 0, 0, 0], config.port).into();
     tracing::info!("Listening on port {}", config.port);
     tracing::info!(
@@ -184,72 +549,343 @@ pub async fn run(
         .map_err(|err| err.to_string())
 }
 
-async fn ws_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<ConnectQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let carrier = carrier_from_headers(&headers);
+    let Backend::PerConnection(children) = state.backend.clone() else {
+        let client_id = Uuid::new_v4().to_string();
+        let socket_client_id = client_id.clone();
+        let socket_state = state.clone();
+        let mut response = ws
+            .on_upgrade(move |socket| {
+                handle_socket(socket, socket_state, socket_client_id, carrier)
+            })
+            .into_response();
+        if let Ok(value) = HeaderValue::from_str(&client_id) {
+            response.headers_mut().insert("Mcp-Session-Id", value);
+        }
+        apply_headers(&state, Some(&client_id), &mut response).await;
+        return response;
+    };
+
+    let requested = headers
+        .get("Mcp-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+        .or(query.session_id);
+    let (session_id, child) = match children.attach(requested).await {
+        Ok(attached) => attached,
+        Err(err) => {
+            tracing::error!("Failed to spawn child for WebSocket connection: {err}");
+            return (StatusCode::BAD_GATEWAY, "Failed to spawn child").into_response();
+        }
+    };
+
+    let evicted = state
+        .registry
+        .register(&session_id, "ws", Some(child.clone()))
+        .await;
+    let failed_children = children.clone();
+    let failed_session = session_id.clone();
+    let upgrade_session = session_id.clone();
+    let socket_state = state.clone();
+    let mut response = ws
+        .on_failed_upgrade(move |err| {
+            tracing::warn!(
+                session_id = %failed_session,
+                "WebSocket upgrade failed for session {failed_session}: {err}"
+            );
+            tokio::spawn(async move {
+                failed_children.release(&failed_session).await;
+            });
+        })
+        .on_upgrade(move |socket| {
+            handle_isolated_socket(
+                socket,
+                children,
+                socket_state,
+                upgrade_session,
+                child,
+                evicted,
+                carrier,
+            )
+        })
+        .into_response();
+    if let Ok(value) = HeaderValue::from_str(&session_id) {
+        response.headers_mut().insert("Mcp-Session-Id", value);
+    }
+    apply_headers(&state, Some(&session_id), &mut response).await;
+    response
 }
 
-async fn handle_socket(stream: WebSocket, state: AppState) {
-    let client_id = Uuid::new_v4().to_string();
+async fn handle_socket(
+    stream: WebSocket,
+    state: AppState,
+    client_id: String,
+    carrier: TraceCarrier,
+) {
+    let Backend::Shared {
+        child,
+        session_children,
+    } = state.backend.clone()
+    else {
+        return;
+    };
     let (mut sender_ws, mut receiver_ws) = stream.split();
     let (tx, mut rx) = mpsc::channel::<serde_json::Value>(WS_CLIENT_BUFFER);
 
+    state.queues.register(&client_id, "ws", &tx).await;
+    let evicted = state
+        .registry
+        .register(&client_id, "ws", Some(child.clone()))
+        .await;
     {
         let mut clients = state.clients.lock().await;
         clients.insert(client_id.clone(), tx);
     }
 
-    let child = state.child.clone();
-    let send_task = tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if let Ok(text) = serde_json::to_string(&msg) {
-                if sender_ws.send(Message::Text(text.into())).await.is_err() {
-                    break;
+    let mut send_task = tokio::spawn(async move {
+        let close = loop {
+            tokio::select! {
+                msg = rx.recv() => {
+                    // The router only drops a live client's sender when the backpressure
+                    // policy disconnects it.
+                    let Some(msg) = msg else {
+                        break CloseFrame {
+                            code: close_code::AGAIN,
+                            reason: "Session outbound queue is full".into(),
+                        };
+                    };
+                    if let Ok(text) = serde_json::to_string(&msg) {
+                        if sender_ws.send(Message::Text(text.into())).await.is_err() {
+                            return;
+                        }
+                    }
                 }
+                _ = evicted.cancelled() => break evicted_close_frame(),
             }
-        }
+        };
+        let _ = sender_ws.send(Message::Close(Some(close))).await;
     });
 
     let client_id_clone = client_id.clone();
-    let recv_task = tokio::spawn(async move {
+    let spans = state.spans.clone();
+    let registry = state.registry.clone();
+    let recv_children = session_children.clone();
+    let recv_child = child.clone();
+    let subscriptions = state.subscriptions.clone();
+    let mut recv_task = tokio::spawn(async move {
         while let Some(Ok(message)) = receiver_ws.next().await {
             if let Message::Text(text) = message {
                 if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(&text) {
-                    if let Some(id) = json.get("id").cloned() {
-                        let prefixed = prefix_id(&client_id_clone, &id);
+                    registry.record_request(&client_id_clone, &json).await;
+                    traffic::record(
+                        TrafficDirection::ClientToServer,
+                        "ws",
+                        Some(&client_id_clone),
+                        &json,
+                    );
+                    let trace = begin_request(
+                        SpanRole::Server,
+                        "ws",
+                        &mut json,
+                        Some(&client_id_clone),
+                        carrier.clone(),
+                    );
+                    let request_id = json.get("id").cloned();
+                    if let Some(id) = &request_id {
+                        if let Some((trace, _)) = trace {
+                            spans.track(Some(&client_id_clone), id, trace);
+                        }
+                        let prefixed = prefix_id(&client_id_clone, id);
                         if let Some(obj) = json.as_object_mut() {
                             obj.insert("id".to_string(), prefixed);
                         }
                     }
-                    let _ = child.send(&json).await;
+                    let dedicated = recv_children.get(&client_id_clone).await;
+                    if dedicated.is_none() {
+                        recv_children
+                            .record_initialize(&client_id_clone, &json)
+                            .await;
+                        if subscriptions
+                            .on_client_message(&client_id_clone, &json, &recv_child)
+                            .await
+                            == ClientRoute::Handled
+                        {
+                            continue;
+                        }
+                    }
+                    let target = dedicated.unwrap_or_else(|| recv_child.clone());
+                    if target.send(&json).await.is_err() {
+                        if let Some(id) = &request_id {
+                            spans.abandon(Some(&client_id_clone), id, "Failed to write to child");
+                            registry.abandon(&client_id_clone, id).await;
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    tokio::select! {
+        _ = &mut send_task => recv_task.abort(),
+        _ = &mut recv_task => send_task.abort(),
+    }
+
+    state.clients.lock().await.remove(&client_id);
+    state.queues.unregister(&client_id).await;
+    state.registry.unregister(&client_id).await;
+    state
+        .spans
+        .abandon_session(Some(&client_id), "Session closed");
+    session_children.release(&client_id).await;
+    state.subscriptions.release(&client_id, &child).await;
+}
+
+async fn handle_isolated_socket(
+    stream: WebSocket,
+    children: Arc<ConnectionChildren>,
+    state: AppState,
+    session_id: String,
+    child: Arc<StdioChild>,
+    evicted: CancellationToken,
+    carrier: TraceCarrier,
+) {
+    let (mut sender_ws, mut receiver_ws) = stream.split();
+    let (tx, mut rx) = mpsc::channel::<serde_json::Value>(WS_CLIENT_BUFFER);
+    let spans = state.spans.clone();
+    let queues = state.queues.clone();
+    queues.register(&session_id, "ws", &tx).await;
+
+    // Child output goes through the session's queue like on the shared child, so the
+    // backpressure policy applies; dropping `tx` closes the socket.
+    let mut child_rx = child.subscribe().await;
+    let outbound_spans = spans.clone();
+    let outbound_registry = children.registry.clone();
+    let outbound_queues = queues.clone();
+    let outbound_session_id = session_id.clone();
+    let route_task = tokio::spawn(async move {
+        while let Some(msg) = child_rx.recv().await {
+            outbound_spans.finish(Some(&outbound_session_id), &msg);
+            outbound_registry
+                .record_response(&outbound_session_id, &msg)
+                .await;
+            traffic::record(
+                TrafficDirection::ServerToClient,
+                "ws",
+                Some(&outbound_session_id),
+                &msg,
+            );
+            match outbound_queues
+                .deliver(&outbound_session_id, &tx, msg.clone(), &msg)
+                .await
+            {
+                Delivery::Delivered | Delivery::Dropped => {}
+                Delivery::Closed | Delivery::Disconnect => break,
+            }
+        }
+    });
+
+    let mut send_task = tokio::spawn(async move {
+        let close = loop {
+            tokio::select! {
+                msg = rx.recv() => {
+                    let Some(msg) = msg else {
+                        break CloseFrame {
+                            code: close_code::AGAIN,
+                            reason: "Session outbound queue is full".into(),
+                        };
+                    };
+                    if let Ok(text) = serde_json::to_string(&msg) {
+                        if sender_ws.send(Message::Text(text.into())).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+                _ = evicted.cancelled() => break evicted_close_frame(),
+            }
+        };
+        let _ = sender_ws.send(Message::Close(Some(close))).await;
+    });
+
+    let recv_session_id = session_id.clone();
+    let registry = children.registry.clone();
+    let mut recv_task = tokio::spawn(async move {
+        while let Some(Ok(message)) = receiver_ws.next().await {
+            if let Message::Text(text) = message {
+                if let Ok(mut json) = serde_json::from_str::<serde_json::Value>(&text) {
+                    registry.record_request(&recv_session_id, &json).await;
+                    traffic::record(
+                        TrafficDirection::ClientToServer,
+                        "ws",
+                        Some(&recv_session_id),
+                        &json,
+                    );
+                    if let Some((trace, _)) = begin_request(
+                        SpanRole::Server,
+                        "ws",
+                        &mut json,
+                        Some(&recv_session_id),
+                        carrier.clone(),
+                    ) {
+                        if let Some(id) = json.get("id") {
+                            spans.track(Some(&recv_session_id), id, trace);
+                        }
+                    }
+                    if child.send(&json).await.is_err() {
+                        if let Some(id) = json.get("id") {
+                            spans.abandon(Some(&recv_session_id), id, "Failed to write to child");
+                            registry.abandon(&recv_session_id, id).await;
+                        }
+                    }
                 }
             }
         }
     });
 
-    let _ = tokio::join!(send_task, recv_task);
+    tokio::select! {
+        _ = &mut send_task => recv_task.abort(),
+        _ = &mut recv_task => send_task.abort(),
+    }
+    route_task.abort();
+    queues.unregister(&session_id).await;
+    state
+        .spans
+        .abandon_session(Some(&session_id), "Session closed");
+    children.release(&session_id).await;
+}
 
-    let mut clients = state.clients.lock().await;
-    clients.remove(&client_id);
+fn evicted_close_frame() -> CloseFrame {
+    CloseFrame {
+        code: close_code::NORMAL,
+        reason: "Session evicted by the runtime admin API".into(),
+    }
 }
 
 async fn health_handler(state: AppState) -> impl IntoResponse {
-    if !state.child.is_alive().await {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Child process has been killed",
-        )
-            .into_response();
+    if let Backend::Shared { child, .. } = &state.backend {
+        if !child.is_alive().await {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Child process has been killed",
+            )
+                .into_response();
+        }
     }
     if !state.ready.load(Ordering::SeqCst) {
         return (StatusCode::INTERNAL_SERVER_ERROR, "Server is not ready").into_response();
     }
     let mut response = (StatusCode::OK, "ok").into_response();
-    apply_headers(&state, &mut response).await;
+    apply_headers(&state, None, &mut response).await;
     response
 }
 
-async fn apply_headers(state: &AppState, response: &mut Response) {
-    let runtime = state.runtime.get_effective(None).await;
+async fn apply_headers(state: &AppState, session_id: Option<&str>, response: &mut Response) {
+    let runtime = state.runtime.get_effective(session_id).await;
     let headers = merge_headers(&state.base_headers, &runtime.headers);
     let header_map = response.headers_mut();
     for (key, value) in headers.iter() {
@@ -257,6 +893,162 @@ async fn apply_headers(state: &AppState, response: &mut Response) {
     }
 }
 
+struct ConnectionChild {
+    child: Arc<StdioChild>,
+    attached: bool,
+    reaper: Option<JoinHandle<()>>,
+}
+
+// Owns one child per WebSocket connection. With an idle grace period, a closed connection's
+// child is parked so a client reconnecting with the same session id picks it back up.
+struct ConnectionChildren {
+    spec: CommandSpec,
+    runtime: RuntimeArgsStore,
+    registry: SessionRegistry,
+    idle_grace: Option<Duration>,
+    children: Mutex<HashMap<String, ConnectionChild>>,
+}
+
+impl ConnectionChildren {
+    fn new(
+        spec: CommandSpec,
+        runtime: RuntimeArgsStore,
+        registry: SessionRegistry,
+        idle_grace: Option<Duration>,
+    ) -> Self {
+        Self {
+            spec,
+            runtime,
+            registry,
+            idle_grace,
+            children: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn attach(&self, requested: Option<String>) -> Result<(String, Arc<StdioChild>), String> {
+        if let Some(session_id) = requested {
+            let mut children = self.children.lock().await;
+            if let Some(entry) = children.get_mut(&session_id) {
+                if !entry.attached {
+                    if let Some(reaper) = entry.reaper.take() {
+                        reaper.abort();
+                    }
+                    entry.attached = true;
+                    tracing::info!(
+                        session_id = %session_id,
+                        "Reattached WebSocket session {session_id}"
+                    );
+                    return Ok((session_id, entry.child.clone()));
+                }
+            }
+        }
+
+        let session_id = Uuid::new_v4().to_string();
+        let runtime = self.runtime.get_effective(Some(&session_id)).await;
+        let child = Arc::new(StdioChild::new(self.spec.clone(), false));
+        child.spawn(&runtime).await?;
+        tracing::info!(
+            session_id = %session_id,
+            "Spawned child for WebSocket session {session_id}"
+        );
+        let mut children = self.children.lock().await;
+        children.insert(
+            session_id.clone(),
+            ConnectionChild {
+                child: child.clone(),
+                attached: true,
+                reaper: None,
+            },
+        );
+        Ok((session_id, child))
+    }
+
+    async fn release(self: &Arc<Self>, session_id: &str) {
+        let Some(grace) = self.idle_grace else {
+            self.remove(session_id).await;
+            return;
+        };
+        let mut children = self.children.lock().await;
+        let Some(entry) = children.get_mut(session_id) else {
+            return;
+        };
+        entry.attached = false;
+        let this = self.clone();
+        let id = session_id.to_string();
+        entry.reaper = Some(tokio::spawn(async move {
+            tokio::time::sleep(grace).await;
+            this.expire(&id).await;
+        }));
+    }
+
+    async fn expire(&self, session_id: &str) {
+        let removed = {
+            let mut children = self.children.lock().await;
+            match children.get(session_id) {
+                Some(entry) if !entry.attached => children.remove(session_id),
+                _ => None,
+            }
+        };
+        if let Some(entry) = removed {
+            tracing::info!(
+                session_id = %session_id,
+                "WebSocket session {session_id} idle grace expired, cleaning up"
+            );
+            self.registry.unregister(session_id).await;
+            self.runtime.remove_session(session_id).await;
+            entry.child.shutdown().await;
+        }
+    }
+
+    async fn remove(&self, session_id: &str) {
+        let removed = self.children.lock().await.remove(session_id);
+        self.registry.unregister(session_id).await;
+        self.runtime.remove_session(session_id).await;
+        if let Some(entry) = removed {
+            entry.child.shutdown().await;
+        }
+    }
+
+    async fn restart(&self, session_id: &str) -> Result<(), String> {
+        let child = {
+            let children = self.children.lock().await;
+            children.get(session_id).map(|entry| entry.child.clone())
+        };
+        if let Some(child) = child {
+            let runtime = self.runtime.get_effective(Some(session_id)).await;
+            child.restart(&runtime).await?;
+        }
+        Ok(())
+    }
+
+    async fn restart_all(&self) -> Result<(), String> {
+        for (session_id, child) in self.snapshot().await {
+            let runtime = self.runtime.get_effective(Some(&session_id)).await;
+            child.restart(&runtime).await?;
+        }
+        Ok(())
+    }
+
+    // Moves every connection's child onto new runtime args with the configured restart
+    // strategy.
+    async fn apply_runtime_all(&self) -> Result<Vec<SwapReport>, String> {
+        let mut swaps = Vec::new();
+        for (session_id, child) in self.snapshot().await {
+            let runtime = self.runtime.get_effective(Some(&session_id)).await;
+            swaps.extend(child.apply_runtime(&runtime).await?);
+        }
+        Ok(swaps)
+    }
+
+    async fn snapshot(&self) -> Vec<(String, Arc<StdioChild>)> {
+        let children = self.children.lock().await;
+        children
+            .iter()
+            .map(|(id, entry)| (id.clone(), entry.child.clone()))
+            .collect()
+    }
+}
+
 fn header_map_from(headers: &std::collections::HashMap<String, String>) -> HeaderMap {
     let mut map = HeaderMap::new();
     for (k, v) in headers {
@@ -5,12 +5,16 @@ use eventsource_stream::Eventsource;
 use futures::StreamExt;
 use tokio::sync::{mpsc, RwLock};
 use tokio_util::codec::{FramedRead, LinesCodec};
+use tracing::{Instrument, Span};
 use uuid::Uuid;
 
 use crate::config::Config;
 use crate::runtime::store::RuntimeArgsStore;
-use crate::runtime::{RuntimeApplyResult, RuntimeScope, RuntimeUpdateRequest};
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdateRequest};
+use crate::support::redact::redacted_json;
 use crate::support::signals::install_signal_handlers;
+use crate::support::trace_context::{begin_request, SpanRole, TraceCarrier};
+use crate::support::traffic::{self, TrafficDirection};
 use crate::transport::pool::{global_pool, transport_fingerprint, TransportPool};
 use crate::transport::reliability::{
     run_with_retry, CircuitBreaker, CircuitBreakerPolicy, RetryPolicy,
@@ -30,10 +34,7 @@ pub async fn run(
         .clone()
         .ok_or("streamable-http url is required")?;
     tracing::info!("  - streamable-http: {streamable_http_url}");
-    tracing::info!(
-        "  - Headers: {}",
-        serde_json::to_string(&config.headers).unwrap_or_else(|_| "(none)".into())
-    );
+    tracing::info!("  - Headers: {}", redacted_json(&config.headers));
     tracing::info!("Connecting to Streamable HTTP...");
 
     install_signal_handlers(None);
@@ -108,6 +109,12 @@ pub async fn run(
                             continue;
                         }
                         if let Ok(json) = serde_json::from_str::<serde_json::Value>(&event.data) {
+                            traffic::record(
+                                TrafficDirection::ServerToClient,
+                                "streamable-http",
+                                None,
+                                &json,
+                            );
                             println!("{}", json);
                         }
                     }
@@ -123,21 +130,31 @@ pub async fn run(
     let runtime_store = runtime.clone();
     tokio::spawn(async move {
         while let Some(req) = updates.recv().await {
-            let result = match req.update.scope {
-                RuntimeScope::Global => {
-                    let update_result = runtime_store.update_global(req.update.update).await;
-                    if update_result.restart_needed {
-                        RuntimeApplyResult::ok(
-                            "Updated runtime args; env/CLI changes require restart of remote server",
-                            false,
-                        )
-                    } else {
-                        RuntimeApplyResult::ok("Updated runtime headers", false)
+            let result = match req.command {
+                RuntimeCommand::Update(update) => match update.scope {
+                    RuntimeScope::Global => {
+                        let update_result = runtime_store.update_global(update.update).await;
+                        if update_result.restart_needed {
+                            RuntimeApplyResult::ok(
+                                "Updated runtime args; env/CLI changes require restart of remote server",
+                                false,
+                            )
+                        } else {
+                            RuntimeApplyResult::ok("Updated runtime headers", false)
+                        }
                     }
+                    RuntimeScope::Session(_) => RuntimeApplyResult::error(
+                        "Per-session runtime overrides are not supported for StreamableHTTP→stdio",
+                    ),
+                },
+                RuntimeCommand::RestartChild => {
+                    RuntimeApplyResult::error("StreamableHTTP→stdio has no local child to restart")
+                }
+                RuntimeCommand::RestartSession(_) | RuntimeCommand::EvictSession(_) => {
+                    RuntimeApplyResult::error(
+                        "StreamableHTTP→stdio does not track gateway sessions",
+                    )
                 }
-                RuntimeScope::Session(_) => RuntimeApplyResult::error(
-                    "Per-session runtime overrides are not supported for StreamableHTTP→stdio",
-                ),
             };
             let _ = req.respond_to.send(result);
         }
@@ -160,7 +177,7 @@ pub async fn run(
         if line.trim().is_empty() {
             continue;
         }
-        let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) else {
+        let Ok(mut message) = serde_json::from_str::<serde_json::Value>(&line) else {
             tracing::error!("Invalid JSON from stdin: {line}");
             continue;
         };
@@ -169,12 +186,30 @@ pub async fn run(
             println!("{}", message);
             continue;
         }
+        traffic::record(
+            TrafficDirection::ClientToServer,
+            "streamable-http",
+            None,
+            &message,
+        );
 
+        let (trace, carrier) = match begin_request(
+            SpanRole::Client,
+            "streamable-http",
+            &mut message,
+            None,
+            TraceCarrier::new(),
+        ) {
+            Some((trace, carrier)) => (Some(trace), carrier),
+            None => (None, TraceCarrier::new()),
+        };
         let runtime_args = runtime.get_effective(None).await;
+        let mut request_headers = runtime_args.headers.clone();
+        request_headers.extend(carrier);
         let request_context = StreamableRequestContext {
             http: &http,
             url: &streamable_http_url,
-            headers: &runtime_args.headers,
+            headers: &request_headers,
             session_id: &session_clone,
             pool: &pool,
             pool_key: &request_key,
@@ -191,6 +226,12 @@ pub async fn run(
             .await;
             if init_payload.get("error").is_some() {
                 let response = wrap_response(&message, init_payload);
+                traffic::record(
+                    TrafficDirection::ServerToClient,
+                    "streamable-http",
+                    None,
+                    &response,
+                );
                 println!("{}", response);
                 continue;
             }
@@ -210,7 +251,16 @@ pub async fn run(
             retry_policy,
             &mut circuit_breaker,
         )
+        .instrument(
+            trace
+                .as_ref()
+                .map(|trace| trace.span().clone())
+                .unwrap_or_else(Span::none),
+        )
         .await;
+        if let Some(trace) = trace {
+            trace.finish(&payload);
+        }
 
         if is_initialize_request(&message) && payload.get("error").is_none() && !initialized {
             if let Err(err) =
@@ -224,6 +274,12 @@ pub async fn run(
         }
 
         let response = wrap_response(&message, payload);
+        traffic::record(
+            TrafficDirection::ServerToClient,
+            "streamable-http",
+            None,
+            &response,
+        );
         println!("{}", response);
     }
 
@@ -7,8 +7,10 @@ use tokio_tungstenite::tungstenite::Message;
 use tokio_util::codec::{FramedRead, LinesCodec};
 
 use crate::runtime::store::RuntimeArgsStore;
-use crate::runtime::{RuntimeApplyResult, RuntimeScope, RuntimeUpdateRequest};
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdateRequest};
 use crate::support::signals::install_signal_handlers;
+use crate::support::trace_context::{begin_request, RequestSpans, SpanRole, TraceCarrier};
+use crate::support::traffic::{self, TrafficDirection};
 use crate::transport::pool::{global_pool, transport_fingerprint};
 
 const WS_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
@@ -45,26 +47,35 @@ pub async fn run(
     let runtime_store = runtime.clone();
     tokio::spawn(async move {
         while let Some(req) = updates.recv().await {
-            let result = match req.update.scope {
-                RuntimeScope::Global => {
-                    let update_result = runtime_store.update_global(req.update.update).await;
-                    if update_result.restart_needed || update_result.headers_changed {
-                        RuntimeApplyResult::ok(
-                            "Updated runtime args/headers; reconnect required for WebSocket endpoint",
-                            true,
-                        )
-                    } else {
-                        RuntimeApplyResult::ok("No runtime changes applied", false)
+            let result = match req.command {
+                RuntimeCommand::Update(update) => match update.scope {
+                    RuntimeScope::Global => {
+                        let update_result = runtime_store.update_global(update.update).await;
+                        if update_result.restart_needed || update_result.headers_changed {
+                            RuntimeApplyResult::ok(
+                                "Updated runtime args/headers; reconnect required for WebSocket endpoint",
+                                true,
+                            )
+                        } else {
+                            RuntimeApplyResult::ok("No runtime changes applied", false)
+                        }
                     }
+                    RuntimeScope::Session(_) => RuntimeApplyResult::error(
+                        "Per-session runtime overrides are not supported for WebSocket→stdio",
+                    ),
+                },
+                RuntimeCommand::RestartChild => {
+                    RuntimeApplyResult::error("WebSocket→stdio has no local child to restart")
+                }
+                RuntimeCommand::RestartSession(_) | RuntimeCommand::EvictSession(_) => {
+                    RuntimeApplyResult::error("WebSocket→stdio does not track gateway sessions")
                 }
-                RuntimeScope::Session(_) => RuntimeApplyResult::error(
-                    "Per-session runtime overrides are not supported for WebSocket→stdio",
-                ),
             };
             let _ = req.respond_to.send(result);
         }
     });
 
+    let spans = RequestSpans::default();
     let (mut ws_writer, mut ws_reader) = stream.split();
     let mut stdin_lines = FramedRead::new(tokio::io::stdin(), LinesCodec::new());
 
@@ -79,7 +90,7 @@ pub async fn run(
                     continue;
                 }
 
-                let Ok(message_json) = serde_json::from_str::<serde_json::Value>(&line) else {
+                let Ok(mut message_json) = serde_json::from_str::<serde_json::Value>(&line) else {
                     tracing::error!("Invalid JSON from stdin: {line}");
                     continue;
                 };
@@ -89,6 +100,19 @@ pub async fn run(
                     continue;
                 }
 
+                traffic::record(TrafficDirection::ClientToServer, "ws", None, &message_json);
+                if let Some((trace, _)) = begin_request(
+                    SpanRole::Client,
+                    "ws",
+                    &mut message_json,
+                    None,
+                    TraceCarrier::new(),
+                ) {
+                    if let Some(id) = message_json.get("id") {
+                        spans.track(None, id, trace);
+                    }
+                }
+
                 let outbound = Message::Text(message_json.to_string().into());
                 ws_writer
                     .send(outbound)
@@ -100,7 +124,7 @@ pub async fn run(
                     return Err("WebSocket connection closed".to_string());
                 };
                 let frame = frame.map_err(|err| format!("WebSocket stream error: {err}"))?;
-                handle_incoming_frame(frame)?;
+                handle_incoming_frame(frame, &spans)?;
             }
         }
     }
@@ -127,16 +151,20 @@ fn build_ws_request(
     Ok(request)
 }
 
-fn handle_incoming_frame(frame: Message) -> Result<(), String> {
+fn handle_incoming_frame(frame: Message, spans: &RequestSpans) -> Result<(), String> {
     match frame {
         Message::Text(text) => {
             let payload: serde_json::Value = serde_json::from_str(&text)
                 .map_err(|err| format!("WebSocket text frame was not valid JSON: {err}"))?;
+            spans.finish(None, &payload);
+            traffic::record(TrafficDirection::ServerToClient, "ws", None, &payload);
             println!("{}", payload);
         }
         Message::Binary(bytes) => {
             let payload: serde_json::Value = serde_json::from_slice(&bytes)
                 .map_err(|err| format!("WebSocket binary frame was not valid JSON: {err}"))?;
+            spans.finish(None, &payload);
+            traffic::record(TrafficDirection::ServerToClient, "ws", None, &payload);
             println!("{}", payload);
         }
         Message::Close(close_frame) => {
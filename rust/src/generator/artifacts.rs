@@ -3,7 +3,7 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::generator::normalize::{required_env_keys, NormalizedDefinition};
+use crate::generator::normalize::{launch_args, required_env_keys, NormalizedDefinition};
 use crate::generator::wrapper_template::render_wrapper_source;
 
 #[derive(Debug, Clone)]
@@ -266,6 +266,11 @@ fn render_posix_launcher(normalized: &NormalizedDefinition) -> String {
         shell_single_quote(&normalized.stdio_command)
     ));
     script.push_str("ARGS=(--stdio \"${STDIO_CMD}\")\n");
+    let fixed_args = launch_args(normalized)
+        .iter()
+        .map(|arg| shell_single_quote(arg))
+        .collect::<Vec<_>>();
+    script.push_str(&format!("ARGS+=({})\n", fixed_args.join(" ")));
 
     for binding in &normalized.env_bindings {
         script.push_str(&format!(
@@ -316,6 +321,9 @@ fn render_windows_launcher(normalized: &NormalizedDefinition) -> String {
     ));
 
     script.push_str("\"%MCPWAY_BIN%\" --stdio \"%STDIO_CMD%\"");
+    for arg in launch_args(normalized) {
+        script.push_str(&format!(" \"{}\"", arg.replace('"', "\\\"")));
+    }
     for binding in &normalized.env_bindings {
         script.push_str(&format!(
             " --env \"{}=%{}%\"",
@@ -368,6 +376,7 @@ fn make_executable(_path: &Path) -> Result<(), String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::generator::definition::ServerSandbox;
     use crate::generator::normalize::{EnvBinding, HeaderBinding, NormalizedDefinition};
 
     #[test]
@@ -386,6 +395,11 @@ mod tests {
                 header: "Authorization".to_string(),
                 source_env: "MCPWAY_HEADER_AUTHORIZATION".to_string(),
             }],
+            sandbox: ServerSandbox {
+                read_only: vec!["/usr".to_string()],
+                read_write: Vec::new(),
+                deny_network: true,
+            },
         };
 
         let script = render_posix_launcher(&normalized);
@@ -393,5 +407,8 @@ mod tests {
         assert!(
             script.contains("ARGS+=(--header \"Authorization: ${MCPWAY_HEADER_AUTHORIZATION}\")")
         );
+        assert!(script.contains(
+            "ARGS+=('--server-name' 'demo' '--sandbox-ro' '/usr' '--sandbox-deny-network')"
+        ));
     }
 }
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::BTreeMap;
 use std::fs;
@@ -10,6 +11,41 @@ pub struct ParsedServerDefinition {
     pub args: Vec<String>,
     pub env: BTreeMap<String, String>,
     pub headers: BTreeMap<String, String>,
+    pub sandbox: ServerSandbox,
+}
+
+// Linux sandbox profile for a server, declared as `"sandbox": {"readOnly": [...],
+// "readWrite": [...], "denyNetwork": true}` next to `command`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerSandbox {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_only: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_write: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deny_network: bool,
+}
+
+impl ServerSandbox {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn cli_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for path in &self.read_only {
+            args.push("--sandbox-ro".to_string());
+            args.push(path.clone());
+        }
+        for path in &self.read_write {
+            args.push("--sandbox-rw".to_string());
+            args.push(path.clone());
+        }
+        if self.deny_network {
+            args.push("--sandbox-deny-network".to_string());
+        }
+        args
+    }
 }
 
 pub fn load_definition(
@@ -84,6 +120,7 @@ fn parse_server_object(
     let args = parse_string_array(server_obj.get("args"), "args", &server_name)?;
     let env = parse_string_map(server_obj.get("env"), "env", &server_name)?;
     let headers = parse_string_map(server_obj.get("headers"), "headers", &server_name)?;
+    let sandbox = parse_sandbox(server_obj.get("sandbox"), &server_name)?;
 
     Ok(ParsedServerDefinition {
         server_name,
@@ -91,6 +128,33 @@ fn parse_server_object(
         args,
         env,
         headers,
+        sandbox,
+    })
+}
+
+fn parse_sandbox(value: Option<&Value>, server_name: &str) -> Result<ServerSandbox, String> {
+    let Some(value) = value else {
+        return Ok(ServerSandbox::default());
+    };
+    let Some(obj) = value.as_object() else {
+        return Err(format!(
+            "Server '{server_name}' field 'sandbox' must be an object"
+        ));
+    };
+
+    let deny_network = match obj.get("denyNetwork") {
+        None => false,
+        Some(Value::Bool(flag)) => *flag,
+        Some(_) => {
+            return Err(format!(
+                "Server '{server_name}' field 'sandbox.denyNetwork' must be a boolean"
+            ))
+        }
+    };
+    Ok(ServerSandbox {
+        read_only: parse_string_array(obj.get("readOnly"), "sandbox.readOnly", server_name)?,
+        read_write: parse_string_array(obj.get("readWrite"), "sandbox.readWrite", server_name)?,
+        deny_network,
     })
 }
 
@@ -202,6 +266,44 @@ mod tests {
         assert_eq!(parsed.args, vec!["-m", "beta"]);
     }
 
+    #[test]
+    fn parses_sandbox_profile() {
+        let root: Value = serde_json::json!({
+            "mcpServers": {
+                "files": {
+                    "command": "npx",
+                    "args": ["-y", "@modelcontextprotocol/server-filesystem"],
+                    "sandbox": {
+                        "readOnly": ["/usr", "/etc"],
+                        "readWrite": ["/srv/data"],
+                        "denyNetwork": true
+                    }
+                }
+            }
+        });
+
+        let parsed = parse_definition_value(&root, None).expect("parse should succeed");
+        assert_eq!(parsed.sandbox.read_only, vec!["/usr", "/etc"]);
+        assert_eq!(
+            parsed.sandbox.cli_args(),
+            vec![
+                "--sandbox-ro",
+                "/usr",
+                "--sandbox-ro",
+                "/etc",
+                "--sandbox-rw",
+                "/srv/data",
+                "--sandbox-deny-network"
+            ]
+        );
+
+        let root: Value = serde_json::json!({
+            "mcpServers": { "bad": { "command": "node", "sandbox": { "denyNetwork": "yes" } } }
+        });
+        let err = parse_definition_value(&root, None).expect_err("parse should fail");
+        assert!(err.contains("sandbox.denyNetwork"));
+    }
+
     #[test]
     fn requires_server_name_when_map_has_multiple_entries() {
         let root: Value = serde_json::json!({
@@ -4,6 +4,8 @@ use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
+use crate::generator::definition::ServerSandbox;
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ArtifactMode {
@@ -42,6 +44,8 @@ pub struct NormalizedMetadata {
     pub args: Vec<String>,
     pub env_template: BTreeMap<String, String>,
     pub headers_template: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "ServerSandbox::is_empty")]
+    pub sandbox: ServerSandbox,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -121,6 +125,11 @@ mod tests {
                     "Authorization".to_string(),
                     "${MCPWAY_HEADER_AUTHORIZATION}".to_string(),
                 )]),
+                sandbox: ServerSandbox {
+                    read_only: vec!["/usr".to_string()],
+                    read_write: Vec::new(),
+                    deny_network: true,
+                },
             },
             generation: GenerationMetadata {
                 options: GenerationOptions {
@@ -151,5 +160,7 @@ mod tests {
         assert_eq!(read_back.artifact_name, "demo");
         assert_eq!(read_back.schema_version, "1");
         assert!(read_back.generation.options.bundle_mcpway);
+        assert_eq!(read_back.normalized.sandbox.read_only, vec!["/usr"]);
+        assert!(read_back.normalized.sandbox.deny_network);
     }
 }
@@ -12,7 +12,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::config::{ConnectConfig, ConnectProtocol, GenerateConfig, RegenerateConfig};
 use crate::generator::artifacts::{build_artifacts, ArtifactBuildOptions, GeneratedArtifacts};
-use crate::generator::definition::load_definition;
+use crate::generator::definition::{load_definition, ServerSandbox};
 use crate::generator::metadata::{
     read_metadata, sha256_hex, write_metadata, ArtifactMetadata, ArtifactMode, ArtifactPaths,
     ConnectProfileMetadata, GenerationMetadata, GenerationOptions, NormalizedMetadata,
@@ -24,11 +24,25 @@ use crate::generator::normalize::{
 };
 
 pub fn run_generate(config: &GenerateConfig) -> Result<(), String> {
+    generate(config, None)
+}
+
+// `fallback_sandbox` carries a sandbox profile recorded in existing artifact metadata, used
+// when the definition itself does not declare one.
+fn generate(
+    config: &GenerateConfig,
+    fallback_sandbox: Option<&ServerSandbox>,
+) -> Result<(), String> {
     let definition_path = absolute_path(&config.definition)?;
     let output_dir = absolute_path(&config.out)?;
 
     let parsed = load_definition(&definition_path, config.server.as_deref())?;
-    let normalized = normalize_definition(&parsed, config.artifact_name.as_deref())?;
+    let mut normalized = normalize_definition(&parsed, config.artifact_name.as_deref())?;
+    if let Some(sandbox) = fallback_sandbox {
+        if normalized.sandbox.is_empty() {
+            normalized.sandbox = sandbox.clone();
+        }
+    }
 
     fs::create_dir_all(&output_dir)
         .map_err(|err| format!("Failed to create {}: {err}", output_dir.display()))?;
@@ -136,7 +150,7 @@ pub fn run_regenerate(config: &RegenerateConfig) -> Result<(), String> {
         compile_wrapper,
     };
 
-    run_generate(&generate_config)
+    generate(&generate_config, Some(&existing.normalized.sandbox))
 }
 
 struct MetadataBuildInput<'a> {
@@ -167,6 +181,7 @@ fn build_metadata(input: MetadataBuildInput<'_>) -> ArtifactMetadata {
             args: input.normalized.args.clone(),
             env_template: env_template_map(input.normalized),
             headers_template: header_template_map(input.normalized),
+            sandbox: input.normalized.sandbox.clone(),
         },
         generation: GenerationMetadata {
             options: input.options,
@@ -312,6 +327,7 @@ fn write_connect_profile(spec: &ConnectProfileSpec) -> Result<ArtifactMetadata,
             args: Vec::new(),
             env_template: BTreeMap::new(),
             headers_template: headers_template.clone(),
+            sandbox: ServerSandbox::default(),
         },
         generation: GenerationMetadata {
             options: GenerationOptions {
@@ -551,6 +567,7 @@ mod tests {
             stdio_command: "node server.js".to_string(),
             env_bindings: Vec::new(),
             header_bindings: Vec::new(),
+            sandbox: ServerSandbox::default(),
         };
 
         let output_dir = PathBuf::from("/tmp/mcpway-demo");
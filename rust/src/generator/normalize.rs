@@ -1,6 +1,6 @@
 use std::collections::{BTreeMap, HashSet};
 
-use crate::generator::definition::ParsedServerDefinition;
+use crate::generator::definition::{ParsedServerDefinition, ServerSandbox};
 
 #[derive(Debug, Clone)]
 pub struct EnvBinding {
@@ -23,6 +23,7 @@ pub struct NormalizedDefinition {
     pub stdio_command: String,
     pub env_bindings: Vec<EnvBinding>,
     pub header_bindings: Vec<HeaderBinding>,
+    pub sandbox: ServerSandbox,
 }
 
 pub fn normalize_definition(
@@ -77,6 +78,7 @@ pub fn normalize_definition(
         stdio_command,
         env_bindings,
         header_bindings,
+        sandbox: parsed.sandbox.clone(),
     })
 }
 
@@ -99,6 +101,13 @@ pub fn header_template_map(normalized: &NormalizedDefinition) -> BTreeMap<String
     out
 }
 
+// Fixed mcpway flags baked into every launcher: the server name plus its sandbox profile.
+pub fn launch_args(normalized: &NormalizedDefinition) -> Vec<String> {
+    let mut args = vec!["--server-name".to_string(), normalized.server_name.clone()];
+    args.extend(normalized.sandbox.cli_args());
+    args
+}
+
 pub fn required_env_keys(normalized: &NormalizedDefinition) -> Vec<String> {
     let mut keys = Vec::new();
     for binding in &normalized.env_bindings {
@@ -155,6 +164,7 @@ mod tests {
             args: vec!["server.js".to_string()],
             env: BTreeMap::from([("API_KEY".to_string(), "secret".to_string())]),
             headers: BTreeMap::from([("Authorization".to_string(), "Bearer abc".to_string())]),
+            sandbox: ServerSandbox::default(),
         };
 
         let normalized = normalize_definition(&parsed, None).expect("normalize should succeed");
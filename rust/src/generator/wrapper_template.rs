@@ -1,6 +1,6 @@
 use std::fmt::Write as _;
 
-use crate::generator::normalize::NormalizedDefinition;
+use crate::generator::normalize::{launch_args, NormalizedDefinition};
 
 pub fn render_wrapper_source(normalized: &NormalizedDefinition, metadata_path: &str) -> String {
     let mut source = String::new();
@@ -16,6 +16,12 @@ pub fn render_wrapper_source(normalized: &NormalizedDefinition, metadata_path: &
     source.push_str(&rust_string_literal(metadata_path));
     source.push_str(";\n\n");
 
+    source.push_str("const LAUNCH_ARGS: &[&str] = &[\n");
+    for arg in launch_args(normalized) {
+        let _ = writeln!(source, "    {},", rust_string_literal(&arg));
+    }
+    source.push_str("];\n\n");
+
     source.push_str("const ENV_BINDINGS: &[(&str, &str)] = &[\n");
     for binding in &normalized.env_bindings {
         let _ = writeln!(
@@ -59,6 +65,7 @@ fn required_env(key: &str) -> Result<String, String> {
 fn main() {
     let mut cmd = Command::new(resolve_mcpway_bin());
     cmd.arg("--stdio").arg(STDIO_COMMAND);
+    cmd.args(LAUNCH_ARGS);
 
     for (key, env_key) in ENV_BINDINGS {
         match required_env(env_key) {
@@ -112,6 +119,7 @@ fn rust_string_literal(input: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::generator::definition::ServerSandbox;
     use crate::generator::normalize::{EnvBinding, HeaderBinding, NormalizedDefinition};
 
     #[test]
@@ -130,11 +138,13 @@ mod tests {
                 header: "Authorization".to_string(),
                 source_env: "MCPWAY_HEADER_AUTHORIZATION".to_string(),
             }],
+            sandbox: ServerSandbox::default(),
         };
 
         let src = render_wrapper_source(&normalized, "/tmp/artifact/mcpway-artifact.json");
         assert!(src.contains("const STDIO_COMMAND"));
         assert!(src.contains("METADATA_PATH"));
         assert!(src.contains("MCPWAY_HEADER_AUTHORIZATION"));
+        assert!(src.contains("const LAUNCH_ARGS"));
     }
 }
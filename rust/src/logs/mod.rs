@@ -0,0 +1,217 @@
+mod query;
+
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::time::sleep;
+
+use crate::config::{LogsConfig, LogsLevel, LogsTailConfig};
+use crate::support::log_rotation::LogFollower;
+use crate::support::log_store::{
+    default_log_path, ensure_log_file, parse_record, recent_records, resolve_instance_log_path,
+    StoredLogRecord,
+};
+use crate::support::redact::{self, Redactor};
+
+pub async fn run(config: LogsConfig) -> Result<(), String> {
+    match config {
+        LogsConfig::Tail(tail) => run_tail(tail).await,
+        LogsConfig::Query(query) => query::run_query(query),
+    }
+}
+
+async fn run_tail(config: LogsTailConfig) -> Result<(), String> {
+    redact::install(Redactor::new(&[], &[], config.reveal_secrets)?);
+    let path = match config.file.clone() {
+        Some(path) => path,
+        None => resolve_instance_log_path(&default_log_path(), config.instance.as_deref()),
+    };
+    ensure_log_file(&path)?;
+
+    // Taken before printing history so nothing written in between is missed.
+    let mut follower = LogFollower::from_end(&path);
+    print_recent_lines(&path, &config)?;
+    if !config.follow {
+        return Ok(());
+    }
+
+    loop {
+        sleep(Duration::from_millis(500)).await;
+        for line in follower.read_new_lines() {
+            let Some(record) = parse_record(&line) else {
+                continue;
+            };
+            if matches_filters(&record, &config) {
+                emit_record(&record, &config);
+            }
+        }
+    }
+}
+
+fn print_recent_lines(path: &Path, config: &LogsTailConfig) -> Result<(), String> {
+    let records = recent_records(path, config.lines, |record| matches_filters(record, config))?;
+    for record in &records {
+        emit_record(record, config);
+    }
+    Ok(())
+}
+
+fn emit_record(record: &StoredLogRecord, config: &LogsTailConfig) {
+    if config.json {
+        if let Ok(json) = serde_json::to_string(record) {
+            println!("{json}");
+        }
+    } else {
+        println!(
+            "[{}][{}][{}:{}][{}] {}",
+            record.ts_utc,
+            record.level.to_uppercase(),
+            record.mode,
+            record.transport,
+            record.instance_id,
+            record.message
+        );
+    }
+}
+
+fn matches_filters(record: &StoredLogRecord, config: &LogsTailConfig) -> bool {
+    if let Some(level) = config.level {
+        let wanted = match level {
+            LogsLevel::Debug => "debug",
+            LogsLevel::Info => "info",
+            LogsLevel::Warn => "warn",
+            LogsLevel::Error => "error",
+        };
+        if record.level != wanted {
+            return false;
+        }
+    }
+
+    if let Some(transport) = config.transport {
+        if record.transport != transport.as_str() {
+            return false;
+        }
+    }
+
+    if let Some(instance) = config.instance.as_deref() {
+        if !record.matches_instance(instance) {
+            return false;
+        }
+    }
+
+    let correlation = [
+        ("session_id", &config.session),
+        ("request_id", &config.request_id),
+        ("method", &config.method),
+    ];
+    for (field, wanted) in correlation {
+        if let Some(wanted) = wanted.as_deref() {
+            if record.field(field) != Some(wanted) {
+                return false;
+            }
+        }
+    }
+
+    if !record.in_time_range(config.since, config.until) {
+        return false;
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LogsTransport;
+
+    #[test]
+    fn filter_matches_expected_transport() {
+        let record = StoredLogRecord {
+            ts_utc: 1,
+            level: "info".to_string(),
+            target: "test".to_string(),
+            message: "hello".to_string(),
+            mode: "connect".to_string(),
+            transport: "ws".to_string(),
+            instance_id: "docs".to_string(),
+            pid: 42,
+            server_name: None,
+            fields: Default::default(),
+        };
+        let cfg = LogsTailConfig {
+            file: None,
+            follow: false,
+            lines: 10,
+            level: None,
+            transport: Some(LogsTransport::Ws),
+            instance: None,
+            session: None,
+            request_id: None,
+            method: None,
+            since: None,
+            until: None,
+            json: false,
+            reveal_secrets: false,
+        };
+        assert!(matches_filters(&record, &cfg));
+
+        let cfg = LogsTailConfig {
+            instance: Some("search".to_string()),
+            ..cfg
+        };
+        assert!(!matches_filters(&record, &cfg));
+        let cfg = LogsTailConfig {
+            instance: Some("42".to_string()),
+            ..cfg
+        };
+        assert!(matches_filters(&record, &cfg));
+    }
+
+    #[test]
+    fn filter_matches_correlation_fields_and_time_range() {
+        let record = StoredLogRecord {
+            ts_utc: 1_000,
+            level: "debug".to_string(),
+            target: "test".to_string(),
+            message: "tools/call request finished (ok)".to_string(),
+            mode: "gateway".to_string(),
+            transport: "sse".to_string(),
+            instance_id: "sse-42".to_string(),
+            pid: 42,
+            server_name: None,
+            fields: [
+                ("session_id".to_string(), "abc".to_string()),
+                ("request_id".to_string(), "7".to_string()),
+                ("method".to_string(), "tools/call".to_string()),
+            ]
+            .into(),
+        };
+        let cfg = LogsTailConfig {
+            file: None,
+            follow: false,
+            lines: 10,
+            level: None,
+            transport: None,
+            instance: None,
+            session: Some("abc".to_string()),
+            request_id: Some("7".to_string()),
+            method: Some("tools/call".to_string()),
+            since: Some(1_000),
+            until: Some(2_000),
+            json: false,
+            reveal_secrets: false,
+        };
+        assert!(matches_filters(&record, &cfg));
+
+        let other_session = LogsTailConfig {
+            session: Some("def".to_string()),
+            ..cfg.clone()
+        };
+        assert!(!matches_filters(&record, &other_session));
+        let too_late = LogsTailConfig {
+            since: Some(1_001),
+            ..cfg
+        };
+        assert!(!matches_filters(&record, &too_late));
+    }
+}
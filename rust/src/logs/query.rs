@@ -0,0 +1,446 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+
+use serde_json::{json, Value};
+
+use crate::config::{LogPredicate, LogsOutputFormat, LogsQueryConfig, PredicateOp};
+use crate::support::log_index::LogIndex;
+use crate::support::log_rotation::{log_segments, read_segment_lines};
+use crate::support::log_store::{
+    default_log_path, format_timestamp, parse_record, StoredLogRecord,
+};
+use crate::support::redact::{self, Redactor};
+
+const LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+
+pub(super) fn run_query(config: LogsQueryConfig) -> Result<(), String> {
+    redact::install(Redactor::new(&[], &[], config.reveal_secrets)?);
+    let path = config.file.clone().unwrap_or_else(default_log_path);
+    let segments = log_segments(&path);
+
+    let mut index = (!config.no_index).then(|| {
+        let mut index = LogIndex::load(&path);
+        index.retain_segments(&segments);
+        index
+    });
+
+    let mut output = QueryOutput::new(&config);
+    for segment in &segments {
+        let lines = match index.as_mut() {
+            Some(index) => {
+                index.refresh(segment)?;
+                index.read_window(segment, config.since, config.until)?
+            }
+            None => read_segment_lines(segment)?,
+        };
+        for line in lines {
+            let Some(record) = parse_record(&line) else {
+                continue;
+            };
+            if matches_query(&record, &config) {
+                output.push(record);
+            }
+        }
+    }
+
+    if let Some(index) = index {
+        // The index only speeds up later queries, so failing to write it is not an error.
+        let _ = index.save(&path);
+    }
+    output.print(config.format);
+    Ok(())
+}
+
+/// Looks up a query key: a record field (`level`, `instance`, `ts`, ...), a correlation
+/// shorthand (`session`, `request`, `method`, `tool`), or `fields.NAME`.
+fn record_value(record: &StoredLogRecord, key: &str) -> Option<String> {
+    match key {
+        "ts" | "ts_utc" => Some(record.ts_utc.to_string()),
+        "level" => Some(record.level.clone()),
+        "target" => Some(record.target.clone()),
+        "message" => Some(record.message.clone()),
+        "mode" => Some(record.mode.clone()),
+        "transport" => Some(record.transport.clone()),
+        "instance" | "instance_id" => Some(record.instance_id.clone()),
+        "pid" => Some(record.pid.to_string()),
+        "server" | "server_name" => record.server_name.clone(),
+        "session" => record.field("session_id").map(str::to_string),
+        "request" => record.field("request_id").map(str::to_string),
+        other => record
+            .field(other.strip_prefix("fields.").unwrap_or(other))
+            .map(str::to_string),
+    }
+}
+
+fn compare_values(key: &str, actual: &str, expected: &str) -> Option<Ordering> {
+    if key == "level" {
+        let rank = |level: &str| {
+            LEVELS
+                .iter()
+                .position(|candidate| candidate.eq_ignore_ascii_case(level))
+        };
+        return Some(rank(actual)?.cmp(&rank(expected)?));
+    }
+    match (actual.parse::<f64>(), expected.parse::<f64>()) {
+        (Ok(actual), Ok(expected)) => actual.partial_cmp(&expected),
+        _ => Some(actual.cmp(expected)),
+    }
+}
+
+fn matches_predicate(record: &StoredLogRecord, predicate: &LogPredicate) -> bool {
+    let Some(actual) = record_value(record, &predicate.key) else {
+        return predicate.op == PredicateOp::Ne;
+    };
+    if predicate.op == PredicateOp::Contains {
+        return actual
+            .to_ascii_lowercase()
+            .contains(&predicate.value.to_ascii_lowercase());
+    }
+    let Some(ordering) = compare_values(&predicate.key, &actual, &predicate.value) else {
+        return false;
+    };
+    match predicate.op {
+        PredicateOp::Eq => ordering == Ordering::Equal,
+        PredicateOp::Ne => ordering != Ordering::Equal,
+        PredicateOp::Gt => ordering == Ordering::Greater,
+        PredicateOp::Ge => ordering != Ordering::Less,
+        PredicateOp::Lt => ordering == Ordering::Less,
+        PredicateOp::Le => ordering != Ordering::Greater,
+        PredicateOp::Contains => unreachable!("handled above"),
+    }
+}
+
+fn matches_query(record: &StoredLogRecord, config: &LogsQueryConfig) -> bool {
+    if !record.in_time_range(config.since, config.until) {
+        return false;
+    }
+    if let Some(text) = config.text.as_deref() {
+        let text = text.to_ascii_lowercase();
+        let found = record.message.to_ascii_lowercase().contains(&text)
+            || record
+                .fields
+                .values()
+                .any(|value| value.to_ascii_lowercase().contains(&text));
+        if !found {
+            return false;
+        }
+    }
+    config
+        .predicates
+        .iter()
+        .all(|predicate| matches_predicate(record, predicate))
+}
+
+#[derive(Default)]
+struct Group {
+    count: usize,
+    values: Vec<f64>,
+}
+
+/// Either the matching records (newest `limit` of them) or per-group aggregates.
+enum QueryOutput {
+    Records {
+        records: VecDeque<StoredLogRecord>,
+        limit: Option<usize>,
+    },
+    Groups {
+        key: Option<String>,
+        field: Option<String>,
+        groups: BTreeMap<String, Group>,
+    },
+}
+
+impl QueryOutput {
+    fn new(config: &LogsQueryConfig) -> Self {
+        if config.count_by.is_none() && config.percentiles.is_none() {
+            return Self::Records {
+                records: VecDeque::new(),
+                limit: config.limit,
+            };
+        }
+        Self::Groups {
+            key: config.count_by.clone(),
+            field: config.percentiles.clone(),
+            groups: BTreeMap::new(),
+        }
+    }
+
+    fn push(&mut self, record: StoredLogRecord) {
+        match self {
+            Self::Records { records, limit } => {
+                if *limit == Some(0) {
+                    return;
+                }
+                records.push_back(record);
+                if limit.is_some_and(|limit| records.len() > limit) {
+                    records.pop_front();
+                }
+            }
+            Self::Groups { key, field, groups } => {
+                let name = match key.as_deref() {
+                    Some(key) => record_value(&record, key).unwrap_or_else(|| "-".to_string()),
+                    None => "all".to_string(),
+                };
+                let group = groups.entry(name).or_default();
+                group.count += 1;
+                if let Some(value) = field
+                    .as_deref()
+                    .and_then(|field| record_value(&record, field))
+                    .and_then(|value| value.parse::<f64>().ok())
+                {
+                    group.values.push(value);
+                }
+            }
+        }
+    }
+
+    fn print(self, format: LogsOutputFormat) {
+        match self {
+            Self::Records { records, .. } => print_records(&records, format),
+            Self::Groups { key, field, groups } => {
+                print_groups(key.as_deref(), field.is_some(), groups, format)
+            }
+        }
+    }
+}
+
+fn print_records(records: &VecDeque<StoredLogRecord>, format: LogsOutputFormat) {
+    if format == LogsOutputFormat::Json {
+        for record in records {
+            if let Ok(line) = serde_json::to_string(record) {
+                println!("{line}");
+            }
+        }
+        return;
+    }
+
+    let headers = [
+        "time", "level", "instance", "session", "request", "method", "message",
+    ];
+    let rows: Vec<Vec<String>> = records
+        .iter()
+        .map(|record| {
+            let field = |name: &str| record.field(name).unwrap_or("-").to_string();
+            vec![
+                format_timestamp(record.ts_utc),
+                record.level.clone(),
+                record.instance_id.clone(),
+                field("session_id"),
+                field("request_id"),
+                field("method"),
+                record.message.clone(),
+            ]
+        })
+        .collect();
+    print_rows(&headers, &rows, format);
+}
+
+/// Nearest-rank percentile of sorted values.
+fn percentile(sorted: &[f64], pct: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.saturating_sub(1)).copied()
+}
+
+fn print_groups(
+    key: Option<&str>,
+    with_percentiles: bool,
+    groups: BTreeMap<String, Group>,
+    format: LogsOutputFormat,
+) {
+    let mut groups: Vec<(String, Group)> = groups.into_iter().collect();
+    groups.sort_by(|(a_key, a), (b_key, b)| b.count.cmp(&a.count).then_with(|| a_key.cmp(b_key)));
+
+    let key_name = key.unwrap_or("group");
+    let mut headers = vec![key_name, "count"];
+    if with_percentiles {
+        headers.extend(["p50", "p95", "max"]);
+    }
+
+    let render = |value: Option<f64>| value.map(|value| value.to_string()).unwrap_or_default();
+    let mut rows = Vec::new();
+    let mut objects = Vec::new();
+    for (name, mut group) in groups {
+        group.values.sort_by(f64::total_cmp);
+        let p50 = percentile(&group.values, 50.0);
+        let p95 = percentile(&group.values, 95.0);
+        let max = group.values.last().copied();
+
+        let mut row = vec![name.clone(), group.count.to_string()];
+        let mut object = json!({ key_name: name, "count": group.count });
+        if with_percentiles {
+            row.extend([render(p50), render(p95), render(max)]);
+            object["p50"] = json!(p50);
+            object["p95"] = json!(p95);
+            object["max"] = json!(max);
+        }
+        rows.push(row);
+        objects.push(object);
+    }
+
+    if format == LogsOutputFormat::Json {
+        for object in objects {
+            println!("{}", Value::to_string(&object));
+        }
+    } else {
+        print_rows(&headers, &rows, format);
+    }
+}
+
+fn print_rows(headers: &[&str], rows: &[Vec<String>], format: LogsOutputFormat) {
+    if format == LogsOutputFormat::Csv {
+        println!("{}", headers.join(","));
+        for row in rows {
+            let cells: Vec<String> = row.iter().map(|cell| csv_cell(cell)).collect();
+            println!("{}", cells.join(","));
+        }
+        return;
+    }
+
+    // Every column but the last is padded to its widest cell.
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let last = cells.len().saturating_sub(1);
+        cells
+            .iter()
+            .enumerate()
+            .map(|(column, cell)| {
+                if column == last {
+                    cell.to_string()
+                } else {
+                    format!("{cell:<width$}", width = widths[column])
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+    let upper: Vec<String> = headers.iter().map(|header| header.to_uppercase()).collect();
+    println!("{}", line(upper.iter().map(String::as_str).collect()));
+    for row in rows {
+        println!("{}", line(row.iter().map(String::as_str).collect()));
+    }
+}
+
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(ts: u64, level: &str, tool: &str, duration_ms: &str) -> StoredLogRecord {
+        StoredLogRecord {
+            ts_utc: ts,
+            level: level.to_string(),
+            target: "test".to_string(),
+            message: format!("tools/call request finished in {duration_ms}ms"),
+            mode: "gateway".to_string(),
+            transport: "sse".to_string(),
+            instance_id: "docs".to_string(),
+            pid: 42,
+            server_name: None,
+            fields: [
+                ("tool".to_string(), tool.to_string()),
+                ("duration_ms".to_string(), duration_ms.to_string()),
+            ]
+            .into(),
+        }
+    }
+
+    fn predicate(key: &str, op: PredicateOp, value: &str) -> LogPredicate {
+        LogPredicate {
+            key: key.to_string(),
+            op,
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn predicates_compare_levels_numbers_and_text() {
+        let warn = record(10, "warn", "search", "120");
+        assert!(matches_predicate(
+            &warn,
+            &predicate("level", PredicateOp::Ge, "info")
+        ));
+        assert!(!matches_predicate(
+            &warn,
+            &predicate("level", PredicateOp::Ge, "error")
+        ));
+        assert!(matches_predicate(
+            &warn,
+            &predicate("fields.duration_ms", PredicateOp::Gt, "99")
+        ));
+        assert!(matches_predicate(
+            &warn,
+            &predicate("tool", PredicateOp::Eq, "search")
+        ));
+        assert!(matches_predicate(
+            &warn,
+            &predicate("message", PredicateOp::Contains, "FINISHED")
+        ));
+        assert!(matches_predicate(
+            &warn,
+            &predicate("session", PredicateOp::Ne, "abc")
+        ));
+        assert!(!matches_predicate(
+            &warn,
+            &predicate("session", PredicateOp::Eq, "abc")
+        ));
+    }
+
+    #[test]
+    fn groups_count_records_and_compute_percentiles() {
+        let config = LogsQueryConfig {
+            file: None,
+            predicates: Vec::new(),
+            text: None,
+            since: None,
+            until: None,
+            limit: None,
+            format: LogsOutputFormat::Json,
+            count_by: Some("tool".to_string()),
+            percentiles: Some("duration_ms".to_string()),
+            no_index: true,
+            reveal_secrets: false,
+        };
+        let mut output = QueryOutput::new(&config);
+        for (index, duration) in (1..=20).enumerate() {
+            output.push(record(
+                index as u64,
+                "debug",
+                "search",
+                &duration.to_string(),
+            ));
+        }
+        output.push(record(30, "debug", "fetch", "7"));
+
+        let QueryOutput::Groups { groups, .. } = output else {
+            panic!("expected grouped output");
+        };
+        assert_eq!(groups["search"].count, 20);
+        assert_eq!(groups["fetch"].count, 1);
+        let mut values = groups["search"].values.clone();
+        values.sort_by(f64::total_cmp);
+        assert_eq!(percentile(&values, 50.0), Some(10.0));
+        assert_eq!(percentile(&values, 95.0), Some(19.0));
+    }
+
+    #[test]
+    fn csv_cells_are_quoted_when_needed() {
+        assert_eq!(csv_cell("plain"), "plain");
+        assert_eq!(csv_cell("a,b"), "\"a,b\"");
+        assert_eq!(csv_cell("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
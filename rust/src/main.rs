@@ -1,5 +1,6 @@
 mod config;
 mod connect;
+mod ctl;
 mod discovery;
 mod gateways;
 mod generator;
@@ -16,6 +17,7 @@ use std::collections::{BTreeMap, HashMap};
 use std::net::ToSocketAddrs;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
 
 use futures::future::BoxFuture;
 use tokio::sync::{mpsc, oneshot};
@@ -34,10 +36,22 @@ use crate::gateways::{
     stdio_to_ws, streamable_http_to_stdio,
 };
 use crate::runtime::admin::{spawn_admin_server, AdminServerOptions};
+use crate::runtime::control::ControlContext;
+use crate::runtime::control_socket::spawn_control_socket;
 use crate::runtime::prompt::spawn_prompt;
+use crate::runtime::reload::ConfigReloader;
 use crate::runtime::store::RuntimeArgsStore;
-use crate::runtime::{RuntimeApplyResult, RuntimeUpdate, RuntimeUpdateRequest};
-use crate::support::telemetry::init_telemetry;
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeUpdateRequest};
+use crate::support::cors::enable_live_cors;
+use crate::support::instance_index::{self, InstanceEntry};
+use crate::support::log_store::{configure_log_instance, LogInstance};
+use crate::support::redact::{self, redactor, Redactor};
+use crate::support::secrets::resolve_secret_map;
+use crate::support::session_queues::SessionQueues;
+use crate::support::session_registry::SessionRegistry;
+#[cfg(unix)]
+use crate::support::signals::reload_on_sighup;
+use crate::support::telemetry::{init_telemetry, set_log_level};
 use crate::types::RuntimeArgs;
 
 #[tokio::main]
@@ -94,7 +108,13 @@ async fn main() {
             }
         }
         CliCommand::Web(config) => {
-            if let Err(err) = web::run(config).await {
+            if let Err(err) = web::run(*config).await {
+                eprintln!("[mcpway] Error: {err}");
+                std::process::exit(1);
+            }
+        }
+        CliCommand::Ctl(config) => {
+            if let Err(err) = ctl::run(config).await {
                 eprintln!("[mcpway] Error: {err}");
                 std::process::exit(1);
             }
@@ -102,7 +122,13 @@ async fn main() {
     }
 }
 
-async fn run_gateway(config: Config) -> Result<(), String> {
+async fn run_gateway(mut config: Config) -> Result<(), String> {
+    redact::install(config.redactor.clone());
+    configure_log_instance(LogInstance {
+        name: config.instance_name.clone(),
+        server_name: config.server_name.clone(),
+        per_instance_file: config.log_per_instance,
+    });
     let _telemetry = init_telemetry(
         config.log_level,
         config.output_transport,
@@ -113,50 +139,82 @@ async fn run_gateway(config: Config) -> Result<(), String> {
     tracing::info!("mcpway gateway runtime initialized",);
     tracing::info!("  - output-transport: {:?}", config.output_transport);
 
+    let reloader = match config.config_file.clone() {
+        Some(path) => {
+            let cli_log_level = config.log_level;
+            let reloader = ConfigReloader::load(&path, &mut config)?;
+            if config.log_level != cli_log_level {
+                set_log_level(config.log_level)?;
+            }
+            enable_live_cors(&config.cors);
+            #[cfg(unix)]
+            reload_on_sighup();
+            tracing::info!("  - config: {}", path.display());
+            Some(reloader)
+        }
+        None => None,
+    };
+
+    // Header secret references are resolved once at startup; env references are resolved
+    // each time a child is spawned. The runtime headers also carry the config file's set,
+    // while `config.headers` stays the command-line base.
+    config.headers = resolve_secret_map(&config.headers, "--header").await?;
+    let runtime_headers = match &reloader {
+        Some(reloader) => resolve_secret_map(&reloader.initial_headers(), "header").await?,
+        None => config.headers.clone(),
+    };
     let runtime_store = RuntimeArgsStore::new(RuntimeArgs {
-        headers: config.headers.clone(),
+        headers: runtime_headers,
         env: config.env.clone(),
-        ..Default::default()
+        extra_cli_args: reloader
+            .as_ref()
+            .map(ConfigReloader::initial_args)
+            .unwrap_or_default(),
     });
 
     let (update_tx, update_rx) = mpsc::channel::<RuntimeUpdateRequest>(32);
-
-    if config.runtime_prompt {
-        let mut prompt_rx = spawn_prompt();
-        let update_tx = update_tx.clone();
-        tokio::spawn(async move {
-            while let Some(update) = prompt_rx.recv().await {
-                let (resp_tx, resp_rx) = oneshot::channel();
-                if update_tx
-                    .send(RuntimeUpdateRequest {
-                        update,
-                        respond_to: resp_tx,
-                    })
-                    .await
-                    .is_err()
-                {
-                    tracing::error!("Runtime update channel closed");
-                    break;
-                }
-                if let Ok(result) = resp_rx.await {
-                    tracing::info!("Runtime update: {}", result.message);
-                }
-            }
-        });
+    let reload_trigger =
+        reloader.map(|reloader| reloader.spawn(update_tx.clone(), config.watch_config));
+    let queues = SessionQueues::new(config.backpressure);
+    let sessions = SessionRegistry::default();
+
+    let mut control_socket = None;
+    if config.runtime_prompt || config.control_socket.is_some() {
+        let context = ControlContext {
+            name: config
+                .instance_name
+                .clone()
+                .or_else(|| config.server_name.clone()),
+            transport: output_transport_label(config.output_transport),
+            runtime: runtime_store.clone(),
+            sessions: sessions.clone(),
+            queues: queues.clone(),
+            update_tx: update_tx.clone(),
+            reload: reload_trigger,
+            started: Instant::now(),
+        };
+        if let Some(path) = config.control_socket.as_deref() {
+            control_socket = Some(spawn_control_socket(path, context.clone())?);
+            tracing::info!("  - control-socket: {}", path.display());
+        }
+        if config.runtime_prompt {
+            spawn_prompt(context);
+        }
     }
 
+    let mut instance_registration = None;
     if let Some(port) = config.runtime_admin_port {
         let addr = resolve_bind_addr(&config.runtime_admin_host, port)?;
         let update_tx = update_tx.clone();
         let handler: Arc<
-            dyn Fn(RuntimeUpdate) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync,
-        > = Arc::new(move |update: RuntimeUpdate| {
+            dyn Fn(RuntimeCommand) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync,
+        > = Arc::new(move |command: RuntimeCommand| {
             let update_tx = update_tx.clone();
             Box::pin(async move {
                 let (resp_tx, resp_rx) = oneshot::channel();
                 if update_tx
                     .send(RuntimeUpdateRequest {
-                        update,
+                        command,
                         respond_to: resp_tx,
                     })
                     .await
@@ -170,6 +228,8 @@ async fn run_gateway(config: Config) -> Result<(), String> {
             }) as BoxFuture<'static, RuntimeApplyResult>
         });
         let runtime_clone = runtime_store.clone();
+        let admin_queues = queues.clone();
+        let admin_sessions = sessions.clone();
         let admin_options = AdminServerOptions {
             bearer_token: config.runtime_admin_token.clone(),
             loopback_only: addr.ip().is_loopback(),
@@ -177,18 +237,46 @@ async fn run_gateway(config: Config) -> Result<(), String> {
             discovery_source: None,
         };
         tokio::spawn(async move {
-            spawn_admin_server(addr, runtime_clone, handler, admin_options).await;
+            spawn_admin_server(
+                addr,
+                runtime_clone,
+                handler,
+                admin_options,
+                admin_queues,
+                admin_sessions,
+            )
+            .await;
         });
+
+        // Lets `mcpway web` find this gateway without being told its admin URL.
+        let entry = InstanceEntry::new(
+            config
+                .instance_name
+                .clone()
+                .or_else(|| config.server_name.clone()),
+            output_transport_label(config.output_transport),
+            addr,
+        );
+        match instance_index::register(&instance_index::default_index_dir(), &entry) {
+            Ok(registration) => instance_registration = Some(registration),
+            Err(err) => tracing::warn!("Failed to register in the local instance index: {err}"),
+        }
     }
 
     let result = if config.stdio.is_some() {
         match config.output_transport {
-            OutputTransport::Sse => stdio_to_sse::run(config, runtime_store, update_rx).await,
-            OutputTransport::Ws => stdio_to_ws::run(config, runtime_store, update_rx).await,
+            OutputTransport::Sse => {
+                stdio_to_sse::run(config, runtime_store, update_rx, queues, sessions).await
+            }
+            OutputTransport::Ws => {
+                stdio_to_ws::run(config, runtime_store, update_rx, queues, sessions).await
+            }
             OutputTransport::StreamableHttp => {
-                stdio_to_streamable_http::run(config, runtime_store, update_rx).await
+                stdio_to_streamable_http::run(config, runtime_store, update_rx, sessions).await
+            }
+            OutputTransport::Grpc => {
+                stdio_to_grpc::run(config, runtime_store, update_rx, queues, sessions).await
             }
-            OutputTransport::Grpc => stdio_to_grpc::run(config, runtime_store, update_rx).await,
             OutputTransport::Stdio => stdio_to_stdio::run(config, runtime_store, update_rx).await,
         }
     } else if config.sse.is_some() {
@@ -215,10 +303,13 @@ async fn run_gateway(config: Config) -> Result<(), String> {
         Err("Invalid input transport".to_string())
     };
 
+    drop(instance_registration);
+    drop(control_socket);
     result
 }
 
 fn run_discover(config: DiscoverConfig) -> Result<(), String> {
+    redact::install(Redactor::new(&[], &[], config.reveal_secrets)?);
     let options = DiscoverOptions {
         from: import_source_to_kind(config.from),
         project_root: config.project_root.clone(),
@@ -233,7 +324,7 @@ fn run_discover(config: DiscoverConfig) -> Result<(), String> {
     let report = discovery::apply_search(&full_report, &search);
 
     if config.print_json {
-        let json = serde_json::to_string_pretty(&report)
+        let json = serde_json::to_string_pretty(&redactor().to_json_value(&report))
             .map_err(|err| format!("Failed to serialize discover report: {err}"))?;
         println!("{json}");
     } else {
@@ -244,6 +335,7 @@ fn run_discover(config: DiscoverConfig) -> Result<(), String> {
 }
 
 fn run_import(config: ImportConfig) -> Result<(), String> {
+    redact::install(Redactor::new(&[], &[], config.reveal_secrets)?);
     let options = DiscoverOptions {
         from: import_source_to_kind(config.from),
         project_root: config.project_root.clone(),
@@ -271,7 +363,7 @@ fn run_import(config: ImportConfig) -> Result<(), String> {
     }
 
     if config.print_json {
-        let payload = serde_json::json!({
+        let mut payload = serde_json::json!({
             "registry_path": registry_path.to_string_lossy().to_string(),
             "project_root": report.project_root,
             "imported": report.servers.len(),
@@ -279,6 +371,7 @@ fn run_import(config: ImportConfig) -> Result<(), String> {
             "conflicts": report.conflicts,
             "issues": report.issues,
         });
+        redactor().redact_json(&mut payload);
         println!(
             "{}",
             serde_json::to_string_pretty(&payload)
@@ -422,7 +515,7 @@ fn print_discover_human(report: &discovery::DiscoveryReport, total_servers: usiz
                 "  - {} {}: {}",
                 issue.source.as_str(),
                 issue.origin_path,
-                issue.message
+                redactor().redact_text(&issue.message)
             );
         }
     }
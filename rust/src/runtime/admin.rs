@@ -6,17 +6,27 @@ use std::sync::Arc;
 
 use crate::discovery::{self, DiscoverOptions, DiscoverySearchOptions, SourceKind};
 use crate::runtime::store::{RuntimeArgsStore, RuntimeArgsUpdate};
-use crate::runtime::{RuntimeApplyResult, RuntimeScope, RuntimeUpdate};
+use crate::runtime::{RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdate};
+use crate::support::child_stderr::{self, StderrLevel};
+use crate::support::metrics;
+use crate::support::redact::redactor;
+use crate::support::session_queues::SessionQueues;
+use crate::support::session_registry::SessionRegistry;
+use crate::support::traffic::{self, TrafficEvent, TrafficFilter};
 use axum::body::Body;
-use axum::extract::{ConnectInfo, MatchedPath, Path, State};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{ConnectInfo, MatchedPath, Path, Query, State};
 use axum::http::{HeaderMap, StatusCode};
 use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use futures::future::BoxFuture;
+use futures::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
+use tokio_stream::wrappers::BroadcastStream;
 
 #[derive(Clone, Debug, Default)]
 pub struct AdminServerOptions {
@@ -29,9 +39,11 @@ pub struct AdminServerOptions {
 #[derive(Clone)]
 pub struct AdminState {
     runtime: RuntimeArgsStore,
-    handler: Arc<dyn Fn(RuntimeUpdate) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync>,
+    handler: Arc<dyn Fn(RuntimeCommand) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync>,
     options: AdminServerOptions,
     metrics: Arc<AdminMetrics>,
+    queues: SessionQueues,
+    sessions: SessionRegistry,
 }
 
 #[derive(Debug, Default)]
@@ -59,6 +71,7 @@ struct AdminMetricsSnapshot {
 impl AdminMetrics {
     async fn record_request(&self, route: &str, status: StatusCode) {
         self.requests_total.fetch_add(1, Ordering::Relaxed);
+        metrics::record_admin_request(route, status.as_u16());
         if status == StatusCode::UNAUTHORIZED {
             self.unauthorized_total.fetch_add(1, Ordering::Relaxed);
         }
@@ -76,10 +89,12 @@ impl AdminMetrics {
 
     fn record_runtime_update(&self) {
         self.runtime_updates_total.fetch_add(1, Ordering::Relaxed);
+        metrics::record_runtime_update();
     }
 
     fn record_discovery_search(&self) {
         self.discovery_search_total.fetch_add(1, Ordering::Relaxed);
+        metrics::record_discovery_search();
     }
 
     async fn snapshot(&self) -> AdminMetricsSnapshot {
@@ -104,14 +119,18 @@ impl AdminMetrics {
 pub async fn spawn_admin_server(
     addr: SocketAddr,
     runtime: RuntimeArgsStore,
-    handler: Arc<dyn Fn(RuntimeUpdate) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync>,
+    handler: Arc<dyn Fn(RuntimeCommand) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync>,
     options: AdminServerOptions,
+    queues: SessionQueues,
+    sessions: SessionRegistry,
 ) {
     let state = AdminState {
         runtime,
         handler,
         options,
         metrics: Arc::new(AdminMetrics::default()),
+        queues,
+        sessions,
     };
 
     let router = build_router()
@@ -141,9 +160,27 @@ pub async fn spawn_admin_server(
 
 fn build_router() -> Router<AdminState> {
     Router::new()
-        .route("/v1/runtime/defaults", post(update_defaults))
-        .route("/v1/runtime/session/{id}", post(update_session))
+        .route(
+            "/v1/runtime/defaults",
+            post(update_defaults).patch(patch_defaults),
+        )
+        .route(
+            "/v1/runtime/session/{id}",
+            post(update_session).patch(patch_session),
+        )
+        .route("/v1/runtime/history", get(runtime_history))
+        .route("/v1/runtime/rollback/{version}", post(rollback))
         .route("/v1/runtime/sessions", get(list_sessions))
+        .route(
+            "/v1/runtime/sessions/{id}",
+            get(get_session).delete(evict_session),
+        )
+        .route("/v1/runtime/sessions/{id}/restart", post(restart_session))
+        .route("/v1/runtime/child/restart", post(restart_child))
+        .route("/v1/runtime/queues", get(list_queues))
+        .route("/v1/runtime/children", get(list_children))
+        .route("/v1/runtime/children/{id}/stderr", get(child_stderr_lines))
+        .route("/v1/runtime/traffic/ws", get(traffic_ws))
         .route("/v1/runtime/health", get(runtime_health))
         .route("/v1/runtime/metrics", get(runtime_metrics_json))
         .route("/v1/runtime/metrics.prom", get(runtime_metrics_prometheus))
@@ -288,34 +325,213 @@ struct DiscoverySearchRequest {
     search: DiscoverySearchOptions,
 }
 
+// Recorded as the actor of changes made through this API.
+const ACTOR: &str = "admin-api";
+
 async fn update_defaults(
     State(state): State<AdminState>,
     Json(update): Json<RuntimeArgsUpdate>,
-) -> impl IntoResponse {
-    state.metrics.record_runtime_update();
-    let update_msg = RuntimeUpdate {
-        scope: RuntimeScope::Global,
-        update,
-    };
-    Json((state.handler)(update_msg).await)
+) -> Response {
+    apply_update(&state, RuntimeScope::Global, update.with_actor(ACTOR)).await
 }
 
 async fn update_session(
     State(state): State<AdminState>,
     Path(id): Path<String>,
     Json(update): Json<RuntimeArgsUpdate>,
-) -> impl IntoResponse {
+) -> Response {
+    apply_update(&state, RuntimeScope::Session(id), update.with_actor(ACTOR)).await
+}
+
+// PATCH bodies are RFC 7396 merge patches (`application/merge-patch+json`): `null` deletes
+// a key and keys left out are kept.
+async fn patch_defaults(
+    State(state): State<AdminState>,
+    Json(patch): Json<serde_json::Value>,
+) -> Response {
+    match RuntimeArgsUpdate::merge_patch(patch, ACTOR) {
+        Ok(update) => apply_update(&state, RuntimeScope::Global, update).await,
+        Err(err) => json_error(StatusCode::BAD_REQUEST, &err),
+    }
+}
+
+async fn patch_session(
+    State(state): State<AdminState>,
+    Path(id): Path<String>,
+    Json(patch): Json<serde_json::Value>,
+) -> Response {
+    match RuntimeArgsUpdate::merge_patch(patch, ACTOR) {
+        Ok(update) => apply_update(&state, RuntimeScope::Session(id), update).await,
+        Err(err) => json_error(StatusCode::BAD_REQUEST, &err),
+    }
+}
+
+async fn apply_update(
+    state: &AdminState,
+    scope: RuntimeScope,
+    update: RuntimeArgsUpdate,
+) -> Response {
+    if let Err(err) = update.reject_secret_refs() {
+        return json_error(StatusCode::BAD_REQUEST, &err);
+    }
     state.metrics.record_runtime_update();
-    let update_msg = RuntimeUpdate {
-        scope: RuntimeScope::Session(id),
-        update,
-    };
-    Json((state.handler)(update_msg).await)
+    let update_msg = RuntimeUpdate { scope, update };
+    Json(redactor().to_json_value(&(state.handler)(RuntimeCommand::Update(update_msg)).await))
+        .into_response()
+}
+
+async fn runtime_history(State(state): State<AdminState>) -> impl IntoResponse {
+    Json(redactor().to_json_value(&state.runtime.history().await))
+}
+
+async fn rollback(State(state): State<AdminState>, Path(version): Path<u64>) -> Response {
+    match state.runtime.rollback_update(version, ACTOR).await {
+        Some(update_msg) => {
+            state.metrics.record_runtime_update();
+            Json(
+                redactor()
+                    .to_json_value(&(state.handler)(RuntimeCommand::Update(update_msg)).await),
+            )
+            .into_response()
+        }
+        None => json_error(StatusCode::NOT_FOUND, &format!("Unknown version {version}")),
+    }
 }
 
 async fn list_sessions(State(state): State<AdminState>) -> impl IntoResponse {
-    let sessions = state.runtime.list_sessions().await;
-    Json(sessions)
+    Json(redactor().to_json_value(&state.runtime.list_session_args().await))
+}
+
+async fn get_session(State(state): State<AdminState>, Path(id): Path<String>) -> Response {
+    match state.sessions.get(&id).await {
+        Some(info) => Json(redactor().to_json_value(&info)).into_response(),
+        None => json_error(StatusCode::NOT_FOUND, &format!("Unknown session {id}")),
+    }
+}
+
+async fn evict_session(State(state): State<AdminState>, Path(id): Path<String>) -> Response {
+    if !state.sessions.contains(&id).await {
+        return json_error(StatusCode::NOT_FOUND, &format!("Unknown session {id}"));
+    }
+    Json((state.handler)(RuntimeCommand::EvictSession(id)).await).into_response()
+}
+
+async fn restart_session(State(state): State<AdminState>, Path(id): Path<String>) -> Response {
+    if !state.sessions.contains(&id).await {
+        return json_error(StatusCode::NOT_FOUND, &format!("Unknown session {id}"));
+    }
+    Json((state.handler)(RuntimeCommand::RestartSession(id)).await).into_response()
+}
+
+async fn restart_child(State(state): State<AdminState>) -> impl IntoResponse {
+    Json((state.handler)(RuntimeCommand::RestartChild).await)
+}
+
+async fn list_queues(State(state): State<AdminState>) -> impl IntoResponse {
+    Json(redactor().to_json_value(&state.queues.snapshot().await))
+}
+
+async fn list_children() -> impl IntoResponse {
+    Json(child_stderr::list())
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StderrQuery {
+    lines: Option<usize>,
+    level: Option<StderrLevel>,
+    #[serde(default)]
+    follow: bool,
+}
+
+async fn child_stderr_lines(Path(id): Path<String>, Query(query): Query<StderrQuery>) -> Response {
+    let Some(buffer) = child_stderr::get(&id) else {
+        return json_error(StatusCode::NOT_FOUND, &format!("Unknown child {id}"));
+    };
+    // Child servers print whatever they like, tokens included, so lines are redacted like
+    // every other admin response.
+    if !query.follow {
+        return Json(redactor().to_json_value(&serde_json::json!({
+            "child": buffer.summary(),
+            "lines": buffer.snapshot(query.level, query.lines),
+        })))
+        .into_response();
+    }
+
+    // Subscribe before taking the snapshot; lines pushed in between are skipped by seq.
+    let rx = buffer.subscribe();
+    let backlog = buffer.snapshot(query.level, query.lines);
+    let last_seq = backlog.last().map(|line| line.seq).unwrap_or(0);
+    let min_level = query.level;
+    let live = BroadcastStream::new(rx).filter_map(move |item| async move {
+        match item {
+            Ok(line) if line.seq > last_seq && min_level.is_none_or(|min| line.level >= min) => {
+                Event::default()
+                    .json_data(redactor().to_json_value(&line))
+                    .ok()
+            }
+            Ok(_) => None,
+            Err(tokio_stream::wrappers::errors::BroadcastStreamRecvError::Lagged(skipped)) => {
+                Some(Event::default().event("lagged").data(skipped.to_string()))
+            }
+        }
+    });
+    let backlog = futures::stream::iter(backlog.into_iter().filter_map(|line| {
+        Event::default()
+            .json_data(redactor().to_json_value(&line))
+            .ok()
+    }));
+    let stream = backlog.chain(live).map(Ok::<_, std::convert::Infallible>);
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn traffic_ws(ws: WebSocketUpgrade, Query(filter): Query<TrafficFilter>) -> Response {
+    // Subscribe before the upgrade so messages crossing during the handshake are kept.
+    let rx = traffic::subscribe();
+    ws.on_upgrade(move |socket| stream_traffic(socket, rx, filter))
+}
+
+async fn stream_traffic(
+    socket: WebSocket,
+    mut rx: broadcast::Receiver<TrafficEvent>,
+    filter: TrafficFilter,
+) {
+    let (mut sender, mut receiver) = socket.split();
+
+    let send_task = tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    if !filter.matches(&event) {
+                        continue;
+                    }
+                    let Ok(line) = serde_json::to_string(&event) else {
+                        continue;
+                    };
+                    if sender.send(Message::Text(line.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    let notice = serde_json::json!({ "lagged": skipped }).to_string();
+                    if sender.send(Message::Text(notice.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    while let Some(message) = receiver.next().await {
+        match message {
+            Ok(Message::Close(_)) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    send_task.abort();
 }
 
 async fn runtime_health(State(state): State<AdminState>) -> impl IntoResponse {
@@ -368,7 +584,7 @@ async fn discovery_search(
     Json(serde_json::json!({
         "total": total,
         "returned": filtered.servers.len(),
-        "report": filtered,
+        "report": redactor().to_json_value(&filtered),
     }))
     .into_response()
 }
@@ -376,6 +592,7 @@ async fn discovery_search(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::BackpressurePolicy;
     use std::time::Duration;
 
     #[test]
@@ -441,17 +658,300 @@ mod tests {
 
     #[tokio::test]
     async fn legacy_runtime_routes_are_removed() {
-        let runtime = RuntimeArgsStore::default();
         let handler: Arc<
-            dyn Fn(RuntimeUpdate) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync,
-        > = Arc::new(|_update: RuntimeUpdate| {
+            dyn Fn(RuntimeCommand) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync,
+        > = Arc::new(|_command: RuntimeCommand| {
             Box::pin(async { RuntimeApplyResult::ok("ok", false) })
         });
+        let addr = serve_test_admin(handler, SessionRegistry::default()).await;
+
+        let client = reqwest::Client::new();
+        let legacy = get_with_retry(&client, &format!("http://{addr}/runtime/sessions")).await;
+        assert_eq!(legacy.status(), StatusCode::NOT_FOUND);
+
+        let v1 = get_with_retry(&client, &format!("http://{addr}/v1/runtime/sessions")).await;
+        assert_eq!(v1.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn session_control_routes_forward_commands_for_known_sessions() {
+        let handler: Arc<
+            dyn Fn(RuntimeCommand) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync,
+        > = Arc::new(|command: RuntimeCommand| {
+            Box::pin(async move {
+                match command {
+                    RuntimeCommand::RestartChild => RuntimeApplyResult::ok("child", true),
+                    RuntimeCommand::RestartSession(id) => {
+                        RuntimeApplyResult::ok(format!("restart {id}"), true)
+                    }
+                    RuntimeCommand::EvictSession(id) => {
+                        RuntimeApplyResult::ok(format!("evict {id}"), false)
+                    }
+                    RuntimeCommand::Update(_) => RuntimeApplyResult::error("unexpected update"),
+                }
+            })
+        });
+        let sessions = SessionRegistry::default();
+        sessions.register("s1", "test", None).await;
+        let addr = serve_test_admin(handler, sessions).await;
+
+        let client = reqwest::Client::new();
+        let detail: serde_json::Value =
+            get_with_retry(&client, &format!("http://{addr}/v1/runtime/sessions/s1"))
+                .await
+                .json()
+                .await
+                .expect("session detail was not JSON");
+        assert_eq!(detail["id"], "s1");
+        assert_eq!(detail["in_flight"], 0);
+        assert_eq!(
+            get_with_retry(&client, &format!("http://{addr}/v1/runtime/sessions/nope"))
+                .await
+                .status(),
+            StatusCode::NOT_FOUND
+        );
+
+        let restarted: serde_json::Value = client
+            .post(format!("http://{addr}/v1/runtime/sessions/s1/restart"))
+            .send()
+            .await
+            .expect("restart session")
+            .json()
+            .await
+            .expect("restart response was not JSON");
+        assert_eq!(restarted["message"], "restart s1");
+        let child: serde_json::Value = client
+            .post(format!("http://{addr}/v1/runtime/child/restart"))
+            .send()
+            .await
+            .expect("restart child")
+            .json()
+            .await
+            .expect("restart response was not JSON");
+        assert_eq!(child["message"], "child");
+
+        let evicted = client
+            .delete(format!("http://{addr}/v1/runtime/sessions/s1"))
+            .send()
+            .await
+            .expect("evict session");
+        assert_eq!(evicted.status(), StatusCode::OK);
+        let missing = client
+            .delete(format!("http://{addr}/v1/runtime/sessions/nope"))
+            .send()
+            .await
+            .expect("evict unknown session");
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn traffic_socket_streams_filtered_events() {
+        let handler: Arc<
+            dyn Fn(RuntimeCommand) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync,
+        > = Arc::new(|_command: RuntimeCommand| {
+            Box::pin(async { RuntimeApplyResult::ok("ok", false) })
+        });
+        let addr = serve_test_admin(handler, SessionRegistry::default()).await;
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!(
+            "ws://{addr}/v1/runtime/traffic/ws?session=admin-traffic&method=tools/list"
+        ))
+        .await
+        .expect("connect traffic socket");
+        // The subscription is taken before the upgrade completes, so recording now is observed.
+        traffic::record(
+            traffic::TrafficDirection::ClientToServer,
+            "test",
+            Some("admin-traffic"),
+            &serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}),
+        );
+        traffic::record(
+            traffic::TrafficDirection::ClientToServer,
+            "test",
+            Some("admin-traffic"),
+            &serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+        );
+
+        let frame = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("traffic event timed out")
+            .expect("traffic socket closed")
+            .expect("traffic frame");
+        let event: serde_json::Value =
+            serde_json::from_str(frame.to_text().expect("text frame")).expect("event JSON");
+        assert_eq!(event["direction"], "client_to_server");
+        assert_eq!(event["session_id"], "admin-traffic");
+        assert_eq!(event["method"], "tools/list");
+        assert_eq!(event["message"]["id"], 2);
+    }
+
+    #[tokio::test]
+    async fn merge_patch_history_and_rollback_routes() {
+        let runtime = RuntimeArgsStore::new(crate::types::RuntimeArgs {
+            extra_cli_args: Vec::new(),
+            env: std::collections::HashMap::from([("A".to_string(), "1".to_string())]),
+            headers: std::collections::HashMap::new(),
+        });
+        let store = runtime.clone();
+        let handler: Arc<
+            dyn Fn(RuntimeCommand) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync,
+        > = Arc::new(move |command: RuntimeCommand| {
+            let store = store.clone();
+            Box::pin(async move {
+                match command {
+                    RuntimeCommand::Update(update) => {
+                        let result = store.update_global(update.update).await;
+                        RuntimeApplyResult::ok("applied", result.restart_needed)
+                    }
+                    _ => RuntimeApplyResult::error("unexpected command"),
+                }
+            })
+        });
+        let addr =
+            serve_test_admin_with_runtime(runtime.clone(), handler, SessionRegistry::default())
+                .await;
+        let client = reqwest::Client::new();
+
+        let patched: serde_json::Value = client
+            .patch(format!("http://{addr}/v1/runtime/defaults"))
+            .header("content-type", "application/merge-patch+json")
+            .body(r#"{"env":{"A":null,"B":"2"}}"#)
+            .send()
+            .await
+            .expect("patch defaults")
+            .json()
+            .await
+            .expect("patch reply was not JSON");
+        assert_eq!(patched["restart"], true);
+        let env = runtime.get_effective(None).await.env;
+        assert!(!env.contains_key("A"));
+        assert_eq!(env["B"], "2");
+
+        let rejected = client
+            .patch(format!("http://{addr}/v1/runtime/defaults"))
+            .json(&serde_json::json!({"env": {"A": 5}}))
+            .send()
+            .await
+            .expect("patch defaults");
+        assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+
+        let history: serde_json::Value =
+            get_with_retry(&client, &format!("http://{addr}/v1/runtime/history"))
+                .await
+                .json()
+                .await
+                .expect("history was not JSON");
+        let history = history.as_array().expect("history array");
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1]["actor"], "admin-api");
+        assert_eq!(history[1]["mode"], "merge-patch");
+
+        let rolled_back: serde_json::Value = client
+            .post(format!("http://{addr}/v1/runtime/rollback/0"))
+            .send()
+            .await
+            .expect("rollback")
+            .json()
+            .await
+            .expect("rollback reply was not JSON");
+        assert_eq!(rolled_back["restart"], true);
+        assert_eq!(runtime.get_effective(None).await.env["A"], "1");
+
+        let missing = client
+            .post(format!("http://{addr}/v1/runtime/rollback/42"))
+            .send()
+            .await
+            .expect("rollback");
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn child_stderr_route_returns_buffered_lines_filtered_by_level() {
+        let handler: Arc<
+            dyn Fn(RuntimeCommand) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync,
+        > = Arc::new(|_command: RuntimeCommand| {
+            Box::pin(async { RuntimeApplyResult::ok("ok", false) })
+        });
+        let addr = serve_test_admin(handler, SessionRegistry::default()).await;
+
+        let options = child_stderr::StderrOptions::default();
+        let handle = child_stderr::register("stderr-route", &options);
+        let output =
+            "server ready\nWARNING:root:cache miss\nERROR:root:boom\nERROR:root:rejected token=abc123\n";
+        child_stderr::capture(
+            std::io::Cursor::new(output.as_bytes().to_vec()),
+            handle.buffer(),
+            options,
+        );
+        for _ in 0..100u8 {
+            if handle.summary().buffered == 4 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let id = handle.summary().id;
+
+        let client = reqwest::Client::new();
+        let children: serde_json::Value =
+            get_with_retry(&client, &format!("http://{addr}/v1/runtime/children"))
+                .await
+                .json()
+                .await
+                .expect("children list was not JSON");
+        assert!(children
+            .as_array()
+            .expect("children array")
+            .iter()
+            .any(|child| child["id"] == id.as_str() && child["name"] == "stderr-route"));
+
+        let body: serde_json::Value = get_with_retry(
+            &client,
+            &format!("http://{addr}/v1/runtime/children/{id}/stderr?level=warn"),
+        )
+        .await
+        .json()
+        .await
+        .expect("stderr body was not JSON");
+        let lines = body["lines"].as_array().expect("lines array");
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["level"], "warn");
+        assert_eq!(lines[1]["line"], "ERROR:root:boom");
+        assert_eq!(lines[2]["line"], "ERROR:root:rejected token=[REDACTED]");
+
+        assert_eq!(
+            get_with_retry(
+                &client,
+                &format!("http://{addr}/v1/runtime/children/0/stderr")
+            )
+            .await
+            .status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    async fn serve_test_admin(
+        handler: Arc<
+            dyn Fn(RuntimeCommand) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync,
+        >,
+        sessions: SessionRegistry,
+    ) -> SocketAddr {
+        serve_test_admin_with_runtime(RuntimeArgsStore::default(), handler, sessions).await
+    }
+
+    async fn serve_test_admin_with_runtime(
+        runtime: RuntimeArgsStore,
+        handler: Arc<
+            dyn Fn(RuntimeCommand) -> BoxFuture<'static, RuntimeApplyResult> + Send + Sync,
+        >,
+        sessions: SessionRegistry,
+    ) -> SocketAddr {
         let state = AdminState {
             runtime,
             handler,
             options: AdminServerOptions::default(),
             metrics: Arc::new(AdminMetrics::default()),
+            queues: SessionQueues::new(BackpressurePolicy::Block),
+            sessions,
         };
 
         let app = build_router()
@@ -473,13 +973,7 @@ mod tests {
             .await
             .expect("serve admin test app");
         });
-
-        let client = reqwest::Client::new();
-        let legacy = get_with_retry(&client, &format!("http://{addr}/runtime/sessions")).await;
-        assert_eq!(legacy.status(), StatusCode::NOT_FOUND);
-
-        let v1 = get_with_retry(&client, &format!("http://{addr}/v1/runtime/sessions")).await;
-        assert_eq!(v1.status(), StatusCode::OK);
+        addr
     }
 
     async fn get_with_retry(client: &reqwest::Client, url: &str) -> reqwest::Response {
@@ -0,0 +1,618 @@
+use std::collections::HashMap;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::runtime::store::{RuntimeArgsStore, RuntimeArgsUpdate};
+use crate::runtime::{
+    RuntimeApplyResult, RuntimeCommand, RuntimeScope, RuntimeUpdate, RuntimeUpdateRequest,
+};
+use crate::support::child_stderr;
+use crate::support::redact::redactor;
+use crate::support::secrets::reject_secret_refs;
+use crate::support::session_queues::SessionQueues;
+use crate::support::session_registry::SessionRegistry;
+use crate::types::RuntimeArgs;
+
+pub const HELP: &str = "\
+Commands:
+  status                                  gateway, child and runtime args overview
+  sessions                                active sessions with in-flight requests
+  metrics                                 queue depths, in-flight totals and child stderr
+  restart [SESSION]                       restart the gateway's child, or one session's
+  set [session ID] env KEY=VALUE...       set child environment variables
+  set [session ID] header NAME=VALUE...   set response headers
+  set [session ID] args ARG...            replace the extra child arguments
+  unset [session ID] env|header KEY...    remove variables or headers
+  unset [session ID] args                 clear the extra child arguments
+  history                                 recorded runtime args versions
+  rollback VERSION                        restore the args recorded by a version
+  reload                                  re-read the --config file
+  help                                    show this list
+Values with spaces can be quoted. A line starting with `{` is read as a JSON runtime update.
+With `session ID`, only that session's overrides change; defaults still apply underneath.";
+
+// Recorded as the actor of changes made through the prompt or the control socket.
+const ACTOR: &str = "control";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    Env,
+    Header,
+}
+
+#[derive(Debug, Clone)]
+pub enum ControlCommand {
+    Help,
+    Status,
+    Sessions,
+    Metrics,
+    Reload,
+    History,
+    Rollback(u64),
+    Restart(Option<String>),
+    Set {
+        session: Option<String>,
+        kind: ArgKind,
+        pairs: Vec<(String, String)>,
+    },
+    Unset {
+        session: Option<String>,
+        kind: ArgKind,
+        keys: Vec<String>,
+    },
+    SetArgs {
+        session: Option<String>,
+        args: Vec<String>,
+    },
+    Update(RuntimeUpdate),
+}
+
+// The JSON line format the runtime prompt accepted before it had commands.
+#[derive(Debug, Deserialize)]
+struct JsonUpdate {
+    scope: String,
+    session_id: Option<String>,
+    extra_cli_args: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+    headers: Option<HashMap<String, String>>,
+}
+
+/// Parses one line of the control language. Blank lines and `#` comments yield `None`.
+pub fn parse_command(line: &str) -> Result<Option<ControlCommand>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    if line.starts_with('{') {
+        return parse_json_update(line).map(Some);
+    }
+
+    let words = shell_words::split(line).map_err(|err| format!("Invalid command: {err}"))?;
+    let Some((verb, rest)) = words.split_first() else {
+        return Ok(None);
+    };
+    let command = match verb.as_str() {
+        "help" | "?" => ControlCommand::Help,
+        "status" => no_args(ControlCommand::Status, verb, rest)?,
+        "sessions" => no_args(ControlCommand::Sessions, verb, rest)?,
+        "metrics" => no_args(ControlCommand::Metrics, verb, rest)?,
+        "reload" => no_args(ControlCommand::Reload, verb, rest)?,
+        "history" => no_args(ControlCommand::History, verb, rest)?,
+        "rollback" => match rest {
+            [version] => ControlCommand::Rollback(
+                version
+                    .trim_start_matches('v')
+                    .parse()
+                    .map_err(|_| format!("Invalid version: {version}"))?,
+            ),
+            _ => return Err("Usage: rollback VERSION".to_string()),
+        },
+        "restart" => match rest {
+            [] => ControlCommand::Restart(None),
+            [session] => ControlCommand::Restart(Some(session.clone())),
+            _ => return Err("Usage: restart [SESSION]".to_string()),
+        },
+        "set" => {
+            let (session, rest) = split_session(rest)?;
+            match rest.split_first() {
+                Some((kind, args)) if kind == "args" => ControlCommand::SetArgs {
+                    session,
+                    args: args.to_vec(),
+                },
+                Some((kind, pairs)) if !pairs.is_empty() => ControlCommand::Set {
+                    session,
+                    kind: parse_kind(kind)?,
+                    pairs: pairs
+                        .iter()
+                        .map(|pair| parse_pair(pair))
+                        .collect::<Result<_, _>>()?,
+                },
+                _ => return Err("Usage: set [session ID] env|header KEY=VALUE...".to_string()),
+            }
+        }
+        "unset" => {
+            let (session, rest) = split_session(rest)?;
+            match rest.split_first() {
+                Some((kind, [])) if kind == "args" => ControlCommand::SetArgs {
+                    session,
+                    args: Vec::new(),
+                },
+                Some((kind, keys)) if !keys.is_empty() => ControlCommand::Unset {
+                    session,
+                    kind: parse_kind(kind)?,
+                    keys: keys.to_vec(),
+                },
+                _ => return Err("Usage: unset [session ID] env|header KEY...".to_string()),
+            }
+        }
+        other => {
+            return Err(format!(
+                "Unknown command '{other}'. Type `help` for the command list."
+            ))
+        }
+    };
+    Ok(Some(command))
+}
+
+fn no_args(command: ControlCommand, verb: &str, rest: &[String]) -> Result<ControlCommand, String> {
+    if rest.is_empty() {
+        Ok(command)
+    } else {
+        Err(format!("`{verb}` takes no arguments"))
+    }
+}
+
+fn split_session(words: &[String]) -> Result<(Option<String>, &[String]), String> {
+    match words {
+        [keyword, id, rest @ ..] if keyword == "session" => Ok((Some(id.clone()), rest)),
+        [keyword] if keyword == "session" => Err("`session` needs a session id".to_string()),
+        _ => Ok((None, words)),
+    }
+}
+
+fn parse_kind(word: &str) -> Result<ArgKind, String> {
+    match word {
+        "env" => Ok(ArgKind::Env),
+        "header" | "headers" => Ok(ArgKind::Header),
+        other => Err(format!(
+            "Unknown target '{other}'; expected env, header or args"
+        )),
+    }
+}
+
+fn parse_pair(raw: &str) -> Result<(String, String), String> {
+    match raw.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("Expected KEY=VALUE, received: {raw}")),
+    }
+}
+
+fn parse_json_update(line: &str) -> Result<ControlCommand, String> {
+    let input: JsonUpdate =
+        serde_json::from_str(line).map_err(|err| format!("Invalid JSON input: {err}"))?;
+    let scope = match input.scope.as_str() {
+        "global" => RuntimeScope::Global,
+        "session" => RuntimeScope::Session(
+            input
+                .session_id
+                .ok_or("JSON input missing session_id for session scope")?,
+        ),
+        other => return Err(format!("Unknown scope: {other}")),
+    };
+    Ok(ControlCommand::Update(RuntimeUpdate {
+        scope,
+        update: RuntimeArgsUpdate {
+            extra_cli_args: input.extra_cli_args,
+            env: input.env,
+            headers: input.headers,
+            ..RuntimeArgsUpdate::default()
+        },
+    }))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlReply {
+    pub ok: bool,
+    pub output: String,
+}
+
+impl ControlReply {
+    fn ok(output: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            output: output.into(),
+        }
+    }
+
+    fn error(output: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            output: output.into(),
+        }
+    }
+}
+
+impl From<RuntimeApplyResult> for ControlReply {
+    fn from(result: RuntimeApplyResult) -> Self {
+        let mut output = result.message;
+        for swap in &result.swaps {
+            output.push_str(&format!(
+                "\n{}: pid {} -> {}, drained {}/{} in {}ms, abandoned {}",
+                swap.child,
+                pid_label(swap.old_pid),
+                pid_label(swap.new_pid),
+                swap.drained,
+                swap.in_flight,
+                swap.drain_ms,
+                swap.abandoned
+            ));
+        }
+        Self {
+            ok: result.status == "ok",
+            output,
+        }
+    }
+}
+
+/// What the runtime prompt and the control socket run commands against.
+#[derive(Clone)]
+pub struct ControlContext {
+    pub name: Option<String>,
+    pub transport: &'static str,
+    pub runtime: RuntimeArgsStore,
+    pub sessions: SessionRegistry,
+    pub queues: SessionQueues,
+    pub update_tx: mpsc::Sender<RuntimeUpdateRequest>,
+    pub reload: Option<mpsc::Sender<&'static str>>,
+    pub started: Instant,
+}
+
+impl ControlContext {
+    pub async fn execute(&self, line: &str) -> ControlReply {
+        match parse_command(line) {
+            Ok(Some(command)) => self.run(command).await,
+            Ok(None) => ControlReply::ok(""),
+            Err(err) => ControlReply::error(err),
+        }
+    }
+
+    async fn run(&self, command: ControlCommand) -> ControlReply {
+        match command {
+            ControlCommand::Help => ControlReply::ok(HELP),
+            ControlCommand::Status => ControlReply::ok(self.status().await),
+            ControlCommand::Sessions => ControlReply::ok(self.session_lines().await),
+            ControlCommand::Metrics => ControlReply::ok(self.metrics().await),
+            ControlCommand::Reload => match &self.reload {
+                Some(reload) if reload.send("control command").await.is_ok() => {
+                    ControlReply::ok("Reload requested; see the log for the result")
+                }
+                Some(_) => ControlReply::error("Config reloader is not running"),
+                None => ControlReply::error("No --config file to reload"),
+            },
+            ControlCommand::Restart(None) => self.dispatch(RuntimeCommand::RestartChild).await,
+            ControlCommand::Restart(Some(session)) => {
+                if !self.sessions.contains(&session).await {
+                    return ControlReply::error(format!("Unknown session {session}"));
+                }
+                self.dispatch(RuntimeCommand::RestartSession(session)).await
+            }
+            ControlCommand::Set {
+                session,
+                kind,
+                pairs,
+            } => {
+                let mut entries = serde_json::Map::new();
+                for (key, value) in pairs {
+                    if let Err(err) = reject_secret_refs(&value) {
+                        return ControlReply::error(format!("{key}: {err}"));
+                    }
+                    entries.insert(key, value.into());
+                }
+                self.patch(session, kind, entries).await
+            }
+            ControlCommand::Unset {
+                session,
+                kind,
+                keys,
+            } => {
+                let entries = keys
+                    .into_iter()
+                    .map(|key| (key, serde_json::Value::Null))
+                    .collect();
+                self.patch(session, kind, entries).await
+            }
+            ControlCommand::SetArgs { session, args } => {
+                let update = RuntimeArgsUpdate {
+                    extra_cli_args: Some(args),
+                    ..RuntimeArgsUpdate::default()
+                };
+                self.update(session, update.with_actor(ACTOR)).await
+            }
+            ControlCommand::Update(update) => {
+                if let Err(err) = update.update.reject_secret_refs() {
+                    return ControlReply::error(err);
+                }
+                let update = RuntimeUpdate {
+                    scope: update.scope,
+                    update: update.update.with_actor(ACTOR),
+                };
+                self.dispatch(RuntimeCommand::Update(update)).await
+            }
+            ControlCommand::History => ControlReply::ok(self.history_lines().await),
+            ControlCommand::Rollback(version) => {
+                match self.runtime.rollback_update(version, ACTOR).await {
+                    Some(update) => self.dispatch(RuntimeCommand::Update(update)).await,
+                    None => ControlReply::error(format!("Unknown version {version}")),
+                }
+            }
+        }
+    }
+
+    async fn dispatch(&self, command: RuntimeCommand) -> ControlReply {
+        let (respond_to, response) = oneshot::channel();
+        if self
+            .update_tx
+            .send(RuntimeUpdateRequest {
+                command,
+                respond_to,
+            })
+            .await
+            .is_err()
+        {
+            return ControlReply::error("Runtime update channel closed");
+        }
+        response
+            .await
+            .map(ControlReply::from)
+            .unwrap_or_else(|_| ControlReply::error("Runtime update handler failed"))
+    }
+
+    // `set` and `unset` are merge patches, so keys not named on the line are left alone.
+    async fn patch(
+        &self,
+        session: Option<String>,
+        kind: ArgKind,
+        entries: serde_json::Map<String, serde_json::Value>,
+    ) -> ControlReply {
+        let field = match kind {
+            ArgKind::Env => "env",
+            ArgKind::Header => "headers",
+        };
+        let mut patch = serde_json::Map::new();
+        patch.insert(field.to_string(), entries.into());
+        match RuntimeArgsUpdate::merge_patch(patch.into(), ACTOR) {
+            Ok(update) => self.update(session, update).await,
+            Err(err) => ControlReply::error(err),
+        }
+    }
+
+    async fn update(&self, session: Option<String>, update: RuntimeArgsUpdate) -> ControlReply {
+        let scope = match session {
+            Some(id) => {
+                if !self.sessions.contains(&id).await {
+                    return ControlReply::error(format!("Unknown session {id}"));
+                }
+                RuntimeScope::Session(id)
+            }
+            None => RuntimeScope::Global,
+        };
+        self.dispatch(RuntimeCommand::Update(RuntimeUpdate { scope, update }))
+            .await
+    }
+
+    async fn history_lines(&self) -> String {
+        self.runtime
+            .history()
+            .await
+            .iter()
+            .map(|entry| {
+                format!(
+                    "v{}  {}  {}  {}  {}{}",
+                    entry.version,
+                    entry.ts_utc,
+                    entry.session_id.as_deref().unwrap_or("defaults"),
+                    entry.mode,
+                    entry.actor,
+                    if entry.restart_needed {
+                        "  (restart)"
+                    } else {
+                        ""
+                    },
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    async fn status(&self) -> String {
+        let args = self.runtime.get_effective(None).await;
+        let sessions = self.sessions.list().await;
+        let children = child_stderr::list();
+        let exited = children.iter().filter(|child| child.exited).count();
+        let mut lines = vec![
+            format!("name: {}", self.name.as_deref().unwrap_or("-")),
+            format!("transport: {}", self.transport),
+            format!("pid: {}", std::process::id()),
+            format!("uptime: {}s", self.started.elapsed().as_secs()),
+            format!("sessions: {}", sessions.len()),
+            format!(
+                "children: {} running, {exited} exited",
+                children.len() - exited
+            ),
+        ];
+        lines.extend(render_args(&args));
+        lines.join("\n")
+    }
+
+    async fn session_lines(&self) -> String {
+        let sessions = self.sessions.list().await;
+        if sessions.is_empty() {
+            return "No active sessions".to_string();
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        sessions
+            .iter()
+            .map(|session| {
+                format!(
+                    "{}  {}  in_flight={}  idle={}s  child_pid={}  protocol={}",
+                    session.id,
+                    session.transport,
+                    session.in_flight,
+                    now.saturating_sub(session.last_activity_utc),
+                    pid_label(session.child_pid),
+                    session.protocol_version.as_deref().unwrap_or("-"),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    async fn metrics(&self) -> String {
+        let sessions = self.sessions.list().await;
+        let queues = self.queues.snapshot().await;
+        let mut lines = vec![
+            format!("sessions: {}", sessions.len()),
+            format!(
+                "in_flight: {}",
+                sessions
+                    .iter()
+                    .map(|session| session.in_flight)
+                    .sum::<usize>()
+            ),
+            format!("backpressure: {:?}", queues.policy),
+        ];
+        for queue in &queues.sessions {
+            lines.push(format!(
+                "queue {}: depth={}/{} dropped={}",
+                queue.id, queue.depth, queue.capacity, queue.dropped
+            ));
+        }
+        for child in child_stderr::list() {
+            lines.push(format!(
+                "child {} ({}): pid={} stderr_lines={} stderr_dropped={}{}",
+                child.id,
+                child.name,
+                pid_label(child.pid),
+                child.buffered,
+                child.dropped,
+                if child.exited { " exited" } else { "" },
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+fn pid_label(pid: Option<u32>) -> String {
+    pid.map(|pid| pid.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn render_args(args: &RuntimeArgs) -> Vec<String> {
+    let redacted = redactor().to_json_value(args);
+    let mut lines = vec![format!("args: {}", args.extra_cli_args.join(" "))];
+    for (label, key) in [("env", "env"), ("header", "headers")] {
+        let Some(map) = redacted.get(key).and_then(|value| value.as_object()) else {
+            continue;
+        };
+        let mut entries: Vec<String> = map
+            .iter()
+            .map(|(name, value)| format!("{name}={}", value.as_str().unwrap_or_default()))
+            .collect();
+        entries.sort();
+        lines.push(format!("{label}: {}", entries.join(" ")));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BackpressurePolicy;
+
+    #[test]
+    fn parse_command_reads_the_command_language() {
+        assert!(matches!(
+            parse_command("restart s1"),
+            Ok(Some(ControlCommand::Restart(Some(id)))) if id == "s1"
+        ));
+        assert!(matches!(
+            parse_command("set env API_KEY=abc 'GREETING=hello there'"),
+            Ok(Some(ControlCommand::Set { session: None, kind: ArgKind::Env, pairs }))
+                if pairs == vec![
+                    ("API_KEY".to_string(), "abc".to_string()),
+                    ("GREETING".to_string(), "hello there".to_string()),
+                ]
+        ));
+        assert!(matches!(
+            parse_command("unset session s1 header X-Trace"),
+            Ok(Some(ControlCommand::Unset { session: Some(id), kind: ArgKind::Header, keys }))
+                if id == "s1" && keys == vec!["X-Trace".to_string()]
+        ));
+        assert!(matches!(
+            parse_command("unset args"),
+            Ok(Some(ControlCommand::SetArgs { session: None, args })) if args.is_empty()
+        ));
+        assert!(matches!(
+            parse_command(r#"{"scope":"session","session_id":"s2","env":{"A":"1"}}"#),
+            Ok(Some(ControlCommand::Update(RuntimeUpdate {
+                scope: RuntimeScope::Session(id),
+                ..
+            }))) if id == "s2"
+        ));
+        assert!(matches!(parse_command("   "), Ok(None)));
+        assert!(parse_command("set env NOEQUALS").is_err());
+        assert!(parse_command("status now").is_err());
+        assert!(parse_command("frobnicate").is_err());
+    }
+
+    #[tokio::test]
+    async fn set_and_unset_edit_the_current_defaults() {
+        let runtime = RuntimeArgsStore::new(RuntimeArgs {
+            extra_cli_args: Vec::new(),
+            env: HashMap::from([("KEEP".to_string(), "1".to_string())]),
+            headers: HashMap::new(),
+        });
+        let (update_tx, mut update_rx) = mpsc::channel::<RuntimeUpdateRequest>(4);
+        let store = runtime.clone();
+        tokio::spawn(async move {
+            while let Some(request) = update_rx.recv().await {
+                if let RuntimeCommand::Update(update) = request.command {
+                    store.update_global(update.update).await;
+                }
+                let _ = request
+                    .respond_to
+                    .send(RuntimeApplyResult::ok("applied", false));
+            }
+        });
+        let context = ControlContext {
+            name: None,
+            transport: "sse",
+            runtime: runtime.clone(),
+            sessions: SessionRegistry::default(),
+            queues: SessionQueues::new(BackpressurePolicy::Block),
+            update_tx,
+            reload: None,
+            started: Instant::now(),
+        };
+
+        assert!(context.execute("set env NEW=2").await.ok);
+        let env = runtime.get_effective(None).await.env;
+        assert_eq!(env["KEEP"], "1");
+        assert_eq!(env["NEW"], "2");
+
+        assert!(context.execute("unset env KEEP").await.ok);
+        assert!(!runtime.get_effective(None).await.env.contains_key("KEEP"));
+
+        let reply = context.execute("restart nope").await;
+        assert!(!reply.ok);
+        assert!(reply.output.contains("Unknown session"));
+        assert!(!context.execute("reload").await.ok);
+    }
+}
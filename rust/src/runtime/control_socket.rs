@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use crate::runtime::control::ControlContext;
+
+/// Removes the socket file when the gateway returns. One left behind by a signal exit is
+/// cleared by the next `spawn_control_socket` on the same path.
+pub struct ControlSocketGuard {
+    path: PathBuf,
+}
+
+impl Drop for ControlSocketGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Serves the runtime command language on a Unix socket. Each request is one command line;
+/// each reply is one JSON line `{"ok": bool, "output": "..."}`. The socket is created
+/// owner-only, so file permissions are what guard it.
+#[cfg(unix)]
+pub fn spawn_control_socket(
+    path: &Path,
+    context: ControlContext,
+) -> Result<ControlSocketGuard, String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixListener;
+
+    if path.exists() {
+        // A socket nobody answers on was left behind by a gateway that did not shut down.
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(format!(
+                "Control socket {} is already in use",
+                path.display()
+            ));
+        }
+        std::fs::remove_file(path)
+            .map_err(|err| format!("Failed to remove stale {}: {err}", path.display()))?;
+    }
+    let listener = UnixListener::bind(path)
+        .map_err(|err| format!("Failed to bind control socket {}: {err}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|err| format!("Failed to restrict {}: {err}", path.display()))?;
+
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    tracing::error!("Control socket accept failed: {err}");
+                    break;
+                }
+            };
+            let context = context.clone();
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let reply = context.execute(&line).await;
+                    let Ok(mut body) = serde_json::to_string(&reply) else {
+                        break;
+                    };
+                    body.push('\n');
+                    if write.write_all(body.as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    Ok(ControlSocketGuard {
+        path: path.to_path_buf(),
+    })
+}
+
+#[cfg(not(unix))]
+pub fn spawn_control_socket(
+    _path: &Path,
+    _context: ControlContext,
+) -> Result<ControlSocketGuard, String> {
+    Err("--control-socket needs Unix domain sockets, which this platform lacks".to_string())
+}
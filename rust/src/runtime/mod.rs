@@ -1,8 +1,12 @@
 pub mod admin;
+pub mod control;
+pub mod control_socket;
 pub mod prompt;
+pub mod reload;
 pub mod store;
 
 use crate::runtime::store::RuntimeArgsUpdate;
+use crate::support::child_swap::SwapReport;
 use serde::Serialize;
 use tokio::sync::oneshot;
 
@@ -18,11 +22,24 @@ pub struct RuntimeUpdate {
     pub update: RuntimeArgsUpdate,
 }
 
+// Everything the admin API, the runtime prompt, the control socket and config reloads can
+// ask a running gateway to do.
+#[derive(Debug, Clone)]
+pub enum RuntimeCommand {
+    Update(RuntimeUpdate),
+    RestartChild,
+    RestartSession(String),
+    EvictSession(String),
+}
+
 #[derive(Debug, Serialize)]
 pub struct RuntimeApplyResult {
     pub status: String,
     pub message: String,
     pub restart: bool,
+    // One entry per child replaced blue/green instead of restarted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub swaps: Vec<SwapReport>,
 }
 
 impl RuntimeApplyResult {
@@ -31,6 +48,19 @@ impl RuntimeApplyResult {
             status: "ok".to_string(),
             message: message.into(),
             restart,
+            swaps: Vec::new(),
+        }
+    }
+
+    // Children moved onto new runtime args: restarted, or swapped blue/green when there are
+    // swap reports.
+    pub fn replaced(swaps: Vec<SwapReport>) -> Self {
+        if swaps.is_empty() {
+            return Self::ok("Restarted child with new runtime args", true);
+        }
+        Self {
+            swaps,
+            ..Self::ok("Swapped in a new child with the new runtime args", true)
         }
     }
 
@@ -39,11 +69,12 @@ impl RuntimeApplyResult {
             status: "error".to_string(),
             message: message.into(),
             restart: false,
+            swaps: Vec::new(),
         }
     }
 }
 
 pub struct RuntimeUpdateRequest {
-    pub update: RuntimeUpdate,
+    pub command: RuntimeCommand,
     pub respond_to: oneshot::Sender<RuntimeApplyResult>,
 }
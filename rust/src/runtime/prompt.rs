@@ -1,84 +1,46 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
 
-use serde::Deserialize;
-use tokio::sync::mpsc;
+use tokio::runtime::Handle;
 
-use crate::runtime::store::RuntimeArgsUpdate;
-use crate::runtime::{RuntimeScope, RuntimeUpdate};
+use crate::runtime::control::ControlContext;
 
-#[derive(Debug, Deserialize)]
-struct PromptInput {
-    scope: String,
-    session_id: Option<String>,
-    extra_cli_args: Option<Vec<String>>,
-    env: Option<std::collections::HashMap<String, String>>,
-    headers: Option<std::collections::HashMap<String, String>>,
-}
-
-pub fn spawn_prompt() -> mpsc::Receiver<RuntimeUpdate> {
-    let (tx, rx) = mpsc::channel(32);
+const PROMPT: &str = "mcpway> ";
 
+/// Runs the command REPL on the controlling terminal. Without one (systemd units, CI) the
+/// prompt stays off; `--control-socket` with `mcpway ctl` offers the same commands.
+pub fn spawn_prompt(context: ControlContext) {
+    let runtime = Handle::current();
     std::thread::spawn(move || {
-        let Ok(file) = File::open("/dev/tty") else {
-            tracing::error!("Runtime prompt disabled: /dev/tty unavailable");
+        let terminal = File::open("/dev/tty")
+            .and_then(|input| Ok((input, OpenOptions::new().write(true).open("/dev/tty")?)));
+        let Ok((input, mut output)) = terminal else {
+            tracing::warn!(
+                "Runtime prompt disabled: no controlling terminal. Use --control-socket and `mcpway ctl` instead."
+            );
             return;
         };
-        let reader = BufReader::new(file);
-        tracing::info!("Runtime prompt enabled. Enter JSON per line.");
-        tracing::info!(
-            "{}",
-            "Example: {\"scope\":\"global\",\"extra_cli_args\":[\"--token\",\"abc\"],\"env\":{\"API_KEY\":\"xyz\"},\"headers\":{\"Authorization\":\"Bearer 123\"}}"
+        let _ = write!(
+            output,
+            "Runtime prompt enabled. Type `help` for commands.\n{PROMPT}"
         );
-        for line in reader.lines() {
-            match line {
-                Ok(line) => {
-                    let trimmed = line.trim();
-                    if trimmed.is_empty() {
-                        continue;
-                    }
-                    match serde_json::from_str::<PromptInput>(trimmed) {
-                        Ok(input) => {
-                            let scope = match input.scope.as_str() {
-                                "global" => RuntimeScope::Global,
-                                "session" => {
-                                    if let Some(id) = input.session_id.clone() {
-                                        RuntimeScope::Session(id)
-                                    } else {
-                                        tracing::error!(
-                                            "Prompt input missing session_id for session scope"
-                                        );
-                                        continue;
-                                    }
-                                }
-                                other => {
-                                    tracing::error!("Unknown scope: {other}");
-                                    continue;
-                                }
-                            };
-                            let update = RuntimeArgsUpdate {
-                                extra_cli_args: input.extra_cli_args,
-                                env: input.env,
-                                headers: input.headers,
-                            };
-                            let update_msg = RuntimeUpdate { scope, update };
-                            if tx.blocking_send(update_msg).is_err() {
-                                tracing::error!("Runtime prompt channel closed");
-                                break;
-                            }
-                        }
-                        Err(err) => {
-                            tracing::error!("Invalid JSON input: {err}");
-                        }
-                    }
-                }
+        let _ = output.flush();
+
+        for line in BufReader::new(input).lines() {
+            let line = match line {
+                Ok(line) => line,
                 Err(err) => {
                     tracing::error!("Runtime prompt read error: {err}");
                     break;
                 }
+            };
+            let reply = runtime.block_on(context.execute(&line));
+            if !reply.output.is_empty() {
+                let prefix = if reply.ok { "" } else { "error: " };
+                let _ = writeln!(output, "{prefix}{}", reply.output);
             }
+            let _ = write!(output, "{PROMPT}");
+            let _ = output.flush();
         }
     });
-
-    rx
 }
@@ -0,0 +1,460 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::config::{Config, CorsConfig, LogLevel};
+use crate::runtime::store::RuntimeArgsUpdate;
+use crate::runtime::{RuntimeCommand, RuntimeScope, RuntimeUpdate, RuntimeUpdateRequest};
+use crate::support::cors::update_live_cors;
+use crate::support::secrets::{resolve_secret_map, validate_secret_refs};
+use crate::support::telemetry::set_log_level;
+use crate::types::HeadersMap;
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+// Keys a config file might be expected to carry that cannot change at runtime. They get a
+// specific error instead of the generic unknown-field one.
+const STARTUP_ONLY: &[&str] = &[
+    "retry-attempts",
+    "retry-base-delay-ms",
+    "retry-max-delay-ms",
+    "circuit-failure-threshold",
+    "circuit-cooldown-ms",
+];
+const UNSUPPORTED: &[&str] = &["rate-limit", "rate-limits", "tool-policy", "tool-policies"];
+
+// Settings a --config file may change while the gateway runs. Every key is optional;
+// anything left out falls back to the command-line value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+    log_level: Option<String>,
+    cors: Option<Vec<String>>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    args: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Settings {
+    log_level: LogLevel,
+    cors: CorsConfig,
+    headers: HeadersMap,
+    env: HashMap<String, String>,
+    args: Vec<String>,
+}
+
+pub struct ConfigReloader {
+    path: PathBuf,
+    base: Settings,
+    current: Settings,
+}
+
+impl ConfigReloader {
+    // Loads the file once and folds it into `config` so startup sees the same values a
+    // reload would produce. Headers are the exception: `config.headers` keeps only the
+    // command-line set, which the gateways serve as their fixed base, and the file's headers
+    // reach them through the runtime args from `initial_headers`, which each reload replaces.
+    pub fn load(path: &Path, config: &mut Config) -> Result<Self, String> {
+        let base = Settings {
+            log_level: config.log_level,
+            cors: config.cors.clone(),
+            headers: config.headers.clone(),
+            env: config.env.clone(),
+            args: Vec::new(),
+        };
+        let current = base.overlay(&read_config_file(path)?)?;
+        config.log_level = current.log_level;
+        config.cors = current.cors.clone();
+        config.env = current.env.clone();
+        Ok(Self {
+            path: path.to_path_buf(),
+            base,
+            current,
+        })
+    }
+
+    pub fn initial_headers(&self) -> HeadersMap {
+        self.current.headers.clone()
+    }
+
+    pub fn initial_args(&self) -> Vec<String> {
+        self.current.args.clone()
+    }
+
+    // Reloads on SIGHUP and, with `watch`, whenever the file's modification time changes.
+    // The returned sender triggers a reload on demand, tagged with the reason to log.
+    pub fn spawn(
+        mut self,
+        update_tx: mpsc::Sender<RuntimeUpdateRequest>,
+        watch: bool,
+    ) -> mpsc::Sender<&'static str> {
+        let (trigger_tx, mut trigger_rx) = mpsc::channel::<&'static str>(4);
+        let manual_trigger = trigger_tx.clone();
+
+        #[cfg(unix)]
+        {
+            let trigger_tx = trigger_tx.clone();
+            tokio::spawn(async move {
+                use tokio::signal::unix::{signal, SignalKind};
+                let Ok(mut hangup) = signal(SignalKind::hangup()) else {
+                    tracing::error!(
+                        "Failed to listen for SIGHUP; config reload is file-watch only"
+                    );
+                    return;
+                };
+                while hangup.recv().await.is_some() {
+                    if trigger_tx.send("SIGHUP").await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        if watch {
+            let path = self.path.clone();
+            tokio::spawn(async move {
+                let mut last = modified_at(&path);
+                loop {
+                    tokio::time::sleep(WATCH_INTERVAL).await;
+                    let seen = modified_at(&path);
+                    if seen != last {
+                        last = seen;
+                        if trigger_tx.send("file change").await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            });
+        } else {
+            drop(trigger_tx);
+        }
+
+        tokio::spawn(async move {
+            while let Some(reason) = trigger_rx.recv().await {
+                tracing::info!(
+                    "Reloading configuration from {} ({reason})",
+                    self.path.display()
+                );
+                self.reload(&update_tx).await;
+            }
+        });
+        manual_trigger
+    }
+
+    async fn reload(&mut self, update_tx: &mpsc::Sender<RuntimeUpdateRequest>) {
+        let next = match read_config_file(&self.path).and_then(|file| self.base.overlay(&file)) {
+            Ok(next) => next,
+            Err(err) => {
+                tracing::error!("Config reload failed, keeping the previous settings: {err}");
+                return;
+            }
+        };
+        let changes = diff(&self.current, &next);
+        if changes.is_empty() {
+            tracing::info!("Config reload: no changes");
+            return;
+        }
+
+        let mut update = RuntimeArgsUpdate::default().with_actor("config reload");
+        if next.headers != self.current.headers {
+            match resolve_secret_map(&next.headers, "header").await {
+                Ok(headers) => update.headers = Some(headers),
+                Err(err) => {
+                    tracing::error!("Config reload failed, keeping the previous settings: {err}");
+                    return;
+                }
+            }
+        }
+        if next.env != self.current.env {
+            update.env = Some(next.env.clone());
+        }
+        if next.args != self.current.args {
+            update.extra_cli_args = Some(next.args.clone());
+        }
+
+        // The runtime update goes first: if it fails, nothing else is applied and the next
+        // reload tries the whole change again.
+        if update.headers.is_some() || update.env.is_some() || update.extra_cli_args.is_some() {
+            let (respond_to, response) = oneshot::channel();
+            let request = RuntimeUpdateRequest {
+                command: RuntimeCommand::Update(RuntimeUpdate {
+                    scope: RuntimeScope::Global,
+                    update,
+                }),
+                respond_to,
+            };
+            if update_tx.send(request).await.is_err() {
+                tracing::error!("Config reload failed: runtime update channel closed");
+                return;
+            }
+            match response.await {
+                Ok(result) if result.status == "ok" => {
+                    tracing::info!("Config reload: {}", result.message)
+                }
+                Ok(result) => {
+                    tracing::error!(
+                        "Config reload failed, keeping the previous settings: {}",
+                        result.message
+                    );
+                    return;
+                }
+                Err(_) => {
+                    tracing::error!("Config reload failed: runtime update handler failed");
+                    return;
+                }
+            }
+        }
+
+        if next.log_level != self.current.log_level {
+            if let Err(err) = set_log_level(next.log_level) {
+                tracing::error!("{err}");
+            }
+        }
+        if next.cors != self.current.cors {
+            update_live_cors(&next.cors);
+        }
+        tracing::info!("Config reload applied: {}", changes.join("; "));
+        self.current = next;
+    }
+}
+
+impl Settings {
+    fn overlay(&self, file: &ConfigFile) -> Result<Self, String> {
+        let mut next = self.clone();
+        if let Some(level) = &file.log_level {
+            next.log_level = LogLevel::from_str(level, true).map_err(|_| {
+                format!("Invalid log-level '{level}' (expected debug, info or none)")
+            })?;
+        }
+        if let Some(origins) = &file.cors {
+            next.cors = cors_from_list(origins);
+        }
+        for (key, value) in &file.headers {
+            validate_secret_refs(value).map_err(|err| format!("header {key}: {err}"))?;
+            next.headers.insert(key.clone(), value.clone());
+        }
+        for (key, value) in &file.env {
+            validate_secret_refs(value).map_err(|err| format!("env {key}: {err}"))?;
+            next.env.insert(key.clone(), value.clone());
+        }
+        if let Some(args) = &file.args {
+            next.args = args.clone();
+        }
+        Ok(next)
+    }
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile, String> {
+    let body = std::fs::read_to_string(path)
+        .map_err(|err| format!("Failed to read config file {}: {err}", path.display()))?;
+    parse_config_file(&body).map_err(|err| format!("Invalid config file {}: {err}", path.display()))
+}
+
+fn parse_config_file(body: &str) -> Result<ConfigFile, String> {
+    let table: toml::Table = toml::from_str(body).map_err(|err| err.to_string())?;
+    if let Some(key) = STARTUP_ONLY.iter().find(|key| table.contains_key(**key)) {
+        return Err(format!(
+            "`{key}` is read once at startup; pass --{key} and restart the gateway"
+        ));
+    }
+    if let Some(key) = UNSUPPORTED.iter().find(|key| table.contains_key(**key)) {
+        return Err(format!(
+            "`{key}` is not supported; mcpway has no rate limits or tool policies"
+        ));
+    }
+    table.try_into().map_err(|err| err.to_string())
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+fn cors_from_list(origins: &[String]) -> CorsConfig {
+    if origins.is_empty() {
+        CorsConfig::Disabled
+    } else if origins.iter().any(|origin| origin == "*") {
+        CorsConfig::AllowAll
+    } else {
+        CorsConfig::AllowList {
+            raw: origins.to_vec(),
+        }
+    }
+}
+
+fn describe_cors(cors: &CorsConfig) -> String {
+    match cors {
+        CorsConfig::Disabled => "disabled".to_string(),
+        CorsConfig::AllowAll => "*".to_string(),
+        CorsConfig::AllowList { raw } => raw.join(", "),
+    }
+}
+
+// Values are never printed for headers and env; only which keys changed.
+fn diff(old: &Settings, new: &Settings) -> Vec<String> {
+    let mut changes = Vec::new();
+    if old.log_level != new.log_level {
+        changes.push(format!(
+            "log-level {:?} -> {:?}",
+            old.log_level, new.log_level
+        ));
+    }
+    if old.cors != new.cors {
+        changes.push(format!(
+            "cors [{}] -> [{}]",
+            describe_cors(&old.cors),
+            describe_cors(&new.cors)
+        ));
+    }
+    if let Some(keys) = diff_keys(&old.headers, &new.headers) {
+        changes.push(format!("headers {keys}"));
+    }
+    if let Some(keys) = diff_keys(&old.env, &new.env) {
+        changes.push(format!("env {keys} (child restart)"));
+    }
+    if old.args != new.args {
+        changes.push(format!(
+            "args [{}] -> [{}] (child restart)",
+            old.args.join(" "),
+            new.args.join(" ")
+        ));
+    }
+    changes
+}
+
+fn diff_keys(old: &HashMap<String, String>, new: &HashMap<String, String>) -> Option<String> {
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    let marks: Vec<String> = keys
+        .into_iter()
+        .filter_map(|key| match (old.get(key), new.get(key)) {
+            (None, Some(_)) => Some(format!("+{key}")),
+            (Some(_), None) => Some(format!("-{key}")),
+            (Some(a), Some(b)) if a != b => Some(format!("~{key}")),
+            _ => None,
+        })
+        .collect();
+    if marks.is_empty() {
+        None
+    } else {
+        Some(marks.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::RuntimeApplyResult;
+
+    fn base() -> Settings {
+        Settings {
+            log_level: LogLevel::Info,
+            cors: CorsConfig::Disabled,
+            headers: HashMap::from([("X-Cli".to_string(), "cli".to_string())]),
+            env: HashMap::new(),
+            args: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn overlay_applies_file_values_over_cli_settings() {
+        let file = parse_config_file(
+            r#"
+log-level = "debug"
+cors = ["https://app.example.com"]
+args = ["--verbose"]
+
+[headers]
+Authorization = "Bearer ${env:TOKEN}"
+
+[env]
+API_KEY = "${file:/run/secrets/api}"
+"#,
+        )
+        .expect("config file should parse");
+        let next = base().overlay(&file).expect("overlay");
+        assert_eq!(next.log_level, LogLevel::Debug);
+        assert_eq!(
+            next.cors,
+            CorsConfig::AllowList {
+                raw: vec!["https://app.example.com".to_string()]
+            }
+        );
+        assert_eq!(next.headers["X-Cli"], "cli");
+        assert_eq!(next.headers["Authorization"], "Bearer ${env:TOKEN}");
+        assert_eq!(next.args, vec!["--verbose".to_string()]);
+
+        let bad = parse_config_file("log-level = \"loud\"").expect("parse");
+        assert!(base().overlay(&bad).is_err());
+        assert!(parse_config_file("unknown = 5").is_err());
+    }
+
+    #[test]
+    fn settings_that_cannot_reload_are_rejected_by_name() {
+        let err = parse_config_file("retry-attempts = 5").expect_err("retry is startup-only");
+        assert!(err.contains("--retry-attempts"), "{err}");
+        let err = parse_config_file("rate-limit = 5").expect_err("no rate limits");
+        assert!(err.contains("no rate limits"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn failed_runtime_update_keeps_the_previous_settings() {
+        let path = std::env::temp_dir().join(format!("mcpway-reload-{}.toml", std::process::id()));
+        std::fs::write(&path, "[env]\nAPI_KEY = \"k\"\n").expect("write config file");
+        let mut reloader = ConfigReloader {
+            path: path.clone(),
+            base: base(),
+            current: base(),
+        };
+        let (update_tx, mut update_rx) = mpsc::channel::<RuntimeUpdateRequest>(1);
+        let handler = tokio::spawn(async move {
+            let mut statuses = vec![
+                RuntimeApplyResult::ok("Restarted child", true),
+                RuntimeApplyResult::error("Failed to restart child: spawn failed"),
+            ];
+            while let Some(request) = update_rx.recv().await {
+                let _ = request
+                    .respond_to
+                    .send(statuses.pop().expect("two updates"));
+            }
+        });
+
+        reloader.reload(&update_tx).await;
+        assert_eq!(reloader.current, base());
+        reloader.reload(&update_tx).await;
+        assert_eq!(reloader.current.env["API_KEY"], "k");
+
+        drop(update_tx);
+        handler.await.expect("handler");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn diff_reports_changed_keys_without_values() {
+        let old = base();
+        let mut new = base();
+        new.log_level = LogLevel::Debug;
+        new.headers
+            .insert("X-Cli".to_string(), "s3cr3t".to_string());
+        new.headers.insert("X-New".to_string(), "v".to_string());
+        new.env.insert("API_KEY".to_string(), "k".to_string());
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                "log-level Info -> Debug".to_string(),
+                "headers ~X-Cli +X-New".to_string(),
+                "env +API_KEY (child restart)".to_string(),
+            ]
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+}
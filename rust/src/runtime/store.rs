@@ -1,81 +1,356 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use arc_swap::ArcSwap;
-use serde::Deserialize;
-use tokio::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock};
 
+use crate::runtime::{RuntimeScope, RuntimeUpdate};
+use crate::support::secrets;
 use crate::types::RuntimeArgs;
 
-#[derive(Debug, Deserialize, Clone)]
+// Oldest entries beyond this are forgotten and can no longer be rolled back to.
+const HISTORY_LIMIT: usize = 200;
+
+/// A change to runtime args. `extra_cli_args`, `env` and `headers` replace the current
+/// values wholesale; `patch` is an RFC 7396 JSON Merge Patch applied after them, where
+/// `null` deletes a key.
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct RuntimeArgsUpdate {
     pub extra_cli_args: Option<Vec<String>>,
     pub env: Option<HashMap<String, String>>,
     pub headers: Option<HashMap<String, String>>,
+    #[serde(skip)]
+    pub patch: Option<serde_json::Value>,
+    // Who asked for the change, as recorded in the history.
+    #[serde(skip)]
+    pub actor: Option<String>,
+}
+
+impl RuntimeArgsUpdate {
+    pub fn merge_patch(patch: serde_json::Value, actor: &str) -> Result<Self, String> {
+        validate_merge_patch(&patch)?;
+        Ok(Self {
+            patch: Some(patch),
+            actor: Some(actor.to_string()),
+            ..Self::default()
+        })
+    }
+
+    pub fn with_actor(mut self, actor: &str) -> Self {
+        self.actor = Some(actor.to_string());
+        self
+    }
+
+    // Updates from the admin API and control socket must not carry secret references, which
+    // would otherwise be resolved by mcpway itself when the child is spawned.
+    pub fn reject_secret_refs(&self) -> Result<(), String> {
+        for (field, values) in [("env", &self.env), ("headers", &self.headers)] {
+            for (key, value) in values.iter().flatten() {
+                secrets::reject_secret_refs(value)
+                    .map_err(|err| format!("{field}.{key}: {err}"))?;
+            }
+            let patched = self
+                .patch
+                .as_ref()
+                .and_then(|patch| patch.get(field))
+                .and_then(serde_json::Value::as_object);
+            for (key, value) in patched.into_iter().flatten() {
+                if let Some(value) = value.as_str() {
+                    secrets::reject_secret_refs(value)
+                        .map_err(|err| format!("{field}.{key}: {err}"))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn changes_headers(&self) -> bool {
+        self.headers.is_some()
+            || self
+                .patch
+                .as_ref()
+                .is_some_and(|patch| patch.get("headers").is_some())
+    }
+
+    fn apply(&self, current: &RuntimeArgs) -> RuntimeArgs {
+        let mut next = current.clone();
+        if let Some(extra) = &self.extra_cli_args {
+            next.extra_cli_args = extra.clone();
+        }
+        if let Some(env) = &self.env {
+            next.env = env.clone();
+        }
+        if let Some(headers) = &self.headers {
+            next.headers = headers.clone();
+        }
+        if let Some(patch) = &self.patch {
+            let mut value = serde_json::to_value(&next).unwrap_or_default();
+            apply_merge_patch(&mut value, patch);
+            // The patch shape was validated when the update was built.
+            match serde_json::from_value(value) {
+                Ok(patched) => next = patched,
+                Err(err) => tracing::error!("Ignoring invalid runtime args patch: {err}"),
+            }
+        }
+        next
+    }
+
+    fn mode(&self) -> &'static str {
+        if self.patch.is_some() {
+            "merge-patch"
+        } else {
+            "replace"
+        }
+    }
+}
+
+// RFC 7396: objects merge key by key, `null` removes a key, anything else replaces.
+fn apply_merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let serde_json::Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(serde_json::Map::new());
+    }
+    let Some(target) = target.as_object_mut() else {
+        return;
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            apply_merge_patch(
+                target.entry(key.clone()).or_insert(serde_json::Value::Null),
+                value,
+            );
+        }
+    }
+}
+
+/// Checks that a merge patch only touches runtime args fields with the right types, so
+/// applying it can never produce args that fail to load.
+pub fn validate_merge_patch(patch: &serde_json::Value) -> Result<(), String> {
+    let Some(fields) = patch.as_object() else {
+        return Err("Merge patch must be a JSON object".to_string());
+    };
+    for (field, value) in fields {
+        match (field.as_str(), value) {
+            (_, serde_json::Value::Null) => {}
+            ("extra_cli_args", serde_json::Value::Array(args)) => {
+                if !args.iter().all(serde_json::Value::is_string) {
+                    return Err("extra_cli_args must be an array of strings".to_string());
+                }
+            }
+            ("env" | "headers", serde_json::Value::Object(entries)) => {
+                if let Some((key, _)) = entries
+                    .iter()
+                    .find(|(_, value)| !(value.is_string() || value.is_null()))
+                {
+                    return Err(format!("{field}.{key} must be a string or null"));
+                }
+            }
+            ("extra_cli_args" | "env" | "headers", _) => {
+                return Err(format!("{field} has the wrong type"));
+            }
+            (other, _) => return Err(format!("Unknown runtime args field '{other}'")),
+        }
+    }
+    Ok(())
 }
 
 #[derive(Debug, Default)]
 pub struct UpdateResult {
     pub restart_needed: bool,
     pub headers_changed: bool,
+    // The changed scope's args before the update, for `revert_global`.
+    pub previous: RuntimeArgs,
+}
+
+impl UpdateResult {
+    fn between(before: &RuntimeArgs, after: &RuntimeArgs) -> Self {
+        Self {
+            restart_needed: before.extra_cli_args != after.extra_cli_args
+                || before.env != after.env,
+            headers_changed: before.headers != after.headers,
+            previous: before.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionArgs {
+    pub id: String,
+    pub overrides: RuntimeArgs,
+    pub effective: RuntimeArgs,
+}
+
+/// One recorded change. `args` is the state of the changed scope afterwards: the defaults,
+/// or the session's overrides.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryEntry {
+    pub version: u64,
+    pub ts_utc: u64,
+    pub actor: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub mode: &'static str,
+    pub restart_needed: bool,
+    pub args: RuntimeArgs,
+}
+
+#[derive(Debug, Default)]
+struct History {
+    next_version: u64,
+    entries: VecDeque<HistoryEntry>,
 }
 
-#[derive(Clone, Default)]
+impl History {
+    fn record(
+        &mut self,
+        actor: String,
+        session_id: Option<String>,
+        mode: &'static str,
+        restart_needed: bool,
+        args: RuntimeArgs,
+    ) -> u64 {
+        let version = self.next_version;
+        self.next_version += 1;
+        if self.entries.len() == HISTORY_LIMIT {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HistoryEntry {
+            version,
+            ts_utc: unix_timestamp_secs(),
+            actor,
+            session_id,
+            mode,
+            restart_needed,
+            args,
+        });
+        version
+    }
+}
+
+#[derive(Clone)]
 pub struct RuntimeArgsStore {
     global: Arc<ArcSwap<RuntimeArgs>>,
     sessions: Arc<RwLock<HashMap<String, RuntimeArgs>>>,
+    history: Arc<Mutex<History>>,
+}
+
+impl Default for RuntimeArgsStore {
+    fn default() -> Self {
+        Self::new(RuntimeArgs::default())
+    }
 }
 
 impl RuntimeArgsStore {
+    /// The starting args are recorded as version 0, so a rollback can always return to them.
     pub fn new(initial: RuntimeArgs) -> Self {
+        let mut history = History::default();
+        history.record(
+            "startup".to_string(),
+            None,
+            "replace",
+            false,
+            initial.clone(),
+        );
         Self {
             global: Arc::new(ArcSwap::from_pointee(initial)),
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            history: Arc::new(Mutex::new(history)),
         }
     }
 
     pub async fn update_global(&self, update: RuntimeArgsUpdate) -> UpdateResult {
-        let mut result = UpdateResult::default();
-        let mut next = (*self.global.load_full()).clone();
-        if let Some(extra) = update.extra_cli_args {
-            next.extra_cli_args = extra;
-            result.restart_needed = true;
-        }
-        if let Some(env) = update.env {
-            next.env = env;
-            result.restart_needed = true;
-        }
-        if let Some(headers) = update.headers {
-            next.headers = headers;
-            result.headers_changed = true;
-        }
+        // Holding the history lock serializes updates, so versions follow the order applied.
+        let mut history = self.history.lock().await;
+        let current = self.global.load_full();
+        let next = update.apply(&current);
+        let result = UpdateResult::between(&current, &next);
+        history.record(
+            update
+                .actor
+                .clone()
+                .unwrap_or_else(|| "runtime".to_string()),
+            None,
+            update.mode(),
+            result.restart_needed,
+            next.clone(),
+        );
         self.global.store(Arc::new(next));
         result
     }
 
+    /// Puts the defaults back to `previous` after the gateway failed to restart its children
+    /// for an update, so the store never reports args that no child is running with.
+    pub async fn revert_global(&self, previous: RuntimeArgs) {
+        let mut history = self.history.lock().await;
+        history.record(
+            "revert (restart failed)".to_string(),
+            None,
+            "replace",
+            false,
+            previous.clone(),
+        );
+        self.global.store(Arc::new(previous));
+    }
+
     pub async fn update_session(
         &self,
         session_id: &str,
         update: RuntimeArgsUpdate,
     ) -> UpdateResult {
-        let mut result = UpdateResult::default();
+        let mut history = self.history.lock().await;
         let mut sessions = self.sessions.write().await;
         let entry = sessions.entry(session_id.to_string()).or_default();
-        if let Some(extra) = update.extra_cli_args {
-            entry.extra_cli_args = extra;
-            result.restart_needed = true;
-        }
-        if let Some(env) = update.env {
-            entry.env = env;
-            result.restart_needed = true;
-        }
-        if let Some(headers) = update.headers {
-            entry.headers = headers;
-            result.headers_changed = true;
-        }
+        let next = update.apply(entry);
+        let result = UpdateResult::between(entry, &next);
+        history.record(
+            update
+                .actor
+                .clone()
+                .unwrap_or_else(|| "runtime".to_string()),
+            Some(session_id.to_string()),
+            update.mode(),
+            result.restart_needed,
+            next.clone(),
+        );
+        *entry = next;
         result
     }
 
+    pub async fn history(&self) -> Vec<HistoryEntry> {
+        self.history.lock().await.entries.iter().cloned().collect()
+    }
+
+    /// The update that puts the scope changed by `version` back to the state it recorded.
+    pub async fn rollback_update(&self, version: u64, actor: &str) -> Option<RuntimeUpdate> {
+        let history = self.history.lock().await;
+        let entry = history
+            .entries
+            .iter()
+            .find(|entry| entry.version == version)?;
+        let scope = match &entry.session_id {
+            Some(id) => RuntimeScope::Session(id.clone()),
+            None => RuntimeScope::Global,
+        };
+        let args = entry.args.clone();
+        Some(RuntimeUpdate {
+            scope,
+            update: RuntimeArgsUpdate {
+                extra_cli_args: Some(args.extra_cli_args),
+                env: Some(args.env),
+                headers: Some(args.headers),
+                patch: None,
+                actor: Some(format!("{actor} (rollback to v{version})")),
+            },
+        })
+    }
+
     pub async fn get_effective(&self, session_id: Option<&str>) -> RuntimeArgs {
         let global = self.global.load_full();
         if let Some(id) = session_id {
@@ -91,4 +366,178 @@ impl RuntimeArgsStore {
         let sessions = self.sessions.read().await;
         sessions.keys().cloned().collect()
     }
+
+    pub async fn list_session_args(&self) -> Vec<SessionArgs> {
+        let global = self.global.load_full();
+        let sessions = self.sessions.read().await;
+        let mut listed: Vec<SessionArgs> = sessions
+            .iter()
+            .map(|(id, overlay)| SessionArgs {
+                id: id.clone(),
+                overrides: overlay.clone(),
+                effective: RuntimeArgs::merge(global.as_ref(), overlay),
+            })
+            .collect();
+        listed.sort_by(|a, b| a.id.cmp(&b.id));
+        listed
+    }
+
+    pub async fn remove_session(&self, session_id: &str) {
+        let mut sessions = self.sessions.write().await;
+        sessions.remove(session_id);
+    }
+}
+
+fn unix_timestamp_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn session_args_merge_overrides_onto_global() {
+        let store = RuntimeArgsStore::new(RuntimeArgs {
+            extra_cli_args: vec!["--base".to_string()],
+            env: HashMap::from([("A".to_string(), "1".to_string())]),
+            headers: HashMap::new(),
+        });
+        let result = store
+            .update_session(
+                "s1",
+                RuntimeArgsUpdate {
+                    extra_cli_args: None,
+                    env: Some(HashMap::from([("B".to_string(), "2".to_string())])),
+                    headers: None,
+                    ..RuntimeArgsUpdate::default()
+                },
+            )
+            .await;
+        assert!(result.restart_needed);
+
+        let listed = store.list_session_args().await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, "s1");
+        assert_eq!(listed[0].overrides.env.len(), 1);
+        assert_eq!(listed[0].effective.env["A"], "1");
+        assert_eq!(listed[0].effective.env["B"], "2");
+        assert_eq!(listed[0].effective.extra_cli_args, vec!["--base"]);
+
+        store.remove_session("s1").await;
+        assert!(store.list_session_args().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn merge_patch_deletes_keys_and_rollback_restores_a_version() {
+        let store = RuntimeArgsStore::new(RuntimeArgs {
+            extra_cli_args: Vec::new(),
+            env: HashMap::from([
+                ("A".to_string(), "1".to_string()),
+                ("B".to_string(), "2".to_string()),
+            ]),
+            headers: HashMap::new(),
+        });
+
+        let patch = RuntimeArgsUpdate::merge_patch(
+            serde_json::json!({"env": {"A": null, "C": "3"}, "headers": {"X-Trace": "on"}}),
+            "test",
+        )
+        .expect("valid patch");
+        let result = store.update_global(patch).await;
+        assert!(result.restart_needed);
+        assert!(result.headers_changed);
+        let args = store.get_effective(None).await;
+        assert!(!args.env.contains_key("A"));
+        assert_eq!(args.env["B"], "2");
+        assert_eq!(args.env["C"], "3");
+
+        // Re-sending the same values changes nothing, so nothing restarts.
+        let same = RuntimeArgsUpdate {
+            env: Some(args.env.clone()),
+            ..RuntimeArgsUpdate::default()
+        };
+        assert!(!store.update_global(same).await.restart_needed);
+
+        let history = store.history().await;
+        assert_eq!(
+            history
+                .iter()
+                .map(|entry| entry.version)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(history[0].actor, "startup");
+        assert_eq!(history[1].actor, "test");
+        assert_eq!(history[1].mode, "merge-patch");
+
+        let rollback = store
+            .rollback_update(0, "admin-api")
+            .await
+            .expect("version 0 is kept");
+        assert!(matches!(rollback.scope, RuntimeScope::Global));
+        let result = store.update_global(rollback.update).await;
+        assert!(result.restart_needed);
+        let args = store.get_effective(None).await;
+        assert_eq!(args.env["A"], "1");
+        assert!(args.headers.is_empty());
+        assert!(store.rollback_update(99, "admin-api").await.is_none());
+
+        assert!(validate_merge_patch(&serde_json::json!({"env": {"A": 1}})).is_err());
+        assert!(validate_merge_patch(&serde_json::json!({"cwd": "/tmp"})).is_err());
+        assert!(validate_merge_patch(&serde_json::json!({"env": null})).is_ok());
+    }
+
+    #[tokio::test]
+    async fn revert_global_restores_the_args_before_a_failed_update() {
+        let store = RuntimeArgsStore::new(RuntimeArgs {
+            extra_cli_args: Vec::new(),
+            env: HashMap::from([("A".to_string(), "1".to_string())]),
+            headers: HashMap::new(),
+        });
+        let result = store
+            .update_global(RuntimeArgsUpdate {
+                env: Some(HashMap::from([("A".to_string(), "2".to_string())])),
+                ..RuntimeArgsUpdate::default()
+            })
+            .await;
+        assert!(result.restart_needed);
+
+        store.revert_global(result.previous).await;
+        assert_eq!(store.get_effective(None).await.env["A"], "1");
+        let history = store.history().await;
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[2].actor, "revert (restart failed)");
+        assert_eq!(history[2].args.env["A"], "1");
+    }
+
+    #[test]
+    fn updates_with_secret_references_are_rejected() {
+        let replace = RuntimeArgsUpdate {
+            headers: Some(HashMap::from([(
+                "Authorization".to_string(),
+                "Bearer ${file:/etc/shadow}".to_string(),
+            )])),
+            ..RuntimeArgsUpdate::default()
+        };
+        let err = replace.reject_secret_refs().unwrap_err();
+        assert!(err.starts_with("headers.Authorization: "), "{err}");
+
+        let patch = RuntimeArgsUpdate::merge_patch(
+            serde_json::json!({"env": {"TOKEN": "${cmd:id}", "GONE": null}}),
+            "test",
+        )
+        .expect("valid patch");
+        assert!(patch.reject_secret_refs().is_err());
+
+        let plain = RuntimeArgsUpdate::merge_patch(
+            serde_json::json!({"env": {"HOME_DIR": "${HOME}"}}),
+            "test",
+        )
+        .expect("valid patch");
+        assert!(plain.reject_secret_refs().is_ok());
+    }
 }
@@ -0,0 +1,433 @@
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio_util::bytes::{Buf, BytesMut};
+use tokio_util::codec::Decoder;
+
+use crate::config::ChildFraming;
+
+pub const DEFAULT_MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+// Content-Length header blocks are a few dozen bytes; anything larger is not a header.
+const MAX_HEADER_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChildFrame {
+    Message(serde_json::Value),
+    NonJson(String),
+    Malformed(String),
+    Oversize,
+}
+
+// Splits child stdout into JSON-RPC messages. Frames over `max_frame_bytes` are skipped
+// (reported once as `Oversize`) without ever being buffered whole.
+pub struct ChildFrameCodec {
+    framing: ChildFraming,
+    max_frame_bytes: usize,
+    // Dropping bytes up to the next newline (ndjson lines, json-stream garbage).
+    discard_line: bool,
+    // Content-Length body bytes still to drop.
+    skip_bytes: usize,
+    scan: JsonScan,
+}
+
+#[derive(Default)]
+struct JsonScan {
+    started: bool,
+    discarding: bool,
+    pos: usize,
+    depth: usize,
+    in_string: bool,
+    escape: bool,
+}
+
+impl ChildFrameCodec {
+    pub fn new(framing: ChildFraming, max_frame_bytes: usize) -> Self {
+        Self {
+            framing,
+            max_frame_bytes,
+            discard_line: false,
+            skip_bytes: 0,
+            scan: JsonScan::default(),
+        }
+    }
+
+    fn decode_line(&mut self, buf: &mut BytesMut) -> Option<ChildFrame> {
+        loop {
+            if self.discard_line && !self.discard_through_newline(buf) {
+                return None;
+            }
+            let line = match self.take_line(buf) {
+                Ok(Some(line)) => line,
+                Ok(None) => return None,
+                Err(oversize) => return Some(oversize),
+            };
+            let line = line.trim_ascii();
+            if !line.is_empty() {
+                return Some(parse_frame(line));
+            }
+        }
+    }
+
+    // Returns false while the rest of an oversize line has not arrived yet.
+    fn discard_through_newline(&mut self, buf: &mut BytesMut) -> bool {
+        match buf.iter().position(|byte| *byte == b'\n') {
+            Some(pos) => {
+                buf.advance(pos + 1);
+                self.discard_line = false;
+                true
+            }
+            None => {
+                buf.clear();
+                false
+            }
+        }
+    }
+
+    fn take_line(&mut self, buf: &mut BytesMut) -> Result<Option<BytesMut>, ChildFrame> {
+        let Some(pos) = buf.iter().position(|byte| *byte == b'\n') else {
+            if buf.len() > self.max_frame_bytes {
+                buf.clear();
+                self.discard_line = true;
+                return Err(ChildFrame::Oversize);
+            }
+            return Ok(None);
+        };
+        let line = buf.split_to(pos + 1);
+        if pos > self.max_frame_bytes {
+            return Err(ChildFrame::Oversize);
+        }
+        Ok(Some(line))
+    }
+
+    fn decode_content_length(&mut self, buf: &mut BytesMut) -> Option<ChildFrame> {
+        loop {
+            if self.skip_bytes > 0 {
+                let skipped = self.skip_bytes.min(buf.len());
+                buf.advance(skipped);
+                self.skip_bytes -= skipped;
+                if self.skip_bytes > 0 {
+                    return None;
+                }
+            }
+            let leading = buf
+                .iter()
+                .take_while(|byte| byte.is_ascii_whitespace())
+                .count();
+            buf.advance(leading);
+            if buf.is_empty() {
+                return None;
+            }
+
+            let Some((header_len, body_start)) = find_header_end(buf) else {
+                if buf.len() > MAX_HEADER_BYTES {
+                    buf.clear();
+                    return Some(ChildFrame::Malformed(format!(
+                        "no Content-Length header block within {MAX_HEADER_BYTES} bytes"
+                    )));
+                }
+                return None;
+            };
+            let header = String::from_utf8_lossy(&buf[..header_len]).into_owned();
+            let Some(length) = content_length(&header) else {
+                buf.advance(body_start);
+                return Some(ChildFrame::Malformed(format!(
+                    "missing or invalid Content-Length in header block: {header}"
+                )));
+            };
+            if length > self.max_frame_bytes {
+                buf.advance(body_start);
+                self.skip_bytes = length;
+                return Some(ChildFrame::Oversize);
+            }
+            if buf.len() < body_start + length {
+                buf.reserve(body_start + length - buf.len());
+                return None;
+            }
+            buf.advance(body_start);
+            let body = buf.split_to(length);
+            if body.trim_ascii().is_empty() {
+                continue;
+            }
+            return Some(parse_frame(&body));
+        }
+    }
+
+    fn decode_json_stream(&mut self, buf: &mut BytesMut) -> Option<ChildFrame> {
+        loop {
+            if !self.scan.started {
+                if self.discard_line && !self.discard_through_newline(buf) {
+                    return None;
+                }
+                let leading = buf
+                    .iter()
+                    .take_while(|byte| byte.is_ascii_whitespace())
+                    .count();
+                buf.advance(leading);
+                if buf.is_empty() {
+                    return None;
+                }
+                if !matches!(buf[0], b'{' | b'[') {
+                    // Anything that does not open an object or array is reported line by line.
+                    return match self.take_line(buf) {
+                        Ok(Some(line)) => Some(ChildFrame::NonJson(
+                            String::from_utf8_lossy(line.trim_ascii()).into_owned(),
+                        )),
+                        Ok(None) => None,
+                        Err(oversize) => Some(oversize),
+                    };
+                }
+                self.scan = JsonScan {
+                    started: true,
+                    ..JsonScan::default()
+                };
+            }
+
+            let mut complete = false;
+            while self.scan.pos < buf.len() {
+                let byte = buf[self.scan.pos];
+                self.scan.pos += 1;
+                if self.scan.in_string {
+                    if self.scan.escape {
+                        self.scan.escape = false;
+                    } else if byte == b'\\' {
+                        self.scan.escape = true;
+                    } else if byte == b'"' {
+                        self.scan.in_string = false;
+                    }
+                    continue;
+                }
+                match byte {
+                    b'"' => self.scan.in_string = true,
+                    b'{' | b'[' => self.scan.depth += 1,
+                    b'}' | b']' => {
+                        self.scan.depth = self.scan.depth.saturating_sub(1);
+                        if self.scan.depth == 0 {
+                            complete = true;
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if complete {
+                let frame = buf.split_to(self.scan.pos);
+                let discarding = self.scan.discarding;
+                self.scan = JsonScan::default();
+                if discarding {
+                    continue;
+                }
+                if frame.len() > self.max_frame_bytes {
+                    return Some(ChildFrame::Oversize);
+                }
+                return Some(parse_frame(&frame));
+            }
+
+            if self.scan.discarding {
+                buf.advance(self.scan.pos);
+                self.scan.pos = 0;
+                return None;
+            }
+            if self.scan.pos > self.max_frame_bytes {
+                buf.advance(self.scan.pos);
+                self.scan.pos = 0;
+                self.scan.discarding = true;
+                return Some(ChildFrame::Oversize);
+            }
+            return None;
+        }
+    }
+}
+
+impl Decoder for ChildFrameCodec {
+    type Item = ChildFrame;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<ChildFrame>, std::io::Error> {
+        Ok(match self.framing {
+            ChildFraming::Ndjson => self.decode_line(buf),
+            ChildFraming::ContentLength => self.decode_content_length(buf),
+            ChildFraming::JsonStream => self.decode_json_stream(buf),
+        })
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<ChildFrame>, std::io::Error> {
+        if let Some(frame) = self.decode(buf)? {
+            return Ok(Some(frame));
+        }
+        let discarding = self.discard_line || self.skip_bytes > 0 || self.scan.discarding;
+        let rest = buf.split().freeze();
+        let rest = rest.trim_ascii();
+        if rest.is_empty() || discarding {
+            return Ok(None);
+        }
+        Ok(Some(match self.framing {
+            ChildFraming::Ndjson => parse_frame(rest),
+            ChildFraming::ContentLength | ChildFraming::JsonStream => {
+                ChildFrame::Malformed(format!("{} trailing bytes in a partial frame", rest.len()))
+            }
+        }))
+    }
+}
+
+pub fn encode_frame(framing: ChildFraming, message: &serde_json::Value) -> Result<Vec<u8>, String> {
+    let body = serde_json::to_vec(message).map_err(|err| err.to_string())?;
+    Ok(match framing {
+        ChildFraming::Ndjson | ChildFraming::JsonStream => {
+            let mut frame = body;
+            frame.push(b'\n');
+            frame
+        }
+        ChildFraming::ContentLength => {
+            let mut frame = format!("Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+            frame.extend_from_slice(&body);
+            frame
+        }
+    })
+}
+
+pub async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    framing: ChildFraming,
+    message: &serde_json::Value,
+) -> Result<(), String> {
+    let frame = encode_frame(framing, message)?;
+    writer
+        .write_all(&frame)
+        .await
+        .map_err(|err| err.to_string())
+}
+
+fn parse_frame(bytes: &[u8]) -> ChildFrame {
+    match serde_json::from_slice(bytes) {
+        Ok(value) => ChildFrame::Message(value),
+        Err(_) => ChildFrame::NonJson(String::from_utf8_lossy(bytes).into_owned()),
+    }
+}
+
+fn find_header_end(buf: &[u8]) -> Option<(usize, usize)> {
+    let crlf = buf
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|pos| (pos, pos + 4));
+    let lf = buf
+        .windows(2)
+        .position(|window| window == b"\n\n")
+        .map(|pos| (pos, pos + 2));
+    match (crlf, lf) {
+        (Some(crlf), Some(lf)) => Some(if crlf.0 <= lf.0 { crlf } else { lf }),
+        (crlf, lf) => crlf.or(lf),
+    }
+}
+
+fn content_length(header: &str) -> Option<usize> {
+    header.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            value.trim().parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(codec: &mut ChildFrameCodec, chunks: &[&[u8]]) -> Vec<ChildFrame> {
+        let mut buf = BytesMut::new();
+        let mut frames = Vec::new();
+        for chunk in chunks {
+            buf.extend_from_slice(chunk);
+            while let Some(frame) = codec.decode(&mut buf).expect("decode failed") {
+                frames.push(frame);
+            }
+        }
+        if let Some(frame) = codec.decode_eof(&mut buf).expect("decode_eof failed") {
+            frames.push(frame);
+        }
+        frames
+    }
+
+    fn message(id: u64) -> ChildFrame {
+        ChildFrame::Message(serde_json::json!({"jsonrpc": "2.0", "id": id}))
+    }
+
+    #[test]
+    fn ndjson_skips_oversize_lines_and_recovers() {
+        let mut codec = ChildFrameCodec::new(ChildFraming::Ndjson, 32);
+        let long = format!("{{\"pad\":\"{}\"}}", "x".repeat(64));
+        let frames = decode_all(
+            &mut codec,
+            &[
+                b"{\"jsonrpc\":\"2.0\",\"id\":1}\nnot json\n",
+                &long.as_bytes()[..40],
+                &long.as_bytes()[40..],
+                b"\n{\"jsonrpc\":\"2.0\",\"id\":2}",
+            ],
+        );
+        assert_eq!(
+            frames,
+            vec![
+                message(1),
+                ChildFrame::NonJson("not json".to_string()),
+                ChildFrame::Oversize,
+                message(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn content_length_frames_across_chunks_and_skips_oversize_bodies() {
+        let mut codec = ChildFrameCodec::new(ChildFraming::ContentLength, 64);
+        let first = encode_frame(
+            ChildFraming::ContentLength,
+            &serde_json::json!({"jsonrpc": "2.0", "id": 1}),
+        )
+        .expect("encode failed");
+        let big = format!("Content-Length: 100\r\n\r\n{}", " ".repeat(100));
+        let frames = decode_all(
+            &mut codec,
+            &[
+                &first[..10],
+                &first[10..],
+                big.as_bytes(),
+                b"Content-Type: application/json\r\ncontent-length: 24\r\n\r\n",
+                b"{\"jsonrpc\":\"2.0\",\"id\":2}",
+            ],
+        );
+        assert_eq!(frames, vec![message(1), ChildFrame::Oversize, message(2)]);
+    }
+
+    #[test]
+    fn json_stream_accepts_pretty_printed_objects() {
+        let mut codec = ChildFrameCodec::new(ChildFraming::JsonStream, 256);
+        let frames = decode_all(
+            &mut codec,
+            &[
+                b"{\n  \"jsonrpc\": \"2.0\",\n  \"note\": \"brace } in string\",\n",
+                b"  \"id\": 1\n}\n{\"jsonrpc\":\"2.0\",\"id\":2}",
+                b"\nstray text\n",
+            ],
+        );
+        assert_eq!(frames.len(), 3);
+        assert!(matches!(&frames[0], ChildFrame::Message(value) if value["id"] == 1));
+        assert_eq!(frames[1], message(2));
+        assert_eq!(frames[2], ChildFrame::NonJson("stray text".to_string()));
+    }
+
+    #[test]
+    fn json_stream_discards_oversize_objects() {
+        let mut codec = ChildFrameCodec::new(ChildFraming::JsonStream, 32);
+        let big = format!("{{\"pad\":\"{}\"}}", "y".repeat(80));
+        let frames = decode_all(
+            &mut codec,
+            &[
+                &big.as_bytes()[..40],
+                &big.as_bytes()[40..],
+                b"{\"jsonrpc\":\"2.0\",\"id\":3}",
+            ],
+        );
+        assert_eq!(frames, vec![ChildFrame::Oversize, message(3)]);
+    }
+}
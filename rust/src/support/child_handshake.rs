@@ -0,0 +1,153 @@
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use serde_json::Value;
+use tokio::io::AsyncRead;
+use tokio::process::ChildStdin;
+use tokio_util::codec::FramedRead;
+
+use crate::config::ChildFraming;
+use crate::support::child_framing::{write_frame, ChildFrame, ChildFrameCodec};
+
+// A child that cannot finish `initialize` in this long is given up on.
+const INITIALIZE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// What a gateway-initialized child does with a client message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientHandshake {
+    Forward,
+    // A client `initialize`, answered with the cached result instead.
+    Answer(Value),
+    // A client `notifications/initialized`; the child already had one from the gateway.
+    Swallow,
+}
+
+// Runs the MCP handshake on a freshly spawned child before any client traffic reaches it and
+// returns its `InitializeResult`. Anything the child prints before that answer is dropped.
+pub async fn initialize<R: AsyncRead + Unpin>(
+    stdin: &mut ChildStdin,
+    frames: &mut FramedRead<R, ChildFrameCodec>,
+    framing: ChildFraming,
+    params: Value,
+    id: &str,
+) -> Result<Value, String> {
+    let started = Instant::now();
+    write_frame(stdin, framing, &initialize_request(id, params)).await?;
+    let answer = tokio::time::timeout(INITIALIZE_TIMEOUT, async {
+        while let Some(frame) = frames.next().await {
+            match frame.map_err(|err| err.to_string())? {
+                ChildFrame::Message(json)
+                    if json.get("method").is_none()
+                        && json.get("id").and_then(Value::as_str) == Some(id) =>
+                {
+                    return Ok(json);
+                }
+                ChildFrame::Message(json) => {
+                    tracing::debug!("Child output before initialize: {json}");
+                }
+                other => {
+                    tracing::debug!("Child output before initialize: {other:?}");
+                }
+            }
+        }
+        Err("child closed stdout before answering initialize".to_string())
+    })
+    .await
+    .map_err(|_| {
+        format!(
+            "child did not answer initialize within {}s",
+            INITIALIZE_TIMEOUT.as_secs()
+        )
+    })??;
+    if let Some(error) = answer.get("error") {
+        return Err(format!("child rejected initialize: {error}"));
+    }
+    let result = answer.get("result").cloned().unwrap_or(Value::Null);
+    write_frame(stdin, framing, &initialized_notification()).await?;
+    tracing::debug!("Child initialized in {}ms", started.elapsed().as_millis());
+    Ok(result)
+}
+
+// Batches are forwarded untouched: `initialize` must not be batched.
+pub fn client_handshake(message: &Value, cached: Option<&Value>) -> ClientHandshake {
+    match message.get("method").and_then(Value::as_str) {
+        Some("notifications/initialized") => ClientHandshake::Swallow,
+        Some("initialize") => match (message.get("id"), cached) {
+            (Some(id), Some(result)) => ClientHandshake::Answer(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": result
+            })),
+            _ => ClientHandshake::Forward,
+        },
+        _ => ClientHandshake::Forward,
+    }
+}
+
+// The params the gateway initializes a child with on its own behalf.
+pub fn initialize_params(protocol_version: &str) -> Value {
+    serde_json::json!({
+        "protocolVersion": protocol_version,
+        "capabilities": {},
+        "clientInfo": {
+            "name": "mcpway",
+            "version": crate::support::version::get_version()
+        }
+    })
+}
+
+fn initialize_request(id: &str, params: Value) -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "initialize",
+        "params": params
+    })
+}
+
+fn initialized_notification() -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/initialized"
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn client_handshake_answers_from_cache_and_swallows_initialized() {
+        let cached = json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": {"tools": {}},
+            "serverInfo": {"name": "demo", "version": "1.0.0"},
+            "instructions": "Use the tools."
+        });
+        let initialize =
+            json!({"jsonrpc": "2.0", "id": "s1:7", "method": "initialize", "params": {}});
+        assert_eq!(
+            client_handshake(&initialize, Some(&cached)),
+            ClientHandshake::Answer(json!({"jsonrpc": "2.0", "id": "s1:7", "result": cached}))
+        );
+        assert_eq!(
+            client_handshake(&initialize, None),
+            ClientHandshake::Forward
+        );
+        assert_eq!(
+            client_handshake(
+                &json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+                Some(&cached)
+            ),
+            ClientHandshake::Swallow
+        );
+        assert_eq!(
+            client_handshake(
+                &json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+                Some(&cached)
+            ),
+            ClientHandshake::Forward
+        );
+    }
+}
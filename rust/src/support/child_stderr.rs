@@ -0,0 +1,419 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use futures::StreamExt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncRead;
+use tokio::sync::broadcast;
+use tokio_util::codec::{FramedRead, LinesCodec};
+
+use crate::support::redact::redactor;
+
+pub const DEFAULT_STDERR_BUFFER_LINES: usize = 1000;
+const STDERR_STREAM_BUFFER: usize = 256;
+// Buffers of children that have gone away are kept for post-mortems, up to this many.
+const MAX_EXITED_CHILDREN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StderrLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl StderrLevel {
+    fn from_token(token: &str) -> Option<Self> {
+        match token.to_ascii_lowercase().as_str() {
+            "trace" => Some(Self::Trace),
+            "debug" => Some(Self::Debug),
+            "info" | "notice" => Some(Self::Info),
+            "warn" | "warning" => Some(Self::Warn),
+            "error" | "err" | "critical" | "fatal" | "panic" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Maps lines matching `regex` to `level`, from `--child-stderr-pattern REGEX=LEVEL`.
+#[derive(Debug, Clone)]
+pub struct StderrPattern {
+    pub regex: Regex,
+    pub level: StderrLevel,
+}
+
+/// How child stderr is classified and how much of it each child keeps.
+#[derive(Debug, Clone)]
+pub struct StderrOptions {
+    pub default_level: StderrLevel,
+    pub buffer_lines: usize,
+    pub patterns: Vec<StderrPattern>,
+}
+
+impl Default for StderrOptions {
+    fn default() -> Self {
+        Self {
+            default_level: StderrLevel::Info,
+            buffer_lines: DEFAULT_STDERR_BUFFER_LINES,
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl StderrOptions {
+    /// Custom patterns win, then the common logging formats, then the default level.
+    pub fn classify(&self, line: &str) -> StderrLevel {
+        if let Some(pattern) = self
+            .patterns
+            .iter()
+            .find(|pattern| pattern.regex.is_match(line))
+        {
+            return pattern.level;
+        }
+        known_level(line).unwrap_or(self.default_level)
+    }
+}
+
+// Level tokens as written by python `logging` (`WARNING:root:...`, `... - ERROR - ...`),
+// bracketed or prefixed loggers (`[info]`, `INFO ...`), logfmt and JSON logs.
+fn known_level(line: &str) -> Option<StderrLevel> {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            r"(?i)^\s*(trace|debug|info|notice|warn|warning|error|err|critical|fatal)\s*[:|\]]",
+            r"(?i)^\s*\[\s*(trace|debug|info|notice|warn|warning|error|critical|fatal)\s*\]",
+            r"(?i)^\s*(TRACE|DEBUG|INFO|WARN|WARNING|ERROR|CRITICAL|FATAL)\s",
+            r" - (TRACE|DEBUG|INFO|WARNING|ERROR|CRITICAL) - ",
+            r"(?i)\blevel=(trace|debug|info|warn|warning|error|fatal)\b",
+            r#"(?i)"level"\s*:\s*"(trace|debug|info|warn|warning|error|fatal)""#,
+            r"\s(TRACE|DEBUG|INFO|WARN|ERROR)\s",
+        ]
+        .iter()
+        .filter_map(|pattern| Regex::new(pattern).ok())
+        .collect()
+    });
+    if line.starts_with("Traceback (most recent call last)") || line.contains("' panicked at ") {
+        return Some(StderrLevel::Error);
+    }
+    patterns.iter().find_map(|pattern| {
+        pattern
+            .captures(line)
+            .and_then(|captures| captures.get(1))
+            .and_then(|token| StderrLevel::from_token(token.as_str()))
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StderrLine {
+    pub seq: u64,
+    pub ts_utc_ms: u64,
+    pub level: StderrLevel,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChildStderrSummary {
+    pub id: String,
+    pub name: String,
+    pub pid: Option<u32>,
+    pub exited: bool,
+    pub buffered: usize,
+    pub capacity: usize,
+    pub dropped: u64,
+}
+
+/// A child's stderr: the last `capacity` lines plus a feed of new ones. It outlives
+/// restarts of the same child, so output from before a crash is still there after it.
+pub struct ChildStderr {
+    id: String,
+    name: String,
+    pid: AtomicU32,
+    exited: AtomicBool,
+    capacity: usize,
+    next_seq: AtomicU64,
+    lines: Mutex<VecDeque<StderrLine>>,
+    sender: broadcast::Sender<StderrLine>,
+}
+
+impl ChildStderr {
+    pub fn set_pid(&self, pid: Option<u32>) {
+        self.pid.store(pid.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    fn push(&self, level: StderrLevel, line: &str) {
+        let entry = StderrLine {
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed) + 1,
+            ts_utc_ms: unix_timestamp_millis(),
+            level,
+            line: redactor().redact_text(line).into_owned(),
+        };
+        if let Ok(mut lines) = self.lines.lock() {
+            if lines.len() == self.capacity {
+                lines.pop_front();
+            }
+            if self.capacity > 0 {
+                lines.push_back(entry.clone());
+            }
+        }
+        let _ = self.sender.send(entry);
+    }
+
+    /// Buffered lines at or above `min_level`, oldest first, limited to the newest `limit`.
+    pub fn snapshot(
+        &self,
+        min_level: Option<StderrLevel>,
+        limit: Option<usize>,
+    ) -> Vec<StderrLine> {
+        let Ok(lines) = self.lines.lock() else {
+            return Vec::new();
+        };
+        let mut matching: Vec<StderrLine> = lines
+            .iter()
+            .filter(|line| min_level.is_none_or(|min| line.level >= min))
+            .cloned()
+            .collect();
+        if let Some(limit) = limit {
+            let excess = matching.len().saturating_sub(limit);
+            matching.drain(..excess);
+        }
+        matching
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StderrLine> {
+        self.sender.subscribe()
+    }
+
+    pub fn summary(&self) -> ChildStderrSummary {
+        let buffered = self.lines.lock().map(|lines| lines.len()).unwrap_or(0);
+        let total = self.next_seq.load(Ordering::Relaxed);
+        let pid = self.pid.load(Ordering::Relaxed);
+        ChildStderrSummary {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            pid: (pid != 0).then_some(pid),
+            exited: self.exited.load(Ordering::Relaxed),
+            buffered,
+            capacity: self.capacity,
+            dropped: total.saturating_sub(buffered as u64),
+        }
+    }
+}
+
+/// Registers the buffer with the process-wide registry and marks it exited when dropped.
+pub struct ChildStderrHandle {
+    buffer: Arc<ChildStderr>,
+}
+
+impl ChildStderrHandle {
+    pub fn buffer(&self) -> Arc<ChildStderr> {
+        self.buffer.clone()
+    }
+}
+
+impl std::ops::Deref for ChildStderrHandle {
+    type Target = ChildStderr;
+
+    fn deref(&self) -> &ChildStderr {
+        &self.buffer
+    }
+}
+
+impl Drop for ChildStderrHandle {
+    fn drop(&mut self) {
+        self.buffer.exited.store(true, Ordering::Relaxed);
+        self.buffer.set_pid(None);
+        registry().prune_exited();
+    }
+}
+
+// Every child registers here so the admin API can list them; ids are assigned in order.
+struct StderrRegistry {
+    next_id: AtomicU64,
+    children: Mutex<BTreeMap<u64, Arc<ChildStderr>>>,
+}
+
+impl StderrRegistry {
+    fn prune_exited(&self) {
+        let Ok(mut children) = self.children.lock() else {
+            return;
+        };
+        let exited: Vec<u64> = children
+            .iter()
+            .filter(|(_, child)| child.exited.load(Ordering::Relaxed))
+            .map(|(id, _)| *id)
+            .collect();
+        let excess = exited.len().saturating_sub(MAX_EXITED_CHILDREN);
+        for id in &exited[..excess] {
+            children.remove(id);
+        }
+    }
+}
+
+fn registry() -> &'static StderrRegistry {
+    static REGISTRY: OnceLock<StderrRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| StderrRegistry {
+        next_id: AtomicU64::new(0),
+        children: Mutex::new(BTreeMap::new()),
+    })
+}
+
+pub fn register(name: &str, options: &StderrOptions) -> ChildStderrHandle {
+    let registry = registry();
+    let id = registry.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let buffer = Arc::new(ChildStderr {
+        id: id.to_string(),
+        name: name.to_string(),
+        pid: AtomicU32::new(0),
+        exited: AtomicBool::new(false),
+        capacity: options.buffer_lines,
+        next_seq: AtomicU64::new(0),
+        lines: Mutex::new(VecDeque::with_capacity(options.buffer_lines.min(1024))),
+        sender: broadcast::channel(STDERR_STREAM_BUFFER).0,
+    });
+    if let Ok(mut children) = registry.children.lock() {
+        children.insert(id, buffer.clone());
+    }
+    ChildStderrHandle { buffer }
+}
+
+pub fn list() -> Vec<ChildStderrSummary> {
+    registry()
+        .children
+        .lock()
+        .map(|children| children.values().map(|child| child.summary()).collect())
+        .unwrap_or_default()
+}
+
+pub fn get(id: &str) -> Option<Arc<ChildStderr>> {
+    let id = id.parse::<u64>().ok()?;
+    registry().children.lock().ok()?.get(&id).cloned()
+}
+
+/// Reads `stderr` line by line into `buffer`, logging each line at its classified level.
+pub fn capture<R>(stderr: R, buffer: Arc<ChildStderr>, options: StderrOptions)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = FramedRead::new(stderr, LinesCodec::new());
+        while let Some(line) = lines.next().await {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    tracing::error!(child_id = %buffer.id, "Error reading child stderr: {err}");
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let level = options.classify(&line);
+            buffer.push(level, &line);
+            let child_id = buffer.id.as_str();
+            match level {
+                StderrLevel::Trace => tracing::trace!(child_id, "Child stderr: {line}"),
+                StderrLevel::Debug => tracing::debug!(child_id, "Child stderr: {line}"),
+                StderrLevel::Info => tracing::info!(child_id, "Child stderr: {line}"),
+                StderrLevel::Warn => tracing::warn!(child_id, "Child stderr: {line}"),
+                StderrLevel::Error => tracing::error!(child_id, "Child stderr: {line}"),
+            }
+        }
+    });
+}
+
+fn unix_timestamp_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_maps_common_logging_formats() {
+        let options = StderrOptions::default();
+        assert_eq!(
+            options.classify("WARNING:root:disk almost full"),
+            StderrLevel::Warn
+        );
+        assert_eq!(
+            options.classify("2026-01-01 10:00:00,123 - server - ERROR - boom"),
+            StderrLevel::Error
+        );
+        assert_eq!(options.classify("[debug] handshake"), StderrLevel::Debug);
+        assert_eq!(
+            options.classify("time=1 level=warn msg=slow"),
+            StderrLevel::Warn
+        );
+        assert_eq!(
+            options.classify(r#"{"level":"error","msg":"x"}"#),
+            StderrLevel::Error
+        );
+        assert_eq!(
+            options.classify("Traceback (most recent call last):"),
+            StderrLevel::Error
+        );
+        assert_eq!(
+            options.classify("Weather MCP server running on stdio"),
+            StderrLevel::Info
+        );
+
+        let custom = StderrOptions {
+            default_level: StderrLevel::Warn,
+            buffer_lines: 10,
+            patterns: vec![StderrPattern {
+                regex: Regex::new("running on stdio").expect("valid regex"),
+                level: StderrLevel::Debug,
+            }],
+        };
+        assert_eq!(
+            custom.classify("Weather MCP server running on stdio"),
+            StderrLevel::Debug
+        );
+        assert_eq!(custom.classify("something odd"), StderrLevel::Warn);
+    }
+
+    #[test]
+    fn buffer_keeps_the_newest_lines_and_filters_by_level() {
+        let options = StderrOptions {
+            buffer_lines: 3,
+            ..StderrOptions::default()
+        };
+        let handle = register("demo", &options);
+        for (index, level) in [
+            StderrLevel::Info,
+            StderrLevel::Error,
+            StderrLevel::Info,
+            StderrLevel::Warn,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            handle.push(level, &format!("line {index}"));
+        }
+
+        let all = handle.snapshot(None, None);
+        assert_eq!(
+            all.iter().map(|line| line.seq).collect::<Vec<_>>(),
+            vec![2, 3, 4]
+        );
+        let warnings = handle.snapshot(Some(StderrLevel::Warn), None);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(handle.snapshot(None, Some(1))[0].line, "line 3");
+        assert_eq!(handle.summary().dropped, 1);
+
+        let id = handle.summary().id;
+        assert!(get(&id).is_some());
+        drop(handle);
+        assert!(get(&id).is_some_and(|buffer| buffer.summary().exited));
+    }
+}
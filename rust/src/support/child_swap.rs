@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::Notify;
+
+pub const DEFAULT_DRAIN_TIMEOUT_MS: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartStrategy {
+    // Stop the running child, then start the new one; in-flight requests are lost.
+    #[default]
+    Kill,
+    // Start and initialize the new child first, move traffic over, then drain the old one.
+    BlueGreen,
+}
+
+// How a runtime update that needs a restart replaces a running child.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplacementOptions {
+    pub strategy: RestartStrategy,
+    pub drain_timeout: Duration,
+}
+
+impl Default for ReplacementOptions {
+    fn default() -> Self {
+        Self {
+            strategy: RestartStrategy::Kill,
+            drain_timeout: Duration::from_millis(DEFAULT_DRAIN_TIMEOUT_MS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SwapReport {
+    pub child: String,
+    pub old_pid: Option<u32>,
+    pub new_pid: Option<u32>,
+    // Requests the old child still owed an answer when traffic moved to the new one.
+    pub in_flight: usize,
+    pub drained: usize,
+    // Requests still unanswered at the drain timeout; clients get an error for each.
+    pub abandoned: usize,
+    pub drain_ms: u64,
+}
+
+// Requests written to one child process that it has not answered yet, keyed by their
+// JSON-RPC id as the child saw it. Gateways that share a child prefix ids with the session,
+// so the key is the (session, id) pair; a repeated id is counted, not collapsed, so each
+// copy waits for its own answer.
+#[derive(Default)]
+pub struct InFlight {
+    pending: Mutex<HashMap<String, (Value, usize)>>,
+    closed: AtomicBool,
+    settled: Notify,
+}
+
+impl InFlight {
+    pub fn track(&self, message: &Value) {
+        let mut pending = self.pending.lock().unwrap_or_else(|err| err.into_inner());
+        for item in batch_items(message) {
+            if item.get("method").is_none() {
+                continue;
+            }
+            if let Some(id) = item.get("id").filter(|id| !id.is_null()) {
+                pending.entry(id.to_string()).or_insert((id.clone(), 0)).1 += 1;
+            }
+        }
+    }
+
+    pub fn settle(&self, message: &Value) {
+        let mut settled = false;
+        {
+            let mut pending = self.pending.lock().unwrap_or_else(|err| err.into_inner());
+            for item in batch_items(message) {
+                if item.get("method").is_some() {
+                    continue;
+                }
+                let Some(id) = item.get("id") else {
+                    continue;
+                };
+                let key = id.to_string();
+                if let Some((_, count)) = pending.get_mut(&key) {
+                    *count -= 1;
+                    if *count == 0 {
+                        pending.remove(&key);
+                    }
+                    settled = true;
+                }
+            }
+        }
+        if settled {
+            self.settled.notify_waiters();
+        }
+    }
+
+    // Called when the child's stdout ends: nothing left can be answered.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.settled.notify_waiters();
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .values()
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Waits until every pending request is answered, the child's output ends or the timeout
+    // passes, then hands back the ids that were never answered.
+    pub async fn drain(&self, timeout: Duration) -> Vec<Value> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let notified = self.settled.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+            if self.is_empty() || self.closed.load(Ordering::SeqCst) {
+                break;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                break;
+            }
+        }
+        let mut pending = self.pending.lock().unwrap_or_else(|err| err.into_inner());
+        pending
+            .drain()
+            .flat_map(|(_, (id, count))| std::iter::repeat_n(id, count))
+            .collect()
+    }
+}
+
+fn batch_items(message: &Value) -> &[Value] {
+    match message {
+        Value::Array(items) => items,
+        single => std::slice::from_ref(single),
+    }
+}
+
+// The answer clients get for a request the old child never finished.
+pub fn abandoned_response(id: Value) -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": -32603,
+            "message": "Server was replaced before it answered this request"
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn drain_returns_once_requests_are_answered_and_reports_the_rest() {
+        let in_flight = std::sync::Arc::new(InFlight::default());
+        in_flight.track(&json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "tools/call"},
+            {"jsonrpc": "2.0", "id": "a", "method": "tools/list"},
+            {"jsonrpc": "2.0", "method": "notifications/cancelled"}
+        ]));
+        // Answers to server-initiated requests carry no method and are not tracked.
+        in_flight.track(&json!({"jsonrpc": "2.0", "id": 9, "result": {}}));
+        assert_eq!(in_flight.len(), 2);
+
+        let answering = in_flight.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            answering.settle(&json!({"jsonrpc": "2.0", "id": 1, "result": {}}));
+            answering.settle(&json!({"jsonrpc": "2.0", "id": "a", "result": {}}));
+        });
+        let started = Instant::now();
+        assert!(in_flight.drain(Duration::from_secs(5)).await.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        in_flight.track(&json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call"}));
+        let abandoned = in_flight.drain(Duration::from_millis(20)).await;
+        assert_eq!(abandoned, vec![json!(2)]);
+        assert_eq!(abandoned_response(json!(2))["error"]["code"], json!(-32603));
+    }
+
+    #[tokio::test]
+    async fn each_copy_of_a_repeated_id_waits_for_its_own_answer() {
+        let in_flight = InFlight::default();
+        in_flight.track(&json!({"jsonrpc": "2.0", "id": "a:1", "method": "tools/call"}));
+        in_flight.track(&json!({"jsonrpc": "2.0", "id": "b:1", "method": "tools/call"}));
+        in_flight.track(&json!({"jsonrpc": "2.0", "id": "b:1", "method": "tools/call"}));
+        assert_eq!(in_flight.len(), 3);
+
+        in_flight.settle(&json!({"jsonrpc": "2.0", "id": "b:1", "result": {}}));
+        assert_eq!(in_flight.len(), 2);
+        let mut abandoned = in_flight.drain(Duration::from_millis(20)).await;
+        abandoned.sort_by_key(Value::to_string);
+        assert_eq!(abandoned, vec![json!("a:1"), json!("b:1")]);
+    }
+}
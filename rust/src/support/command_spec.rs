@@ -1,3 +1,8 @@
+use crate::config::ChildFraming;
+use crate::support::child_framing::DEFAULT_MAX_FRAME_BYTES;
+use crate::support::child_stderr::StderrOptions;
+use crate::support::child_swap::ReplacementOptions;
+use crate::support::launch::LaunchOptions;
 use crate::support::stdio_child::CommandSpec;
 
 pub fn parse_command_spec(cmd: &str) -> Result<CommandSpec, String> {
@@ -5,8 +10,22 @@ pub fn parse_command_spec(cmd: &str) -> Result<CommandSpec, String> {
     if parts.is_empty() {
         return Err("stdio command is empty".into());
     }
+    // Until --server-name says otherwise, a child is known by its program name.
+    let name = std::path::Path::new(&parts[0])
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| parts[0].clone());
     Ok(CommandSpec {
+        name,
         program: parts[0].clone(),
         args: parts[1..].to_vec(),
+        framing: ChildFraming::Ndjson,
+        max_frame_bytes: DEFAULT_MAX_FRAME_BYTES,
+        launch: LaunchOptions::default(),
+        stderr: StderrOptions::default(),
+        replacement: ReplacementOptions::default(),
+        protocol_version: "2024-11-05".to_string(),
+        managed_initialize: false,
+        initialize_params: None,
     })
 }
@@ -1,50 +1,103 @@
+use std::sync::{Arc, OnceLock};
+
+use arc_swap::ArcSwap;
 use axum::http::header::HeaderName;
 use regex::Regex;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 
 use crate::config::CorsConfig;
 
+// Set when the gateway runs with --config; routers then consult the current policy on
+// every request instead of baking it into the layer.
+static LIVE_POLICY: OnceLock<ArcSwap<OriginPolicy>> = OnceLock::new();
+
+enum OriginPolicy {
+    Disabled,
+    AllowAll,
+    AllowList {
+        exact: Vec<String>,
+        regexes: Vec<Regex>,
+    },
+}
+
+impl OriginPolicy {
+    fn from_config(cors: &CorsConfig) -> Self {
+        match cors {
+            CorsConfig::Disabled => Self::Disabled,
+            CorsConfig::AllowAll => Self::AllowAll,
+            CorsConfig::AllowList { raw } => {
+                if raw.is_empty() {
+                    return Self::Disabled;
+                }
+                if raw.iter().any(|origin| origin == "*") {
+                    return Self::AllowAll;
+                }
+                let mut exact = Vec::new();
+                let mut regexes: Vec<Regex> = Vec::new();
+                for origin in raw {
+                    if origin.starts_with('/') && origin.ends_with('/') && origin.len() > 2 {
+                        let pattern = &origin[1..origin.len() - 1];
+                        if let Ok(re) = Regex::new(pattern) {
+                            regexes.push(re);
+                            continue;
+                        }
+                    }
+                    exact.push(origin.clone());
+                }
+                Self::AllowList { exact, regexes }
+            }
+        }
+    }
+
+    fn allows(&self, origin: &str) -> bool {
+        match self {
+            Self::Disabled => false,
+            Self::AllowAll => true,
+            Self::AllowList { exact, regexes } => {
+                exact.iter().any(|v| v == origin) || regexes.iter().any(|re| re.is_match(origin))
+            }
+        }
+    }
+}
+
+// Must run before the gateway builds its router.
+pub fn enable_live_cors(cors: &CorsConfig) {
+    LIVE_POLICY.get_or_init(|| ArcSwap::from_pointee(OriginPolicy::from_config(cors)));
+}
+
+pub fn update_live_cors(cors: &CorsConfig) {
+    if let Some(live) = LIVE_POLICY.get() {
+        live.store(Arc::new(OriginPolicy::from_config(cors)));
+    }
+}
+
 pub fn build_cors_layer(cors: &CorsConfig) -> Option<CorsLayer> {
-    match cors {
-        CorsConfig::Disabled => None,
-        CorsConfig::AllowAll => Some(
+    if let Some(live) = LIVE_POLICY.get() {
+        let allow = AllowOrigin::predicate(move |origin, _req_parts| {
+            origin
+                .to_str()
+                .map(|origin| live.load().allows(origin))
+                .unwrap_or(false)
+        });
+        return Some(
+            CorsLayer::very_permissive()
+                .allow_origin(allow)
+                .expose_headers([HeaderName::from_static("mcp-session-id")]),
+        );
+    }
+
+    match OriginPolicy::from_config(cors) {
+        OriginPolicy::Disabled => None,
+        OriginPolicy::AllowAll => Some(
             CorsLayer::very_permissive()
                 .expose_headers([HeaderName::from_static("mcp-session-id")]),
         ),
-        CorsConfig::AllowList { raw } => {
-            if raw.is_empty() {
-                return None;
-            }
-            if raw.iter().any(|origin| origin == "*") {
-                return Some(
-                    CorsLayer::very_permissive()
-                        .expose_headers([HeaderName::from_static("mcp-session-id")]),
-                );
-            }
-            let mut exact = Vec::new();
-            let mut regexes: Vec<Regex> = Vec::new();
-            for origin in raw {
-                if origin.starts_with('/') && origin.ends_with('/') && origin.len() > 2 {
-                    let pattern = &origin[1..origin.len() - 1];
-                    if let Ok(re) = Regex::new(pattern) {
-                        regexes.push(re);
-                        continue;
-                    }
-                }
-                exact.push(origin.clone());
-            }
+        policy => {
             let allow = AllowOrigin::predicate(move |origin, _req_parts| {
-                if let Ok(origin_str) = origin.to_str() {
-                    if exact.iter().any(|v| v == origin_str) {
-                        return true;
-                    }
-                    for re in &regexes {
-                        if re.is_match(origin_str) {
-                            return true;
-                        }
-                    }
-                }
-                false
+                origin
+                    .to_str()
+                    .map(|origin| policy.allows(origin))
+                    .unwrap_or(false)
             });
             Some(
                 CorsLayer::new()
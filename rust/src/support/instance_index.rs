@@ -0,0 +1,160 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// A running gateway that exposes a runtime admin endpoint, as recorded in the local index.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceEntry {
+    pub pid: u32,
+    pub name: Option<String>,
+    pub output_transport: String,
+    pub admin_url: String,
+    pub started_at_utc: u64,
+}
+
+impl InstanceEntry {
+    pub fn new(
+        name: Option<String>,
+        output_transport: &str,
+        admin_addr: SocketAddr,
+    ) -> InstanceEntry {
+        // A wildcard bind is reachable from this host on loopback.
+        let host = if admin_addr.ip().is_unspecified() {
+            if admin_addr.is_ipv6() {
+                "[::1]".to_string()
+            } else {
+                "127.0.0.1".to_string()
+            }
+        } else if admin_addr.is_ipv6() {
+            format!("[{}]", admin_addr.ip())
+        } else {
+            admin_addr.ip().to_string()
+        };
+
+        InstanceEntry {
+            pid: std::process::id(),
+            name,
+            output_transport: output_transport.to_string(),
+            admin_url: format!("http://{host}:{}", admin_addr.port()),
+            started_at_utc: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self.name.as_deref() {
+            Some(name) => name.to_string(),
+            None => format!("{}-{}", self.output_transport, self.pid),
+        }
+    }
+}
+
+/// Removes the instance's index file when dropped.
+pub struct InstanceRegistration {
+    path: PathBuf,
+}
+
+impl Drop for InstanceRegistration {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+pub fn default_index_dir() -> PathBuf {
+    if let Some(path) = std::env::var_os("MCPWAY_INSTANCE_DIR") {
+        return PathBuf::from(path);
+    }
+    if let Some(home) = crate::discovery::user_home_dir() {
+        return home.join(".mcpway").join("instances");
+    }
+    PathBuf::from(".mcpway/instances")
+}
+
+pub fn register(dir: &Path, entry: &InstanceEntry) -> Result<InstanceRegistration, String> {
+    std::fs::create_dir_all(dir)
+        .map_err(|err| format!("Failed to create {}: {err}", dir.display()))?;
+    let path = dir.join(format!("{}.json", entry.pid));
+    let body = serde_json::to_string_pretty(entry)
+        .map_err(|err| format!("Failed to serialize instance entry: {err}"))?;
+    std::fs::write(&path, body)
+        .map_err(|err| format!("Failed to write {}: {err}", path.display()))?;
+    Ok(InstanceRegistration { path })
+}
+
+/// Lists live instances, pruning entries left behind by processes that have exited.
+pub fn list(dir: &Path) -> Vec<InstanceEntry> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+    for item in read_dir.flatten() {
+        let path = item.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(entry) = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|body| serde_json::from_str::<InstanceEntry>(&body).ok())
+        else {
+            continue;
+        };
+        if !process_alive(entry.pid) {
+            let _ = std::fs::remove_file(&path);
+            continue;
+        }
+        entries.push(entry);
+    }
+    entries.sort_by_key(|entry| entry.pid);
+    entries
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks that the pid exists; EPERM still means it is running.
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_and_list_round_trip_and_prune_dead_entries() {
+        let dir = std::env::temp_dir().join(format!("mcpway-instances-{}", uuid::Uuid::new_v4()));
+        let entry = InstanceEntry::new(
+            Some("docs".to_string()),
+            "sse",
+            "0.0.0.0:9101".parse().expect("socket addr"),
+        );
+        assert_eq!(entry.admin_url, "http://127.0.0.1:9101");
+
+        let registration = register(&dir, &entry).expect("register instance");
+        let stale = InstanceEntry {
+            pid: u32::MAX / 2,
+            ..entry.clone()
+        };
+        std::fs::write(
+            dir.join(format!("{}.json", stale.pid)),
+            serde_json::to_string(&stale).expect("serialize stale entry"),
+        )
+        .expect("write stale entry");
+
+        assert_eq!(list(&dir), vec![entry]);
+        assert!(!dir.join(format!("{}.json", stale.pid)).exists());
+
+        drop(registration);
+        assert!(list(&dir).is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
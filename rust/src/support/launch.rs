@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+
+use tokio::process::Command;
+
+use crate::config::ChildEnvMode;
+use crate::support::sandbox::SandboxPolicy;
+
+#[cfg(target_os = "linux")]
+const MEMORY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    pub address_space: Option<u64>,
+    pub cpu_seconds: Option<u64>,
+    pub open_files: Option<u64>,
+    pub processes: Option<u64>,
+}
+
+impl ResourceLimits {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+// How a stdio child is launched beyond its command line: working directory, environment,
+// credentials and resource limits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchOptions {
+    pub cwd: Option<PathBuf>,
+    pub env_mode: ChildEnvMode,
+    pub env_allow: Vec<String>,
+    pub path_prepend: Vec<PathBuf>,
+    pub umask: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub rlimits: ResourceLimits,
+    pub max_rss_bytes: Option<u64>,
+    pub sandbox: SandboxPolicy,
+}
+
+impl LaunchOptions {
+    // Runtime env (from --env and runtime overrides) is applied after the inherited
+    // environment is filtered, so explicit values always reach the child.
+    pub fn apply(
+        &self,
+        cmd: &mut Command,
+        runtime_env: &HashMap<String, String>,
+        server_name: &str,
+    ) -> Result<(), String> {
+        if let Some(cwd) = &self.cwd {
+            cmd.current_dir(cwd);
+        }
+
+        match self.env_mode {
+            ChildEnvMode::Inherit => {}
+            ChildEnvMode::Allowlist => {
+                cmd.env_clear();
+                for name in &self.env_allow {
+                    if let Some(value) = std::env::var_os(name) {
+                        cmd.env(name, value);
+                    }
+                }
+            }
+            ChildEnvMode::Clear => {
+                cmd.env_clear();
+            }
+        }
+        if !runtime_env.is_empty() {
+            cmd.envs(runtime_env.clone());
+        }
+
+        if !self.path_prepend.is_empty() {
+            let mut entries = self.path_prepend.clone();
+            if let Some(base) = self.base_path(runtime_env) {
+                entries.extend(std::env::split_paths(&base));
+            }
+            let path = std::env::join_paths(entries)
+                .map_err(|err| format!("Invalid --child-path entry: {err}"))?;
+            cmd.env("PATH", path);
+        }
+
+        self.apply_process_controls(cmd, server_name)
+    }
+
+    fn base_path(&self, runtime_env: &HashMap<String, String>) -> Option<OsString> {
+        if let Some(path) = runtime_env.get("PATH") {
+            return Some(OsString::from(path));
+        }
+        let inherited = match self.env_mode {
+            ChildEnvMode::Inherit => true,
+            ChildEnvMode::Allowlist => self.env_allow.iter().any(|name| name == "PATH"),
+            ChildEnvMode::Clear => false,
+        };
+        if inherited {
+            std::env::var_os("PATH")
+        } else {
+            None
+        }
+    }
+
+    #[cfg(unix)]
+    fn apply_process_controls(&self, cmd: &mut Command, server_name: &str) -> Result<(), String> {
+        if let Some(gid) = self.gid {
+            cmd.gid(gid);
+        }
+        if let Some(uid) = self.uid {
+            cmd.uid(uid);
+        }
+        if self.umask.is_none() && self.rlimits.is_empty() && !self.sandbox.is_enabled() {
+            return Ok(());
+        }
+
+        let umask = self.umask;
+        let limits = self.rlimits;
+        let sandbox = self.sandbox.prepare(server_name)?;
+        // SAFETY: the hook runs between fork and exec, so it only makes async-signal-safe
+        // libc calls and does not allocate.
+        unsafe {
+            cmd.pre_exec(move || {
+                if let Some(mask) = umask {
+                    libc::umask(mask as libc::mode_t);
+                }
+                set_rlimit(libc::RLIMIT_AS, limits.address_space)?;
+                set_rlimit(libc::RLIMIT_CPU, limits.cpu_seconds)?;
+                set_rlimit(libc::RLIMIT_NOFILE, limits.open_files)?;
+                set_rlimit(libc::RLIMIT_NPROC, limits.processes)?;
+                // Last, so nothing above is subject to the sandbox.
+                if let Some(sandbox) = &sandbox {
+                    sandbox.enforce()?;
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn apply_process_controls(&self, _cmd: &mut Command, _server_name: &str) -> Result<(), String> {
+        if self.umask.is_some()
+            || self.uid.is_some()
+            || self.gid.is_some()
+            || !self.rlimits.is_empty()
+        {
+            return Err(
+                "--child-umask, --child-uid, --child-gid and --child-rlimit-* require a Unix host"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    // Kills the child once its resident memory passes --child-max-rss. The watchdog stops
+    // on its own when the process goes away.
+    pub fn watch_memory(&self, pid: Option<u32>) {
+        let (Some(limit), Some(pid)) = (self.max_rss_bytes, pid) else {
+            return;
+        };
+        spawn_memory_watchdog(pid, limit);
+    }
+}
+
+// libc types the resource argument differently on glibc and other Unix targets.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RlimitResource = libc::c_int;
+
+#[cfg(unix)]
+fn set_rlimit(resource: RlimitResource, value: Option<u64>) -> std::io::Result<()> {
+    let Some(value) = value else {
+        return Ok(());
+    };
+    let limit = libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn spawn_memory_watchdog(pid: u32, limit: u64) {
+    tokio::spawn(async move {
+        let status_path = format!("/proc/{pid}/status");
+        loop {
+            tokio::time::sleep(MEMORY_POLL_INTERVAL).await;
+            let Ok(status) = tokio::fs::read_to_string(&status_path).await else {
+                return;
+            };
+            let Some(rss) = parse_vm_rss(&status) else {
+                // Zombies drop their VmRSS line; nothing left to watch.
+                return;
+            };
+            if rss > limit {
+                tracing::error!(
+                    "Child pid {pid} uses {rss} bytes of resident memory, over the {limit}-byte limit (--child-max-rss); killing it"
+                );
+                unsafe {
+                    libc::kill(pid as libc::pid_t, libc::SIGKILL);
+                }
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(not(target_os = "linux"))]
+fn spawn_memory_watchdog(_pid: u32, _limit: u64) {
+    tracing::warn!("--child-max-rss needs /proc and is ignored on this platform");
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes: u64 = line["VmRSS:".len()..]
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vm_rss_reads_kilobytes() {
+        let status = "Name:\tcat\nVmPeak:\t    9000 kB\nVmRSS:\t    1532 kB\nThreads:\t1\n";
+        assert_eq!(parse_vm_rss(status), Some(1532 * 1024));
+        assert_eq!(parse_vm_rss("Name:\tzombie\nState:\tZ (zombie)\n"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn apply_prepends_path_and_filters_environment() {
+        let options = LaunchOptions {
+            env_mode: ChildEnvMode::Clear,
+            path_prepend: vec![PathBuf::from("/opt/tools/bin")],
+            ..LaunchOptions::default()
+        };
+        let runtime_env = HashMap::from([("PATH".to_string(), "/usr/bin".to_string())]);
+        let mut cmd = Command::new("env");
+        options
+            .apply(&mut cmd, &runtime_env, "env")
+            .expect("launch options should apply");
+        let envs: HashMap<_, _> = cmd
+            .as_std()
+            .get_envs()
+            .map(|(key, value)| (key.to_os_string(), value.map(|v| v.to_os_string())))
+            .collect();
+        assert_eq!(
+            envs.get(&OsString::from("PATH")),
+            Some(&Some(OsString::from("/opt/tools/bin:/usr/bin")))
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::support::log_rotation::read_segment_lines;
+
+// Lines per indexed block. Queries with a time window only read blocks that overlap it.
+const BLOCK_LINES: usize = 512;
+// Leading bytes hashed to notice that a segment name now points at a different file.
+const HEAD_BYTES: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct IndexBlock {
+    offset: u64,
+    min_ts: u64,
+    max_ts: u64,
+}
+
+impl IndexBlock {
+    fn overlaps(&self, since: Option<u64>, until: Option<u64>) -> bool {
+        since.is_none_or(|since| self.max_ts >= since)
+            && until.is_none_or(|until| self.min_ts <= until)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SegmentIndex {
+    indexed_len: u64,
+    head: String,
+    blocks: Vec<IndexBlock>,
+}
+
+#[derive(Deserialize)]
+struct Stamp {
+    ts_utc: u64,
+}
+
+/// Sidecar index of a log's segments, stored as `<log>.idx`. Each segment is split into
+/// blocks of lines with their offset and timestamp range, so a time-bounded query can
+/// skip whole segments and seek past blocks outside the window. Entries are extended as
+/// the active file grows and rebuilt when a rotation puts a different file under a name.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LogIndex {
+    segments: BTreeMap<String, SegmentIndex>,
+}
+
+pub fn index_path(log: &Path) -> PathBuf {
+    let mut name = log.as_os_str().to_owned();
+    name.push(".idx");
+    PathBuf::from(name)
+}
+
+fn segment_key(segment: &Path) -> String {
+    segment
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn is_compressed(segment: &Path) -> bool {
+    segment.extension().and_then(|ext| ext.to_str()) == Some("gz")
+}
+
+fn head_digest(segment: &Path) -> Result<String, String> {
+    let mut file = File::open(segment)
+        .map_err(|err| format!("Failed to open {}: {err}", segment.display()))?;
+    let mut head = Vec::with_capacity(HEAD_BYTES);
+    file.by_ref()
+        .take(HEAD_BYTES as u64)
+        .read_to_end(&mut head)
+        .map_err(|err| format!("Failed to read {}: {err}", segment.display()))?;
+    Ok(Sha256::digest(&head)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+impl LogIndex {
+    /// Loads the index next to `log`; a missing or unreadable index starts empty.
+    pub fn load(log: &Path) -> LogIndex {
+        std::fs::read_to_string(index_path(log))
+            .ok()
+            .and_then(|body| serde_json::from_str(&body).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, log: &Path) -> Result<(), String> {
+        let path = index_path(log);
+        let body = serde_json::to_string(self)
+            .map_err(|err| format!("Failed to serialize log index: {err}"))?;
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        std::fs::write(&tmp, body)
+            .and_then(|_| std::fs::rename(&tmp, &path))
+            .map_err(|err| format!("Failed to write {}: {err}", path.display()))
+    }
+
+    /// Drops entries for segments that rotation or retention removed.
+    pub fn retain_segments(&mut self, segments: &[PathBuf]) {
+        let live: Vec<String> = segments
+            .iter()
+            .map(|segment| segment_key(segment))
+            .collect();
+        self.segments.retain(|key, _| live.contains(key));
+    }
+
+    /// Brings the segment's entry up to date, indexing only what was appended since the
+    /// last refresh unless the file under this name changed.
+    pub fn refresh(&mut self, segment: &Path) -> Result<(), String> {
+        let len = std::fs::metadata(segment)
+            .map_err(|err| format!("Failed to stat {}: {err}", segment.display()))?
+            .len();
+        let head = head_digest(segment)?;
+        let compressed = is_compressed(segment);
+
+        let mut entry = self
+            .segments
+            .remove(&segment_key(segment))
+            .unwrap_or_default();
+        let reusable = entry.head == head
+            && if compressed {
+                entry.indexed_len == len
+            } else {
+                entry.indexed_len <= len
+            };
+        if !reusable {
+            entry = SegmentIndex {
+                indexed_len: 0,
+                head,
+                blocks: Vec::new(),
+            };
+        }
+
+        if entry.indexed_len != len || entry.blocks.is_empty() {
+            if compressed {
+                let file = File::open(segment)
+                    .map_err(|err| format!("Failed to open {}: {err}", segment.display()))?;
+                index_lines(BufReader::new(GzDecoder::new(file)), 0, &mut entry.blocks)
+                    .map_err(|err| format!("Failed to read {}: {err}", segment.display()))?;
+                entry.indexed_len = len;
+            } else {
+                // The last block may have been partial, so it is re-read along with the new lines.
+                let start = entry.blocks.pop().map(|block| block.offset).unwrap_or(0);
+                let mut file = File::open(segment)
+                    .map_err(|err| format!("Failed to open {}: {err}", segment.display()))?;
+                file.seek(SeekFrom::Start(start))
+                    .map_err(|err| format!("Failed to seek {}: {err}", segment.display()))?;
+                entry.indexed_len = index_lines(BufReader::new(file), start, &mut entry.blocks)
+                    .map_err(|err| format!("Failed to read {}: {err}", segment.display()))?;
+            }
+        }
+
+        self.segments.insert(segment_key(segment), entry);
+        Ok(())
+    }
+
+    /// Reads the segment's lines that may fall inside `[since, until]`. Lines outside the
+    /// window can still be returned; callers filter records by timestamp themselves.
+    pub fn read_window(
+        &self,
+        segment: &Path,
+        since: Option<u64>,
+        until: Option<u64>,
+    ) -> Result<Vec<String>, String> {
+        let Some(entry) = self.segments.get(&segment_key(segment)) else {
+            return read_segment_lines(segment);
+        };
+        if since.is_none() && until.is_none() {
+            return read_segment_lines(segment);
+        }
+        if !entry
+            .blocks
+            .iter()
+            .any(|block| block.overlaps(since, until))
+        {
+            return Ok(Vec::new());
+        }
+        if is_compressed(segment) {
+            return read_segment_lines(segment);
+        }
+
+        let mut file = File::open(segment)
+            .map_err(|err| format!("Failed to open {}: {err}", segment.display()))?;
+        let mut lines = Vec::new();
+        for (position, block) in entry.blocks.iter().enumerate() {
+            if !block.overlaps(since, until) {
+                continue;
+            }
+            let end = entry
+                .blocks
+                .get(position + 1)
+                .map(|next| next.offset)
+                .unwrap_or(entry.indexed_len);
+            file.seek(SeekFrom::Start(block.offset))
+                .map_err(|err| format!("Failed to seek {}: {err}", segment.display()))?;
+            let reader = BufReader::new((&mut file).take(end - block.offset));
+            for line in reader.lines() {
+                lines.push(
+                    line.map_err(|err| format!("Failed to read {}: {err}", segment.display()))?,
+                );
+            }
+        }
+        Ok(lines)
+    }
+}
+
+/// Appends blocks for the complete lines read from `reader`, which starts at `offset`.
+/// Returns the offset just past the last complete line.
+fn index_lines(
+    mut reader: impl BufRead,
+    mut offset: u64,
+    blocks: &mut Vec<IndexBlock>,
+) -> std::io::Result<u64> {
+    let mut line = Vec::new();
+    let mut current: Option<IndexBlock> = None;
+    let mut lines_in_block = 0;
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        // A line without its newline is still being written; it is indexed next time.
+        if read == 0 || line.last() != Some(&b'\n') {
+            break;
+        }
+
+        let block = current.get_or_insert(IndexBlock {
+            offset,
+            min_ts: u64::MAX,
+            max_ts: 0,
+        });
+        if let Ok(stamp) = serde_json::from_slice::<Stamp>(&line) {
+            block.min_ts = block.min_ts.min(stamp.ts_utc);
+            block.max_ts = block.max_ts.max(stamp.ts_utc);
+        }
+        offset += read as u64;
+        lines_in_block += 1;
+        if lines_in_block == BLOCK_LINES {
+            blocks.extend(current.take());
+            lines_in_block = 0;
+        }
+    }
+    blocks.extend(current);
+    Ok(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn record_line(ts: u64) -> String {
+        format!(
+            r#"{{"ts_utc":{ts},"level":"info","target":"t","message":"m{ts}","mode":"gateway","transport":"sse"}}"#
+        )
+    }
+
+    #[test]
+    fn refresh_extends_the_index_and_window_reads_skip_old_blocks() {
+        let dir = std::env::temp_dir().join(format!("mcpway-index-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        let log = dir.join("mcpway.ndjson");
+
+        let mut file = File::create(&log).expect("create log");
+        for ts in 0..(BLOCK_LINES as u64 * 2) {
+            writeln!(file, "{}", record_line(ts)).expect("write line");
+        }
+        // A partial trailing line is left for the next refresh.
+        write!(file, "{{\"ts_utc\":").expect("write partial");
+        drop(file);
+
+        let mut index = LogIndex::default();
+        index.refresh(&log).expect("refresh");
+        let entry = &index.segments["mcpway.ndjson"];
+        assert_eq!(entry.blocks.len(), 2);
+        assert_eq!(entry.blocks[1].min_ts, BLOCK_LINES as u64);
+
+        let recent = index
+            .read_window(&log, Some(BLOCK_LINES as u64 + 10), None)
+            .expect("read window");
+        assert_eq!(recent.len(), BLOCK_LINES);
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&log)
+            .expect("open log");
+        writeln!(file, "5000,\"level\":\"info\",\"target\":\"t\",\"message\":\"late\",\"mode\":\"gateway\",\"transport\":\"sse\"}}")
+            .expect("finish partial line");
+        drop(file);
+
+        index.refresh(&log).expect("refresh again");
+        index.save(&log).expect("save index");
+        let reloaded = LogIndex::load(&log);
+        let newest = reloaded
+            .read_window(&log, Some(4_000), None)
+            .expect("read window");
+        assert_eq!(newest.len(), 1);
+        assert!(newest[0].contains("late"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,541 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+const DEFAULT_MAX_FILE_BYTES: u64 = 20 * 1024 * 1024;
+const DEFAULT_MAX_FILES: usize = 5;
+
+/// When the active log file is rotated and how many rotated segments are kept.
+///
+/// Read from `MCPWAY_LOG_MAX_BYTES`, `MCPWAY_LOG_ROTATE_SECS`, `MCPWAY_LOG_MAX_FILES`,
+/// `MCPWAY_LOG_MAX_TOTAL_BYTES`, `MCPWAY_LOG_MAX_AGE_SECS` and `MCPWAY_LOG_COMPRESS`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRotation {
+    pub max_file_bytes: u64,
+    pub rotate_every: Option<Duration>,
+    pub max_files: usize,
+    pub max_total_bytes: Option<u64>,
+    pub max_age: Option<Duration>,
+    pub compress: bool,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            rotate_every: None,
+            max_files: DEFAULT_MAX_FILES,
+            max_total_bytes: None,
+            max_age: None,
+            compress: false,
+        }
+    }
+}
+
+impl LogRotation {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_file_bytes: env_u64("MCPWAY_LOG_MAX_BYTES")
+                .filter(|bytes| *bytes > 0)
+                .unwrap_or(defaults.max_file_bytes),
+            rotate_every: env_u64("MCPWAY_LOG_ROTATE_SECS")
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            max_files: env_u64("MCPWAY_LOG_MAX_FILES")
+                .map(|files| files as usize)
+                .unwrap_or(defaults.max_files),
+            max_total_bytes: env_u64("MCPWAY_LOG_MAX_TOTAL_BYTES").filter(|bytes| *bytes > 0),
+            max_age: env_u64("MCPWAY_LOG_MAX_AGE_SECS")
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+            compress: std::env::var("MCPWAY_LOG_COMPRESS")
+                .map(|value| matches!(value.trim(), "1" | "true" | "yes" | "gzip"))
+                .unwrap_or(false),
+        }
+    }
+}
+
+fn env_u64(name: &str) -> Option<u64> {
+    std::env::var(name).ok()?.trim().parse().ok()
+}
+
+/// Returns `path.N` or `path.N.gz`, whichever exists.
+fn rotated_segment(path: &Path, index: usize) -> Option<PathBuf> {
+    let plain = segment_path(path, index, false);
+    if plain.exists() {
+        return Some(plain);
+    }
+    let compressed = segment_path(path, index, true);
+    compressed.exists().then_some(compressed)
+}
+
+fn segment_path(path: &Path, index: usize, compressed: bool) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{index}"));
+    if compressed {
+        name.push(".gz");
+    }
+    PathBuf::from(name)
+}
+
+/// Lists the log's segments from oldest to newest; the active file comes last.
+pub fn log_segments(path: &Path) -> Vec<PathBuf> {
+    let mut rotated = Vec::new();
+    let mut index = 1;
+    while let Some(segment) = rotated_segment(path, index) {
+        rotated.push(segment);
+        index += 1;
+    }
+    rotated.reverse();
+    if path.exists() {
+        rotated.push(path.to_path_buf());
+    }
+    rotated
+}
+
+/// Reads every line of a segment, decompressing `.gz` segments.
+pub fn read_segment_lines(path: &Path) -> Result<Vec<String>, String> {
+    let file =
+        File::open(path).map_err(|err| format!("Failed to open {}: {err}", path.display()))?;
+    let reader: Box<dyn Read> = if path.extension().and_then(|ext| ext.to_str()) == Some("gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    BufReader::new(reader)
+        .lines()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("Failed to read {}: {err}", path.display()))
+}
+
+/// Identifies the file behind a path so a rename by another writer can be noticed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileIdentity {
+    #[cfg(unix)]
+    dev: u64,
+    #[cfg(unix)]
+    ino: u64,
+}
+
+fn file_identity(meta: &std::fs::Metadata) -> FileIdentity {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        FileIdentity {
+            dev: meta.dev(),
+            ino: meta.ino(),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = meta;
+        FileIdentity {}
+    }
+}
+
+fn path_identity(path: &Path) -> Option<FileIdentity> {
+    std::fs::metadata(path)
+        .ok()
+        .map(|meta| file_identity(&meta))
+}
+
+/// Appends lines to the active log file and rotates it by size or age.
+///
+/// Several processes may share one log file. Each checks before writing whether the path
+/// still points at the file it holds open and reopens it if another process rotated it.
+pub struct RotatingLogWriter {
+    path: PathBuf,
+    policy: LogRotation,
+    writer: BufWriter<File>,
+    identity: FileIdentity,
+    size: u64,
+    opened_at: SystemTime,
+    // Compression of the last rotated segment, which must finish before that segment is
+    // shifted again.
+    compressing: Option<JoinHandle<()>>,
+}
+
+impl RotatingLogWriter {
+    pub fn open(path: &Path, policy: LogRotation) -> Result<Self, String> {
+        let (file, identity, size, opened_at) = open_active(path)?;
+        let mut writer = Self {
+            path: path.to_path_buf(),
+            policy,
+            writer: BufWriter::new(file),
+            identity,
+            size,
+            opened_at,
+            compressing: None,
+        };
+        if writer.should_rotate() {
+            writer.rotate()?;
+        }
+        Ok(writer)
+    }
+
+    pub fn write_line(&mut self, line: &str) -> Result<(), String> {
+        if path_identity(&self.path) != Some(self.identity) {
+            self.reopen()?;
+        }
+        writeln!(self.writer, "{line}")
+            .and_then(|_| self.writer.flush())
+            .map_err(|err| format!("Failed to write {}: {err}", self.path.display()))?;
+        // Other processes append to the same file, so its length is the real size.
+        self.size = self
+            .writer
+            .get_ref()
+            .metadata()
+            .map(|meta| meta.len())
+            .unwrap_or(self.size + line.len() as u64 + 1);
+        if self.should_rotate() {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    fn should_rotate(&self) -> bool {
+        if self.size > self.policy.max_file_bytes {
+            return true;
+        }
+        match self.policy.rotate_every {
+            Some(every) => {
+                self.size > 0
+                    && SystemTime::now()
+                        .duration_since(self.opened_at)
+                        .map(|age| age >= every)
+                        .unwrap_or(false)
+            }
+            None => false,
+        }
+    }
+
+    fn reopen(&mut self) -> Result<(), String> {
+        let (file, identity, size, opened_at) = open_active(&self.path)?;
+        self.writer = BufWriter::new(file);
+        self.identity = identity;
+        self.size = size;
+        self.opened_at = opened_at;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<(), String> {
+        let _ = self.writer.flush();
+        // Another writer got there first; pick up the file it created.
+        if path_identity(&self.path) != Some(self.identity) {
+            return self.reopen();
+        }
+        if let Some(compressing) = self.compressing.take() {
+            let _ = compressing.join();
+        }
+
+        if self.policy.max_files == 0 {
+            let _ = std::fs::remove_file(&self.path);
+        } else {
+            shift_segments(&self.path, self.policy.max_files);
+            let first = segment_path(&self.path, 1, false);
+            std::fs::rename(&self.path, &first)
+                .map_err(|err| format!("Failed to rotate {}: {err}", self.path.display()))?;
+            if self.policy.compress {
+                self.compressing = Some(std::thread::spawn(move || {
+                    if let Err(err) = compress_segment(&first) {
+                        eprintln!("[mcpway] {err}");
+                    }
+                }));
+            }
+        }
+        self.reopen()?;
+        enforce_retention(&self.path, &self.policy);
+        Ok(())
+    }
+}
+
+fn open_active(path: &Path) -> Result<(File, FileIdentity, u64, SystemTime), String> {
+    let file = open_append(path)?;
+    let meta = file
+        .metadata()
+        .map_err(|err| format!("Failed to stat {}: {err}", path.display()))?;
+    let opened_at = meta
+        .created()
+        .or_else(|_| meta.modified())
+        .unwrap_or_else(|_| SystemTime::now());
+    Ok((file, file_identity(&meta), meta.len(), opened_at))
+}
+
+pub(crate) fn open_append(path: &Path) -> Result<File, String> {
+    let parent = path
+        .parent()
+        .ok_or_else(|| format!("Invalid log file path: {}", path.display()))?;
+    std::fs::create_dir_all(parent)
+        .map_err(|err| format!("Failed to create {}: {err}", parent.display()))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| format!("Failed to open {}: {err}", path.display()))
+}
+
+// Moves `.N` to `.N+1` from the oldest down, dropping the segment that would exceed `max_files`.
+fn shift_segments(path: &Path, max_files: usize) {
+    if let Some(oldest) = rotated_segment(path, max_files) {
+        let _ = std::fs::remove_file(oldest);
+    }
+    for index in (1..max_files).rev() {
+        let Some(from) = rotated_segment(path, index) else {
+            continue;
+        };
+        let compressed = from.extension().and_then(|ext| ext.to_str()) == Some("gz");
+        let _ = std::fs::rename(&from, segment_path(path, index + 1, compressed));
+    }
+}
+
+fn compress_segment(segment: &Path) -> Result<(), String> {
+    let mut target = segment.as_os_str().to_owned();
+    target.push(".gz");
+    let target = PathBuf::from(target);
+
+    let mut input = File::open(segment)
+        .map_err(|err| format!("Failed to open {}: {err}", segment.display()))?;
+    let output = File::create(&target)
+        .map_err(|err| format!("Failed to create {}: {err}", target.display()))?;
+    let mut encoder = GzEncoder::new(BufWriter::new(output), Compression::default());
+    std::io::copy(&mut input, &mut encoder)
+        .and_then(|_| encoder.finish())
+        .and_then(|mut writer| writer.flush())
+        .map_err(|err| format!("Failed to compress {}: {err}", segment.display()))?;
+    std::fs::remove_file(segment)
+        .map_err(|err| format!("Failed to remove {}: {err}", segment.display()))
+}
+
+fn enforce_retention(path: &Path, policy: &LogRotation) {
+    let mut rotated = log_segments(path);
+    rotated.retain(|segment| segment != path);
+
+    if let Some(max_age) = policy.max_age {
+        let now = SystemTime::now();
+        rotated.retain(|segment| {
+            let expired = std::fs::metadata(segment)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age > max_age);
+            if expired {
+                let _ = std::fs::remove_file(segment);
+            }
+            !expired
+        });
+    }
+
+    if let Some(max_total) = policy.max_total_bytes {
+        let size_of = |segment: &Path| {
+            std::fs::metadata(segment)
+                .map(|meta| meta.len())
+                .unwrap_or(0)
+        };
+        let mut total: u64 =
+            size_of(path) + rotated.iter().map(|segment| size_of(segment)).sum::<u64>();
+        // Oldest segments go first; the active file is never removed.
+        for segment in rotated {
+            if total <= max_total {
+                break;
+            }
+            total = total.saturating_sub(size_of(&segment));
+            let _ = std::fs::remove_file(&segment);
+        }
+    }
+}
+
+/// Follows the active log file across rotations, yielding complete lines as they are written.
+pub struct LogFollower {
+    path: PathBuf,
+    file: Option<File>,
+    identity: Option<FileIdentity>,
+    offset: u64,
+    partial: String,
+}
+
+impl LogFollower {
+    /// Starts at the current end of the log so only new lines are returned.
+    pub fn from_end(path: &Path) -> Self {
+        let mut follower = Self {
+            path: path.to_path_buf(),
+            file: None,
+            identity: None,
+            offset: 0,
+            partial: String::new(),
+        };
+        if let Ok(file) = File::open(path) {
+            if let Ok(meta) = file.metadata() {
+                follower.offset = meta.len();
+                follower.identity = Some(file_identity(&meta));
+                follower.file = Some(file);
+            }
+        }
+        follower
+    }
+
+    pub fn read_new_lines(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        self.drain(&mut lines);
+
+        let current = path_identity(&self.path);
+        let rotated = current.is_some() && current != self.identity;
+        let truncated = self
+            .file
+            .as_ref()
+            .and_then(|file| file.metadata().ok())
+            .is_some_and(|meta| meta.len() < self.offset);
+        if rotated || truncated || self.file.is_none() {
+            // The old handle was drained above, so nothing written before the rotation is lost.
+            if let Ok(file) = File::open(&self.path) {
+                self.identity = file.metadata().ok().map(|meta| file_identity(&meta));
+                self.file = Some(file);
+                self.offset = 0;
+                self.partial.clear();
+                self.drain(&mut lines);
+            }
+        }
+        lines
+    }
+
+    fn drain(&mut self, lines: &mut Vec<String>) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        if file.seek(SeekFrom::Start(self.offset)).is_err() {
+            return;
+        }
+        let mut chunk = String::new();
+        let Ok(read) = file.read_to_string(&mut chunk) else {
+            return;
+        };
+        self.offset += read as u64;
+
+        self.partial.push_str(&chunk);
+        let Some(last_newline) = self.partial.rfind('\n') else {
+            return;
+        };
+        let complete: String = self.partial.drain(..=last_newline).collect();
+        lines.extend(complete.lines().map(str::to_string));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("mcpway-rotation-{}", uuid::Uuid::new_v4()))
+            .join("mcpway.ndjson")
+    }
+
+    #[test]
+    fn rotates_by_size_and_keeps_max_files() {
+        let path = temp_log();
+        let policy = LogRotation {
+            max_file_bytes: 10,
+            max_files: 2,
+            ..LogRotation::default()
+        };
+        let mut writer = RotatingLogWriter::open(&path, policy).expect("open writer");
+        for index in 0..4 {
+            writer
+                .write_line(&format!("line-{index}-padding"))
+                .expect("write line");
+        }
+
+        let segments = log_segments(&path);
+        assert_eq!(
+            segments,
+            vec![
+                segment_path(&path, 2, false),
+                segment_path(&path, 1, false),
+                path.clone()
+            ]
+        );
+        assert_eq!(
+            read_segment_lines(&segments[1]).expect("read segment"),
+            vec!["line-3-padding".to_string()]
+        );
+        let _ = std::fs::remove_dir_all(path.parent().expect("log dir"));
+    }
+
+    #[test]
+    fn follower_reads_lines_written_before_and_after_rotation() {
+        let path = temp_log();
+        let policy = LogRotation {
+            max_file_bytes: 1024,
+            ..LogRotation::default()
+        };
+        let mut writer = RotatingLogWriter::open(&path, policy).expect("open writer");
+        let mut follower = LogFollower::from_end(&path);
+
+        writer.write_line("before").expect("write before");
+        writer.rotate().expect("rotate");
+        writer.write_line("after").expect("write after");
+
+        assert_eq!(
+            follower.read_new_lines(),
+            vec!["before".to_string(), "after".to_string()]
+        );
+        let _ = std::fs::remove_dir_all(path.parent().expect("log dir"));
+    }
+
+    #[test]
+    fn back_to_back_rotations_keep_every_compressed_segment() {
+        let path = temp_log();
+        let policy = LogRotation {
+            max_file_bytes: 1024,
+            compress: true,
+            ..LogRotation::default()
+        };
+        let mut writer = RotatingLogWriter::open(&path, policy).expect("open writer");
+        for line in ["first", "second", "third"] {
+            writer.write_line(line).expect("write line");
+            writer.rotate().expect("rotate");
+        }
+        if let Some(compressing) = writer.compressing.take() {
+            compressing.join().expect("compression thread");
+        }
+
+        let segments = log_segments(&path);
+        assert_eq!(
+            segments,
+            vec![
+                segment_path(&path, 3, true),
+                segment_path(&path, 2, true),
+                segment_path(&path, 1, true),
+                path.clone()
+            ]
+        );
+        let lines: Vec<String> = segments[..3]
+            .iter()
+            .flat_map(|segment| read_segment_lines(segment).expect("read segment"))
+            .collect();
+        assert_eq!(lines, vec!["first", "second", "third"]);
+        let _ = std::fs::remove_dir_all(path.parent().expect("log dir"));
+    }
+
+    #[test]
+    fn compressed_segments_are_readable() {
+        let path = temp_log();
+        let segment = segment_path(&path, 1, false);
+        std::fs::create_dir_all(path.parent().expect("log dir")).expect("create log dir");
+        std::fs::write(&segment, "one\ntwo\n").expect("write segment");
+
+        compress_segment(&segment).expect("compress segment");
+        assert_eq!(log_segments(&path), vec![segment_path(&path, 1, true)]);
+        assert_eq!(
+            read_segment_lines(&segment_path(&path, 1, true)).expect("read gz"),
+            vec!["one".to_string(), "two".to_string()]
+        );
+        let _ = std::fs::remove_dir_all(path.parent().expect("log dir"));
+    }
+}
@@ -1,16 +1,18 @@
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use std::fs::{File, OpenOptions};
-use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
 use tracing::{Event, Subscriber};
 use tracing_subscriber::layer::{Context, Layer};
 use tracing_subscriber::registry::LookupSpan;
 
-const MAX_LOG_FILE_BYTES: u64 = 20 * 1024 * 1024;
+use crate::support::log_rotation::{
+    log_segments, open_append, read_segment_lines, LogRotation, RotatingLogWriter,
+};
+use crate::support::redact::redactor;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredLogRecord {
@@ -21,13 +23,38 @@ pub struct StoredLogRecord {
     pub mode: String,
     pub transport: String,
     #[serde(default)]
+    pub instance_id: String,
+    #[serde(default)]
+    pub pid: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+    #[serde(default)]
     pub fields: BTreeMap<String, String>,
 }
 
+/// Identifies the process writing log records, so gateways sharing one log file can be
+/// told apart. Without a configured name the id is `<transport>-<pid>`.
+#[derive(Debug, Clone, Default)]
+pub struct LogInstance {
+    pub name: Option<String>,
+    pub server_name: Option<String>,
+    pub per_instance_file: bool,
+}
+
+static LOG_INSTANCE: OnceLock<LogInstance> = OnceLock::new();
+
+/// Must be called before telemetry is initialized to take effect.
+pub fn configure_log_instance(instance: LogInstance) {
+    let _ = LOG_INSTANCE.set(instance);
+}
+
 pub struct LogFileLayer {
-    writer: Arc<Mutex<BufWriter<File>>>,
+    writer: Arc<Mutex<RotatingLogWriter>>,
     mode: String,
     transport: String,
+    instance_id: String,
+    pid: u32,
+    server_name: Option<String>,
 }
 
 pub fn default_log_path() -> PathBuf {
@@ -40,57 +67,253 @@ pub fn default_log_path() -> PathBuf {
     PathBuf::from(".mcpway/logs/mcpway.ndjson")
 }
 
+/// The file an instance writes to when per-instance log files are enabled:
+/// `mcpway.ndjson` becomes `mcpway-<instance>.ndjson` in the same directory.
+pub fn instance_log_path(base: &Path, instance_id: &str) -> PathBuf {
+    let safe: String = instance_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.') {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let stem = base
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("mcpway");
+    let file_name = match base.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => format!("{stem}-{safe}.{ext}"),
+        None => format!("{stem}-{safe}"),
+    };
+    base.with_file_name(file_name)
+}
+
+/// Picks the file to read for `instance`: its own file when it writes one, else `base`.
+pub fn resolve_instance_log_path(base: &Path, instance: Option<&str>) -> PathBuf {
+    if let Some(instance) = instance {
+        let path = instance_log_path(base, instance);
+        if path.exists() {
+            return path;
+        }
+    }
+    base.to_path_buf()
+}
+
 pub fn ensure_log_file(path: &Path) -> Result<(), String> {
-    let _ = prepare_log_file(path)?;
+    let _ = open_append(path)?;
     Ok(())
 }
 
 pub fn build_log_file_layer(mode: &str, transport: &str) -> Result<LogFileLayer, String> {
-    let path = default_log_path();
-    let file = prepare_log_file(&path)?;
+    let instance = LOG_INSTANCE.get().cloned().unwrap_or_default();
+    let pid = std::process::id();
+    let instance_id = instance
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("{transport}-{pid}"));
+
+    let mut path = default_log_path();
+    if instance.per_instance_file {
+        path = instance_log_path(&path, &instance_id);
+    }
+    let writer = RotatingLogWriter::open(&path, LogRotation::from_env())?;
     Ok(LogFileLayer {
-        writer: Arc::new(Mutex::new(BufWriter::new(file))),
+        writer: Arc::new(Mutex::new(writer)),
         mode: mode.to_string(),
         transport: transport.to_string(),
+        instance_id,
+        pid,
+        server_name: instance.server_name,
     })
 }
 
+// Records are redacted on read too, so files written before redaction existed stay safe.
 pub fn parse_record(line: &str) -> Option<StoredLogRecord> {
-    serde_json::from_str::<StoredLogRecord>(line).ok()
-}
-
-fn prepare_log_file(path: &Path) -> Result<File, String> {
-    let parent = path
-        .parent()
-        .ok_or_else(|| format!("Invalid log file path: {}", path.display()))?;
-    std::fs::create_dir_all(parent)
-        .map_err(|err| format!("Failed to create {}: {err}", parent.display()))?;
-
-    if let Ok(meta) = std::fs::metadata(path) {
-        if meta.len() > MAX_LOG_FILE_BYTES {
-            OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(path)
-                .map_err(|err| format!("Failed to truncate {}: {err}", path.display()))?;
+    serde_json::from_str::<StoredLogRecord>(line)
+        .ok()
+        .map(StoredLogRecord::redacted)
+}
+
+impl StoredLogRecord {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.get(name).map(String::as_str)
+    }
+
+    /// Both bounds are inclusive, in unix seconds.
+    pub fn in_time_range(&self, since: Option<u64>, until: Option<u64>) -> bool {
+        since.is_none_or(|since| self.ts_utc >= since)
+            && until.is_none_or(|until| self.ts_utc <= until)
+    }
+
+    /// Matches an instance by id, pid or wrapped server name.
+    pub fn matches_instance(&self, instance: &str) -> bool {
+        self.instance_id == instance
+            || self.pid.to_string() == instance
+            || self.server_name.as_deref() == Some(instance)
+    }
+
+    pub fn redacted(mut self) -> Self {
+        let redactor = redactor();
+        self.message = redactor.redact_text(&self.message).into_owned();
+        for (key, value) in self.fields.iter_mut() {
+            *value = redactor.redact_value(key, value).into_owned();
+        }
+        self
+    }
+}
+
+/// Returns the newest `limit` records accepted by `keep`, oldest first. Rotated segments
+/// are only read when the newer ones do not hold enough matching records.
+pub fn recent_records(
+    path: &Path,
+    limit: usize,
+    keep: impl Fn(&StoredLogRecord) -> bool,
+) -> Result<Vec<StoredLogRecord>, String> {
+    let mut newest_first = Vec::with_capacity(limit);
+    for segment in log_segments(path).iter().rev() {
+        let lines = read_segment_lines(segment)?;
+        for line in lines.iter().rev() {
+            if newest_first.len() >= limit {
+                break;
+            }
+            let Some(record) = parse_record(line) else {
+                continue;
+            };
+            if keep(&record) {
+                newest_first.push(record);
+            }
+        }
+        if newest_first.len() >= limit {
+            break;
         }
     }
 
-    OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(path)
-        .map_err(|err| format!("Failed to open {}: {err}", path.display()))
+    newest_first.reverse();
+    Ok(newest_first)
 }
 
-fn unix_timestamp_secs() -> u64 {
+/// Parses a `--since`/`--until` bound into unix seconds. Accepts unix seconds, an
+/// RFC 3339 UTC timestamp (`2026-03-01T12:00:00Z`), or an age such as `90s`, `15m`,
+/// `2h` or `1d`, counted back from `now`.
+pub fn parse_time_bound(value: &str, now: u64) -> Result<u64, String> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Ok(secs);
+    }
+
+    if let Some(unit) = value.chars().last().filter(char::is_ascii_alphabetic) {
+        let amount = &value[..value.len() - 1];
+        if let Ok(amount) = amount.parse::<u64>() {
+            let scale = match unit {
+                's' => 1,
+                'm' => 60,
+                'h' => 3_600,
+                'd' => 86_400,
+                _ => return Err(format!("Unknown time unit in '{value}'")),
+            };
+            return Ok(now.saturating_sub(amount.saturating_mul(scale)));
+        }
+    }
+
+    parse_rfc3339_utc(value).ok_or_else(|| {
+        format!("Invalid time '{value}': use unix seconds, RFC 3339 or an age like 15m")
+    })
+}
+
+fn parse_rfc3339_utc(value: &str) -> Option<u64> {
+    let value = value.strip_suffix('Z').unwrap_or(value);
+    let (date, time) = value.split_once(['T', ' ']).unwrap_or((value, "00:00:00"));
+    let mut date_parts = date.split('-').map(|part| part.parse::<u64>().ok());
+    let (year, month, day) = (
+        date_parts.next()??,
+        date_parts.next()??,
+        date_parts.next()??,
+    );
+    let time = time.split('.').next()?;
+    let mut time_parts = time.split(':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute) = (time_parts.next()??, time_parts.next()??);
+    let second = time_parts.next().flatten().unwrap_or(0);
+    if date_parts.next().is_some()
+        || !(1970..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    // Days since the epoch for a proleptic Gregorian date.
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * m + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+/// Formats unix seconds as an RFC 3339 UTC timestamp, the inverse of `parse_time_bound`.
+pub fn format_timestamp(ts: u64) -> String {
+    let days = ts / 86_400;
+    let secs = ts % 86_400;
+    // Civil date from days since the epoch for a proleptic Gregorian calendar.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+pub(crate) fn unix_timestamp_secs() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|duration| duration.as_secs())
         .unwrap_or(0)
 }
 
+// Span attributes that correlate records, stored under the names the log filters use.
+const CORRELATION_FIELDS: &[(&str, &str)] = &[
+    ("session_id", "session_id"),
+    ("mcp.session_id", "session_id"),
+    ("request_id", "request_id"),
+    ("rpc.jsonrpc.request_id", "request_id"),
+    ("method", "method"),
+    ("rpc.method", "method"),
+    ("tool", "tool"),
+    ("mcp.tool", "tool"),
+];
+
+/// Correlation fields recorded on a span, inherited by every event inside it.
+struct SpanFields(BTreeMap<String, String>);
+
+fn correlation_fields(fields: BTreeMap<String, String>) -> impl Iterator<Item = (String, String)> {
+    fields.into_iter().filter_map(|(name, value)| {
+        CORRELATION_FIELDS
+            .iter()
+            .find(|(alias, _)| *alias == name)
+            .map(|(_, canonical)| (canonical.to_string(), value))
+    })
+}
+
 #[derive(Default)]
 struct EventVisitor {
     message: Option<String>,
@@ -135,10 +358,43 @@ impl<S> Layer<S> for LogFileLayer
 where
     S: Subscriber + for<'lookup> LookupSpan<'lookup>,
 {
-    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut visitor = EventVisitor::default();
+        attrs.record(&mut visitor);
+        let fields = correlation_fields(visitor.fields).collect();
+        span.extensions_mut().insert(SpanFields(fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut visitor = EventVisitor::default();
+        values.record(&mut visitor);
+        let mut extensions = span.extensions_mut();
+        if let Some(fields) = extensions.get_mut::<SpanFields>() {
+            fields.0.extend(correlation_fields(visitor.fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
         let mut visitor = EventVisitor::default();
         event.record(&mut visitor);
 
+        // Inner spans override outer ones and the event's own fields override both.
+        let mut fields = BTreeMap::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(span_fields) = span.extensions().get::<SpanFields>() {
+                    fields.extend(span_fields.0.clone());
+                }
+            }
+        }
+        fields.extend(visitor.fields);
+
         let metadata = event.metadata();
         let record = StoredLogRecord {
             ts_utc: unix_timestamp_secs(),
@@ -147,8 +403,12 @@ where
             message: visitor.message.unwrap_or_default(),
             mode: self.mode.clone(),
             transport: self.transport.clone(),
-            fields: visitor.fields,
-        };
+            instance_id: self.instance_id.clone(),
+            pid: self.pid,
+            server_name: self.server_name.clone(),
+            fields,
+        }
+        .redacted();
 
         let Ok(line) = serde_json::to_string(&record) else {
             return;
@@ -157,9 +417,7 @@ where
         let Ok(mut writer) = self.writer.lock() else {
             return;
         };
-        if writeln!(&mut *writer, "{line}").is_ok() {
-            let _ = writer.flush();
-        }
+        let _ = writer.write_line(&line);
     }
 }
 
@@ -176,6 +434,9 @@ mod tests {
             message: "hello".to_string(),
             mode: "connect".to_string(),
             transport: "sse".to_string(),
+            instance_id: "docs".to_string(),
+            pid: 42,
+            server_name: Some("docs".to_string()),
             fields: BTreeMap::new(),
         })
         .expect("serialize");
@@ -183,5 +444,113 @@ mod tests {
         let parsed = parse_record(&line).expect("parse record");
         assert_eq!(parsed.level, "info");
         assert_eq!(parsed.transport, "sse");
+        assert_eq!(parsed.instance_id, "docs");
+        assert_eq!(parsed.pid, 42);
+    }
+
+    #[test]
+    fn parse_record_accepts_lines_without_instance_identity() {
+        let line = r#"{"ts_utc":1,"level":"info","target":"t","message":"m","mode":"gateway","transport":"sse"}"#;
+        let parsed = parse_record(line).expect("parse record");
+        assert_eq!(parsed.instance_id, "");
+        assert_eq!(parsed.server_name, None);
+    }
+
+    #[test]
+    fn parse_time_bound_accepts_unix_rfc3339_and_ages() {
+        assert_eq!(parse_time_bound("1700000000", 0), Ok(1_700_000_000));
+        assert_eq!(parse_time_bound("1970-01-02T00:00:01Z", 0), Ok(86_401));
+        assert_eq!(parse_time_bound("2024-02-29", 0), Ok(1_709_164_800));
+        assert_eq!(parse_time_bound("15m", 10_000), Ok(9_100));
+        assert_eq!(parse_time_bound("1d", 10), Ok(0));
+        assert!(parse_time_bound("3w", 0).is_err());
+        assert!(parse_time_bound("2024-13-01", 0).is_err());
+        assert_eq!(format_timestamp(1_709_164_800), "2024-02-29T00:00:00Z");
+        assert_eq!(
+            parse_time_bound(&format_timestamp(1_767_225_599), 0),
+            Ok(1_767_225_599)
+        );
+    }
+
+    #[test]
+    fn span_correlation_fields_are_captured_into_records() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let path =
+            std::env::temp_dir().join(format!("mcpway-spans-{}.ndjson", uuid::Uuid::new_v4()));
+        let layer = LogFileLayer {
+            writer: Arc::new(Mutex::new(
+                RotatingLogWriter::open(&path, LogRotation::default()).expect("open log"),
+            )),
+            mode: "gateway".to_string(),
+            transport: "sse".to_string(),
+            instance_id: "sse-1".to_string(),
+            pid: 1,
+            server_name: None,
+        };
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "jsonrpc.request",
+                rpc.method = "tools/call",
+                rpc.jsonrpc.request_id = 7,
+                mcp.session_id = tracing::field::Empty,
+                otel.kind = "server",
+            );
+            span.record("mcp.session_id", "abc");
+            tracing::info!(parent: &span, tool = "search", "inside");
+            tracing::info!("outside");
+        });
+
+        let records: Vec<StoredLogRecord> = read_segment_lines(&path)
+            .expect("read log")
+            .iter()
+            .filter_map(|line| parse_record(line))
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].field("session_id"), Some("abc"));
+        assert_eq!(records[0].field("request_id"), Some("7"));
+        assert_eq!(records[0].field("method"), Some("tools/call"));
+        assert_eq!(records[0].field("tool"), Some("search"));
+        assert_eq!(records[0].field("otel.kind"), None);
+        assert_eq!(records[1].field("session_id"), None);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn instance_log_path_sits_next_to_the_shared_file() {
+        let base = Path::new("/var/log/mcpway.ndjson");
+        assert_eq!(
+            instance_log_path(base, "docs/prod"),
+            PathBuf::from("/var/log/mcpway-docs_prod.ndjson")
+        );
+    }
+
+    #[test]
+    fn parse_record_redacts_secrets_in_message_and_fields() {
+        let line = serde_json::to_string(&StoredLogRecord {
+            ts_utc: 123,
+            level: "info".to_string(),
+            target: "test".to_string(),
+            message: r#"  - Headers: {"Authorization":"Bearer abc123"}"#.to_string(),
+            mode: "gateway".to_string(),
+            transport: "sse".to_string(),
+            instance_id: "docs".to_string(),
+            pid: 42,
+            server_name: Some("docs".to_string()),
+            fields: BTreeMap::from([
+                ("api_key".to_string(), "s3cr3t".to_string()),
+                ("session_id".to_string(), "abc".to_string()),
+            ]),
+        })
+        .expect("serialize");
+
+        let parsed = parse_record(&line).expect("parse record");
+        assert_eq!(
+            parsed.message,
+            r#"  - Headers: {"Authorization":"[REDACTED]"}"#
+        );
+        assert_eq!(parsed.fields["api_key"], "[REDACTED]");
+        assert_eq!(parsed.fields["session_id"], "abc");
     }
 }
@@ -0,0 +1,84 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use opentelemetry::global;
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::KeyValue;
+
+// Instruments bind to whichever meter provider is global on first use, so `init_telemetry`
+// must run before the first request is recorded.
+struct Instruments {
+    jsonrpc_requests: Counter<u64>,
+    jsonrpc_duration: Histogram<f64>,
+    admin_requests: Counter<u64>,
+    runtime_updates: Counter<u64>,
+    discovery_searches: Counter<u64>,
+}
+
+fn instruments() -> &'static Instruments {
+    static INSTRUMENTS: OnceLock<Instruments> = OnceLock::new();
+    INSTRUMENTS.get_or_init(|| {
+        let meter = global::meter("mcpway");
+        Instruments {
+            jsonrpc_requests: meter
+                .u64_counter("mcpway.jsonrpc.requests")
+                .with_description("JSON-RPC requests handled by the gateway")
+                .build(),
+            jsonrpc_duration: meter
+                .f64_histogram("mcpway.jsonrpc.duration")
+                .with_description("Time from receiving a JSON-RPC request to its response")
+                .with_unit("s")
+                .build(),
+            admin_requests: meter
+                .u64_counter("mcpway.admin.requests")
+                .with_description("Requests served by the admin API")
+                .build(),
+            runtime_updates: meter
+                .u64_counter("mcpway.admin.runtime_updates")
+                .with_description("Runtime updates applied through the admin API")
+                .build(),
+            discovery_searches: meter
+                .u64_counter("mcpway.admin.discovery_searches")
+                .with_description("Discovery searches served by the admin API")
+                .build(),
+        }
+    })
+}
+
+pub fn record_jsonrpc_request(
+    role: &'static str,
+    transport: &'static str,
+    method: &str,
+    status: &'static str,
+    elapsed: Duration,
+) {
+    let attributes = [
+        KeyValue::new("otel.kind", role),
+        KeyValue::new("mcp.transport", transport),
+        KeyValue::new("rpc.method", method.to_string()),
+        KeyValue::new("rpc.status", status),
+    ];
+    let instruments = instruments();
+    instruments.jsonrpc_requests.add(1, &attributes);
+    instruments
+        .jsonrpc_duration
+        .record(elapsed.as_secs_f64(), &attributes);
+}
+
+pub fn record_admin_request(route: &str, status: u16) {
+    instruments().admin_requests.add(
+        1,
+        &[
+            KeyValue::new("http.route", route.to_string()),
+            KeyValue::new("http.response.status_code", i64::from(status)),
+        ],
+    );
+}
+
+pub fn record_runtime_update() {
+    instruments().runtime_updates.add(1, &[]);
+}
+
+pub fn record_discovery_search() {
+    instruments().discovery_searches.add(1, &[]);
+}
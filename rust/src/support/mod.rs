@@ -1,8 +1,26 @@
+pub mod child_framing;
+pub mod child_handshake;
+pub mod child_stderr;
+pub mod child_swap;
 pub mod command_spec;
 pub mod cors;
+pub mod instance_index;
+pub mod launch;
+pub mod log_index;
+pub mod log_rotation;
 pub mod log_store;
+pub mod metrics;
+pub mod redact;
+pub mod resource_subscriptions;
+pub mod sandbox;
+pub mod secrets;
 pub mod session_access_counter;
+pub mod session_children;
+pub mod session_queues;
+pub mod session_registry;
 pub mod signals;
 pub mod stdio_child;
 pub mod telemetry;
+pub mod trace_context;
+pub mod traffic;
 pub mod version;
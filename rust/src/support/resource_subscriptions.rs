@@ -0,0 +1,568 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::support::stdio_child::StdioChild;
+
+// What a shared-child gateway does with a client message after the subscription registry
+// has seen it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientRoute {
+    Forward,
+    // Answered by the gateway, right away or once the child answers the upstream subscribe.
+    Handled,
+}
+
+// Where a message from the shared child goes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChildRoute {
+    Everyone,
+    // A `notifications/resources/updated`, only for the sessions subscribed to its URI.
+    Sessions(BTreeSet<String>),
+    // The answer to a request the gateway sent on its own.
+    Drop,
+}
+
+// Shared-child gateways keep one upstream `resources/subscribe` per URI and count the
+// sessions behind it, so the child sees each URI once and updates reach subscribers only.
+// Upstream requests carry gateway-made ids, so clients' own ids never collide with them.
+#[derive(Clone)]
+pub struct ResourceSubscriptions {
+    inner: Arc<Mutex<Registry>>,
+    id_prefix: Arc<str>,
+    seq: Arc<AtomicU64>,
+}
+
+impl Default for ResourceSubscriptions {
+    fn default() -> Self {
+        Self {
+            inner: Arc::default(),
+            id_prefix: format!("mcpway-resources-{}-", Uuid::new_v4()).into(),
+            seq: Arc::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    uris: BTreeMap<String, BTreeSet<String>>,
+    // Upstream subscribes still waiting on the child, by gateway request id.
+    pending: HashMap<String, PendingSubscribe>,
+}
+
+struct PendingSubscribe {
+    uri: String,
+    // The sessions that asked for the URI meanwhile, with the ids of their own requests.
+    waiters: Vec<(String, Value)>,
+    child: Arc<StdioChild>,
+}
+
+impl ResourceSubscriptions {
+    // `message` is what the child would receive, so its id is the one the child answers with.
+    pub async fn on_client_message(
+        &self,
+        session_id: &str,
+        message: &Value,
+        child: &Arc<StdioChild>,
+    ) -> ClientRoute {
+        let Some((method, uri, id)) = subscription_request(message) else {
+            return ClientRoute::Forward;
+        };
+        let mut answers = Vec::new();
+        let mut upstream = None;
+        {
+            let mut registry = self.inner.lock().await;
+            if method == "resources/subscribe" {
+                if let Some(sessions) = registry.uris.get_mut(uri) {
+                    sessions.insert(session_id.to_string());
+                    answers.push(empty_result(id));
+                } else if let Some(pending) = registry.pending_for(uri) {
+                    pending.waiters.push((session_id.to_string(), id.clone()));
+                } else {
+                    let key = self.next_id();
+                    registry.pending.insert(
+                        key.clone(),
+                        PendingSubscribe {
+                            uri: uri.to_string(),
+                            waiters: vec![(session_id.to_string(), id.clone())],
+                            child: child.clone(),
+                        },
+                    );
+                    upstream = Some(key);
+                }
+            } else if registry
+                .uris
+                .get(uri)
+                .is_some_and(|sessions| sessions.contains(session_id))
+            {
+                if registry.forget(uri, session_id) {
+                    return ClientRoute::Forward;
+                }
+                answers.push(empty_result(id));
+            } else if let Some(pending) = registry.pending_for(uri) {
+                // Never counted yet: answer both the subscribe and the unsubscribe.
+                let Some(waiting) = pending.take_waiter(session_id) else {
+                    return ClientRoute::Forward;
+                };
+                answers.push(empty_result(&waiting));
+                answers.push(empty_result(id));
+            } else {
+                return ClientRoute::Forward;
+            }
+        }
+
+        if let Some(key) = upstream {
+            let request = upstream_request(&key, "resources/subscribe", uri);
+            if let Err(err) = child.send(&request).await {
+                tracing::warn!("Failed to send resources/subscribe for {uri} to child: {err}");
+                let pending = self.inner.lock().await.pending.remove(&key);
+                for (_, waiting) in pending.map(|pending| pending.waiters).unwrap_or_default() {
+                    answers.push(error_response(
+                        &waiting,
+                        &serde_json::json!({
+                            "code": -32603,
+                            "message": format!("Failed to write to child: {err}")
+                        }),
+                    ));
+                }
+            }
+        }
+        for answer in answers {
+            child.reply(answer).await;
+        }
+        ClientRoute::Handled
+    }
+
+    pub async fn on_child_message(&self, message: &Value) -> ChildRoute {
+        let mut registry = self.inner.lock().await;
+        if message.get("method").and_then(Value::as_str) == Some("notifications/resources/updated")
+        {
+            let uri = message
+                .pointer("/params/uri")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            return ChildRoute::Sessions(registry.uris.get(uri).cloned().unwrap_or_default());
+        }
+        if message.get("method").is_some() || !self.owns(message) {
+            return ChildRoute::Everyone;
+        }
+        let key = message
+            .get("id")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let error = message.get("error");
+        let Some(pending) = registry.pending.remove(key) else {
+            if let Some(error) = error {
+                tracing::warn!("Child rejected a gateway resource subscription change: {error}");
+            }
+            return ChildRoute::Drop;
+        };
+
+        // Answering goes back through the child's output, which this message is still
+        // being routed from, so it happens on its own task.
+        let mut answers = Vec::new();
+        let mut unsubscribe = false;
+        match error {
+            Some(error) => {
+                for (_, waiting) in &pending.waiters {
+                    answers.push(error_response(waiting, error));
+                }
+            }
+            None if pending.waiters.is_empty() => unsubscribe = true,
+            None => {
+                let sessions = registry.uris.entry(pending.uri.clone()).or_default();
+                for (session_id, waiting) in &pending.waiters {
+                    sessions.insert(session_id.clone());
+                    answers.push(empty_result(waiting));
+                }
+            }
+        }
+        let this = self.clone();
+        tokio::spawn(async move {
+            for answer in answers {
+                pending.child.reply(answer).await;
+            }
+            if unsubscribe {
+                this.send_internal(&pending.child, "resources/unsubscribe", &pending.uri)
+                    .await;
+            }
+        });
+        ChildRoute::Drop
+    }
+
+    // True for the answers to requests the gateway sent on its own, whichever child sent them.
+    pub fn owns(&self, message: &Value) -> bool {
+        message
+            .get("id")
+            .and_then(Value::as_str)
+            .is_some_and(|id| id.starts_with(&*self.id_prefix))
+    }
+
+    // Drops a session's subscriptions, unsubscribing upstream from URIs nobody else wants.
+    pub async fn release(&self, session_id: &str, child: &StdioChild) {
+        let emptied: Vec<String> = {
+            let mut registry = self.inner.lock().await;
+            for pending in registry.pending.values_mut() {
+                pending.take_waiter(session_id);
+            }
+            let uris: Vec<String> = registry
+                .uris
+                .iter()
+                .filter(|(_, sessions)| sessions.contains(session_id))
+                .map(|(uri, _)| uri.clone())
+                .collect();
+            uris.into_iter()
+                .filter(|uri| registry.forget(uri, session_id))
+                .collect()
+        };
+        for uri in emptied {
+            self.send_internal(child, "resources/unsubscribe", &uri)
+                .await;
+        }
+    }
+
+    // Moves a session's subscriptions from the shared child onto its dedicated child.
+    pub async fn move_session(
+        &self,
+        session_id: &str,
+        shared: &StdioChild,
+        dedicated: &StdioChild,
+    ) {
+        let (uris, answers) = {
+            let mut registry = self.inner.lock().await;
+            let mut uris: Vec<String> = registry
+                .uris
+                .iter()
+                .filter(|(_, sessions)| sessions.contains(session_id))
+                .map(|(uri, _)| uri.clone())
+                .collect();
+            let mut answers = Vec::new();
+            for pending in registry.pending.values_mut() {
+                if let Some(waiting) = pending.take_waiter(session_id) {
+                    uris.push(pending.uri.clone());
+                    answers.push(empty_result(&waiting));
+                }
+            }
+            (uris, answers)
+        };
+        self.release(session_id, shared).await;
+        for answer in answers {
+            shared.reply(answer).await;
+        }
+        for uri in uris {
+            self.send_internal(dedicated, "resources/subscribe", &uri)
+                .await;
+        }
+    }
+
+    // A restarted or replaced child starts with no subscriptions; subscribe it to every URI
+    // that still has sessions, and send subscribes still waiting on an answer again.
+    pub async fn resubscribe(&self, child: &StdioChild) {
+        let (uris, pending) = {
+            let mut registry = self.inner.lock().await;
+            let uris: Vec<String> = registry.uris.keys().cloned().collect();
+            let old_keys: Vec<String> = registry.pending.keys().cloned().collect();
+            let mut pending = Vec::new();
+            for old_key in old_keys {
+                if let Some(entry) = registry.pending.remove(&old_key) {
+                    let key = self.next_id();
+                    pending.push((key.clone(), entry.uri.clone()));
+                    registry.pending.insert(key, entry);
+                }
+            }
+            (uris, pending)
+        };
+        for uri in uris {
+            self.send_internal(child, "resources/subscribe", &uri).await;
+        }
+        for (key, uri) in pending {
+            let request = upstream_request(&key, "resources/subscribe", &uri);
+            if let Err(err) = child.send(&request).await {
+                tracing::warn!("Failed to send resources/subscribe for {uri} to child: {err}");
+            }
+        }
+    }
+
+    async fn send_internal(&self, child: &StdioChild, method: &str, uri: &str) {
+        let request = upstream_request(&self.next_id(), method, uri);
+        if let Err(err) = child.send(&request).await {
+            tracing::warn!("Failed to send {method} for {uri} to child: {err}");
+        }
+    }
+
+    fn next_id(&self) -> String {
+        format!(
+            "{}{}",
+            self.id_prefix,
+            self.seq.fetch_add(1, Ordering::SeqCst) + 1
+        )
+    }
+}
+
+impl Registry {
+    // Returns true when the URI lost its last session.
+    fn forget(&mut self, uri: &str, session_id: &str) -> bool {
+        let Some(sessions) = self.uris.get_mut(uri) else {
+            return false;
+        };
+        sessions.remove(session_id);
+        if sessions.is_empty() {
+            self.uris.remove(uri);
+            return true;
+        }
+        false
+    }
+
+    fn pending_for(&mut self, uri: &str) -> Option<&mut PendingSubscribe> {
+        self.pending.values_mut().find(|pending| pending.uri == uri)
+    }
+}
+
+impl PendingSubscribe {
+    // Returns the id of the session's waiting subscribe, if it has one.
+    fn take_waiter(&mut self, session_id: &str) -> Option<Value> {
+        let index = self
+            .waiters
+            .iter()
+            .position(|(waiting, _)| waiting == session_id)?;
+        Some(self.waiters.remove(index).1)
+    }
+}
+
+fn subscription_request(message: &Value) -> Option<(&str, &str, &Value)> {
+    let method = message.get("method").and_then(Value::as_str)?;
+    if method != "resources/subscribe" && method != "resources/unsubscribe" {
+        return None;
+    }
+    let uri = message.pointer("/params/uri").and_then(Value::as_str)?;
+    let id = message.get("id").filter(|id| !id.is_null())?;
+    Some((method, uri, id))
+}
+
+fn upstream_request(id: &str, method: &str, uri: &str) -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": { "uri": uri }
+    })
+}
+
+fn empty_result(id: &Value) -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "result": {}
+    })
+}
+
+fn error_response(id: &Value, error: &Value) -> Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": error
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    use crate::support::command_spec::parse_command_spec;
+    use crate::types::RuntimeArgs;
+
+    fn request(id: &str, method: &str, uri: &str) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": {"uri": uri}})
+    }
+
+    fn updated(uri: &str) -> Value {
+        json!({"jsonrpc": "2.0", "method": "notifications/resources/updated", "params": {"uri": uri}})
+    }
+
+    // `cat` echoes what the gateway sends upstream, so the tests can answer it by hand.
+    async fn echo_child() -> (Arc<StdioChild>, mpsc::Receiver<Value>) {
+        let child = Arc::new(StdioChild::new(parse_command_spec("cat").unwrap(), false));
+        let output = child.subscribe().await;
+        child.spawn(&RuntimeArgs::default()).await.unwrap();
+        (child, output)
+    }
+
+    async fn next(output: &mut mpsc::Receiver<Value>) -> Value {
+        tokio::time::timeout(Duration::from_secs(5), output.recv())
+            .await
+            .expect("timed out waiting for child output")
+            .expect("child output closed")
+    }
+
+    #[tokio::test]
+    async fn subscriptions_are_counted_per_uri_and_updates_reach_subscribers_only() {
+        let subs = ResourceSubscriptions::default();
+        let (child, mut output) = echo_child().await;
+
+        // The second subscriber waits for the child's answer to the first.
+        let first = request("a:1", "resources/subscribe", "file:///x");
+        assert_eq!(
+            subs.on_client_message("a", &first, &child).await,
+            ClientRoute::Handled
+        );
+        let upstream = next(&mut output).await;
+        assert_eq!(upstream["method"], "resources/subscribe");
+        assert!(subs.owns(&upstream));
+        assert_eq!(
+            subs.on_client_message(
+                "b",
+                &request("b:1", "resources/subscribe", "file:///x"),
+                &child
+            )
+            .await,
+            ClientRoute::Handled
+        );
+        assert_eq!(
+            subs.on_child_message(&json!({"jsonrpc": "2.0", "id": upstream["id"], "result": {}}))
+                .await,
+            ChildRoute::Drop
+        );
+        assert_eq!(next(&mut output).await["id"], "a:1");
+        assert_eq!(next(&mut output).await["id"], "b:1");
+        assert_eq!(
+            subs.on_child_message(&updated("file:///x")).await,
+            ChildRoute::Sessions(BTreeSet::from(["a".to_string(), "b".to_string()]))
+        );
+        assert_eq!(
+            subs.on_child_message(&updated("file:///y")).await,
+            ChildRoute::Sessions(BTreeSet::new())
+        );
+
+        // Only the last unsubscribe reaches the child.
+        assert_eq!(
+            subs.on_client_message(
+                "a",
+                &request("a:2", "resources/unsubscribe", "file:///x"),
+                &child
+            )
+            .await,
+            ClientRoute::Handled
+        );
+        assert_eq!(next(&mut output).await["id"], "a:2");
+        assert_eq!(
+            subs.on_client_message(
+                "b",
+                &request("b:2", "resources/unsubscribe", "file:///x"),
+                &child
+            )
+            .await,
+            ClientRoute::Forward
+        );
+        assert_eq!(
+            subs.on_child_message(&updated("file:///x")).await,
+            ChildRoute::Sessions(BTreeSet::new())
+        );
+
+        // A subscribe the child rejects is answered with its error and not counted.
+        subs.on_client_message(
+            "a",
+            &request("a:3", "resources/subscribe", "file:///z"),
+            &child,
+        )
+        .await;
+        let upstream = next(&mut output).await;
+        subs.on_child_message(&json!({
+            "jsonrpc": "2.0",
+            "id": upstream["id"],
+            "error": {"code": -32602, "message": "no"}
+        }))
+        .await;
+        let rejected = next(&mut output).await;
+        assert_eq!(rejected["id"], "a:3");
+        assert_eq!(rejected["error"]["message"], "no");
+        subs.on_client_message(
+            "b",
+            &request("b:3", "resources/subscribe", "file:///z"),
+            &child,
+        )
+        .await;
+        assert_eq!(next(&mut output).await["method"], "resources/subscribe");
+
+        child.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn sessions_reusing_request_ids_are_answered_separately() {
+        let subs = ResourceSubscriptions::default();
+        let (child, mut output) = echo_child().await;
+
+        // SSE forwards raw client ids, so two sessions can both send id 1.
+        subs.on_client_message(
+            "a",
+            &request("1", "resources/subscribe", "file:///a"),
+            &child,
+        )
+        .await;
+        let upstream_a = next(&mut output).await;
+        subs.on_client_message(
+            "b",
+            &request("1", "resources/subscribe", "file:///b"),
+            &child,
+        )
+        .await;
+        let upstream_b = next(&mut output).await;
+        assert_ne!(upstream_a["id"], upstream_b["id"]);
+
+        subs.on_child_message(&json!({
+            "jsonrpc": "2.0",
+            "id": upstream_a["id"],
+            "error": {"code": -32602, "message": "no"}
+        }))
+        .await;
+        assert_eq!(next(&mut output).await["error"]["message"], "no");
+        subs.on_child_message(&json!({"jsonrpc": "2.0", "id": upstream_b["id"], "result": {}}))
+            .await;
+        assert_eq!(next(&mut output).await["result"], json!({}));
+        assert_eq!(
+            subs.on_child_message(&updated("file:///a")).await,
+            ChildRoute::Sessions(BTreeSet::new())
+        );
+        assert_eq!(
+            subs.on_child_message(&updated("file:///b")).await,
+            ChildRoute::Sessions(BTreeSet::from(["b".to_string()]))
+        );
+
+        child.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn moved_sessions_are_subscribed_on_their_dedicated_child() {
+        let subs = ResourceSubscriptions::default();
+        let (shared, mut shared_output) = echo_child().await;
+        let (dedicated, mut dedicated_output) = echo_child().await;
+
+        subs.on_client_message(
+            "a",
+            &request("a:1", "resources/subscribe", "file:///x"),
+            &shared,
+        )
+        .await;
+        let upstream = next(&mut shared_output).await;
+        subs.on_child_message(&json!({"jsonrpc": "2.0", "id": upstream["id"], "result": {}}))
+            .await;
+        assert_eq!(next(&mut shared_output).await["id"], "a:1");
+
+        subs.move_session("a", &shared, &dedicated).await;
+        let unsubscribed = next(&mut shared_output).await;
+        assert_eq!(unsubscribed["method"], "resources/unsubscribe");
+        let resubscribed = next(&mut dedicated_output).await;
+        assert_eq!(resubscribed["method"], "resources/subscribe");
+        assert_eq!(resubscribed["params"]["uri"], "file:///x");
+        assert!(subs.owns(&resubscribed));
+
+        shared.shutdown().await;
+        dedicated.shutdown().await;
+    }
+}
@@ -0,0 +1,567 @@
+use std::path::PathBuf;
+
+// Filesystem and syscall restrictions applied to a stdio child right before it execs.
+// Landlock is enabled as soon as any path is listed; everything else is then denied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SandboxPolicy {
+    pub read_only: Vec<PathBuf>,
+    pub read_write: Vec<PathBuf>,
+    pub deny_network: bool,
+}
+
+impl SandboxPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.restricts_filesystem() || self.deny_network
+    }
+
+    fn restricts_filesystem(&self) -> bool {
+        !self.read_only.is_empty() || !self.read_write.is_empty()
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.restricts_filesystem() {
+            parts.push(format!(
+                "landlock ({} read-only, {} read-write paths)",
+                self.read_only.len(),
+                self.read_write.len()
+            ));
+        }
+        if self.deny_network {
+            parts.push("network sockets denied".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::PreparedSandbox;
+
+#[cfg(target_os = "linux")]
+impl SandboxPolicy {
+    // Builds the Landlock ruleset and seccomp program in the parent so the pre-exec hook
+    // only has to install them. Denied network calls are reported under `server_name`.
+    pub fn prepare(&self, server_name: &str) -> Result<Option<PreparedSandbox>, String> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+        let ruleset = if self.restricts_filesystem() {
+            Some(linux::build_ruleset(&self.read_only, &self.read_write)?)
+        } else {
+            None
+        };
+        let network = if self.deny_network {
+            Some(linux::NetworkGuard::new(server_name)?)
+        } else {
+            None
+        };
+        Ok(Some(PreparedSandbox { ruleset, network }))
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub struct PreparedSandbox;
+
+#[cfg(all(unix, not(target_os = "linux")))]
+impl PreparedSandbox {
+    pub fn enforce(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+impl SandboxPolicy {
+    pub fn prepare(&self, _server_name: &str) -> Result<Option<PreparedSandbox>, String> {
+        if self.is_enabled() {
+            return Err(
+                "--sandbox-ro, --sandbox-rw and --sandbox-deny-network require Linux".into(),
+            );
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::CString;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    const LANDLOCK_CREATE_RULESET_VERSION: libc::c_uint = 1;
+    const LANDLOCK_RULE_PATH_BENEATH: libc::c_uint = 1;
+
+    const ACCESS_EXECUTE: u64 = 1 << 0;
+    const ACCESS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_READ_FILE: u64 = 1 << 2;
+    const ACCESS_READ_DIR: u64 = 1 << 3;
+    const ACCESS_REFER: u64 = 1 << 13;
+    const ACCESS_TRUNCATE: u64 = 1 << 14;
+    // Every filesystem right defined by Landlock ABI 1.
+    const ACCESS_ABI_V1: u64 = (1 << 13) - 1;
+    const ACCESS_READ: u64 = ACCESS_EXECUTE | ACCESS_READ_FILE | ACCESS_READ_DIR;
+    // Rights that make sense on a non-directory; the kernel rejects the rest for files.
+    const ACCESS_FILE: u64 =
+        ACCESS_EXECUTE | ACCESS_WRITE_FILE | ACCESS_READ_FILE | ACCESS_TRUNCATE;
+    const ACCESS_DEVICE: u64 = ACCESS_READ_FILE | ACCESS_WRITE_FILE | ACCESS_TRUNCATE;
+
+    // Device nodes runtimes such as node and python open unconditionally; always readable
+    // and writable under the ruleset.
+    const DEFAULT_DEVICES: &[&str] = &[
+        "/dev/null",
+        "/dev/zero",
+        "/dev/full",
+        "/dev/random",
+        "/dev/urandom",
+        "/dev/tty",
+    ];
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xc000_003e;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xc000_00b7;
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+    // Offsets into `struct seccomp_data`; args[0] is read as its low 32 bits (little endian).
+    const SECCOMP_NR_OFFSET: u32 = 0;
+    const SECCOMP_ARCH_OFFSET: u32 = 4;
+    const SECCOMP_ARG0_OFFSET: u32 = 16;
+    const SECCOMP_IOCTL_NOTIF_RECV: u32 =
+        seccomp_ioctl(0, std::mem::size_of::<libc::seccomp_notif>());
+    const SECCOMP_IOCTL_NOTIF_SEND: u32 =
+        seccomp_ioctl(1, std::mem::size_of::<libc::seccomp_notif_resp>());
+
+    // `_IOWR('!', nr, size)` from <linux/seccomp.h>.
+    const fn seccomp_ioctl(nr: u32, size: usize) -> u32 {
+        (3 << 30) | ((size as u32) << 16) | ((b'!' as u32) << 8) | nr
+    }
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    pub struct PreparedSandbox {
+        pub(super) ruleset: Option<OwnedFd>,
+        pub(super) network: Option<NetworkGuard>,
+    }
+
+    // The seccomp program for `--sandbox-deny-network` plus the socket its listener fd is
+    // handed back on. Denied calls are held by the kernel until a supervisor thread in the
+    // parent logs them and fails them with EACCES, so every report is an actual denial.
+    pub struct NetworkGuard {
+        filter: Vec<libc::sock_filter>,
+        sender: OwnedFd,
+    }
+
+    impl NetworkGuard {
+        pub fn new(server_name: &str) -> Result<Self, String> {
+            let filter = network_filter()?;
+            let mut fds = [0; 2];
+            let rc = unsafe {
+                libc::socketpair(
+                    libc::AF_UNIX,
+                    libc::SOCK_STREAM | libc::SOCK_CLOEXEC,
+                    0,
+                    fds.as_mut_ptr(),
+                )
+            };
+            if rc != 0 {
+                return Err(format!(
+                    "Failed to create the sandbox notification socket: {}",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            let (receiver, sender) =
+                unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+            let server_name = server_name.to_string();
+            std::thread::Builder::new()
+                .name("mcpway-sandbox".to_string())
+                .spawn(move || supervise(receiver, &server_name))
+                .map_err(|err| format!("Failed to start the sandbox supervisor: {err}"))?;
+            Ok(Self { filter, sender })
+        }
+
+        // Runs between fork and exec, after Landlock.
+        fn install(&self) -> std::io::Result<()> {
+            let program = libc::sock_fprog {
+                len: self.filter.len() as libc::c_ushort,
+                filter: self.filter.as_ptr() as *mut libc::sock_filter,
+            };
+            let listener = unsafe {
+                libc::syscall(
+                    libc::SYS_seccomp,
+                    libc::SECCOMP_SET_MODE_FILTER,
+                    libc::SECCOMP_FILTER_FLAG_NEW_LISTENER,
+                    &program as *const libc::sock_fprog,
+                )
+            };
+            if listener < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            let listener = listener as RawFd;
+            let sent = send_fd(self.sender.as_raw_fd(), listener);
+            unsafe { libc::close(listener) };
+            sent
+        }
+    }
+
+    // Passes `fd` over a Unix socket without allocating, so it is safe before exec.
+    fn send_fd(socket: RawFd, fd: RawFd) -> std::io::Result<()> {
+        let mut byte = 0u8;
+        let mut iov = libc::iovec {
+            iov_base: (&mut byte as *mut u8).cast(),
+            iov_len: 1,
+        };
+        // Room for one SCM_RIGHTS header and fd, aligned for `cmsghdr`.
+        let mut control = [0u64; 4];
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen =
+            unsafe { libc::CMSG_SPACE(std::mem::size_of::<libc::c_int>() as u32) } as _;
+        unsafe {
+            let header = libc::CMSG_FIRSTHDR(&msg);
+            (*header).cmsg_level = libc::SOL_SOCKET;
+            (*header).cmsg_type = libc::SCM_RIGHTS;
+            (*header).cmsg_len = libc::CMSG_LEN(std::mem::size_of::<libc::c_int>() as u32) as _;
+            std::ptr::write_unaligned(libc::CMSG_DATA(header).cast::<libc::c_int>(), fd);
+            if libc::sendmsg(socket, &msg, 0) != 1 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    // None once every copy of the sending end is closed without a listener arriving, e.g.
+    // when the command was never spawned.
+    fn receive_fd(socket: &OwnedFd) -> Option<OwnedFd> {
+        let mut byte = 0u8;
+        let mut iov = libc::iovec {
+            iov_base: (&mut byte as *mut u8).cast(),
+            iov_len: 1,
+        };
+        let mut control = [0u64; 4];
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = std::mem::size_of_val(&control) as _;
+        loop {
+            let received =
+                unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, libc::MSG_CMSG_CLOEXEC) };
+            if received < 0
+                && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted
+            {
+                continue;
+            }
+            if received <= 0 {
+                return None;
+            }
+            break;
+        }
+        unsafe {
+            let header = libc::CMSG_FIRSTHDR(&msg);
+            if header.is_null()
+                || (*header).cmsg_level != libc::SOL_SOCKET
+                || (*header).cmsg_type != libc::SCM_RIGHTS
+            {
+                return None;
+            }
+            let fd = std::ptr::read_unaligned(libc::CMSG_DATA(header).cast::<libc::c_int>());
+            Some(OwnedFd::from_raw_fd(fd))
+        }
+    }
+
+    // Answers the child's denied network calls until every process under the filter is
+    // gone, which the kernel signals with POLLHUP on the listener.
+    fn supervise(receiver: OwnedFd, server_name: &str) {
+        let Some(listener) = receive_fd(&receiver) else {
+            return;
+        };
+        drop(receiver);
+        loop {
+            let mut poll = libc::pollfd {
+                fd: listener.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if unsafe { libc::poll(&mut poll, 1, -1) } < 0 {
+                if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return;
+            }
+            if poll.revents & libc::POLLIN == 0 {
+                return;
+            }
+            let mut request: libc::seccomp_notif = unsafe { std::mem::zeroed() };
+            let rc = unsafe {
+                libc::ioctl(
+                    listener.as_raw_fd(),
+                    SECCOMP_IOCTL_NOTIF_RECV as _,
+                    &mut request as *mut libc::seccomp_notif,
+                )
+            };
+            if rc != 0 {
+                match std::io::Error::last_os_error().raw_os_error() {
+                    // The caller died or was interrupted before the request was read.
+                    Some(libc::ENOENT) | Some(libc::EINTR) => continue,
+                    _ => return,
+                }
+            }
+            tracing::warn!(
+                "Sandbox violation in server {server_name}: pid {} was denied {}",
+                request.pid,
+                denied_call(request.data.nr, request.data.args[0])
+            );
+            let response = libc::seccomp_notif_resp {
+                id: request.id,
+                val: 0,
+                error: -libc::EACCES,
+                flags: 0,
+            };
+            // Fails only if the caller is already gone.
+            unsafe {
+                libc::ioctl(
+                    listener.as_raw_fd(),
+                    SECCOMP_IOCTL_NOTIF_SEND as _,
+                    &response as *const libc::seccomp_notif_resp,
+                )
+            };
+        }
+    }
+
+    pub(super) fn denied_call(nr: libc::c_int, arg0: u64) -> String {
+        if nr as libc::c_long == libc::SYS_io_uring_setup {
+            return "io_uring_setup".to_string();
+        }
+        let family = match arg0 as libc::c_int {
+            libc::AF_INET => "AF_INET".to_string(),
+            libc::AF_INET6 => "AF_INET6".to_string(),
+            libc::AF_NETLINK => "AF_NETLINK".to_string(),
+            libc::AF_PACKET => "AF_PACKET".to_string(),
+            libc::AF_VSOCK => "AF_VSOCK".to_string(),
+            other => format!("family {other}"),
+        };
+        format!("a socket ({family})")
+    }
+
+    impl PreparedSandbox {
+        // Runs between fork and exec: only raw syscalls, no allocation.
+        pub fn enforce(&self) -> std::io::Result<()> {
+            if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if let Some(ruleset) = &self.ruleset {
+                let rc = unsafe {
+                    libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0)
+                };
+                if rc != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            if let Some(network) = &self.network {
+                network.install()?;
+            }
+            Ok(())
+        }
+    }
+
+    pub fn build_ruleset(read_only: &[PathBuf], read_write: &[PathBuf]) -> Result<OwnedFd, String> {
+        let abi = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        };
+        if abi < 1 {
+            return Err(format!(
+                "Landlock is not available on this kernel ({}); refusing to start a sandboxed child",
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        let mut handled = ACCESS_ABI_V1;
+        if abi >= 2 {
+            handled |= ACCESS_REFER;
+        }
+        if abi >= 3 {
+            handled |= ACCESS_TRUNCATE;
+        }
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+        };
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0,
+            )
+        };
+        if fd < 0 {
+            return Err(format!(
+                "Failed to create Landlock ruleset: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        let ruleset = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+        for path in read_only {
+            add_path_rule(&ruleset, path, ACCESS_READ & handled)?;
+        }
+        for path in read_write {
+            add_path_rule(&ruleset, path, handled)?;
+        }
+        for device in DEFAULT_DEVICES {
+            let device = Path::new(device);
+            if device.exists() {
+                add_path_rule(&ruleset, device, ACCESS_DEVICE & handled)?;
+            }
+        }
+        Ok(ruleset)
+    }
+
+    fn add_path_rule(ruleset: &OwnedFd, path: &Path, access: u64) -> Result<(), String> {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            tracing::warn!(
+                "Sandbox path {} does not exist; skipping it",
+                path.display()
+            );
+            return Ok(());
+        };
+        let access = if metadata.is_dir() {
+            access
+        } else {
+            access & ACCESS_FILE
+        };
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| format!("Sandbox path {} contains a NUL byte", path.display()))?;
+        let raw = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+        if raw < 0 {
+            return Err(format!(
+                "Failed to open sandbox path {}: {}",
+                path.display(),
+                std::io::Error::last_os_error()
+            ));
+        }
+        let parent = unsafe { OwnedFd::from_raw_fd(raw) };
+        let rule = PathBeneathAttr {
+            allowed_access: access,
+            parent_fd: parent.as_raw_fd(),
+        };
+        let rc = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                LANDLOCK_RULE_PATH_BENEATH,
+                &rule as *const PathBeneathAttr,
+                0,
+            )
+        };
+        if rc != 0 {
+            return Err(format!(
+                "Failed to add Landlock rule for {}: {}",
+                path.display(),
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    // Hands every socket family except AF_UNIX, plus io_uring (which can open sockets
+    // without the socket syscall), to the supervisor. Foreign-ABI syscalls kill the child
+    // outright.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    fn network_filter() -> Result<Vec<libc::sock_filter>, String> {
+        let deny = libc::SECCOMP_RET_USER_NOTIF;
+        Ok(vec![
+            load(SECCOMP_ARCH_OFFSET),
+            jump(libc::BPF_JEQ, AUDIT_ARCH, 1, 0),
+            ret(libc::SECCOMP_RET_KILL_PROCESS),
+            load(SECCOMP_NR_OFFSET),
+            jump(libc::BPF_JGE, X32_SYSCALL_BIT, 0, 1),
+            ret(libc::SECCOMP_RET_KILL_PROCESS),
+            jump(libc::BPF_JEQ, libc::SYS_io_uring_setup as u32, 3, 0),
+            jump(libc::BPF_JEQ, libc::SYS_socket as u32, 0, 3),
+            load(SECCOMP_ARG0_OFFSET),
+            jump(libc::BPF_JEQ, libc::AF_UNIX as u32, 1, 0),
+            ret(deny),
+            ret(libc::SECCOMP_RET_ALLOW),
+        ])
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    fn network_filter() -> Result<Vec<libc::sock_filter>, String> {
+        Err("--sandbox-deny-network is only supported on x86_64 and aarch64".to_string())
+    }
+
+    fn load(offset: u32) -> libc::sock_filter {
+        libc::sock_filter {
+            code: (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+            jt: 0,
+            jf: 0,
+            k: offset,
+        }
+    }
+
+    fn jump(op: u32, value: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter {
+            code: (libc::BPF_JMP | op | libc::BPF_K) as u16,
+            jt,
+            jf,
+            k: value,
+        }
+    }
+
+    fn ret(value: u32) -> libc::sock_filter {
+        libc::sock_filter {
+            code: (libc::BPF_RET | libc::BPF_K) as u16,
+            jt: 0,
+            jf: 0,
+            k: value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_lists_enabled_restrictions() {
+        let policy = SandboxPolicy {
+            read_only: vec![PathBuf::from("/usr")],
+            read_write: Vec::new(),
+            deny_network: true,
+        };
+        assert!(policy.is_enabled());
+        assert_eq!(
+            policy.describe(),
+            "landlock (1 read-only, 0 read-write paths), network sockets denied"
+        );
+        assert!(!SandboxPolicy::default().is_enabled());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn denied_calls_name_the_socket_family() {
+        assert_eq!(
+            linux::denied_call(libc::SYS_socket as libc::c_int, libc::AF_INET6 as u64),
+            "a socket (AF_INET6)"
+        );
+        assert_eq!(
+            linux::denied_call(libc::SYS_io_uring_setup as libc::c_int, 8),
+            "io_uring_setup"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::process::Command;
+
+// Secret references look like `${scheme:reference}` and may sit anywhere inside a value,
+// e.g. `Bearer ${env:GITHUB_TOKEN}`. Anything else in `${...}` (including shell-style
+// `${VAR}` or `${VAR:-default}`) is left untouched.
+const SCHEMES: &[&str] = &["env", "file", "cmd", "dotenv"];
+
+// `${cmd:..}` helpers such as `pass` can sit on a pinentry prompt; a launch gives up on them
+// after this long.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+enum SecretRef<'a> {
+    Env(&'a str),
+    File(&'a str),
+    Cmd(&'a str),
+    Dotenv { path: &'a str, key: &'a str },
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Ref {
+        raw: &'a str,
+        reference: SecretRef<'a>,
+    },
+}
+
+// Checks reference syntax without resolving anything, so bad input fails at parse time.
+pub fn validate_secret_refs(value: &str) -> Result<(), String> {
+    segments(value).map(|_| ())
+}
+
+// Runtime updates come from the admin API and control socket; resolving references there
+// would run commands and read files on their behalf, so only the operator's command line
+// and config file may carry them.
+pub fn reject_secret_refs(value: &str) -> Result<(), String> {
+    if segments(value)?
+        .iter()
+        .any(|segment| matches!(segment, Segment::Ref { .. }))
+    {
+        return Err(
+            "secret references are only accepted on the command line and in the config file"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+pub async fn resolve_secret_refs(value: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    for segment in segments(value)? {
+        match segment {
+            Segment::Text(text) => out.push_str(text),
+            Segment::Ref { raw, reference } => {
+                let resolved = resolve_ref(&reference)
+                    .await
+                    .map_err(|err| format!("Failed to resolve {raw}: {err}"))?;
+                out.push_str(&resolved);
+            }
+        }
+    }
+    Ok(out)
+}
+
+// Resolves every value in `values`; `label` names the flag or field in error messages.
+pub async fn resolve_secret_map(
+    values: &HashMap<String, String>,
+    label: &str,
+) -> Result<HashMap<String, String>, String> {
+    let mut resolved = HashMap::with_capacity(values.len());
+    for (key, value) in values {
+        let value = resolve_secret_refs(value)
+            .await
+            .map_err(|err| format!("{label} {key}: {err}"))?;
+        resolved.insert(key.clone(), value);
+    }
+    Ok(resolved)
+}
+
+fn segments(value: &str) -> Result<Vec<Segment<'_>>, String> {
+    let mut out = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        out.push(Segment::Text(&rest[..start]));
+        let candidate = &rest[start..];
+        let Some(reference) = parse_ref(candidate)? else {
+            out.push(Segment::Text(&candidate[..2]));
+            rest = &candidate[2..];
+            continue;
+        };
+        let end = candidate
+            .find('}')
+            .expect("parse_ref only accepts closed references");
+        out.push(Segment::Ref {
+            raw: &candidate[..=end],
+            reference,
+        });
+        rest = &candidate[end + 1..];
+    }
+    out.push(Segment::Text(rest));
+    Ok(out)
+}
+
+fn parse_ref(candidate: &str) -> Result<Option<SecretRef<'_>>, String> {
+    let body = &candidate[2..];
+    let Some((scheme, after)) = body.split_once(':') else {
+        return Ok(None);
+    };
+    if !SCHEMES.contains(&scheme) {
+        return Ok(None);
+    }
+    let Some(end) = after.find('}') else {
+        return Err(format!(
+            "Unterminated secret reference `${{{scheme}:...`; expected a closing `}}`"
+        ));
+    };
+    let reference = after[..end].trim();
+    if reference.is_empty() {
+        return Err(format!("Empty secret reference `${{{scheme}:}}`"));
+    }
+    let parsed = match scheme {
+        "env" => SecretRef::Env(reference),
+        "file" => SecretRef::File(reference),
+        "cmd" => SecretRef::Cmd(reference),
+        _ => {
+            let Some((path, key)) = reference.rsplit_once('#') else {
+                return Err(format!(
+                    "Invalid secret reference `${{dotenv:{reference}}}`; expected `${{dotenv:PATH#KEY}}`"
+                ));
+            };
+            if path.is_empty() || key.is_empty() {
+                return Err(format!(
+                    "Invalid secret reference `${{dotenv:{reference}}}`; expected `${{dotenv:PATH#KEY}}`"
+                ));
+            }
+            SecretRef::Dotenv { path, key }
+        }
+    };
+    Ok(Some(parsed))
+}
+
+async fn resolve_ref(reference: &SecretRef<'_>) -> Result<String, String> {
+    match reference {
+        SecretRef::Env(name) => {
+            std::env::var(name).map_err(|_| format!("environment variable {name} is not set"))
+        }
+        SecretRef::File(path) => tokio::fs::read_to_string(path)
+            .await
+            .map(|body| body.trim_end_matches(['\r', '\n']).to_string())
+            .map_err(|err| format!("failed to read {path}: {err}")),
+        SecretRef::Cmd(command) => run_command(command, COMMAND_TIMEOUT).await,
+        SecretRef::Dotenv { path, key } => read_dotenv(Path::new(path), key).await,
+    }
+}
+
+async fn run_command(command: &str, timeout: Duration) -> Result<String, String> {
+    #[cfg(windows)]
+    let mut cmd = Command::new("cmd");
+    #[cfg(windows)]
+    cmd.args(["/C", command]);
+    #[cfg(not(windows))]
+    let mut cmd = Command::new("sh");
+    #[cfg(not(windows))]
+    cmd.args(["-c", command]);
+    cmd.stdin(Stdio::null()).kill_on_drop(true);
+
+    let output = tokio::time::timeout(timeout, cmd.output())
+        .await
+        .map_err(|_| format!("`{command}` did not finish within {timeout:?}"))?
+        .map_err(|err| format!("failed to run `{command}`: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        return Err(if stderr.is_empty() {
+            format!("`{command}` exited with {}", output.status)
+        } else {
+            format!("`{command}` exited with {}: {stderr}", output.status)
+        });
+    }
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|_| format!("`{command}` printed non-UTF-8 output"))?;
+    Ok(stdout.trim_end_matches(['\r', '\n']).to_string())
+}
+
+async fn read_dotenv(path: &Path, key: &str) -> Result<String, String> {
+    let body = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+    for line in body.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        if name.trim() != key {
+            continue;
+        }
+        let value = value.trim();
+        for quote in ['"', '\''] {
+            if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+                return Ok(value[1..value.len() - 1].to_string());
+            }
+        }
+        return Ok(value.to_string());
+    }
+    Err(format!("{key} is not defined in {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn resolves_env_file_and_dotenv_references() {
+        let dir = std::env::temp_dir().join(format!("mcpway-secrets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        let token_file = dir.join("token");
+        std::fs::write(&token_file, "from-file\n").expect("write token file");
+        let dotenv = dir.join(".env");
+        std::fs::write(&dotenv, "# comment\nexport GH=\"from-dotenv\"\nOTHER=x\n")
+            .expect("write dotenv");
+        std::env::set_var("MCPWAY_SECRETS_TEST", "from-env");
+
+        assert_eq!(
+            resolve_secret_refs("Bearer ${env:MCPWAY_SECRETS_TEST}")
+                .await
+                .unwrap(),
+            "Bearer from-env"
+        );
+        assert_eq!(
+            resolve_secret_refs(&format!("${{file:{}}}", token_file.display()))
+                .await
+                .unwrap(),
+            "from-file"
+        );
+        assert_eq!(
+            resolve_secret_refs(&format!("${{dotenv:{}#GH}}", dotenv.display()))
+                .await
+                .unwrap(),
+            "from-dotenv"
+        );
+        assert_eq!(
+            resolve_secret_refs("${HOME} and ${PORT:-80}")
+                .await
+                .unwrap(),
+            "${HOME} and ${PORT:-80}"
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn resolves_command_references() {
+        assert_eq!(
+            resolve_secret_refs("${cmd:echo s3cr3t}").await.unwrap(),
+            "s3cr3t"
+        );
+        let err = resolve_secret_refs("${cmd:exit 3}").await.unwrap_err();
+        assert!(err.contains("Failed to resolve ${cmd:exit 3}"), "{err}");
+        let err = run_command("sleep 5", Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "`sleep 5` did not finish within 100ms");
+    }
+
+    #[tokio::test]
+    async fn reports_unresolvable_and_malformed_references() {
+        let values = HashMap::from([(
+            "GITHUB_TOKEN".to_string(),
+            "${env:MCPWAY_SECRETS_TEST_MISSING}".to_string(),
+        )]);
+        assert_eq!(
+            resolve_secret_map(&values, "--env").await.unwrap_err(),
+            "--env GITHUB_TOKEN: Failed to resolve ${env:MCPWAY_SECRETS_TEST_MISSING}: environment variable MCPWAY_SECRETS_TEST_MISSING is not set"
+        );
+        assert!(validate_secret_refs("${dotenv:.env}").is_err());
+        assert!(validate_secret_refs("${file:/run/secrets/gh").is_err());
+        assert!(validate_secret_refs("${file:/run/secrets/gh}").is_ok());
+        assert!(reject_secret_refs("Bearer ${cmd:cat /etc/shadow}").is_err());
+        assert!(reject_secret_refs("${HOME} and ${PORT:-80}").is_ok());
+    }
+}
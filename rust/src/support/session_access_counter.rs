@@ -26,22 +26,30 @@ impl SessionAccessCounter {
     }
 
     pub async fn inc(&self, session_id: &str, reason: &str) {
-        tracing::info!("SessionAccessCounter.inc() {session_id}, caused by {reason}");
+        tracing::info!(
+            session_id = %session_id,
+            "SessionAccessCounter.inc() {session_id}, caused by {reason}"
+        );
         let mut sessions = self.sessions.lock().await;
         match sessions.remove(session_id) {
             None => {
-                tracing::info!("Session access count 0 -> 1 for {session_id} (new session)");
+                tracing::info!(
+                    session_id = %session_id,
+                    "Session access count 0 -> 1 for {session_id} (new session)"
+                );
                 sessions.insert(session_id.to_string(), SessionState::Active { count: 1 });
             }
             Some(SessionState::Timeout { handle }) => {
                 handle.abort();
                 tracing::info!(
+                    session_id = %session_id,
                     "Session access count 0 -> 1, clearing cleanup timeout for {session_id}"
                 );
                 sessions.insert(session_id.to_string(), SessionState::Active { count: 1 });
             }
             Some(SessionState::Active { count }) => {
                 tracing::info!(
+                    session_id = %session_id,
                     "Session access count {count} -> {} for {session_id}",
                     count + 1
                 );
@@ -54,28 +62,42 @@ impl SessionAccessCounter {
     }
 
     pub async fn dec(&self, session_id: &str, reason: &str) {
-        tracing::info!("SessionAccessCounter.dec() {session_id}, caused by {reason}");
+        tracing::info!(
+            session_id = %session_id,
+            "SessionAccessCounter.dec() {session_id}, caused by {reason}"
+        );
         let mut sessions = self.sessions.lock().await;
         let Some(state) = sessions.remove(session_id) else {
-            tracing::error!("Called dec() on non-existent session {session_id}, ignoring");
+            tracing::error!(
+                session_id = %session_id,
+                "Called dec() on non-existent session {session_id}, ignoring"
+            );
             return;
         };
         match state {
             SessionState::Timeout { .. } => {
                 tracing::error!(
+                    session_id = %session_id,
                     "Called dec() on session {session_id} that is already pending cleanup, ignoring"
                 );
                 sessions.insert(session_id.to_string(), state);
             }
             SessionState::Active { count } => {
                 if count == 0 {
-                    tracing::error!("Invalid access count 0 for session {session_id}");
+                    tracing::error!(
+                        session_id = %session_id,
+                        "Invalid access count 0 for session {session_id}"
+                    );
                     return;
                 }
                 let next = count - 1;
-                tracing::info!("Session access count {count} -> {next} for {session_id}");
+                tracing::info!(
+                    session_id = %session_id,
+                    "Session access count {count} -> {next} for {session_id}"
+                );
                 if next == 0 {
                     tracing::info!(
+                        session_id = %session_id,
                         "Session access count reached 0, setting cleanup timeout for {session_id}"
                     );
                     let timeout = self.timeout;
@@ -83,7 +105,10 @@ impl SessionAccessCounter {
                     let cleanup = self.cleanup.clone();
                     let handle = tokio::spawn(async move {
                         tokio::time::sleep(timeout).await;
-                        tracing::info!("Session {session} timed out, cleaning up");
+                        tracing::info!(
+                            session_id = %session,
+                            "Session {session} timed out, cleaning up"
+                        );
                         cleanup(session);
                     });
                     sessions.insert(session_id.to_string(), SessionState::Timeout { handle });
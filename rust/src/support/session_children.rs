@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::runtime::store::RuntimeArgsStore;
+use crate::support::child_swap::SwapReport;
+use crate::support::stdio_child::{CommandSpec, StdioChild};
+
+// Shared-child gateways move a session onto its own child once it has overrides that need
+// a restart (extra args or env); everything else keeps using the shared child.
+#[derive(Clone)]
+pub struct SessionChildren {
+    spec: Arc<CommandSpec>,
+    runtime: RuntimeArgsStore,
+    children: Arc<Mutex<HashMap<String, Arc<StdioChild>>>>,
+    // The `initialize` params each session sent to the shared child, replayed to its
+    // dedicated child so the session stays initialized after the move.
+    initialized: Arc<Mutex<HashMap<String, serde_json::Value>>>,
+    // Held for a whole `isolate` so two concurrent updates cannot spawn two children.
+    isolating: Arc<Mutex<()>>,
+}
+
+impl SessionChildren {
+    pub fn new(spec: CommandSpec, runtime: RuntimeArgsStore) -> Self {
+        Self {
+            spec: Arc::new(spec),
+            runtime,
+            children: Arc::new(Mutex::new(HashMap::new())),
+            initialized: Arc::new(Mutex::new(HashMap::new())),
+            isolating: Arc::new(Mutex::new(())),
+        }
+    }
+
+    // Called with every message a session sends to the shared child.
+    pub async fn record_initialize(&self, session_id: &str, message: &serde_json::Value) {
+        if message.get("method").and_then(serde_json::Value::as_str) != Some("initialize") {
+            return;
+        }
+        let params = message
+            .get("params")
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!({}));
+        self.initialized
+            .lock()
+            .await
+            .insert(session_id.to_string(), params);
+    }
+
+    pub async fn get(&self, session_id: &str) -> Option<Arc<StdioChild>> {
+        let children = self.children.lock().await;
+        children.get(session_id).cloned()
+    }
+
+    // Restarts the session's dedicated child with its effective args, spawning one if needed.
+    // Returns the child only when it was newly spawned so the caller can route its output.
+    pub async fn isolate(&self, session_id: &str) -> Result<Option<Arc<StdioChild>>, String> {
+        let _isolating = self.isolating.lock().await;
+        let runtime = self.runtime.get_effective(Some(session_id)).await;
+        if let Some(child) = self.get(session_id).await {
+            child.restart(&runtime).await?;
+            return Ok(None);
+        }
+
+        // A session that already initialized on the shared child never sends `initialize`
+        // again, so the gateway replays it on every spawn of the dedicated child.
+        let mut spec = self.spec.as_ref().clone();
+        if let Some(params) = self.initialized.lock().await.get(session_id).cloned() {
+            spec = spec
+                .with_managed_initialize(true)
+                .with_initialize_params(params);
+        }
+        let child = Arc::new(StdioChild::new(spec, false));
+        child.spawn(&runtime).await?;
+        tracing::info!(
+            session_id = %session_id,
+            "Moved session {session_id} onto a dedicated child"
+        );
+        let mut children = self.children.lock().await;
+        children.insert(session_id.to_string(), child.clone());
+        Ok(Some(child))
+    }
+
+    // Drops the session's overrides and shuts down its dedicated child, if any.
+    pub async fn release(&self, session_id: &str) {
+        self.runtime.remove_session(session_id).await;
+        self.initialized.lock().await.remove(session_id);
+        let removed = self.children.lock().await.remove(session_id);
+        if let Some(child) = removed {
+            child.shutdown().await;
+        }
+    }
+
+    pub async fn restart_all(&self) -> Result<(), String> {
+        let children: Vec<(String, Arc<StdioChild>)> = {
+            let children = self.children.lock().await;
+            children
+                .iter()
+                .map(|(id, child)| (id.clone(), child.clone()))
+                .collect()
+        };
+        for (session_id, child) in children {
+            let runtime = self.runtime.get_effective(Some(&session_id)).await;
+            child.restart(&runtime).await?;
+        }
+        Ok(())
+    }
+
+    // Moves every dedicated child onto its new effective args with the configured restart
+    // strategy, returning the blue/green swaps.
+    pub async fn apply_runtime_all(&self) -> Result<Vec<SwapReport>, String> {
+        let children: Vec<(String, Arc<StdioChild>)> = {
+            let children = self.children.lock().await;
+            children
+                .iter()
+                .map(|(id, child)| (id.clone(), child.clone()))
+                .collect()
+        };
+        let mut swaps = Vec::new();
+        for (session_id, child) in children {
+            let runtime = self.runtime.get_effective(Some(&session_id)).await;
+            swaps.extend(child.apply_runtime(&runtime).await?);
+        }
+        Ok(swaps)
+    }
+}
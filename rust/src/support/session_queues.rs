@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::config::BackpressurePolicy;
+
+// How long a disconnected session gets to drain before its error notice is abandoned.
+const DISCONNECT_NOTICE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Delivered,
+    Dropped,
+    Closed,
+    Disconnect,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionQueueDepth {
+    pub id: String,
+    pub transport: &'static str,
+    pub depth: usize,
+    pub capacity: usize,
+    pub dropped: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionQueuesSnapshot {
+    pub policy: BackpressurePolicy,
+    pub sessions: Vec<SessionQueueDepth>,
+}
+
+struct QueueEntry {
+    transport: &'static str,
+    capacity: usize,
+    depth: Box<dyn Fn() -> usize + Send + Sync>,
+    dropped: Arc<AtomicU64>,
+}
+
+// Applies the configured backpressure policy to per-session outbound queues and tracks
+// their depth for the admin API.
+#[derive(Clone)]
+pub struct SessionQueues {
+    policy: BackpressurePolicy,
+    entries: Arc<Mutex<BTreeMap<String, QueueEntry>>>,
+}
+
+impl SessionQueues {
+    pub fn new(policy: BackpressurePolicy) -> Self {
+        Self {
+            policy,
+            entries: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    pub async fn register<T: Send + 'static>(
+        &self,
+        session_id: &str,
+        transport: &'static str,
+        sender: &mpsc::Sender<T>,
+    ) {
+        let weak = sender.downgrade();
+        let entry = QueueEntry {
+            transport,
+            capacity: sender.max_capacity(),
+            depth: Box::new(move || {
+                weak.upgrade()
+                    .map(|sender| sender.max_capacity() - sender.capacity())
+                    .unwrap_or(0)
+            }),
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+        self.entries
+            .lock()
+            .await
+            .insert(session_id.to_string(), entry);
+    }
+
+    pub async fn unregister(&self, session_id: &str) {
+        self.entries.lock().await.remove(session_id);
+    }
+
+    pub async fn snapshot(&self) -> SessionQueuesSnapshot {
+        let entries = self.entries.lock().await;
+        SessionQueuesSnapshot {
+            policy: self.policy,
+            sessions: entries
+                .iter()
+                .map(|(id, entry)| SessionQueueDepth {
+                    id: id.clone(),
+                    transport: entry.transport,
+                    depth: (entry.depth)(),
+                    capacity: entry.capacity,
+                    dropped: entry.dropped.load(Ordering::Relaxed),
+                })
+                .collect(),
+        }
+    }
+
+    // Queues `message` for a session. Only `Block` waits for room; `Disconnect` asks the
+    // caller to drop a full session and `DropNotifications` discards notifications only.
+    pub async fn deliver<T>(
+        &self,
+        session_id: &str,
+        sender: &mpsc::Sender<T>,
+        item: T,
+        message: &serde_json::Value,
+    ) -> Delivery {
+        let wait = match self.policy {
+            BackpressurePolicy::Block => true,
+            BackpressurePolicy::Disconnect => false,
+            BackpressurePolicy::DropNotifications => !is_notification(message),
+        };
+        if wait {
+            return match sender.send(item).await {
+                Ok(()) => Delivery::Delivered,
+                Err(_) => Delivery::Closed,
+            };
+        }
+
+        match sender.try_send(item) {
+            Ok(()) => Delivery::Delivered,
+            Err(mpsc::error::TrySendError::Closed(_)) => Delivery::Closed,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                if self.policy == BackpressurePolicy::Disconnect {
+                    tracing::warn!(
+                        session_id = %session_id,
+                        "Disconnecting slow session {session_id}: outbound queue is full ({})",
+                        sender.max_capacity()
+                    );
+                    return Delivery::Disconnect;
+                }
+                if let Some(entry) = self.entries.lock().await.get(session_id) {
+                    entry.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                tracing::warn!(
+                    session_id = %session_id,
+                    "Dropped notification for slow session {session_id}: outbound queue is full"
+                );
+                Delivery::Dropped
+            }
+        }
+    }
+}
+
+// Queues a final error for a session that was disconnected, once it drains enough to take it.
+pub fn send_disconnect_notice<T: Send + 'static>(sender: mpsc::Sender<T>, notice: T) {
+    tokio::spawn(async move {
+        let _ = tokio::time::timeout(DISCONNECT_NOTICE_TIMEOUT, sender.send(notice)).await;
+    });
+}
+
+fn is_notification(message: &serde_json::Value) -> bool {
+    message.get("method").is_some() && message.get("id").is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification() -> serde_json::Value {
+        serde_json::json!({"jsonrpc": "2.0", "method": "notifications/progress"})
+    }
+
+    fn response() -> serde_json::Value {
+        serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": {}})
+    }
+
+    #[tokio::test]
+    async fn drop_notifications_keeps_responses_and_counts_drops() {
+        let queues = SessionQueues::new(BackpressurePolicy::DropNotifications);
+        let (tx, mut rx) = mpsc::channel(1);
+        queues.register("s1", "test", &tx).await;
+
+        let first = notification();
+        let second = notification();
+        assert_eq!(
+            queues.deliver("s1", &tx, first.clone(), &first).await,
+            Delivery::Delivered
+        );
+        assert_eq!(
+            queues.deliver("s1", &tx, second.clone(), &second).await,
+            Delivery::Dropped
+        );
+
+        let reply = response();
+        let pending = {
+            let queues = queues.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move { queues.deliver("s1", &tx, reply.clone(), &reply).await })
+        };
+        assert_eq!(rx.recv().await, Some(notification()));
+        assert_eq!(pending.await.unwrap(), Delivery::Delivered);
+        assert_eq!(rx.recv().await, Some(response()));
+
+        let snapshot = queues.snapshot().await;
+        assert_eq!(snapshot.sessions.len(), 1);
+        assert_eq!(snapshot.sessions[0].dropped, 1);
+        assert_eq!(snapshot.sessions[0].capacity, 1);
+    }
+
+    #[tokio::test]
+    async fn disconnect_policy_flags_full_queue() {
+        let queues = SessionQueues::new(BackpressurePolicy::Disconnect);
+        let (tx, _rx) = mpsc::channel(1);
+        queues.register("s1", "test", &tx).await;
+
+        let reply = response();
+        assert_eq!(
+            queues.deliver("s1", &tx, reply.clone(), &reply).await,
+            Delivery::Delivered
+        );
+        assert_eq!(queues.snapshot().await.sessions[0].depth, 1);
+        assert_eq!(
+            queues.deliver("s1", &tx, reply.clone(), &reply).await,
+            Delivery::Disconnect
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::support::stdio_child::StdioChild;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub transport: &'static str,
+    pub created_at_utc: u64,
+    pub last_activity_utc: u64,
+    pub in_flight: usize,
+    pub protocol_version: Option<String>,
+    pub client_info: Option<serde_json::Value>,
+    pub child_pid: Option<u32>,
+}
+
+struct SessionRecord {
+    transport: &'static str,
+    created_at_utc: u64,
+    last_activity_utc: u64,
+    // Request id -> method, for requests the server has not answered yet.
+    pending: HashMap<String, String>,
+    protocol_version: Option<String>,
+    client_info: Option<serde_json::Value>,
+    child: Option<Arc<StdioChild>>,
+    evicted: CancellationToken,
+}
+
+// Tracks what the admin API reports about live sessions. Gateways record traffic as it
+// passes; the token returned by `register` fires when the session is evicted.
+#[derive(Clone, Default)]
+pub struct SessionRegistry {
+    sessions: Arc<Mutex<BTreeMap<String, SessionRecord>>>,
+}
+
+impl SessionRegistry {
+    // Re-registering a known session (e.g. a reattached connection) keeps what was already
+    // recorded and returns the same eviction token.
+    pub async fn register(
+        &self,
+        session_id: &str,
+        transport: &'static str,
+        child: Option<Arc<StdioChild>>,
+    ) -> CancellationToken {
+        let now = unix_timestamp_secs();
+        let mut sessions = self.sessions.lock().await;
+        let record = sessions
+            .entry(session_id.to_string())
+            .or_insert_with(|| SessionRecord {
+                transport,
+                created_at_utc: now,
+                last_activity_utc: now,
+                pending: HashMap::new(),
+                protocol_version: None,
+                client_info: None,
+                child: None,
+                evicted: CancellationToken::new(),
+            });
+        record.last_activity_utc = now;
+        if child.is_some() {
+            record.child = child;
+        }
+        record.evicted.clone()
+    }
+
+    pub async fn unregister(&self, session_id: &str) {
+        self.sessions.lock().await.remove(session_id);
+    }
+
+    pub async fn contains(&self, session_id: &str) -> bool {
+        self.sessions.lock().await.contains_key(session_id)
+    }
+
+    // Removes the session and fires its eviction token so the gateway closes the connection.
+    pub async fn evict(&self, session_id: &str) -> bool {
+        let removed = self.sessions.lock().await.remove(session_id);
+        match removed {
+            Some(record) => {
+                record.evicted.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn set_child(&self, session_id: &str, child: Arc<StdioChild>) {
+        if let Some(record) = self.sessions.lock().await.get_mut(session_id) {
+            record.child = Some(child);
+        }
+    }
+
+    // Records a client → server message.
+    pub async fn record_request(&self, session_id: &str, message: &serde_json::Value) {
+        let mut sessions = self.sessions.lock().await;
+        let Some(record) = sessions.get_mut(session_id) else {
+            return;
+        };
+        record.last_activity_utc = unix_timestamp_secs();
+        let Some(method) = message.get("method").and_then(|value| value.as_str()) else {
+            return;
+        };
+        if method == "initialize" {
+            let params = message.get("params");
+            if let Some(version) = params
+                .and_then(|params| params.get("protocolVersion"))
+                .and_then(|value| value.as_str())
+            {
+                record.protocol_version = Some(version.to_string());
+            }
+            if let Some(client_info) = params.and_then(|params| params.get("clientInfo")) {
+                record.client_info = Some(client_info.clone());
+            }
+        }
+        if let Some(id) = message.get("id") {
+            record.pending.insert(id.to_string(), method.to_string());
+        }
+    }
+
+    // Records a server → client message. A response to `initialize` carries the protocol
+    // version the server actually agreed to.
+    pub async fn record_response(&self, session_id: &str, message: &serde_json::Value) {
+        let mut sessions = self.sessions.lock().await;
+        let Some(record) = sessions.get_mut(session_id) else {
+            return;
+        };
+        record.last_activity_utc = unix_timestamp_secs();
+        if message.get("method").is_some() {
+            return;
+        }
+        let Some(id) = message.get("id") else {
+            return;
+        };
+        if record.pending.remove(&id.to_string()).as_deref() == Some("initialize") {
+            if let Some(version) = message
+                .get("result")
+                .and_then(|result| result.get("protocolVersion"))
+                .and_then(|value| value.as_str())
+            {
+                record.protocol_version = Some(version.to_string());
+            }
+        }
+    }
+
+    // Forgets a request that will never be answered, e.g. after a failed write.
+    pub async fn abandon(&self, session_id: &str, id: &serde_json::Value) {
+        if let Some(record) = self.sessions.lock().await.get_mut(session_id) {
+            record.pending.remove(&id.to_string());
+        }
+    }
+
+    // A restarted child drops whatever it was working on; `None` resets every session.
+    pub async fn reset_in_flight(&self, session_id: Option<&str>) {
+        let mut sessions = self.sessions.lock().await;
+        for (id, record) in sessions.iter_mut() {
+            if session_id.is_none_or(|target| target == id) {
+                record.pending.clear();
+            }
+        }
+    }
+
+    pub async fn get(&self, session_id: &str) -> Option<SessionInfo> {
+        let (mut info, child) = {
+            let sessions = self.sessions.lock().await;
+            let record = sessions.get(session_id)?;
+            (
+                SessionInfo {
+                    id: session_id.to_string(),
+                    transport: record.transport,
+                    created_at_utc: record.created_at_utc,
+                    last_activity_utc: record.last_activity_utc,
+                    in_flight: record.pending.len(),
+                    protocol_version: record.protocol_version.clone(),
+                    client_info: record.client_info.clone(),
+                    child_pid: None,
+                },
+                record.child.clone(),
+            )
+        };
+        if let Some(child) = child {
+            info.child_pid = child.pid().await;
+        }
+        Some(info)
+    }
+
+    pub async fn list(&self) -> Vec<SessionInfo> {
+        let ids: Vec<String> = self.sessions.lock().await.keys().cloned().collect();
+        let mut listed = Vec::with_capacity(ids.len());
+        for id in ids {
+            // A session can go away between the two lookups; it is simply left out.
+            if let Some(info) = self.get(&id).await {
+                listed.push(info);
+            }
+        }
+        listed
+    }
+}
+
+fn unix_timestamp_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn tracks_in_flight_requests_and_initialize_details() {
+        let registry = SessionRegistry::default();
+        let evicted = registry.register("s1", "test", None).await;
+
+        registry
+            .record_request(
+                "s1",
+                &serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "initialize",
+                    "params": {
+                        "protocolVersion": "2025-06-18",
+                        "clientInfo": {"name": "inspector", "version": "1.0"}
+                    }
+                }),
+            )
+            .await;
+        registry
+            .record_request(
+                "s1",
+                &serde_json::json!({"jsonrpc": "2.0", "id": "t", "method": "tools/list"}),
+            )
+            .await;
+        registry
+            .record_request(
+                "s1",
+                &serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            )
+            .await;
+        let info = registry.get("s1").await.expect("registered session");
+        assert_eq!(info.in_flight, 2);
+        assert_eq!(info.protocol_version.as_deref(), Some("2025-06-18"));
+        assert_eq!(info.client_info.expect("client info")["name"], "inspector");
+
+        registry
+            .record_response(
+                "s1",
+                &serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": {"protocolVersion": "2025-03-26"}
+                }),
+            )
+            .await;
+        let info = registry.get("s1").await.expect("registered session");
+        assert_eq!(info.in_flight, 1);
+        assert_eq!(info.protocol_version.as_deref(), Some("2025-03-26"));
+
+        registry.reset_in_flight(None).await;
+        assert_eq!(registry.get("s1").await.expect("session").in_flight, 0);
+
+        assert!(registry.evict("s1").await);
+        assert!(evicted.is_cancelled());
+        assert!(registry.get("s1").await.is_none());
+        assert!(!registry.evict("s1").await);
+    }
+}
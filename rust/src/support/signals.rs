@@ -1,7 +1,16 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 type CleanupHandler = Arc<dyn Fn() + Send + Sync>;
 
+static SIGHUP_RELOADS: AtomicBool = AtomicBool::new(false);
+
+// Hands SIGHUP to the configuration reloader instead of treating it as a shutdown signal.
+// Call before any gateway installs its handlers.
+pub fn reload_on_sighup() {
+    SIGHUP_RELOADS.store(true, Ordering::SeqCst);
+}
+
 pub fn install_signal_handlers(cleanup: Option<CleanupHandler>) {
     #[cfg(unix)]
     install_unix_signal_handlers(cleanup.clone());
@@ -33,7 +42,9 @@ fn install_unix_signal_handlers(cleanup: Option<CleanupHandler>) {
 
     handler("SIGINT", cleanup.clone());
     handler("SIGTERM", cleanup.clone());
-    handler("SIGHUP", cleanup);
+    if !SIGHUP_RELOADS.load(Ordering::SeqCst) {
+        handler("SIGHUP", cleanup);
+    }
 }
 
 #[cfg(not(unix))]
@@ -1,36 +1,101 @@
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU64, Ordering},
     Arc,
 };
+use std::time::Instant;
 
 use futures::StreamExt;
-use tokio::io::AsyncWriteExt;
-use tokio::process::{Child, ChildStdin, Command};
-use tokio::sync::{broadcast, Mutex};
-use tokio_util::codec::{FramedRead, LinesCodec};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{mpsc, Mutex};
+use tokio_util::codec::FramedRead;
 
+use crate::config::ChildFraming;
+use crate::support::child_framing::{write_frame, ChildFrame, ChildFrameCodec};
+use crate::support::child_handshake::{self, ClientHandshake};
+use crate::support::child_stderr::{self, ChildStderrHandle, StderrOptions};
+use crate::support::child_swap::{self, InFlight, ReplacementOptions, RestartStrategy, SwapReport};
+use crate::support::launch::LaunchOptions;
+use crate::support::secrets::resolve_secret_map;
 use crate::types::RuntimeArgs;
 
 // Keep enough headroom for bursty integration/CI traffic before consumers drain.
-const CHILD_BROADCAST_BUFFER: usize = 2048;
+const CHILD_OUTPUT_BUFFER: usize = 2048;
+
+type Subscribers = Arc<Mutex<Vec<mpsc::Sender<serde_json::Value>>>>;
 
 #[derive(Debug, Clone)]
 pub struct CommandSpec {
+    pub name: String,
     pub program: String,
     pub args: Vec<String>,
+    pub framing: ChildFraming,
+    pub max_frame_bytes: usize,
+    pub launch: LaunchOptions,
+    pub stderr: StderrOptions,
+    pub replacement: ReplacementOptions,
+    pub protocol_version: String,
+    // The gateway runs `initialize` itself on every spawn and answers clients from the cached
+    // result, for children shared by several clients.
+    pub managed_initialize: bool,
+    // `initialize` params the gateway sends instead of its own, e.g. those a session already
+    // sent to the shared child before moving onto a dedicated one.
+    pub initialize_params: Option<serde_json::Value>,
 }
 
 impl CommandSpec {
-    pub fn build_command(&self, runtime: &RuntimeArgs) -> Command {
+    pub fn with_framing(mut self, framing: ChildFraming, max_frame_bytes: usize) -> Self {
+        self.framing = framing;
+        self.max_frame_bytes = max_frame_bytes;
+        self
+    }
+
+    pub fn with_server_name(mut self, name: Option<&str>) -> Self {
+        if let Some(name) = name {
+            self.name = name.to_string();
+        }
+        self
+    }
+
+    pub fn with_launch(mut self, launch: LaunchOptions) -> Self {
+        self.launch = launch;
+        self
+    }
+
+    pub fn with_stderr(mut self, stderr: StderrOptions) -> Self {
+        self.stderr = stderr;
+        self
+    }
+
+    pub fn with_replacement(mut self, replacement: ReplacementOptions) -> Self {
+        self.replacement = replacement;
+        self
+    }
+
+    pub fn with_protocol_version(mut self, protocol_version: &str) -> Self {
+        self.protocol_version = protocol_version.to_string();
+        self
+    }
+
+    pub fn with_managed_initialize(mut self, managed: bool) -> Self {
+        self.managed_initialize = managed;
+        self
+    }
+
+    pub fn with_initialize_params(mut self, params: serde_json::Value) -> Self {
+        self.initialize_params = Some(params);
+        self
+    }
+
+    pub async fn build_command(&self, runtime: &RuntimeArgs) -> Result<Command, String> {
         let mut cmd = Command::new(&self.program);
         cmd.args(&self.args);
         if !runtime.extra_cli_args.is_empty() {
             cmd.args(&runtime.extra_cli_args);
         }
-        if !runtime.env.is_empty() {
-            cmd.envs(runtime.env.clone());
-        }
-        cmd
+        // Secret references are resolved on every spawn so rotated values are picked up.
+        let env = resolve_secret_map(&runtime.env, "env").await?;
+        self.launch.apply(&mut cmd, &env, &self.name)?;
+        Ok(cmd)
     }
 }
 
@@ -38,68 +103,154 @@ pub struct StdioChild {
     spec: CommandSpec,
     stdin: Mutex<Option<ChildStdin>>,
     child: Mutex<Option<Child>>,
-    sender: broadcast::Sender<serde_json::Value>,
+    subscribers: Subscribers,
     restarting: Arc<AtomicBool>,
+    // Bumped on every spawn and shutdown so a replaced child's reader can tell it is stale.
+    generation: Arc<AtomicU64>,
     exit_on_close: bool,
+    stderr: ChildStderrHandle,
+    // Requests written to the current child process that it has not answered yet.
+    in_flight: Mutex<Arc<InFlight>>,
+    // The current child's `InitializeResult` when the gateway initializes it.
+    initialize_result: Mutex<Option<serde_json::Value>>,
+}
+
+struct Launched {
+    child: Child,
+    stdin: ChildStdin,
+    frames: FramedRead<ChildStdout, ChildFrameCodec>,
 }
 
 impl StdioChild {
     pub fn new(spec: CommandSpec, exit_on_close: bool) -> Self {
-        let (sender, _) = broadcast::channel(CHILD_BROADCAST_BUFFER);
+        let stderr = child_stderr::register(&spec.name, &spec.stderr);
         Self {
             spec,
             stdin: Mutex::new(None),
             child: Mutex::new(None),
-            sender,
+            subscribers: Arc::new(Mutex::new(Vec::new())),
             restarting: Arc::new(AtomicBool::new(false)),
+            generation: Arc::new(AtomicU64::new(0)),
             exit_on_close,
+            stderr,
+            in_flight: Mutex::new(Arc::new(InFlight::default())),
+            initialize_result: Mutex::new(None),
         }
     }
 
-    pub fn subscribe(&self) -> broadcast::Receiver<serde_json::Value> {
-        self.sender.subscribe()
+    pub async fn subscribe(&self) -> mpsc::Receiver<serde_json::Value> {
+        let (tx, rx) = mpsc::channel(CHILD_OUTPUT_BUFFER);
+        self.subscribers.lock().await.push(tx);
+        rx
     }
 
     pub async fn spawn(&self, runtime: &RuntimeArgs) -> Result<(), String> {
-        let mut cmd = self.spec.build_command(runtime);
+        let mut launched = self.launch(runtime).await?;
+        if self.spec.managed_initialize {
+            let result = self.handshake(&mut launched).await?;
+            *self.initialize_result.lock().await = Some(result);
+        }
+        let in_flight = Arc::new(InFlight::default());
+        {
+            let mut guard = self.stdin.lock().await;
+            *guard = Some(launched.stdin);
+        }
+        {
+            let mut guard = self.child.lock().await;
+            *guard = Some(launched.child);
+        }
+        *self.in_flight.lock().await = in_flight.clone();
+        self.start_reader(launched.frames, in_flight);
+        Ok(())
+    }
+
+    async fn launch(&self, runtime: &RuntimeArgs) -> Result<Launched, String> {
+        let mut cmd = self.spec.build_command(runtime).await?;
         cmd.stdin(std::process::Stdio::piped())
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
 
         let mut child = cmd.spawn().map_err(|err| err.to_string())?;
+        self.spec.launch.watch_memory(child.id());
+        self.stderr.set_pid(child.id());
+        if self.spec.launch.sandbox.is_enabled() {
+            tracing::info!(
+                "Sandboxed server {}: {}",
+                self.spec.name,
+                self.spec.launch.sandbox.describe()
+            );
+        }
         let stdin = child.stdin.take().ok_or("Missing child stdin")?;
         let stdout = child.stdout.take().ok_or("Missing child stdout")?;
         let stderr = child.stderr.take().ok_or("Missing child stderr")?;
 
-        {
-            let mut guard = self.stdin.lock().await;
-            *guard = Some(stdin);
-        }
-        {
-            let mut guard = self.child.lock().await;
-            *guard = Some(child);
+        child_stderr::capture(stderr, self.stderr.buffer(), self.spec.stderr.clone());
+
+        let frames = FramedRead::new(
+            stdout,
+            ChildFrameCodec::new(self.spec.framing, self.spec.max_frame_bytes),
+        );
+        Ok(Launched {
+            child,
+            stdin,
+            frames,
+        })
+    }
+
+    // Initializes a launched child before it is made current; a child that fails is killed.
+    async fn handshake(&self, launched: &mut Launched) -> Result<serde_json::Value, String> {
+        let id = format!("mcpway-init-{}", self.generation.load(Ordering::SeqCst) + 1);
+        let params = self
+            .spec
+            .initialize_params
+            .clone()
+            .unwrap_or_else(|| child_handshake::initialize_params(&self.spec.protocol_version));
+        let result = child_handshake::initialize(
+            &mut launched.stdin,
+            &mut launched.frames,
+            self.spec.framing,
+            params,
+            &id,
+        )
+        .await;
+        if result.is_err() {
+            let _ = launched.child.kill().await;
+            self.stderr.set_pid(self.pid().await);
         }
+        result
+    }
 
-        let sender = self.sender.clone();
+    // Publishes the child's output; the caller must already have made it the current child
+    // so an earlier child's reader sees itself replaced.
+    fn start_reader(
+        &self,
+        mut frames: FramedRead<ChildStdout, ChildFrameCodec>,
+        in_flight: Arc<InFlight>,
+    ) {
+        let subscribers = self.subscribers.clone();
+        let max_frame_bytes = self.spec.max_frame_bytes;
         let restarting = self.restarting.clone();
+        let generation = self.generation.clone();
+        let spawned_generation = generation.fetch_add(1, Ordering::SeqCst) + 1;
         let exit_on_close = self.exit_on_close;
         tokio::spawn(async move {
-            let mut lines = FramedRead::new(stdout, LinesCodec::new());
-            while let Some(line) = lines.next().await {
-                match line {
-                    Ok(line) => {
-                        if line.trim().is_empty() {
-                            continue;
-                        }
-                        match serde_json::from_str::<serde_json::Value>(&line) {
-                            Ok(json) => {
-                                tracing::debug!("Child → Gateway: {json}");
-                                let _ = sender.send(json);
-                            }
-                            Err(_) => {
-                                tracing::error!("Child non-JSON: {line}");
-                            }
-                        }
+            while let Some(frame) = frames.next().await {
+                match frame {
+                    Ok(ChildFrame::Message(json)) => {
+                        tracing::debug!("Child → Gateway: {json}");
+                        in_flight.settle(&json);
+                        publish(&subscribers, json).await;
+                    }
+                    Ok(ChildFrame::NonJson(text)) => {
+                        tracing::error!("Child non-JSON: {text}");
+                    }
+                    Ok(ChildFrame::Malformed(reason)) => {
+                        tracing::error!("Malformed child frame: {reason}");
+                    }
+                    Ok(ChildFrame::Oversize) => {
+                        tracing::error!(
+                            "Child frame exceeds the {max_frame_bytes}-byte limit (--max-frame-bytes); discarding it"
+                        );
                     }
                     Err(err) => {
                         tracing::error!("Error reading child stdout: {err}");
@@ -107,45 +258,42 @@ impl StdioChild {
                     }
                 }
             }
-            if exit_on_close && !restarting.load(Ordering::SeqCst) {
+            in_flight.close();
+            let replaced = generation.load(Ordering::SeqCst) != spawned_generation;
+            if exit_on_close && !replaced && !restarting.load(Ordering::SeqCst) {
                 tracing::error!("Child stdout closed. Exiting...");
                 std::process::exit(1);
             }
         });
+    }
 
-        tokio::spawn(async move {
-            let mut lines = FramedRead::new(stderr, LinesCodec::new());
-            while let Some(line) = lines.next().await {
-                match line {
-                    Ok(line) => {
-                        if !line.trim().is_empty() {
-                            tracing::error!("Child stderr: {line}");
-                        }
-                    }
-                    Err(err) => {
-                        tracing::error!("Error reading child stderr: {err}");
-                        break;
-                    }
+    pub async fn send(&self, message: &serde_json::Value) -> Result<(), String> {
+        if self.spec.managed_initialize {
+            let cached = self.initialize_result.lock().await.clone();
+            match child_handshake::client_handshake(message, cached.as_ref()) {
+                ClientHandshake::Forward => {}
+                ClientHandshake::Answer(response) => {
+                    self.reply(response).await;
+                    return Ok(());
                 }
+                ClientHandshake::Swallow => return Ok(()),
             }
-        });
+        }
+        let mut guard = self.stdin.lock().await;
+        let stdin = guard.as_mut().ok_or("Child stdin not available")?;
+        self.in_flight.lock().await.track(message);
+        write_frame(stdin, self.spec.framing, message).await
+    }
 
-        Ok(())
+    // Hands a gateway-made answer to subscribers as if the child had sent it, so it is routed
+    // like any other child output. Answers carry the request's id, which gateways sharing the
+    // child prefix with the session, so only the requesting session receives them.
+    pub async fn reply(&self, message: serde_json::Value) {
+        publish(&self.subscribers, message).await;
     }
 
-    pub async fn send(&self, message: &serde_json::Value) -> Result<(), String> {
-        let line = serde_json::to_string(message).map_err(|err| err.to_string())?;
-        let mut guard = self.stdin.lock().await;
-        let stdin = guard.as_mut().ok_or("Child stdin not available")?;
-        stdin
-            .write_all(line.as_bytes())
-            .await
-            .map_err(|err| err.to_string())?;
-        stdin
-            .write_all(b"\n")
-            .await
-            .map_err(|err| err.to_string())?;
-        Ok(())
+    pub async fn pid(&self) -> Option<u32> {
+        self.child.lock().await.as_ref().and_then(Child::id)
     }
 
     pub async fn is_alive(&self) -> bool {
@@ -187,13 +335,93 @@ impl StdioChild {
             let mut stdin = self.stdin.lock().await;
             *stdin = None;
         }
+        *self.initialize_result.lock().await = None;
         let result = self.spawn(runtime).await;
         self.restarting.store(false, Ordering::SeqCst);
         result
     }
 
+    // Moves the child onto new runtime args with the configured restart strategy. Only
+    // blue/green replacement produces a swap report.
+    pub async fn apply_runtime(&self, runtime: &RuntimeArgs) -> Result<Option<SwapReport>, String> {
+        match self.spec.replacement.strategy {
+            RestartStrategy::Kill => self.restart(runtime).await.map(|()| None),
+            RestartStrategy::BlueGreen => self.replace(runtime).await.map(Some),
+        }
+    }
+
+    // Blue/green replacement: the new child is started and initialized while the old one keeps
+    // serving, then takes all new traffic. The old child keeps its stdin open until it has
+    // answered what it already had or the drain timeout passes, and is stopped after that.
+    pub async fn replace(&self, runtime: &RuntimeArgs) -> Result<SwapReport, String> {
+        let mut launched = self.launch(runtime).await?;
+        let result = self
+            .handshake(&mut launched)
+            .await
+            .map_err(|err| format!("{err}; kept the running child"))?;
+
+        let new_pid = launched.child.id();
+        let in_flight = Arc::new(InFlight::default());
+        let (old_stdin, old_child, old_in_flight) = {
+            let mut stdin = self.stdin.lock().await;
+            let mut current = self.in_flight.lock().await;
+            let mut child = self.child.lock().await;
+            let old_in_flight = std::mem::replace(&mut *current, in_flight.clone());
+            let swapped = (
+                stdin.replace(launched.stdin),
+                child.replace(launched.child),
+                old_in_flight,
+            );
+            self.start_reader(launched.frames, in_flight);
+            swapped
+        };
+        if self.spec.managed_initialize {
+            *self.initialize_result.lock().await = Some(result);
+        }
+
+        let old_pid = old_child.as_ref().and_then(Child::id);
+        let pending = old_in_flight.len();
+        tracing::info!(
+            "Switched {} to pid {}; draining {pending} request(s) on pid {}",
+            self.spec.name,
+            new_pid.unwrap_or_default(),
+            old_pid.unwrap_or_default()
+        );
+        let drain_started = Instant::now();
+        let abandoned = old_in_flight
+            .drain(self.spec.replacement.drain_timeout)
+            .await;
+        let drain_ms = drain_started.elapsed().as_millis() as u64;
+        drop(old_stdin);
+        if let Some(mut child) = old_child {
+            let _ = child.kill().await;
+        }
+        if !abandoned.is_empty() {
+            tracing::warn!(
+                "Old {} child stopped with {} unanswered request(s) after the drain timeout",
+                self.spec.name,
+                abandoned.len()
+            );
+        }
+        let abandoned_count = abandoned.len();
+        for id in abandoned {
+            publish(&self.subscribers, child_swap::abandoned_response(id)).await;
+        }
+
+        Ok(SwapReport {
+            child: self.spec.name.clone(),
+            old_pid,
+            new_pid,
+            in_flight: pending,
+            drained: pending.saturating_sub(abandoned_count),
+            abandoned: abandoned_count,
+            drain_ms,
+        })
+    }
+
     pub async fn shutdown(&self) {
         self.restarting.store(true, Ordering::SeqCst);
+        self.generation.fetch_add(1, Ordering::SeqCst);
         {
             let mut guard = self.child.lock().await;
             if let Some(child) = guard.as_mut() {
@@ -208,3 +436,19 @@ impl StdioChild {
         self.restarting.store(false, Ordering::SeqCst);
     }
 }
+
+// Waits on every subscriber in turn, so a full consumer queue pauses the child reader
+// instead of silently losing output.
+async fn publish(subscribers: &Subscribers, message: serde_json::Value) {
+    let targets = subscribers.lock().await.clone();
+    let mut closed = false;
+    for target in targets {
+        closed |= target.send(message.clone()).await.is_err();
+    }
+    if closed {
+        subscribers
+            .lock()
+            .await
+            .retain(|target| !target.is_closed());
+    }
+}
@@ -0,0 +1,492 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use opentelemetry::global;
+use tracing::field::Empty;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::support::metrics;
+
+pub const TRACEPARENT: &str = "traceparent";
+pub const TRACESTATE: &str = "tracestate";
+
+// Once a gateway has this many requests outstanding, the ones older than
+// `REQUEST_SPAN_TTL` are given up on, or the oldest one if none is that old.
+const MAX_TRACKED_REQUESTS: usize = 4096;
+const REQUEST_SPAN_TTL: Duration = Duration::from_secs(600);
+
+pub type TraceCarrier = HashMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanRole {
+    Server,
+    Client,
+}
+
+impl SpanRole {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Server => "server",
+            Self::Client => "client",
+        }
+    }
+}
+
+pub struct RequestTrace {
+    span: Span,
+    role: SpanRole,
+    transport: &'static str,
+    method: String,
+    started: Instant,
+}
+
+impl RequestTrace {
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn finish(self, response: &serde_json::Value) {
+        record_response(&self.span, response);
+        let status = if response.get("error").is_some() {
+            "error"
+        } else {
+            "ok"
+        };
+        // Logged inside the span so the record carries its session and request fields.
+        let elapsed = self.started.elapsed();
+        tracing::debug!(
+            parent: &self.span,
+            duration_ms = elapsed.as_millis() as u64,
+            "{} request finished ({status}) in {elapsed:?}",
+            self.method,
+        );
+        self.record_metrics(status);
+    }
+
+    pub fn fail(self, message: &str) {
+        record_error(&self.span, message);
+        tracing::warn!(parent: &self.span, "{} request failed: {message}", self.method);
+        self.record_metrics("error");
+    }
+
+    fn record_metrics(&self, status: &'static str) {
+        metrics::record_jsonrpc_request(
+            self.role.as_str(),
+            self.transport,
+            &self.method,
+            status,
+            self.started.elapsed(),
+        );
+    }
+}
+
+// Requests are tracked by session and id, since sessions pick their ids independently.
+type SpanKey = (Option<String>, String);
+
+#[derive(Clone, Default)]
+pub struct RequestSpans {
+    inner: Arc<Mutex<HashMap<SpanKey, RequestTrace>>>,
+}
+
+impl RequestSpans {
+    pub fn track(&self, session_id: Option<&str>, id: &serde_json::Value, trace: RequestTrace) {
+        let (evicted, superseded) = {
+            let Ok(mut spans) = self.inner.lock() else {
+                return;
+            };
+            let evicted = if spans.len() >= MAX_TRACKED_REQUESTS {
+                evict_stale(&mut spans)
+            } else {
+                Vec::new()
+            };
+            let superseded = spans.insert(span_key(session_id, id), trace);
+            (evicted, superseded)
+        };
+        for trace in evicted {
+            trace.fail("Gave up waiting for a response");
+        }
+        if let Some(trace) = superseded {
+            trace.fail("Superseded by a request with the same id");
+        }
+    }
+
+    pub fn finish(&self, session_id: Option<&str>, response: &serde_json::Value) {
+        if !is_response(response) {
+            return;
+        }
+        let Some(id) = response.get("id") else {
+            return;
+        };
+        let trace = {
+            let Ok(mut spans) = self.inner.lock() else {
+                return;
+            };
+            spans.remove(&span_key(session_id, id))
+        };
+        if let Some(trace) = trace {
+            trace.finish(response);
+        }
+    }
+
+    pub fn abandon(&self, session_id: Option<&str>, id: &serde_json::Value, reason: &str) {
+        let trace = {
+            let Ok(mut spans) = self.inner.lock() else {
+                return;
+            };
+            spans.remove(&span_key(session_id, id))
+        };
+        if let Some(trace) = trace {
+            trace.fail(reason);
+        }
+    }
+
+    // Fails the requests a session still has open, e.g. when it disconnects or its child
+    // restarts; `None` covers every session.
+    pub fn abandon_session(&self, session_id: Option<&str>, reason: &str) {
+        let abandoned: Vec<RequestTrace> = {
+            let Ok(mut spans) = self.inner.lock() else {
+                return;
+            };
+            let keys: Vec<SpanKey> = spans
+                .keys()
+                .filter(|(session, _)| session_id.is_none() || session.as_deref() == session_id)
+                .cloned()
+                .collect();
+            keys.iter().filter_map(|key| spans.remove(key)).collect()
+        };
+        for trace in abandoned {
+            trace.fail(reason);
+        }
+    }
+}
+
+// Removes the requests older than `REQUEST_SPAN_TTL`, or the oldest one if none is.
+fn evict_stale(spans: &mut HashMap<SpanKey, RequestTrace>) -> Vec<RequestTrace> {
+    let mut keys: Vec<SpanKey> = spans
+        .iter()
+        .filter(|(_, trace)| trace.started.elapsed() >= REQUEST_SPAN_TTL)
+        .map(|(key, _)| key.clone())
+        .collect();
+    if keys.is_empty() {
+        keys.extend(
+            spans
+                .iter()
+                .min_by_key(|(_, trace)| trace.started)
+                .map(|(key, _)| key.clone()),
+        );
+    }
+    keys.iter().filter_map(|key| spans.remove(key)).collect()
+}
+
+fn span_key(session_id: Option<&str>, id: &serde_json::Value) -> SpanKey {
+    (session_id.map(str::to_string), id_key(id))
+}
+
+pub fn carrier_from_headers(headers: &axum::http::HeaderMap) -> TraceCarrier {
+    let mut carrier = TraceCarrier::new();
+    for key in [TRACEPARENT, TRACESTATE] {
+        if let Some(value) = headers.get(key).and_then(|value| value.to_str().ok()) {
+            carrier.insert(key.to_string(), value.to_string());
+        }
+    }
+    carrier
+}
+
+pub fn carrier_from_metadata(metadata: &tonic::metadata::MetadataMap) -> TraceCarrier {
+    let mut carrier = TraceCarrier::new();
+    for key in [TRACEPARENT, TRACESTATE] {
+        if let Some(value) = metadata.get(key).and_then(|value| value.to_str().ok()) {
+            carrier.insert(key.to_string(), value.to_string());
+        }
+    }
+    carrier
+}
+
+pub fn carrier_from_map(map: &HashMap<String, String>) -> TraceCarrier {
+    let mut carrier = TraceCarrier::new();
+    for (key, value) in map {
+        let lower = key.to_ascii_lowercase();
+        if lower == TRACEPARENT || lower == TRACESTATE {
+            carrier.insert(lower, value.clone());
+        }
+    }
+    carrier
+}
+
+pub fn carrier_from_message(message: &serde_json::Value) -> TraceCarrier {
+    let mut carrier = TraceCarrier::new();
+    let Some(meta) = message
+        .get("params")
+        .and_then(|params| params.get("_meta"))
+        .and_then(|meta| meta.as_object())
+    else {
+        return carrier;
+    };
+    for key in [TRACEPARENT, TRACESTATE] {
+        if let Some(value) = meta.get(key).and_then(|value| value.as_str()) {
+            carrier.insert(key.to_string(), value.to_string());
+        }
+    }
+    carrier
+}
+
+pub fn inject_into_message(message: &mut serde_json::Value, carrier: &TraceCarrier) {
+    if carrier.is_empty() {
+        return;
+    }
+    let Some(obj) = message.as_object_mut() else {
+        return;
+    };
+    let params = obj
+        .entry("params")
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    let Some(params) = params.as_object_mut() else {
+        return;
+    };
+    let meta = params
+        .entry("_meta")
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    let Some(meta) = meta.as_object_mut() else {
+        return;
+    };
+    for (key, value) in carrier {
+        meta.insert(key.clone(), serde_json::Value::String(value.clone()));
+    }
+}
+
+pub fn request_span(
+    role: SpanRole,
+    transport: &'static str,
+    message: &serde_json::Value,
+    session_id: Option<&str>,
+) -> Option<Span> {
+    if !is_request(message) {
+        return None;
+    }
+    let method = message
+        .get("method")
+        .and_then(|value| value.as_str())
+        .unwrap_or_default();
+    let id = message.get("id").map(id_key).unwrap_or_default();
+    let span = tracing::info_span!(
+        "jsonrpc.request",
+        otel.name = %format!("{method} ({transport})"),
+        otel.kind = role.as_str(),
+        otel.status_code = Empty,
+        otel.status_message = Empty,
+        rpc.system = "jsonrpc",
+        rpc.method = method,
+        rpc.jsonrpc.request_id = %id,
+        mcp.transport = transport,
+        mcp.session_id = Empty,
+        mcp.tool = Empty,
+    );
+    if let Some(session_id) = session_id {
+        span.record("mcp.session_id", session_id);
+    }
+    if let Some(tool) = tool_name(message) {
+        span.record("mcp.tool", tool);
+    }
+    Some(span)
+}
+
+// Opens a span for an outgoing request, parents it on the inbound carrier, and rewrites
+// `params._meta` so the next hop continues the same trace.
+pub fn begin_request(
+    role: SpanRole,
+    transport: &'static str,
+    message: &mut serde_json::Value,
+    session_id: Option<&str>,
+    inbound: TraceCarrier,
+) -> Option<(RequestTrace, TraceCarrier)> {
+    let span = request_span(role, transport, message, session_id)?;
+    let mut parent = inbound;
+    parent.extend(carrier_from_message(message));
+    set_parent(&span, &parent);
+    let outgoing = outgoing_carrier(&span, parent);
+    inject_into_message(message, &outgoing);
+    let method = message
+        .get("method")
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let trace = RequestTrace {
+        span,
+        role,
+        transport,
+        method,
+        started: Instant::now(),
+    };
+    Some((trace, outgoing))
+}
+
+pub fn set_parent(span: &Span, carrier: &TraceCarrier) {
+    if !carrier.contains_key(TRACEPARENT) {
+        return;
+    }
+    let cx = global::get_text_map_propagator(|propagator| propagator.extract(carrier));
+    let _ = span.set_parent(cx);
+}
+
+pub fn outgoing_carrier(span: &Span, fallback: TraceCarrier) -> TraceCarrier {
+    let mut carrier = TraceCarrier::new();
+    let cx = span.context();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&cx, &mut carrier));
+    if carrier.contains_key(TRACEPARENT) {
+        carrier
+    } else {
+        fallback
+    }
+}
+
+fn record_response(span: &Span, response: &serde_json::Value) {
+    if let Some(error) = response.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|value| value.as_str())
+            .unwrap_or("JSON-RPC error");
+        record_error(span, message);
+    } else {
+        span.record("otel.status_code", "OK");
+    }
+}
+
+fn record_error(span: &Span, message: &str) {
+    span.record("otel.status_code", "ERROR");
+    span.record("otel.status_message", message);
+}
+
+pub fn tool_name(message: &serde_json::Value) -> Option<&str> {
+    if message.get("method").and_then(|value| value.as_str()) != Some("tools/call") {
+        return None;
+    }
+    message
+        .get("params")
+        .and_then(|params| params.get("name"))
+        .and_then(|name| name.as_str())
+}
+
+fn is_request(message: &serde_json::Value) -> bool {
+    message.get("method").is_some() && message.get("id").is_some()
+}
+
+fn is_response(message: &serde_json::Value) -> bool {
+    message.get("method").is_none()
+        && (message.get("result").is_some() || message.get("error").is_some())
+}
+
+fn id_key(id: &serde_json::Value) -> String {
+    match id {
+        serde_json::Value::String(value) => value.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn inject_creates_params_meta() {
+        let mut message = serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"});
+        let carrier = TraceCarrier::from([(TRACEPARENT.to_string(), PARENT.to_string())]);
+        inject_into_message(&mut message, &carrier);
+        assert_eq!(message["params"]["_meta"]["traceparent"], PARENT);
+        assert_eq!(carrier_from_message(&message), carrier);
+    }
+
+    #[test]
+    fn inject_preserves_existing_meta_fields() {
+        let mut message = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "search", "_meta": {"progressToken": 7}}
+        });
+        let carrier = TraceCarrier::from([(TRACEPARENT.to_string(), PARENT.to_string())]);
+        inject_into_message(&mut message, &carrier);
+        assert_eq!(message["params"]["_meta"]["progressToken"], 7);
+        assert_eq!(message["params"]["_meta"]["traceparent"], PARENT);
+        assert_eq!(tool_name(&message), Some("search"));
+    }
+
+    #[test]
+    fn carrier_from_headers_reads_w3c_headers() {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(TRACEPARENT, PARENT.parse().expect("valid header"));
+        headers.insert("x-other", "1".parse().expect("valid header"));
+        let carrier = carrier_from_headers(&headers);
+        assert_eq!(carrier.len(), 1);
+        assert_eq!(carrier[TRACEPARENT], PARENT);
+    }
+
+    #[test]
+    fn begin_request_forwards_inbound_carrier_without_exporter() {
+        let mut message = serde_json::json!({"jsonrpc": "2.0", "id": "a", "method": "ping"});
+        let inbound = TraceCarrier::from([(TRACEPARENT.to_string(), PARENT.to_string())]);
+        let (_trace, outgoing) =
+            begin_request(SpanRole::Server, "sse", &mut message, None, inbound)
+                .expect("requests open a span");
+        assert_eq!(outgoing[TRACEPARENT], PARENT);
+        assert_eq!(message["params"]["_meta"]["traceparent"], PARENT);
+    }
+
+    fn trace(id: usize) -> RequestTrace {
+        let mut message = serde_json::json!({"jsonrpc": "2.0", "id": id, "method": "ping"});
+        begin_request(
+            SpanRole::Server,
+            "ws",
+            &mut message,
+            None,
+            TraceCarrier::new(),
+        )
+        .expect("requests open a span")
+        .0
+    }
+
+    #[test]
+    fn full_tracker_evicts_the_oldest_request() {
+        let spans = RequestSpans::default();
+        for id in 0..MAX_TRACKED_REQUESTS {
+            spans.track(Some("a"), &serde_json::json!(id), trace(id));
+        }
+        spans.track(Some("b"), &serde_json::json!(0), trace(0));
+        let tracked = spans.inner.lock().unwrap();
+        assert_eq!(tracked.len(), MAX_TRACKED_REQUESTS);
+        assert!(!tracked.contains_key(&(Some("a".to_string()), "0".to_string())));
+        assert!(tracked.contains_key(&(Some("b".to_string()), "0".to_string())));
+    }
+
+    #[test]
+    fn sessions_keep_their_own_ids_and_are_abandoned_together() {
+        let spans = RequestSpans::default();
+        spans.track(Some("a"), &serde_json::json!(1), trace(1));
+        spans.track(Some("b"), &serde_json::json!(1), trace(1));
+        spans.abandon_session(Some("a"), "Session closed");
+        assert_eq!(spans.inner.lock().unwrap().len(), 1);
+        spans.finish(
+            Some("b"),
+            &serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": {}}),
+        );
+        assert!(spans.inner.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn notifications_do_not_open_spans() {
+        let mut message =
+            serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert!(begin_request(
+            SpanRole::Server,
+            "ws",
+            &mut message,
+            None,
+            TraceCarrier::new()
+        )
+        .is_none());
+        assert!(message.get("params").is_none());
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use crate::support::redact::redactor;
+use crate::support::trace_context::tool_name;
+
+const TRAFFIC_BUFFER: usize = 1024;
+// Requests stop being correlated once this many are waiting for a response.
+const MAX_PENDING_REQUESTS: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrafficDirection {
+    ClientToServer,
+    ServerToClient,
+}
+
+impl TrafficDirection {
+    fn reverse(self) -> Self {
+        match self {
+            Self::ClientToServer => Self::ServerToClient,
+            Self::ServerToClient => Self::ClientToServer,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrafficEvent {
+    pub ts_utc_ms: u64,
+    pub direction: TrafficDirection,
+    pub transport: &'static str,
+    pub session_id: Option<String>,
+    // Responses carry the method and tool of the request they answer.
+    pub method: Option<String>,
+    pub tool: Option<String>,
+    pub latency_ms: Option<f64>,
+    pub message: Value,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TrafficFilter {
+    pub session: Option<String>,
+    pub method: Option<String>,
+    pub tool: Option<String>,
+}
+
+impl TrafficFilter {
+    pub fn matches(&self, event: &TrafficEvent) -> bool {
+        matches_field(self.session.as_deref(), event.session_id.as_deref())
+            && matches_field(self.method.as_deref(), event.method.as_deref())
+            && matches_field(self.tool.as_deref(), event.tool.as_deref())
+    }
+}
+
+fn matches_field(expected: Option<&str>, actual: Option<&str>) -> bool {
+    expected.is_none_or(|expected| actual == Some(expected))
+}
+
+struct PendingRequest {
+    started: Instant,
+    method: String,
+    tool: Option<String>,
+}
+
+type PendingKey = (TrafficDirection, Option<String>, String);
+
+// Every gateway publishes here; nothing is captured unless someone is subscribed.
+struct TrafficTap {
+    sender: broadcast::Sender<TrafficEvent>,
+    pending: Mutex<HashMap<PendingKey, PendingRequest>>,
+}
+
+fn tap() -> &'static TrafficTap {
+    static TAP: OnceLock<TrafficTap> = OnceLock::new();
+    TAP.get_or_init(|| TrafficTap {
+        sender: broadcast::channel(TRAFFIC_BUFFER).0,
+        pending: Mutex::new(HashMap::new()),
+    })
+}
+
+pub fn subscribe() -> broadcast::Receiver<TrafficEvent> {
+    tap().sender.subscribe()
+}
+
+pub fn record(
+    direction: TrafficDirection,
+    transport: &'static str,
+    session_id: Option<&str>,
+    message: &Value,
+) {
+    let tap = tap();
+    if tap.sender.receiver_count() == 0 {
+        return;
+    }
+    let Ok(mut pending) = tap.pending.lock() else {
+        return;
+    };
+
+    let id = message.get("id").map(|id| id.to_string());
+    let mut method = message
+        .get("method")
+        .and_then(|value| value.as_str())
+        .map(str::to_string);
+    let mut tool = tool_name(message).map(str::to_string);
+    let mut latency_ms = None;
+    match (&method, id) {
+        (Some(request_method), Some(id)) if pending.len() < MAX_PENDING_REQUESTS => {
+            pending.insert(
+                (direction, session_id.map(str::to_string), id),
+                PendingRequest {
+                    started: Instant::now(),
+                    method: request_method.clone(),
+                    tool: tool.clone(),
+                },
+            );
+        }
+        (None, Some(id)) => {
+            let key = (direction.reverse(), session_id.map(str::to_string), id);
+            if let Some(request) = pending.remove(&key) {
+                latency_ms = Some(request.started.elapsed().as_secs_f64() * 1000.0);
+                method = Some(request.method);
+                tool = request.tool;
+            }
+        }
+        _ => {}
+    }
+    drop(pending);
+
+    let mut message = message.clone();
+    redactor().redact_json(&mut message);
+    let _ = tap.sender.send(TrafficEvent {
+        ts_utc_ms: unix_timestamp_millis(),
+        direction,
+        transport,
+        session_id: session_id.map(str::to_string),
+        method,
+        tool,
+        latency_ms,
+        message,
+    });
+}
+
+fn unix_timestamp_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn correlates_responses_with_their_requests() {
+        let mut rx = subscribe();
+        record(
+            TrafficDirection::ClientToServer,
+            "test",
+            Some("traffic-s1"),
+            &serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 7,
+                "method": "tools/call",
+                "params": {"name": "echo", "arguments": {}}
+            }),
+        );
+        record(
+            TrafficDirection::ServerToClient,
+            "test",
+            Some("traffic-s1"),
+            &serde_json::json!({"jsonrpc": "2.0", "id": 7, "result": {}}),
+        );
+
+        let filter = TrafficFilter {
+            session: Some("traffic-s1".to_string()),
+            method: None,
+            tool: Some("echo".to_string()),
+        };
+        let mut events = Vec::new();
+        while events.len() < 2 {
+            let event = rx.recv().await.expect("traffic event");
+            if filter.matches(&event) {
+                events.push(event);
+            }
+        }
+        assert_eq!(events[0].direction, TrafficDirection::ClientToServer);
+        assert!(events[0].latency_ms.is_none());
+        assert_eq!(events[1].direction, TrafficDirection::ServerToClient);
+        assert_eq!(events[1].method.as_deref(), Some("tools/call"));
+        assert!(events[1].latency_ms.is_some());
+    }
+}
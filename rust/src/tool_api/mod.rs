@@ -11,3 +11,4 @@ pub use client::{
 };
 pub use ergonomic::ErgonomicToolsFacade;
 pub use error::ToolCallError;
+pub use schema::apply_defaults;
@@ -1,6 +1,6 @@
-use std::collections::VecDeque;
-use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+mod targets;
+mod tools;
+
 use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -18,11 +18,19 @@ use include_dir::{include_dir, Dir};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tokio::sync::{broadcast, Mutex};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message as UpstreamMessage;
 
 use crate::config::{OutputTransport, WebConfig};
 use crate::discovery::user_home_dir;
-use crate::support::log_store::{default_log_path, ensure_log_file, parse_record, StoredLogRecord};
+use crate::support::log_rotation::LogFollower;
+use crate::support::log_store::{
+    default_log_path, ensure_log_file, parse_record, parse_time_bound, recent_records,
+    resolve_instance_log_path, StoredLogRecord,
+};
 use crate::support::telemetry::init_telemetry;
+use targets::{AdminTargets, TargetLookupError, TargetParam};
+use tools::ToolPlayground;
 
 static WEB_DIST: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../web/dist");
 const LOG_STREAM_BUFFER: usize = 2048;
@@ -32,10 +40,14 @@ struct AppState {
     log_path: PathBuf,
     log_sender: broadcast::Sender<StoredLogRecord>,
     auth_token: Option<String>,
-    admin_proxy: Option<AdminProxy>,
+    admins: AdminTargets,
+    tools: ToolPlayground,
     themes: ThemeService,
 }
 
+type AdminSocket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
 #[derive(Clone)]
 struct AdminProxy {
     base_url: String,
@@ -81,6 +93,34 @@ impl AdminProxy {
             .map_err(|err| format!("Admin response is not valid JSON: {err}; body: {body}"))
     }
 
+    async fn connect_ws(&self, path: &str) -> Result<AdminSocket, String> {
+        let base = if let Some(rest) = self.base_url.strip_prefix("https://") {
+            format!("wss://{rest}")
+        } else if let Some(rest) = self.base_url.strip_prefix("http://") {
+            format!("ws://{rest}")
+        } else {
+            self.base_url.clone()
+        };
+        let url = format!("{base}{path}");
+        let mut request = url
+            .as_str()
+            .into_client_request()
+            .map_err(|err| format!("Invalid admin WebSocket URL {url}: {err}"))?;
+        if let Some(token) = self.token.as_deref() {
+            let value = format!("Bearer {token}")
+                .parse()
+                .map_err(|err| format!("Invalid admin token header: {err}"))?;
+            request
+                .headers_mut()
+                .insert(axum::http::header::AUTHORIZATION, value);
+        }
+
+        let (stream, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .map_err(|err| format!("Admin WebSocket connect failed: {err}"))?;
+        Ok(stream)
+    }
+
     async fn post_json(&self, path: &str, payload: &Value) -> Result<Value, String> {
         let url = format!("{}{}", self.base_url, path);
         let mut request = self.client.post(url).json(payload);
@@ -147,6 +187,12 @@ struct LogsRecentQuery {
     lines: Option<usize>,
     level: Option<String>,
     transport: Option<String>,
+    instance: Option<String>,
+    session: Option<String>,
+    request_id: Option<String>,
+    method: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
     search: Option<String>,
 }
 
@@ -175,10 +221,9 @@ pub async fn run(config: WebConfig) -> Result<(), String> {
         lock: Arc::new(Mutex::new(())),
     };
 
-    let admin_proxy = config
-        .admin_base_url
-        .clone()
-        .map(|url| AdminProxy::new(url, config.admin_token.clone()));
+    let admins = AdminTargets::new(&config);
+
+    let tools = ToolPlayground::new(&config)?;
 
     let (log_sender, _) = broadcast::channel(LOG_STREAM_BUFFER);
 
@@ -186,20 +231,35 @@ pub async fn run(config: WebConfig) -> Result<(), String> {
         log_path: log_path.clone(),
         log_sender: log_sender.clone(),
         auth_token: config.auth_token.clone(),
-        admin_proxy,
+        admins: admins.clone(),
+        tools,
         themes,
     };
 
     tokio::spawn(spawn_log_tailer(log_path.clone(), log_sender));
+    tokio::spawn(admins.spawn_poller(Duration::from_secs(config.poll_interval_seconds)));
 
     let api_router = Router::new()
         .route("/health", get(api_health))
         .route("/logs/recent", get(api_logs_recent))
         .route("/logs/ws", get(api_logs_ws))
+        .route("/targets", get(targets::api_targets))
+        .route("/targets/{name}", get(targets::api_target_detail))
         .route("/runtime/health", get(api_runtime_health))
         .route("/runtime/metrics", get(api_runtime_metrics))
         .route("/runtime/sessions", get(api_runtime_sessions))
+        .route("/runtime/traffic/ws", get(api_runtime_traffic_ws))
         .route("/discovery/search", post(api_discovery_search))
+        .route("/tools/list", get(tools::api_tools_list))
+        .route("/tools/call", post(tools::api_tools_call))
+        .route(
+            "/tools/history",
+            get(tools::api_tools_history).delete(tools::api_tools_history_clear),
+        )
+        .route(
+            "/tools/history/export",
+            get(tools::api_tools_history_export),
+        )
         .route("/themes/catalog", get(api_theme_catalog))
         .route("/themes/refresh", post(api_theme_refresh))
         .with_state(state.clone())
@@ -280,7 +340,8 @@ async fn api_health(State(state): State<AppState>) -> impl IntoResponse {
     Json(serde_json::json!({
         "status": "ok",
         "auth_enabled": state.auth_token.is_some(),
-        "runtime_admin_enabled": state.admin_proxy.is_some(),
+        "runtime_admin_enabled": !state.admins.is_empty(),
+        "tool_target_configured": state.tools.is_configured(),
         "log_path": state.log_path,
     }))
 }
@@ -289,7 +350,20 @@ async fn api_logs_recent(
     State(state): State<AppState>,
     Query(query): Query<LogsRecentQuery>,
 ) -> impl IntoResponse {
-    match read_recent_logs(&state.log_path, &query) {
+    // Instances started with `--log-per-instance` write next to the shared file.
+    let path = resolve_instance_log_path(&state.log_path, query.instance.as_deref());
+    let now = unix_timestamp_secs();
+    let bounds = [&query.since, &query.until].map(|bound| {
+        bound
+            .as_deref()
+            .map(|value| parse_time_bound(value, now))
+            .transpose()
+    });
+    let [since, until] = match bounds {
+        [Ok(since), Ok(until)] => [since, until],
+        [Err(err), _] | [_, Err(err)] => return json_error(StatusCode::BAD_REQUEST, &err),
+    };
+    match read_recent_logs(&path, &query, since, until) {
         Ok(records) => Json(serde_json::json!({ "records": records })).into_response(),
         Err(err) => json_error(StatusCode::INTERNAL_SERVER_ERROR, &err),
     }
@@ -329,28 +403,111 @@ async fn handle_logs_socket(socket: WebSocket, mut rx: broadcast::Receiver<Store
     send_task.abort();
 }
 
-async fn api_runtime_health(State(state): State<AppState>) -> impl IntoResponse {
-    forward_admin_get(state.admin_proxy.clone(), "/v1/runtime/health").await
+async fn api_runtime_health(
+    State(state): State<AppState>,
+    Query(param): Query<TargetParam>,
+) -> impl IntoResponse {
+    forward_admin_get(
+        state.admins.resolve(param.target.as_deref()),
+        "/v1/runtime/health",
+    )
+    .await
 }
 
-async fn api_runtime_metrics(State(state): State<AppState>) -> impl IntoResponse {
-    forward_admin_get(state.admin_proxy.clone(), "/v1/runtime/metrics").await
+async fn api_runtime_metrics(
+    State(state): State<AppState>,
+    Query(param): Query<TargetParam>,
+) -> impl IntoResponse {
+    forward_admin_get(
+        state.admins.resolve(param.target.as_deref()),
+        "/v1/runtime/metrics",
+    )
+    .await
 }
 
-async fn api_runtime_sessions(State(state): State<AppState>) -> impl IntoResponse {
-    forward_admin_get(state.admin_proxy.clone(), "/v1/runtime/sessions").await
+async fn api_runtime_sessions(
+    State(state): State<AppState>,
+    Query(param): Query<TargetParam>,
+) -> impl IntoResponse {
+    forward_admin_get(
+        state.admins.resolve(param.target.as_deref()),
+        "/v1/runtime/sessions",
+    )
+    .await
+}
+
+async fn api_runtime_traffic_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(param): Query<TargetParam>,
+    uri: Uri,
+) -> Response {
+    let proxy = match state.admins.resolve(param.target.as_deref()) {
+        Ok(proxy) => proxy,
+        Err(err) => return err.into_response(),
+    };
+
+    let path = format!("/v1/runtime/traffic/ws{}", traffic_filter_query(&uri));
+    let upstream = match proxy.connect_ws(&path).await {
+        Ok(upstream) => upstream,
+        Err(err) => return json_error(StatusCode::BAD_GATEWAY, &err),
+    };
+    ws.on_upgrade(move |socket| handle_traffic_socket(socket, upstream))
+}
+
+// Forwards the inspector's filters but never its own `token` or `target` to the admin server.
+fn traffic_filter_query(uri: &Uri) -> String {
+    let Some(query) = uri.query() else {
+        return String::new();
+    };
+    let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        if matches!(key.as_ref(), "session" | "method" | "tool") {
+            serializer.append_pair(&key, &value);
+        }
+    }
+    let filtered = serializer.finish();
+    if filtered.is_empty() {
+        filtered
+    } else {
+        format!("?{filtered}")
+    }
+}
+
+async fn handle_traffic_socket(socket: WebSocket, mut upstream: AdminSocket) {
+    let (mut sender, mut receiver) = socket.split();
+
+    let send_task = tokio::spawn(async move {
+        while let Some(frame) = upstream.next().await {
+            let text = match frame {
+                Ok(UpstreamMessage::Text(text)) => text.to_string(),
+                Ok(UpstreamMessage::Close(_)) | Err(_) => break,
+                Ok(_) => continue,
+            };
+            if sender.send(Message::Text(text.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    while let Some(message) = receiver.next().await {
+        match message {
+            Ok(Message::Close(_)) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    send_task.abort();
 }
 
 async fn api_discovery_search(
     State(state): State<AppState>,
+    Query(param): Query<TargetParam>,
     Json(payload): Json<Value>,
 ) -> impl IntoResponse {
-    let Some(proxy) = state.admin_proxy.as_ref() else {
-        return Json(serde_json::json!({
-            "status": "disabled",
-            "reason": "admin_not_configured",
-        }))
-        .into_response();
+    let proxy = match state.admins.resolve(param.target.as_deref()) {
+        Ok(proxy) => proxy,
+        Err(err) => return err.into_response(),
     };
 
     match proxy.post_json("/v1/discovery/search", &payload).await {
@@ -359,13 +516,10 @@ async fn api_discovery_search(
     }
 }
 
-async fn forward_admin_get(proxy: Option<AdminProxy>, path: &str) -> Response {
-    let Some(proxy) = proxy.as_ref() else {
-        return Json(serde_json::json!({
-            "status": "disabled",
-            "reason": "admin_not_configured",
-        }))
-        .into_response();
+async fn forward_admin_get(proxy: Result<AdminProxy, TargetLookupError>, path: &str) -> Response {
+    let proxy = match proxy {
+        Ok(proxy) => proxy,
+        Err(err) => return err.into_response(),
     };
 
     match proxy.get_json(path).await {
@@ -640,29 +794,24 @@ fn builtin_theme() -> ThemeDescriptor {
     }
 }
 
-fn read_recent_logs(path: &Path, query: &LogsRecentQuery) -> Result<Vec<StoredLogRecord>, String> {
+fn read_recent_logs(
+    path: &Path,
+    query: &LogsRecentQuery,
+    since: Option<u64>,
+    until: Option<u64>,
+) -> Result<Vec<StoredLogRecord>, String> {
     let lines = query.lines.unwrap_or(300).clamp(1, 5000);
-    let content = std::fs::read_to_string(path)
-        .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
-
-    let mut buffer = VecDeque::with_capacity(lines);
-    for line in content.lines() {
-        let Some(record) = parse_record(line) else {
-            continue;
-        };
-        if !matches_log_filter(&record, query) {
-            continue;
-        }
-        if buffer.len() >= lines {
-            buffer.pop_front();
-        }
-        buffer.push_back(record);
-    }
-
-    Ok(buffer.into_iter().collect())
+    recent_records(path, lines, |record| {
+        matches_log_filter(record, query, since, until)
+    })
 }
 
-fn matches_log_filter(record: &StoredLogRecord, query: &LogsRecentQuery) -> bool {
+fn matches_log_filter(
+    record: &StoredLogRecord,
+    query: &LogsRecentQuery,
+    since: Option<u64>,
+    until: Option<u64>,
+) -> bool {
     if let Some(level) = query.level.as_deref() {
         if record.level != level.to_ascii_lowercase() {
             return false;
@@ -673,6 +822,26 @@ fn matches_log_filter(record: &StoredLogRecord, query: &LogsRecentQuery) -> bool
             return false;
         }
     }
+    if let Some(instance) = query.instance.as_deref() {
+        if !record.matches_instance(instance) {
+            return false;
+        }
+    }
+    let correlation = [
+        ("session_id", &query.session),
+        ("request_id", &query.request_id),
+        ("method", &query.method),
+    ];
+    for (field, wanted) in correlation {
+        if let Some(wanted) = wanted.as_deref() {
+            if record.field(field) != Some(wanted) {
+                return false;
+            }
+        }
+    }
+    if !record.in_time_range(since, until) {
+        return false;
+    }
     if let Some(search) = query.search.as_deref() {
         let search = search.to_ascii_lowercase();
         let message = record.message.to_ascii_lowercase();
@@ -684,42 +853,13 @@ fn matches_log_filter(record: &StoredLogRecord, query: &LogsRecentQuery) -> bool
 }
 
 async fn spawn_log_tailer(path: PathBuf, sender: broadcast::Sender<StoredLogRecord>) {
-    let mut offset = match std::fs::metadata(&path) {
-        Ok(meta) => meta.len(),
-        Err(_) => 0,
-    };
+    let mut follower = LogFollower::from_end(&path);
 
     loop {
         tokio::time::sleep(Duration::from_millis(400)).await;
 
-        let current_len = match std::fs::metadata(&path) {
-            Ok(meta) => meta.len(),
-            Err(_) => continue,
-        };
-        if current_len < offset {
-            offset = 0;
-        }
-
-        let mut file = match File::open(&path) {
-            Ok(file) => file,
-            Err(_) => continue,
-        };
-        if file.seek(SeekFrom::Start(offset)).is_err() {
-            continue;
-        }
-
-        let mut text = String::new();
-        if file.read_to_string(&mut text).is_err() {
-            continue;
-        }
-
-        match file.seek(SeekFrom::End(0)) {
-            Ok(next_offset) => offset = next_offset,
-            Err(_) => continue,
-        }
-
-        for line in text.lines() {
-            if let Some(record) = parse_record(line) {
+        for line in follower.read_new_lines() {
+            if let Some(record) = parse_record(&line) {
                 let _ = sender.send(record);
             }
         }
@@ -823,6 +963,16 @@ mod tests {
         assert!(token_matches(&headers, &uri, "secret"));
     }
 
+    #[test]
+    fn traffic_filter_query_drops_inspector_token() {
+        let uri = Uri::from_static(
+            "/api/runtime/traffic/ws?token=secret&target=docs&session=s1&tool=echo",
+        );
+        assert_eq!(traffic_filter_query(&uri), "?session=s1&tool=echo");
+        let uri = Uri::from_static("/api/runtime/traffic/ws?token=secret");
+        assert_eq!(traffic_filter_query(&uri), "");
+    }
+
     #[test]
     fn builtin_theme_has_full_ansi_palette() {
         let theme = builtin_theme();
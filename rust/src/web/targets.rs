@@ -0,0 +1,342 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+use super::{json_error, AdminProxy, AppState};
+use crate::config::WebConfig;
+use crate::support::instance_index::{self, default_index_dir};
+
+pub(super) const DEFAULT_TARGET: &str = "default";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum TargetSource {
+    Config,
+    InstanceIndex,
+}
+
+#[derive(Clone)]
+struct AdminTarget {
+    name: String,
+    source: TargetSource,
+    pid: Option<u32>,
+    proxy: AdminProxy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TargetStatus {
+    name: String,
+    base_url: String,
+    source: TargetSource,
+    pid: Option<u32>,
+    reachable: bool,
+    last_polled_utc: u64,
+    health: Option<Value>,
+    metrics: Option<Value>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct TargetParam {
+    pub(super) target: Option<String>,
+}
+
+pub(super) enum TargetLookupError {
+    NotConfigured,
+    Unknown(String),
+    Ambiguous,
+}
+
+impl TargetLookupError {
+    pub(super) fn into_response(self) -> Response {
+        match self {
+            Self::NotConfigured => Json(serde_json::json!({
+                "status": "disabled",
+                "reason": "admin_not_configured",
+            }))
+            .into_response(),
+            Self::Unknown(name) => json_error(
+                StatusCode::NOT_FOUND,
+                &format!("Unknown admin target '{name}'"),
+            ),
+            Self::Ambiguous => json_error(
+                StatusCode::BAD_REQUEST,
+                "Several admin targets are known; pass `target`",
+            ),
+        }
+    }
+}
+
+/// Admin endpoints the inspector can reach: configured targets plus gateways in the
+/// local instance index, with the latest health and metrics polled from each.
+#[derive(Clone)]
+pub(super) struct AdminTargets {
+    configured: Vec<AdminTarget>,
+    index_dir: Option<PathBuf>,
+    token: Option<String>,
+    statuses: Arc<RwLock<BTreeMap<String, TargetStatus>>>,
+}
+
+impl AdminTargets {
+    pub(super) fn new(config: &WebConfig) -> Self {
+        let mut configured = Vec::new();
+        if let Some(base_url) = config.admin_base_url.clone() {
+            configured.push(AdminTarget {
+                name: DEFAULT_TARGET.to_string(),
+                source: TargetSource::Config,
+                pid: None,
+                proxy: AdminProxy::new(base_url, config.admin_token.clone()),
+            });
+        }
+        for spec in &config.admin_targets {
+            configured.push(AdminTarget {
+                name: spec.name.clone(),
+                source: TargetSource::Config,
+                pid: None,
+                proxy: AdminProxy::new(spec.base_url.clone(), config.admin_token.clone()),
+            });
+        }
+
+        let index_dir = if config.no_instance_index {
+            None
+        } else {
+            Some(
+                config
+                    .instance_dir
+                    .clone()
+                    .unwrap_or_else(default_index_dir),
+            )
+        };
+
+        Self {
+            configured,
+            index_dir,
+            token: config.admin_token.clone(),
+            statuses: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+
+    fn current(&self) -> Vec<AdminTarget> {
+        let mut targets = self.configured.clone();
+        let Some(dir) = self.index_dir.as_deref() else {
+            return targets;
+        };
+
+        for entry in instance_index::list(dir) {
+            let base_url = entry.admin_url.trim_end_matches('/');
+            // A gateway that is also configured explicitly keeps its configured name.
+            if targets
+                .iter()
+                .any(|target| target.proxy.base_url == base_url)
+            {
+                continue;
+            }
+            let mut name = entry.label();
+            if targets.iter().any(|target| target.name == name) {
+                name = format!("{name}-{}", entry.pid);
+            }
+            targets.push(AdminTarget {
+                name,
+                source: TargetSource::InstanceIndex,
+                pid: Some(entry.pid),
+                proxy: AdminProxy::new(entry.admin_url, self.token.clone()),
+            });
+        }
+        targets
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.current().is_empty()
+    }
+
+    pub(super) fn resolve(&self, name: Option<&str>) -> Result<AdminProxy, TargetLookupError> {
+        let targets = self.current();
+        if targets.is_empty() {
+            return Err(TargetLookupError::NotConfigured);
+        }
+
+        let target = match name {
+            Some(name) => targets.into_iter().find(|target| target.name == name),
+            None if targets.len() == 1 => targets.into_iter().next(),
+            None => {
+                let default = targets
+                    .into_iter()
+                    .find(|target| target.name == DEFAULT_TARGET);
+                return default
+                    .map(|target| target.proxy)
+                    .ok_or(TargetLookupError::Ambiguous);
+            }
+        };
+        target
+            .map(|target| target.proxy)
+            .ok_or_else(|| TargetLookupError::Unknown(name.unwrap_or_default().to_string()))
+    }
+
+    async fn poll_once(&self) {
+        let targets = self.current();
+        let polls = targets.into_iter().map(|target| async move {
+            let health = target.proxy.get_json("/v1/runtime/health").await;
+            let metrics = target.proxy.get_json("/v1/runtime/metrics").await;
+            let error = health.as_ref().err().or(metrics.as_ref().err()).cloned();
+            TargetStatus {
+                name: target.name,
+                base_url: target.proxy.base_url.clone(),
+                source: target.source,
+                pid: target.pid,
+                reachable: health.is_ok(),
+                last_polled_utc: unix_timestamp_secs(),
+                health: health.ok(),
+                metrics: metrics.ok(),
+                error,
+            }
+        });
+        let polled = futures::future::join_all(polls).await;
+
+        let mut statuses = self.statuses.write().await;
+        *statuses = polled
+            .into_iter()
+            .map(|status| (status.name.clone(), status))
+            .collect();
+    }
+
+    pub(super) async fn spawn_poller(self, interval: Duration) {
+        loop {
+            self.poll_once().await;
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+fn unix_timestamp_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+pub(super) async fn api_targets(State(state): State<AppState>) -> impl IntoResponse {
+    let statuses = state.admins.statuses.read().await;
+    let targets: Vec<&TargetStatus> = statuses.values().collect();
+    Json(serde_json::json!({
+        "total": targets.len(),
+        "reachable": targets.iter().filter(|status| status.reachable).count(),
+        "targets": targets,
+    }))
+}
+
+pub(super) async fn api_target_detail(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Response {
+    let proxy = match state.admins.resolve(Some(&name)) {
+        Ok(proxy) => proxy,
+        Err(err) => return err.into_response(),
+    };
+
+    let status = state.admins.statuses.read().await.get(&name).cloned();
+    let sessions = proxy.get_json("/v1/runtime/sessions").await;
+    let queues = proxy.get_json("/v1/runtime/queues").await;
+    let errors: Vec<&String> = [sessions.as_ref().err(), queues.as_ref().err()]
+        .into_iter()
+        .flatten()
+        .collect();
+    Json(serde_json::json!({
+        "status": status,
+        "sessions": sessions.as_ref().ok(),
+        "queues": queues.as_ref().ok(),
+        "errors": errors,
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AdminTargetSpec, LogLevel};
+
+    fn web_config(admin_base_url: Option<&str>, targets: &[(&str, &str)]) -> WebConfig {
+        WebConfig {
+            host: "127.0.0.1".to_string(),
+            port: 5173,
+            log_file: None,
+            admin_base_url: admin_base_url.map(str::to_string),
+            admin_token: None,
+            admin_targets: targets
+                .iter()
+                .map(|(name, base_url)| AdminTargetSpec {
+                    name: name.to_string(),
+                    base_url: base_url.to_string(),
+                })
+                .collect(),
+            instance_dir: None,
+            no_instance_index: true,
+            poll_interval_seconds: 5,
+            auth_token: None,
+            tool_endpoint: None,
+            tool_protocol: None,
+            tool_headers: Default::default(),
+            registry_path: None,
+            theme_catalog_url: String::new(),
+            theme_cache_ttl_seconds: 60,
+            theme_cache_file: None,
+            no_open_browser: true,
+            log_level: LogLevel::Info,
+        }
+    }
+
+    #[test]
+    fn resolve_picks_the_only_target_or_requires_a_name() {
+        let single = AdminTargets::new(&web_config(None, &[("docs", "http://127.0.0.1:9102")]));
+        assert_eq!(
+            single.resolve(None).ok().map(|proxy| proxy.base_url),
+            Some("http://127.0.0.1:9102".to_string())
+        );
+
+        let several = AdminTargets::new(&web_config(
+            None,
+            &[
+                ("docs", "http://127.0.0.1:9102"),
+                ("search", "http://127.0.0.1:9103"),
+            ],
+        ));
+        assert!(matches!(
+            several.resolve(None),
+            Err(TargetLookupError::Ambiguous)
+        ));
+        assert!(matches!(
+            several.resolve(Some("nope")),
+            Err(TargetLookupError::Unknown(_))
+        ));
+        assert_eq!(
+            several
+                .resolve(Some("search"))
+                .ok()
+                .map(|proxy| proxy.base_url),
+            Some("http://127.0.0.1:9103".to_string())
+        );
+
+        let with_default = AdminTargets::new(&web_config(
+            Some("http://127.0.0.1:9101"),
+            &[("docs", "http://127.0.0.1:9102")],
+        ));
+        assert_eq!(
+            with_default.resolve(None).ok().map(|proxy| proxy.base_url),
+            Some("http://127.0.0.1:9101".to_string())
+        );
+
+        let none = AdminTargets::new(&web_config(None, &[]));
+        assert!(matches!(
+            none.resolve(None),
+            Err(TargetLookupError::NotConfigured)
+        ));
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use clap::ValueEnum;
+use mcpway::tool_api::{apply_defaults, ToolClient, ToolClientBuilder, Transport};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use super::{json_error, AppState};
+use crate::config::{ConnectProtocol, WebConfig};
+use crate::connect::infer_protocol;
+use crate::discovery::registry::{resolve_server, ResolvedImportedServer};
+use crate::support::redact::redactor;
+use crate::support::secrets::resolve_secret_map;
+use crate::types::HeadersMap;
+
+const MAX_HISTORY: usize = 500;
+
+#[derive(Clone)]
+pub(super) struct ToolPlayground {
+    configured: Option<ToolTarget>,
+    registry_path: Option<PathBuf>,
+    clients: Arc<Mutex<HashMap<String, ToolClient>>>,
+    history: Arc<Mutex<VecDeque<ToolCallRecord>>>,
+    next_call_id: Arc<AtomicU64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ToolTarget {
+    server: Option<String>,
+    endpoint: String,
+    protocol: &'static str,
+    #[serde(skip)]
+    transport: Transport,
+    #[serde(skip)]
+    headers: HeadersMap,
+}
+
+impl ToolTarget {
+    fn cache_key(&self) -> String {
+        format!(
+            "{}|{}|{}",
+            self.server.as_deref().unwrap_or_default(),
+            self.protocol,
+            self.endpoint
+        )
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct TargetQuery {
+    server: Option<String>,
+    endpoint: Option<String>,
+    protocol: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct ToolCallRequest {
+    #[serde(flatten)]
+    target: TargetQuery,
+    name: String,
+    #[serde(default = "empty_arguments")]
+    arguments: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolEntry {
+    name: String,
+    description: Option<String>,
+    input_schema: Value,
+    // Arguments pre-filled from the schema defaults, used to seed the argument form.
+    form_defaults: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ToolCallRecord {
+    id: u64,
+    ts_utc_ms: u64,
+    target: ToolTarget,
+    tool: String,
+    arguments: Value,
+    duration_ms: f64,
+    ok: bool,
+    is_error: bool,
+    result: Option<Value>,
+    rendered: Option<String>,
+    error: Option<String>,
+}
+
+impl ToolPlayground {
+    pub(super) fn new(config: &WebConfig) -> Result<Self, String> {
+        let configured = match config.tool_endpoint.as_deref() {
+            Some(endpoint) => {
+                let protocol = match config.tool_protocol {
+                    Some(protocol) => protocol,
+                    None => infer_protocol(endpoint)?,
+                };
+                Some(build_target(
+                    None,
+                    endpoint.to_string(),
+                    protocol,
+                    config.tool_headers.clone(),
+                ))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            configured,
+            registry_path: config.registry_path.clone(),
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            history: Arc::new(Mutex::new(VecDeque::new())),
+            next_call_id: Arc::new(AtomicU64::new(1)),
+        })
+    }
+
+    pub(super) fn is_configured(&self) -> bool {
+        self.configured.is_some()
+    }
+
+    async fn resolve_target(&self, query: &TargetQuery) -> Result<ToolTarget, String> {
+        let protocol = query
+            .protocol
+            .as_deref()
+            .map(|raw| {
+                ConnectProtocol::from_str(raw, true)
+                    .map_err(|_| format!("Unsupported protocol '{raw}'"))
+            })
+            .transpose()?;
+
+        if let Some(endpoint) = query.endpoint.as_deref() {
+            let protocol = match protocol {
+                Some(protocol) => protocol,
+                None => infer_protocol(endpoint)?,
+            };
+            return Ok(build_target(
+                None,
+                endpoint.to_string(),
+                protocol,
+                HeadersMap::new(),
+            ));
+        }
+
+        if let Some(server) = query.server.as_deref() {
+            return match resolve_server(server, self.registry_path.as_deref())? {
+                ResolvedImportedServer::Remote {
+                    name,
+                    endpoint,
+                    protocol: registry_protocol,
+                    headers,
+                } => {
+                    let headers = resolve_secret_map(&headers, "header").await?;
+                    Ok(build_target(
+                        Some(name),
+                        endpoint,
+                        protocol.unwrap_or(registry_protocol),
+                        headers,
+                    ))
+                }
+                ResolvedImportedServer::Stdio { name, .. } => Err(format!(
+                    "Server '{name}' is a stdio server; run it behind a gateway and pass its endpoint"
+                )),
+            };
+        }
+
+        self.configured.clone().ok_or_else(|| {
+            "No tool target: pass `endpoint` or `server`, or start with --tool-endpoint".to_string()
+        })
+    }
+
+    async fn client_for(&self, target: &ToolTarget) -> Result<ToolClient, String> {
+        let mut clients = self.clients.lock().await;
+        if let Some(client) = clients.get(&target.cache_key()) {
+            return Ok(client.clone());
+        }
+
+        let client = ToolClientBuilder::new(target.endpoint.clone(), target.transport)
+            .headers(target.headers.clone())
+            .build()
+            .map_err(|err| err.to_string())?;
+        clients.insert(target.cache_key(), client.clone());
+        Ok(client)
+    }
+
+    // A failed connection leaves the client uninitialized; drop it so the next call reconnects.
+    async fn forget_client(&self, target: &ToolTarget) {
+        self.clients.lock().await.remove(&target.cache_key());
+    }
+
+    async fn list_tools(&self, target: &ToolTarget) -> Result<Vec<ToolEntry>, String> {
+        let client = self.client_for(target).await?;
+        if let Err(err) = client.refresh_tools().await {
+            self.forget_client(target).await;
+            return Err(err.to_string());
+        }
+
+        Ok(client
+            .tools()
+            .list()
+            .await
+            .into_iter()
+            .map(|tool| {
+                let mut form_defaults = empty_arguments();
+                apply_defaults(&tool.input_schema, &mut form_defaults);
+                ToolEntry {
+                    name: tool.name,
+                    description: tool.description,
+                    input_schema: tool.input_schema,
+                    form_defaults,
+                }
+            })
+            .collect())
+    }
+
+    async fn call_tool(
+        &self,
+        target: ToolTarget,
+        name: String,
+        arguments: Value,
+    ) -> ToolCallRecord {
+        let started = Instant::now();
+        let outcome = match self.client_for(&target).await {
+            Ok(client) => client
+                .call_by_name(&name, arguments.clone())
+                .await
+                .map_err(|err| err.to_string()),
+            Err(err) => Err(err),
+        };
+        let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        if outcome.is_err() {
+            self.forget_client(&target).await;
+        }
+
+        let mut arguments = arguments;
+        redactor().redact_json(&mut arguments);
+        let mut record = ToolCallRecord {
+            id: self.next_call_id.fetch_add(1, Ordering::Relaxed),
+            ts_utc_ms: unix_timestamp_millis(),
+            target,
+            tool: name,
+            arguments,
+            duration_ms,
+            ok: outcome.is_ok(),
+            is_error: false,
+            result: None,
+            rendered: None,
+            error: None,
+        };
+        match outcome {
+            Ok(mut response) => {
+                redactor().redact_json(&mut response);
+                let result = response.get("result").unwrap_or(&Value::Null);
+                record.is_error = result
+                    .get("isError")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                record.rendered = Some(render_tool_result(result));
+                record.result = Some(response);
+            }
+            Err(err) => record.error = Some(redactor().redact_text(&err).into_owned()),
+        }
+
+        let mut history = self.history.lock().await;
+        if history.len() >= MAX_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(record.clone());
+        record
+    }
+
+    async fn history(&self) -> Vec<ToolCallRecord> {
+        self.history.lock().await.iter().cloned().collect()
+    }
+}
+
+fn build_target(
+    server: Option<String>,
+    endpoint: String,
+    protocol: ConnectProtocol,
+    headers: HeadersMap,
+) -> ToolTarget {
+    let transport = match protocol {
+        ConnectProtocol::Sse => Transport::Sse,
+        ConnectProtocol::Ws => Transport::Ws,
+        ConnectProtocol::StreamableHttp => Transport::StreamableHttp,
+        ConnectProtocol::Grpc => Transport::Grpc,
+    };
+    ToolTarget {
+        server,
+        endpoint,
+        protocol: protocol.as_str(),
+        transport,
+        headers,
+    }
+}
+
+fn empty_arguments() -> Value {
+    Value::Object(Default::default())
+}
+
+// Flattens MCP content blocks into the text shown next to the raw result.
+fn render_tool_result(result: &Value) -> String {
+    let Some(content) = result.get("content").and_then(Value::as_array) else {
+        return serde_json::to_string_pretty(result).unwrap_or_default();
+    };
+
+    let mut parts = Vec::with_capacity(content.len());
+    for block in content {
+        let kind = block
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let rendered = match kind {
+            "text" => block
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            "image" | "audio" => format!(
+                "[{kind} {}]",
+                block
+                    .get("mimeType")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown")
+            ),
+            "resource" => {
+                let resource = block.get("resource").unwrap_or(&Value::Null);
+                match resource.get("text").and_then(Value::as_str) {
+                    Some(text) => text.to_string(),
+                    None => format!(
+                        "[resource {}]",
+                        resource
+                            .get("uri")
+                            .and_then(Value::as_str)
+                            .unwrap_or("unknown")
+                    ),
+                }
+            }
+            "resource_link" => format!(
+                "[resource {}]",
+                block
+                    .get("uri")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown")
+            ),
+            _ => serde_json::to_string_pretty(block).unwrap_or_default(),
+        };
+        parts.push(rendered);
+    }
+    parts.join("\n")
+}
+
+fn unix_timestamp_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub(super) async fn api_tools_list(
+    State(state): State<AppState>,
+    Query(query): Query<TargetQuery>,
+) -> Response {
+    let target = match state.tools.resolve_target(&query).await {
+        Ok(target) => target,
+        Err(err) => return json_error(StatusCode::BAD_REQUEST, &err),
+    };
+
+    match state.tools.list_tools(&target).await {
+        Ok(tools) => Json(serde_json::json!({
+            "target": target,
+            "tools": tools,
+        }))
+        .into_response(),
+        Err(err) => json_error(StatusCode::BAD_GATEWAY, &err),
+    }
+}
+
+pub(super) async fn api_tools_call(
+    State(state): State<AppState>,
+    Json(request): Json<ToolCallRequest>,
+) -> Response {
+    let target = match state.tools.resolve_target(&request.target).await {
+        Ok(target) => target,
+        Err(err) => return json_error(StatusCode::BAD_REQUEST, &err),
+    };
+
+    let record = state
+        .tools
+        .call_tool(target, request.name, request.arguments)
+        .await;
+    Json(record).into_response()
+}
+
+pub(super) async fn api_tools_history(State(state): State<AppState>) -> impl IntoResponse {
+    Json(serde_json::json!({ "records": state.tools.history().await }))
+}
+
+pub(super) async fn api_tools_history_export(State(state): State<AppState>) -> impl IntoResponse {
+    let body = serde_json::to_string_pretty(&serde_json::json!({
+        "exported_at_utc_ms": unix_timestamp_millis(),
+        "records": state.tools.history().await,
+    }))
+    .unwrap_or_default();
+    (
+        StatusCode::OK,
+        [
+            (axum::http::header::CONTENT_TYPE, "application/json"),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                "attachment; filename=\"mcpway-tool-history.json\"",
+            ),
+        ],
+        body,
+    )
+}
+
+pub(super) async fn api_tools_history_clear(State(state): State<AppState>) -> impl IntoResponse {
+    state.tools.history.lock().await.clear();
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_tool_result_joins_content_blocks() {
+        let result = serde_json::json!({
+            "content": [
+                {"type": "text", "text": "hello"},
+                {"type": "image", "mimeType": "image/png", "data": "AAAA"},
+                {"type": "resource", "resource": {"uri": "file:///tmp/a.txt"}}
+            ]
+        });
+        assert_eq!(
+            render_tool_result(&result),
+            "hello\n[image image/png]\n[resource file:///tmp/a.txt]"
+        );
+    }
+
+    #[tokio::test]
+    async fn explicit_endpoint_overrides_configured_target() {
+        let playground = ToolPlayground {
+            configured: Some(build_target(
+                None,
+                "http://127.0.0.1:8000/mcp".to_string(),
+                ConnectProtocol::StreamableHttp,
+                HeadersMap::new(),
+            )),
+            registry_path: None,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            history: Arc::new(Mutex::new(VecDeque::new())),
+            next_call_id: Arc::new(AtomicU64::new(1)),
+        };
+
+        let configured = playground
+            .resolve_target(&TargetQuery::default())
+            .await
+            .expect("configured target");
+        assert_eq!(configured.endpoint, "http://127.0.0.1:8000/mcp");
+
+        let explicit = playground
+            .resolve_target(&TargetQuery {
+                endpoint: Some("ws://127.0.0.1:9000/message".to_string()),
+                ..TargetQuery::default()
+            })
+            .await
+            .expect("explicit target");
+        assert_eq!(explicit.protocol, "ws");
+    }
+}
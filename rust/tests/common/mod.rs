@@ -16,10 +16,20 @@ pub fn find_free_port() -> u16 {
 }
 
 pub async fn spawn_mcpway(args: &[&str], pipe_stdin: bool, pipe_stdout: bool) -> Child {
+    spawn_mcpway_with_env(args, &[], pipe_stdin, pipe_stdout).await
+}
+
+pub async fn spawn_mcpway_with_env(
+    args: &[&str],
+    envs: &[(&str, &str)],
+    pipe_stdin: bool,
+    pipe_stdout: bool,
+) -> Child {
     let exe = mcpway_exe_path();
 
     let mut cmd = Command::new(exe);
     cmd.args(args)
+        .envs(envs.iter().copied())
         .env_remove("PORT")
         .stdin(if pipe_stdin {
             Stdio::piped()
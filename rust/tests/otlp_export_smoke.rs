@@ -0,0 +1,113 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::http::{HeaderMap, Uri};
+use axum::Router;
+use common::{
+    find_free_port, initialize_request, spawn_mcpway_with_env, stop_child, wait_for_condition,
+    wait_for_http_status,
+};
+
+type Received = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+async fn spawn_otlp_receiver() -> (u16, Received) {
+    let received: Received = Arc::new(Mutex::new(Vec::new()));
+    let captured = received.clone();
+    let router = Router::new().fallback(move |uri: Uri, headers: HeaderMap| {
+        let captured = captured.clone();
+        async move {
+            let token = headers
+                .get("x-otlp-token")
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            captured
+                .lock()
+                .expect("receiver lock poisoned")
+                .push((uri.path().to_string(), token));
+            ""
+        }
+    });
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+        .await
+        .expect("failed to bind OTLP receiver");
+    let port = listener
+        .local_addr()
+        .expect("failed to read receiver address")
+        .port();
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, router).await;
+    });
+    (port, received)
+}
+
+#[tokio::test]
+async fn stdio_to_streamable_http_exports_traces_and_metrics_over_otlp_http() {
+    let (otlp_port, received) = spawn_otlp_receiver().await;
+    let endpoint = format!("http://127.0.0.1:{otlp_port}");
+    let log_dir = std::env::temp_dir().join(format!("mcpway-otlp-test-{otlp_port}"));
+    let log_path = log_dir.join("mcpway.log");
+    let log_path_str = log_path.to_string_lossy().to_string();
+
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let mut child = spawn_mcpway_with_env(
+        &[
+            "--stdio",
+            "cat",
+            "--output-transport",
+            "streamable-http",
+            "--stateful",
+            "--port",
+            &port_str,
+            "--streamable-http-path",
+            "/mcp",
+            "--health-endpoint",
+            "/healthz",
+        ],
+        &[
+            ("OTEL_EXPORTER_OTLP_ENDPOINT", &endpoint),
+            ("OTEL_EXPORTER_OTLP_PROTOCOL", "http/protobuf"),
+            ("OTEL_EXPORTER_OTLP_HEADERS", "x-otlp-token=secret%20value"),
+            ("OTEL_METRIC_EXPORT_INTERVAL", "200"),
+            ("OTEL_BSP_SCHEDULE_DELAY", "200"),
+            ("OTEL_BLRP_SCHEDULE_DELAY", "200"),
+            ("MCPWAY_LOG_PATH", &log_path_str),
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let response = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{port}/mcp"))
+        .json(&initialize_request("otlp-export"))
+        .send()
+        .await
+        .expect("failed to POST initialize");
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    wait_for_condition(Duration::from_secs(10), || {
+        let received = received.clone();
+        async move {
+            let received = received.lock().expect("receiver lock poisoned");
+            ["/v1/traces", "/v1/metrics"].iter().all(|path| {
+                received.iter().any(|(received_path, token)| {
+                    received_path == path && token.as_deref() == Some("secret value")
+                })
+            })
+        }
+    })
+    .await;
+
+    stop_child(&mut child).await;
+    let _ = std::fs::remove_dir_all(&log_dir);
+}
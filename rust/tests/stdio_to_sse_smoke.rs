@@ -3,6 +3,7 @@ mod common;
 use std::time::Duration;
 
 use eventsource_stream::Eventsource;
+use futures::stream::BoxStream;
 use futures::StreamExt;
 
 use common::{find_free_port, initialize_request, spawn_mcpway, stop_child, wait_for_http_status};
@@ -99,3 +100,369 @@ async fn stdio_to_sse_roundtrip_smoke() {
 
     stop_child(&mut child).await;
 }
+
+type EventStream = BoxStream<
+    'static,
+    Result<eventsource_stream::Event, eventsource_stream::EventStreamError<reqwest::Error>>,
+>;
+
+// Opens an SSE session and returns its event stream and message endpoint.
+async fn open_session(client: &reqwest::Client, port: u16) -> (EventStream, String) {
+    let response = client
+        .get(format!("http://127.0.0.1:{port}/sse"))
+        .send()
+        .await
+        .expect("failed to connect to SSE endpoint");
+    let mut stream: EventStream = response.bytes_stream().eventsource().boxed();
+    let endpoint = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            match stream.next().await {
+                Some(Ok(event)) if event.event == "endpoint" => break event.data,
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => panic!("SSE stream error: {err}"),
+                None => panic!("SSE stream ended before endpoint event"),
+            }
+        }
+    })
+    .await
+    .expect("timed out waiting for endpoint event");
+    (stream, format!("http://127.0.0.1:{port}{endpoint}"))
+}
+
+async fn next_payload(stream: &mut EventStream, timeout: Duration) -> Option<serde_json::Value> {
+    tokio::time::timeout(timeout, async {
+        loop {
+            match stream.next().await {
+                Some(Ok(event)) if !event.data.trim().is_empty() && event.event != "endpoint" => {
+                    break serde_json::from_str(&event.data).expect("SSE data was not valid JSON");
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => panic!("SSE stream error: {err}"),
+                None => panic!("SSE stream ended unexpectedly"),
+            }
+        }
+    })
+    .await
+    .ok()
+}
+
+#[tokio::test]
+async fn stdio_to_sse_routes_colliding_request_ids_to_their_own_session() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            "cat",
+            "--port",
+            &port_str,
+            "--sse-path",
+            "/sse",
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let client = reqwest::Client::new();
+    let (mut first, first_endpoint) = open_session(&client, port).await;
+    let (mut second, second_endpoint) = open_session(&client, port).await;
+
+    for (endpoint, name) in [(&first_endpoint, "first"), (&second_endpoint, "second")] {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": name}
+        });
+        let response = client
+            .post(endpoint.as_str())
+            .json(&request)
+            .send()
+            .await
+            .expect("failed to post request");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    for (stream, name) in [(&mut first, "first"), (&mut second, "second")] {
+        let payload = next_payload(stream, Duration::from_secs(5))
+            .await
+            .expect("session did not receive its own message");
+        assert_eq!(payload["id"], 1);
+        assert_eq!(payload["params"]["name"], name);
+        assert!(
+            next_payload(stream, Duration::from_millis(300))
+                .await
+                .is_none(),
+            "{name} session received another session's message"
+        );
+    }
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_sse_counts_in_flight_requests_per_session_when_ids_collide() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let admin_port = find_free_port();
+    let admin_port_str = admin_port.to_string();
+
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            r#"sed -u 's/"method":"ping"/"result":{}/'"#,
+            "--port",
+            &port_str,
+            "--sse-path",
+            "/sse",
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--runtime-admin-port",
+            &admin_port_str,
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{admin_port}/v1/runtime/health"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let client = reqwest::Client::new();
+    let (mut waiting, waiting_endpoint) = open_session(&client, port).await;
+    let (mut answered, answered_endpoint) = open_session(&client, port).await;
+
+    // The `sed` child echoes the unanswered request back as a request and answers the ping.
+    client
+        .post(waiting_endpoint.as_str())
+        .json(&serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call"}))
+        .send()
+        .await
+        .expect("failed to post request");
+    next_payload(&mut waiting, Duration::from_secs(5))
+        .await
+        .expect("waiting session did not see its echoed request");
+    client
+        .post(answered_endpoint.as_str())
+        .json(&serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "ping"}))
+        .send()
+        .await
+        .expect("failed to post ping");
+    let pong = next_payload(&mut answered, Duration::from_secs(5))
+        .await
+        .expect("answered session did not receive its response");
+    assert_eq!(pong["id"], 1);
+    assert!(pong.get("result").is_some(), "unexpected response: {pong}");
+
+    for (endpoint, expected) in [(&waiting_endpoint, 1), (&answered_endpoint, 0)] {
+        let session_id = endpoint
+            .split("sessionId=")
+            .nth(1)
+            .expect("endpoint without a session id");
+        let info: serde_json::Value = client
+            .get(format!(
+                "http://127.0.0.1:{admin_port}/v1/runtime/sessions/{session_id}"
+            ))
+            .send()
+            .await
+            .expect("failed to fetch session")
+            .json()
+            .await
+            .expect("session response was not JSON");
+        assert_eq!(info["in_flight"], expected, "unexpected session: {info}");
+    }
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_sse_blue_green_swap_answers_colliding_ids_in_every_session() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let admin_port = find_free_port();
+    let admin_port_str = admin_port.to_string();
+
+    // Echoes everything like `cat`, but answers `initialize` so the replacement child can
+    // finish its handshake.
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            r#"sed -u 's/"method":"initialize"/"result":{}/'"#,
+            "--port",
+            &port_str,
+            "--sse-path",
+            "/sse",
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--restart-strategy",
+            "blue-green",
+            "--drain-timeout-ms",
+            "300",
+            "--runtime-admin-port",
+            &admin_port_str,
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{admin_port}/v1/runtime/health"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let client = reqwest::Client::new();
+    let mut sessions = [
+        open_session(&client, port).await,
+        open_session(&client, port).await,
+    ];
+
+    // Echoed requests never get a response, so both are still in flight at the swap.
+    for (stream, endpoint) in sessions.iter_mut() {
+        client
+            .post(endpoint.as_str())
+            .json(&serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "tools/call"}))
+            .send()
+            .await
+            .expect("failed to post request");
+        let echoed = next_payload(stream, Duration::from_secs(5))
+            .await
+            .expect("timed out waiting for echoed request");
+        assert_eq!(echoed["id"], 1);
+    }
+
+    let applied: serde_json::Value = client
+        .post(format!("http://127.0.0.1:{admin_port}/v1/runtime/defaults"))
+        .json(&serde_json::json!({"env": {"MCPWAY_TEST_SWAP": "1"}}))
+        .send()
+        .await
+        .expect("failed to post runtime defaults")
+        .json()
+        .await
+        .expect("runtime update response was not JSON");
+    assert_eq!(applied["status"], "ok", "unexpected response: {applied}");
+    let swap = &applied["swaps"][0];
+    assert_eq!(swap["in_flight"], 2, "unexpected swap: {swap}");
+    assert_eq!(swap["abandoned"], 2, "unexpected swap: {swap}");
+
+    // The new child echoes the gateway's `notifications/initialized` to every session too.
+    for (stream, _) in sessions.iter_mut() {
+        let mut abandoned = next_payload(stream, Duration::from_secs(5))
+            .await
+            .expect("timed out waiting for the abandoned request's error");
+        if abandoned["method"] == "notifications/initialized" {
+            abandoned = next_payload(stream, Duration::from_secs(5))
+                .await
+                .expect("timed out waiting for the abandoned request's error");
+        }
+        assert_eq!(abandoned["id"], 1, "unexpected message: {abandoned}");
+        assert_eq!(abandoned["error"]["code"], -32603);
+    }
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_sse_managed_initialize_answers_only_the_joining_session() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+
+    // Turns an `initialize` request into its answer; the gateway initializes the child itself.
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            r#"sed -u 's/"method":"initialize"/"result":{"serverInfo":{"name":"sed"}}/'"#,
+            "--port",
+            &port_str,
+            "--sse-path",
+            "/sse",
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--managed-initialize",
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let client = reqwest::Client::new();
+    let (mut first, first_endpoint) = open_session(&client, port).await;
+    let (mut second, second_endpoint) = open_session(&client, port).await;
+
+    for (stream, endpoint, id) in [
+        (&mut first, &first_endpoint, "first-init"),
+        (&mut second, &second_endpoint, "second-init"),
+    ] {
+        client
+            .post(endpoint.as_str())
+            .json(&initialize_request(id))
+            .send()
+            .await
+            .expect("failed to post initialize");
+        let payload = next_payload(stream, Duration::from_secs(5))
+            .await
+            .expect("timed out waiting for initialize response");
+        assert_eq!(payload["id"], id);
+        assert_eq!(payload["result"]["serverInfo"]["name"], "sed");
+    }
+    assert!(
+        next_payload(&mut first, Duration::from_millis(300))
+            .await
+            .is_none(),
+        "an initialized session received the late joiner's initialize answer"
+    );
+
+    stop_child(&mut child).await;
+}
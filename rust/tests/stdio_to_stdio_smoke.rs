@@ -102,3 +102,230 @@ async fn stdio_to_stdio_logs_invalid_json_and_continues() {
 
     stop_child(&mut child).await;
 }
+
+#[tokio::test]
+async fn stdio_to_stdio_content_length_framing_roundtrip() {
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            "cat",
+            "--output-transport",
+            "stdio",
+            "--child-framing",
+            "content-length",
+            "--log-level",
+            "none",
+        ],
+        true,
+        true,
+    )
+    .await;
+
+    // `cat` echoes the Content-Length framed request, so the gateway must decode it back.
+    let request = initialize_request("stdio-content-length");
+    let stdin = child.stdin.as_mut().expect("stdin was not piped");
+    stdin
+        .write_all(format!("{request}\n").as_bytes())
+        .await
+        .expect("failed to write initialize request to stdin");
+    stdin.flush().await.expect("failed to flush stdin");
+
+    let stdout = child.stdout.take().expect("stdout was not piped");
+    let mut lines = BufReader::new(stdout).lines();
+    let line = tokio::time::timeout(Duration::from_secs(5), lines.next_line())
+        .await
+        .expect("timed out waiting for stdout response")
+        .expect("failed reading stdout line")
+        .expect("stdout closed before response line");
+
+    let payload: serde_json::Value =
+        serde_json::from_str(&line).expect("stdout line was not valid JSON");
+    assert_eq!(
+        payload.get("id"),
+        Some(&serde_json::json!("stdio-content-length"))
+    );
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_stdio_discards_oversize_child_frames_and_continues() {
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            "cat",
+            "--output-transport",
+            "stdio",
+            "--max-frame-bytes",
+            "512",
+            "--log-level",
+            "none",
+        ],
+        true,
+        true,
+    )
+    .await;
+
+    let oversize = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/oversize",
+        "params": { "pad": "x".repeat(2048) }
+    });
+    let request = initialize_request("stdio-after-oversize");
+    let stdin = child.stdin.as_mut().expect("stdin was not piped");
+    stdin
+        .write_all(format!("{oversize}\n{request}\n").as_bytes())
+        .await
+        .expect("failed to write requests to stdin");
+    stdin.flush().await.expect("failed to flush stdin");
+
+    let stdout = child.stdout.take().expect("stdout was not piped");
+    let mut lines = BufReader::new(stdout).lines();
+    let line = tokio::time::timeout(Duration::from_secs(5), lines.next_line())
+        .await
+        .expect("timed out waiting for stdout response")
+        .expect("failed reading stdout line")
+        .expect("stdout closed before response line");
+
+    let payload: serde_json::Value =
+        serde_json::from_str(&line).expect("stdout line was not valid JSON");
+    assert_eq!(
+        payload.get("id"),
+        Some(&serde_json::json!("stdio-after-oversize"))
+    );
+
+    stop_child(&mut child).await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn stdio_to_stdio_applies_child_cwd_and_environment_controls() {
+    let cwd = std::env::temp_dir()
+        .canonicalize()
+        .expect("temp dir should resolve");
+    let cwd = cwd.to_str().expect("temp dir should be UTF-8");
+    // The child reports its working directory and environment instead of echoing.
+    let script = r#"/bin/sh -c 'read -r _; echo "{\"jsonrpc\":\"2.0\",\"id\":\"launch\",\"result\":{\"cwd\":\"$(pwd -P)\",\"home\":\"${HOME:-unset}\",\"path\":\"$PATH\"}}"; cat >/dev/null'"#;
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            script,
+            "--output-transport",
+            "stdio",
+            "--child-cwd",
+            cwd,
+            "--child-env-mode",
+            "clear",
+            "--child-path",
+            "/opt/mcp/bin",
+            "--env",
+            "PATH=/usr/bin:/bin",
+            "--log-level",
+            "none",
+        ],
+        true,
+        true,
+    )
+    .await;
+
+    let request = initialize_request("stdio-launch");
+    let stdin = child.stdin.as_mut().expect("stdin was not piped");
+    stdin
+        .write_all(format!("{request}\n").as_bytes())
+        .await
+        .expect("failed to write initialize request to stdin");
+    stdin.flush().await.expect("failed to flush stdin");
+
+    let stdout = child.stdout.take().expect("stdout was not piped");
+    let mut lines = BufReader::new(stdout).lines();
+    let line = tokio::time::timeout(Duration::from_secs(5), lines.next_line())
+        .await
+        .expect("timed out waiting for stdout response")
+        .expect("failed reading stdout line")
+        .expect("stdout closed before response line");
+
+    let payload: serde_json::Value =
+        serde_json::from_str(&line).expect("stdout line was not valid JSON");
+    let result = payload.get("result").expect("missing result");
+    assert_eq!(result.get("cwd"), Some(&serde_json::json!(cwd)));
+    assert_eq!(result.get("home"), Some(&serde_json::json!("unset")));
+    assert_eq!(
+        result.get("path"),
+        Some(&serde_json::json!("/opt/mcp/bin:/usr/bin:/bin"))
+    );
+
+    stop_child(&mut child).await;
+}
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+#[tokio::test]
+async fn stdio_to_stdio_sandbox_blocks_unlisted_paths_and_network() {
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<u8>(),
+            0usize,
+            1u32,
+        )
+    };
+    if abi < 1 {
+        eprintln!("skipping: Landlock is not available on this kernel");
+        return;
+    }
+
+    let root = std::env::temp_dir().join(format!("mcpway-sandbox-{}", std::process::id()));
+    let allowed_dir = root.join("allowed");
+    let outside = root.join("outside");
+    std::fs::create_dir_all(&allowed_dir).expect("failed to create allowed dir");
+    std::fs::create_dir_all(&outside).expect("failed to create outside dir");
+    let allowed = allowed_dir.join("notes.txt");
+    std::fs::write(&allowed, "notes").expect("failed to write allowed file");
+    let secret = outside.join("secret.txt");
+    std::fs::write(&secret, "secret").expect("failed to write secret file");
+
+    // Reports whether the allowed file, the unlisted file, /dev/null and a TCP socket are
+    // reachable from inside the sandbox, then keeps reading stdin so the child outlives its
+    // reply. File probes capture output instead of redirecting to /dev.
+    let script = format!(
+        r#"/bin/bash -c 'read -r _; probe() {{ if out=$(cat "$1" 2>&1); then echo open; else echo denied; fi; }}; allowed=$(probe {allowed}); fs=$(probe {secret}); if ( : >/dev/null ) 2>/dev/null; then dev=open; else dev=denied; fi; case "$( {{ exec 3<>/dev/tcp/127.0.0.1/9; }} 2>&1 )" in *"Permission denied"*) net=denied;; *) net=open;; esac; echo "{{\"jsonrpc\":\"2.0\",\"id\":\"sandbox\",\"result\":{{\"allowed\":\"$allowed\",\"fs\":\"$fs\",\"dev\":\"$dev\",\"net\":\"$net\"}}}}"; while read -r _; do :; done'"#,
+        allowed = allowed.display(),
+        secret = secret.display()
+    );
+    let mut args = vec!["--stdio", script.as_str(), "--output-transport", "stdio"];
+    let allowed_dir = allowed_dir.display().to_string();
+    for path in ["/usr", "/bin", "/lib", "/lib64", "/etc", allowed_dir.as_str()] {
+        args.extend(["--sandbox-ro", path]);
+    }
+    args.extend(["--sandbox-deny-network", "--log-level", "none"]);
+    let mut child = spawn_mcpway(&args, true, true).await;
+
+    let request = initialize_request("stdio-sandbox");
+    let stdin = child.stdin.as_mut().expect("stdin was not piped");
+    stdin
+        .write_all(format!("{request}\n").as_bytes())
+        .await
+        .expect("failed to write initialize request to stdin");
+    stdin.flush().await.expect("failed to flush stdin");
+
+    let stdout = child.stdout.take().expect("stdout was not piped");
+    let mut lines = BufReader::new(stdout).lines();
+    let line = tokio::time::timeout(Duration::from_secs(5), lines.next_line())
+        .await
+        .expect("timed out waiting for stdout response")
+        .expect("failed reading stdout line")
+        .expect("stdout closed before response line");
+    let _ = std::fs::remove_dir_all(&root);
+
+    let payload: serde_json::Value =
+        serde_json::from_str(&line).expect("stdout line was not valid JSON");
+    let result = payload.get("result").expect("missing result");
+    assert_eq!(result.get("allowed"), Some(&serde_json::json!("open")));
+    assert_eq!(result.get("fs"), Some(&serde_json::json!("denied")));
+    assert_eq!(result.get("dev"), Some(&serde_json::json!("open")));
+    assert_eq!(result.get("net"), Some(&serde_json::json!("denied")));
+
+    stop_child(&mut child).await;
+}
@@ -168,3 +168,63 @@ async fn stdio_to_streamable_http_stateful_smoke() {
 
     stop_child(&mut child).await;
 }
+
+#[tokio::test]
+async fn stdio_to_streamable_http_propagates_traceparent_to_child() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            "cat",
+            "--output-transport",
+            "streamable-http",
+            "--stateful",
+            "--port",
+            &port_str,
+            "--streamable-http-path",
+            "/mcp",
+            "--health-endpoint",
+            "/healthz",
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    let client = reqwest::Client::new();
+    let post_response = client
+        .post(format!("http://127.0.0.1:{port}/mcp"))
+        .header("traceparent", traceparent)
+        .json(&initialize_request("streamable-traceparent"))
+        .send()
+        .await
+        .expect("failed to POST initialize with traceparent");
+    assert_eq!(post_response.status(), reqwest::StatusCode::OK);
+
+    let echoed: serde_json::Value = post_response
+        .json()
+        .await
+        .expect("stateful POST response was not JSON");
+    assert_eq!(
+        echoed["params"]["_meta"]["traceparent"],
+        serde_json::json!(traceparent)
+    );
+    assert_eq!(
+        echoed["params"]["clientInfo"]["name"],
+        serde_json::json!("integration-test")
+    );
+
+    stop_child(&mut child).await;
+}
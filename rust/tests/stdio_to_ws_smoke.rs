@@ -70,3 +70,1152 @@ async fn stdio_to_ws_roundtrip_smoke() {
 
     stop_child(&mut child).await;
 }
+
+async fn next_text(
+    socket: &mut tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >,
+    timeout: Duration,
+) -> Option<serde_json::Value> {
+    let message = tokio::time::timeout(timeout, socket.next())
+        .await
+        .ok()?
+        .expect("websocket closed unexpectedly")
+        .expect("websocket returned error");
+    let text = message.into_text().expect("websocket message was not text");
+    Some(serde_json::from_str(&text).expect("websocket message was not valid JSON"))
+}
+
+#[tokio::test]
+async fn stdio_to_ws_per_connection_isolates_children_and_reattaches() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            "cat",
+            "--output-transport",
+            "ws",
+            "--ws-isolation",
+            "per-connection",
+            "--ws-idle-grace-ms",
+            "5000",
+            "--port",
+            &port_str,
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let url = format!("ws://127.0.0.1:{port}/message");
+    let (mut first, first_response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect first websocket");
+    let (mut second, second_response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect second websocket");
+    let first_session = first_response
+        .headers()
+        .get("Mcp-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .expect("missing session id on first connection")
+        .to_string();
+    let second_session = second_response
+        .headers()
+        .get("Mcp-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .expect("missing session id on second connection")
+        .to_string();
+    assert_ne!(first_session, second_session);
+
+    let notification = serde_json::json!({"jsonrpc": "2.0", "method": "notifications/first"});
+    first
+        .send(Message::Text(notification.to_string().into()))
+        .await
+        .expect("failed to send notification");
+    let echoed = next_text(&mut first, Duration::from_secs(5))
+        .await
+        .expect("first connection did not receive its own notification");
+    assert_eq!(echoed["method"], "notifications/first");
+    assert!(
+        next_text(&mut second, Duration::from_millis(500))
+            .await
+            .is_none(),
+        "second connection saw output from the first connection's child"
+    );
+
+    first.close(None).await.expect("failed to close websocket");
+    drop(first);
+
+    // The server may not have observed the close yet, so retry until the parked child is found.
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    let mut reattached = loop {
+        let (socket, response) = connect_async(format!("{url}?sessionId={first_session}"))
+            .await
+            .expect("failed to reconnect websocket");
+        let session = response
+            .headers()
+            .get("Mcp-Session-Id")
+            .and_then(|value| value.to_str().ok());
+        if session == Some(first_session.as_str()) {
+            break socket;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "reconnect never reattached to session {first_session}"
+        );
+        drop(socket);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    };
+    reattached
+        .send(Message::Text(
+            initialize_request("ws-reattach").to_string().into(),
+        ))
+        .await
+        .expect("failed to send initialize after reattach");
+    let payload = next_text(&mut reattached, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for reattached response");
+    assert_eq!(payload["id"], "ws-reattach");
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_ws_per_connection_close_drops_session_overrides() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let admin_port = find_free_port();
+    let admin_port_str = admin_port.to_string();
+
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            "cat",
+            "--output-transport",
+            "ws",
+            "--ws-isolation",
+            "per-connection",
+            "--port",
+            &port_str,
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--runtime-admin-port",
+            &admin_port_str,
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{admin_port}/v1/runtime/health"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let (mut socket, response) = connect_async(format!("ws://127.0.0.1:{port}/message"))
+        .await
+        .expect("failed to connect websocket");
+    let session = response
+        .headers()
+        .get("Mcp-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .expect("missing session id on connection")
+        .to_string();
+
+    let client = reqwest::Client::new();
+    let applied: serde_json::Value = client
+        .post(format!(
+            "http://127.0.0.1:{admin_port}/v1/runtime/session/{session}"
+        ))
+        .json(&serde_json::json!({"env": {"MCPWAY_TEST_SESSION": "1"}}))
+        .send()
+        .await
+        .expect("failed to post session override")
+        .json()
+        .await
+        .expect("session override response was not JSON");
+    assert_eq!(applied["status"], "ok", "unexpected response: {applied}");
+
+    let listed = |sessions: &serde_json::Value| {
+        sessions
+            .as_array()
+            .expect("sessions response was not an array")
+            .iter()
+            .any(|entry| entry["id"] == session.as_str())
+    };
+    let list_sessions = || async {
+        client
+            .get(format!("http://127.0.0.1:{admin_port}/v1/runtime/sessions"))
+            .send()
+            .await
+            .expect("failed to list sessions")
+            .json::<serde_json::Value>()
+            .await
+            .expect("sessions response was not JSON")
+    };
+    assert!(listed(&list_sessions().await));
+
+    socket.close(None).await.expect("failed to close websocket");
+    drop(socket);
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while listed(&list_sessions().await) {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "session {session} overrides outlived its connection"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_ws_config_reload_drops_headers_removed_from_the_file() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let config_path = std::env::temp_dir().join(format!("mcpway-ws-reload-{port}.toml"));
+    std::fs::write(&config_path, "[headers]\nX-File = \"file\"\n").expect("write config file");
+    let config_str = config_path.to_string_lossy().to_string();
+
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            "cat",
+            "--output-transport",
+            "ws",
+            "--port",
+            &port_str,
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--header",
+            "X-Cli: cli",
+            "--config",
+            &config_str,
+            "--watch-config",
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    let health_url = format!("http://127.0.0.1:{port}/healthz");
+    wait_for_http_status(
+        &health_url,
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+    let response = reqwest::get(health_url.as_str())
+        .await
+        .expect("failed to fetch health endpoint");
+    assert_eq!(response.headers()["x-cli"], "cli");
+    assert_eq!(response.headers()["x-file"], "file");
+
+    std::fs::write(&config_path, "[headers]\n").expect("rewrite config file");
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    loop {
+        let response = reqwest::get(health_url.as_str())
+            .await
+            .expect("failed to fetch health endpoint");
+        if !response.headers().contains_key("x-file") {
+            assert_eq!(response.headers()["x-cli"], "cli");
+            break;
+        }
+        assert!(
+            std::time::Instant::now() < deadline,
+            "header removed from the config file is still served"
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    stop_child(&mut child).await;
+    let _ = std::fs::remove_file(&config_path);
+}
+
+#[tokio::test]
+async fn stdio_to_ws_session_overrides_move_session_onto_dedicated_child() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let admin_port = find_free_port();
+    let admin_port_str = admin_port.to_string();
+
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            "cat",
+            "--output-transport",
+            "ws",
+            "--port",
+            &port_str,
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--runtime-admin-port",
+            &admin_port_str,
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{admin_port}/v1/runtime/health"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let url = format!("ws://127.0.0.1:{port}/message");
+    let (mut first, first_response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect first websocket");
+    let (mut second, _second_response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect second websocket");
+    let first_session = first_response
+        .headers()
+        .get("Mcp-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .expect("missing session id on shared connection")
+        .to_string();
+
+    let client = reqwest::Client::new();
+    let rejected: serde_json::Value = client
+        .post(format!(
+            "http://127.0.0.1:{admin_port}/v1/runtime/session/{first_session}"
+        ))
+        .json(&serde_json::json!({"headers": {"X-Session": "1"}}))
+        .send()
+        .await
+        .expect("failed to post session headers")
+        .json()
+        .await
+        .expect("session headers response was not JSON");
+    assert_eq!(
+        rejected["status"], "error",
+        "unexpected response: {rejected}"
+    );
+
+    let applied: serde_json::Value = client
+        .post(format!(
+            "http://127.0.0.1:{admin_port}/v1/runtime/session/{first_session}"
+        ))
+        .json(&serde_json::json!({"env": {"MCPWAY_TEST_SESSION": "1"}}))
+        .send()
+        .await
+        .expect("failed to post session override")
+        .json()
+        .await
+        .expect("session override response was not JSON");
+    assert_eq!(applied["status"], "ok", "unexpected response: {applied}");
+    assert_eq!(applied["restart"], true);
+
+    // Notifications from the shared `cat` child reach every client; the dedicated child must
+    // only answer the overridden session.
+    let notification = serde_json::json!({"jsonrpc": "2.0", "method": "notifications/first"});
+    first
+        .send(Message::Text(notification.to_string().into()))
+        .await
+        .expect("failed to send notification");
+    let echoed = next_text(&mut first, Duration::from_secs(5))
+        .await
+        .expect("overridden session did not receive its own notification");
+    assert_eq!(echoed["method"], "notifications/first");
+    assert!(
+        next_text(&mut second, Duration::from_millis(500))
+            .await
+            .is_none(),
+        "second connection saw output from the dedicated child"
+    );
+
+    let sessions: serde_json::Value = client
+        .get(format!("http://127.0.0.1:{admin_port}/v1/runtime/sessions"))
+        .send()
+        .await
+        .expect("failed to list sessions")
+        .json()
+        .await
+        .expect("sessions response was not JSON");
+    let listed = sessions
+        .as_array()
+        .expect("sessions response was not an array")
+        .iter()
+        .find(|session| session["id"] == first_session.as_str())
+        .expect("overridden session missing from /v1/runtime/sessions");
+    assert_eq!(listed["effective"]["env"]["MCPWAY_TEST_SESSION"], "1");
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_ws_dedicated_child_replays_the_session_initialize() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let admin_port = find_free_port();
+    let admin_port_str = admin_port.to_string();
+    let input_log = std::env::temp_dir().join(format!("mcpway-ws-replay-{port}.log"));
+    let _ = std::fs::remove_file(&input_log);
+
+    // Every child appends what it reads to the log, then answers `initialize` like a server.
+    let command = format!(
+        r#"sh -c "tee -a {} | sed -u 's/\"method\":\"initialize\"/\"result\":{{}}/'""#,
+        input_log.display()
+    );
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            &command,
+            "--output-transport",
+            "ws",
+            "--port",
+            &port_str,
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--runtime-admin-port",
+            &admin_port_str,
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{admin_port}/v1/runtime/health"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let url = format!("ws://127.0.0.1:{port}/message");
+    let (mut socket, response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect websocket");
+    let session = response
+        .headers()
+        .get("Mcp-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .expect("missing session id on shared connection")
+        .to_string();
+    socket
+        .send(Message::Text(initialize_request("init").to_string().into()))
+        .await
+        .expect("failed to send initialize");
+    let answer = next_text(&mut socket, Duration::from_secs(5))
+        .await
+        .expect("shared child did not answer initialize");
+    assert_eq!(answer["id"], "init");
+
+    let applied: serde_json::Value = reqwest::Client::new()
+        .post(format!(
+            "http://127.0.0.1:{admin_port}/v1/runtime/session/{session}"
+        ))
+        .json(&serde_json::json!({"env": {"MCPWAY_TEST_SESSION": "1"}}))
+        .send()
+        .await
+        .expect("failed to post session override")
+        .json()
+        .await
+        .expect("session override response was not JSON");
+    assert_eq!(applied["status"], "ok", "unexpected response: {applied}");
+
+    // The dedicated child gets the client's `initialize` params a second time.
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    let replayed = loop {
+        let initializes: Vec<serde_json::Value> = std::fs::read_to_string(&input_log)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .filter(|message: &serde_json::Value| message["method"] == "initialize")
+            .collect();
+        if initializes.len() == 2 || tokio::time::Instant::now() >= deadline {
+            break initializes;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    };
+    assert_eq!(
+        replayed.len(),
+        2,
+        "initialize was not replayed: {replayed:?}"
+    );
+    assert_eq!(
+        replayed[1]["params"]["clientInfo"]["name"],
+        "integration-test"
+    );
+
+    stop_child(&mut child).await;
+    let _ = std::fs::remove_file(&input_log);
+}
+
+#[tokio::test]
+async fn stdio_to_ws_blue_green_update_swaps_child_under_open_connections() {
+    assert_blue_green_swap("shared").await;
+}
+
+#[tokio::test]
+async fn stdio_to_ws_blue_green_update_swaps_per_connection_children() {
+    assert_blue_green_swap("per-connection").await;
+}
+
+async fn assert_blue_green_swap(isolation: &str) {
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let admin_port = find_free_port();
+    let admin_port_str = admin_port.to_string();
+
+    // Echoes everything like `cat`, but answers `initialize` so the replacement child can
+    // finish its handshake.
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            r#"sed -u 's/"method":"initialize"/"result":{}/'"#,
+            "--output-transport",
+            "ws",
+            "--port",
+            &port_str,
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--ws-isolation",
+            isolation,
+            "--restart-strategy",
+            "blue-green",
+            "--drain-timeout-ms",
+            "300",
+            "--runtime-admin-port",
+            &admin_port_str,
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{admin_port}/v1/runtime/health"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let url = format!("ws://127.0.0.1:{port}/message");
+    let (mut socket, _response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect websocket");
+
+    // An echoed request never gets a response, so it is still in flight at the swap.
+    let pending = serde_json::json!({"jsonrpc": "2.0", "id": "slow", "method": "tools/call"});
+    socket
+        .send(Message::Text(pending.to_string().into()))
+        .await
+        .expect("failed to send request");
+    let echoed = next_text(&mut socket, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for echoed request");
+    assert_eq!(echoed["id"], "slow");
+
+    let client = reqwest::Client::new();
+    let applied: serde_json::Value = client
+        .post(format!("http://127.0.0.1:{admin_port}/v1/runtime/defaults"))
+        .json(&serde_json::json!({"env": {"MCPWAY_TEST_SWAP": "1"}}))
+        .send()
+        .await
+        .expect("failed to post runtime defaults")
+        .json()
+        .await
+        .expect("runtime update response was not JSON");
+    assert_eq!(applied["status"], "ok", "unexpected response: {applied}");
+    assert_eq!(applied["restart"], true);
+    let swap = &applied["swaps"][0];
+    assert_ne!(swap["old_pid"], swap["new_pid"], "unexpected swap: {swap}");
+    assert_eq!(swap["in_flight"], 1, "unexpected swap: {swap}");
+    assert_eq!(swap["abandoned"], 1, "unexpected swap: {swap}");
+
+    // The new child echoes the gateway's `notifications/initialized` too; skip past it.
+    let mut abandoned = next_text(&mut socket, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for the abandoned request's error");
+    if abandoned["method"] == "notifications/initialized" {
+        abandoned = next_text(&mut socket, Duration::from_secs(5))
+            .await
+            .expect("timed out waiting for the abandoned request's error");
+    }
+    assert_eq!(abandoned["id"], "slow", "unexpected message: {abandoned}");
+    assert_eq!(abandoned["error"]["code"], -32603);
+
+    // The connection stays up and is served by the new child without re-initializing.
+    let notification = serde_json::json!({"jsonrpc": "2.0", "method": "notifications/after"});
+    socket
+        .send(Message::Text(notification.to_string().into()))
+        .await
+        .expect("failed to send notification after swap");
+    let echoed = next_text(&mut socket, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for echo from the new child");
+    assert_eq!(echoed["method"], "notifications/after");
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_ws_managed_initialize_answers_clients_from_the_cached_result() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let admin_port = find_free_port();
+    let admin_port_str = admin_port.to_string();
+
+    // Echoes everything like `cat`, but turns an `initialize` request into its answer. A
+    // forwarded client `initialize` therefore comes back with its `params`; the cached answer
+    // has none.
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            r#"sed -u 's/"method":"initialize"/"result":{"serverInfo":{"name":"sed"}}/'"#,
+            "--output-transport",
+            "ws",
+            "--port",
+            &port_str,
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--managed-initialize",
+            "--runtime-admin-port",
+            &admin_port_str,
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{admin_port}/v1/runtime/health"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let url = format!("ws://127.0.0.1:{port}/message");
+    let (mut first, _response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect first websocket");
+    let (mut second, _response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect second websocket");
+
+    for (socket, id) in [(&mut first, "first-init"), (&mut second, "second-init")] {
+        socket
+            .send(Message::Text(initialize_request(id).to_string().into()))
+            .await
+            .expect("failed to send initialize");
+        let payload = next_text(socket, Duration::from_secs(5))
+            .await
+            .expect("timed out waiting for initialize response");
+        assert_eq!(payload["id"], id);
+        assert_eq!(payload["result"]["serverInfo"]["name"], "sed");
+        assert!(
+            payload.get("params").is_none(),
+            "initialize reached the child: {payload}"
+        );
+    }
+
+    // The client's `notifications/initialized` is swallowed, so the next echo is the ping.
+    for method in ["notifications/initialized", "notifications/ping"] {
+        let notification = serde_json::json!({"jsonrpc": "2.0", "method": method});
+        first
+            .send(Message::Text(notification.to_string().into()))
+            .await
+            .expect("failed to send notification");
+    }
+    let echoed = next_text(&mut first, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for echoed ping");
+    assert_eq!(echoed["method"], "notifications/ping");
+    let _ = next_text(&mut second, Duration::from_secs(5)).await;
+
+    // A restarted child is initialized again before clients reach it.
+    let restarted: serde_json::Value = reqwest::Client::new()
+        .post(format!(
+            "http://127.0.0.1:{admin_port}/v1/runtime/child/restart"
+        ))
+        .send()
+        .await
+        .expect("failed to restart child")
+        .json()
+        .await
+        .expect("restart response was not JSON");
+    assert_eq!(
+        restarted["status"], "ok",
+        "unexpected response: {restarted}"
+    );
+
+    // The new child echoes the gateway's own `notifications/initialized`; skip past it.
+    second
+        .send(Message::Text(
+            initialize_request("after-restart").to_string().into(),
+        ))
+        .await
+        .expect("failed to send initialize after restart");
+    let mut payload = next_text(&mut second, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for initialize response after restart");
+    if payload["method"] == "notifications/initialized" {
+        payload = next_text(&mut second, Duration::from_secs(5))
+            .await
+            .expect("timed out waiting for initialize response after restart");
+    }
+    assert_eq!(payload["id"], "after-restart");
+    assert!(
+        payload.get("params").is_none(),
+        "initialize reached the restarted child: {payload}"
+    );
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_ws_fans_out_resource_subscriptions_on_the_shared_child() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+
+    // Echoes everything like `cat`, but answers `resources/subscribe`. That answer keeps the
+    // request's `params`, so it is told apart from the gateway's own answer to a client.
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            r#"sed -u 's/"method":"resources\/subscribe"/"result":{}/'"#,
+            "--output-transport",
+            "ws",
+            "--port",
+            &port_str,
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let url = format!("ws://127.0.0.1:{port}/message");
+    let (mut first, _response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect first websocket");
+    let (mut second, _response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect second websocket");
+    let (mut third, _response) = connect_async(url.as_str())
+        .await
+        .expect("failed to connect third websocket");
+
+    let uri = "file:///notes.txt";
+    for (socket, id) in [(&mut first, "sub-1"), (&mut second, "sub-2")] {
+        let subscribe = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "resources/subscribe",
+            "params": {"uri": uri}
+        });
+        socket
+            .send(Message::Text(subscribe.to_string().into()))
+            .await
+            .expect("failed to send subscribe");
+        let payload = next_text(socket, Duration::from_secs(5))
+            .await
+            .expect("timed out waiting for subscribe response");
+        assert_eq!(payload["id"], id);
+        assert_eq!(payload["result"], serde_json::json!({}));
+        assert!(
+            payload.get("params").is_none(),
+            "the child's answer reached a client: {payload}"
+        );
+    }
+
+    // The child's update reaches the two subscribers but not the third connection, which
+    // sees only the ping that follows it.
+    for notification in [
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/resources/updated",
+            "params": {"uri": uri}
+        }),
+        serde_json::json!({"jsonrpc": "2.0", "method": "notifications/ping"}),
+    ] {
+        third
+            .send(Message::Text(notification.to_string().into()))
+            .await
+            .expect("failed to send notification");
+    }
+    for socket in [&mut first, &mut second] {
+        let update = next_text(socket, Duration::from_secs(5))
+            .await
+            .expect("timed out waiting for resource update");
+        assert_eq!(update["method"], "notifications/resources/updated");
+        assert_eq!(update["params"]["uri"], uri);
+        let ping = next_text(socket, Duration::from_secs(5))
+            .await
+            .expect("timed out waiting for ping");
+        assert_eq!(ping["method"], "notifications/ping");
+    }
+    let ping = next_text(&mut third, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for ping");
+    assert_eq!(ping["method"], "notifications/ping");
+
+    // The child keeps its subscription while the second connection still holds it.
+    let unsubscribe = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": "unsub-1",
+        "method": "resources/unsubscribe",
+        "params": {"uri": uri}
+    });
+    first
+        .send(Message::Text(unsubscribe.to_string().into()))
+        .await
+        .expect("failed to send unsubscribe");
+    let payload = next_text(&mut first, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for unsubscribe response");
+    assert_eq!(payload["id"], "unsub-1");
+    assert!(
+        payload.get("method").is_none(),
+        "unsubscribe reached the child: {payload}"
+    );
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_ws_reports_session_queue_depths() {
+    assert_session_queue_depths("shared").await;
+}
+
+#[tokio::test]
+async fn stdio_to_ws_reports_per_connection_queue_depths() {
+    assert_session_queue_depths("per-connection").await;
+}
+
+async fn assert_session_queue_depths(isolation: &str) {
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let admin_port = find_free_port();
+    let admin_port_str = admin_port.to_string();
+
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            "cat",
+            "--output-transport",
+            "ws",
+            "--port",
+            &port_str,
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--ws-isolation",
+            isolation,
+            "--backpressure",
+            "disconnect",
+            "--runtime-admin-port",
+            &admin_port_str,
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{admin_port}/v1/runtime/health"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let (mut socket, response) = connect_async(format!("ws://127.0.0.1:{port}/message"))
+        .await
+        .expect("failed to connect websocket");
+    let session = response
+        .headers()
+        .get("Mcp-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .expect("missing session id")
+        .to_string();
+    socket
+        .send(Message::Text(
+            initialize_request("ws-queue").to_string().into(),
+        ))
+        .await
+        .expect("failed to send initialize");
+    let payload = next_text(&mut socket, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for response");
+    assert_eq!(payload["id"], "ws-queue");
+
+    let queues: serde_json::Value =
+        reqwest::get(format!("http://127.0.0.1:{admin_port}/v1/runtime/queues"))
+            .await
+            .expect("failed to query queues")
+            .json()
+            .await
+            .expect("queues response was not JSON");
+    assert_eq!(queues["policy"], "disconnect");
+    let entry = queues["sessions"]
+        .as_array()
+        .expect("sessions was not an array")
+        .iter()
+        .find(|entry| entry["id"] == session.as_str())
+        .expect("websocket session missing from queue report");
+    assert_eq!(entry["transport"], "ws");
+    assert_eq!(entry["depth"], 0);
+    assert_eq!(entry["dropped"], 0);
+
+    stop_child(&mut child).await;
+}
+
+#[tokio::test]
+async fn stdio_to_ws_admin_inspects_restarts_and_evicts_sessions() {
+    let port = find_free_port();
+    let port_str = port.to_string();
+    let admin_port = find_free_port();
+    let admin_port_str = admin_port.to_string();
+
+    // Answers `initialize`, which the session's dedicated child replays, and echoes the rest.
+    let mut child = spawn_mcpway(
+        &[
+            "--stdio",
+            r#"sed -u 's/"method":"initialize"/"result":{}/'"#,
+            "--output-transport",
+            "ws",
+            "--port",
+            &port_str,
+            "--message-path",
+            "/message",
+            "--health-endpoint",
+            "/healthz",
+            "--runtime-admin-port",
+            &admin_port_str,
+            "--log-level",
+            "none",
+        ],
+        false,
+        false,
+    )
+    .await;
+
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{port}/healthz"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+    wait_for_http_status(
+        &format!("http://127.0.0.1:{admin_port}/v1/runtime/health"),
+        reqwest::StatusCode::OK,
+        Duration::from_secs(10),
+    )
+    .await;
+
+    let (mut socket, response) = connect_async(format!("ws://127.0.0.1:{port}/message"))
+        .await
+        .expect("failed to connect websocket");
+    let session = response
+        .headers()
+        .get("Mcp-Session-Id")
+        .and_then(|value| value.to_str().ok())
+        .expect("missing session id")
+        .to_string();
+    socket
+        .send(Message::Text(
+            initialize_request("ws-admin").to_string().into(),
+        ))
+        .await
+        .expect("failed to send initialize");
+    next_text(&mut socket, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for initialize answer");
+    let ping = serde_json::json!({"jsonrpc": "2.0", "id": "ws-admin-ping", "method": "ping"});
+    socket
+        .send(Message::Text(ping.to_string().into()))
+        .await
+        .expect("failed to send ping");
+    next_text(&mut socket, Duration::from_secs(5))
+        .await
+        .expect("timed out waiting for echoed ping");
+
+    let client = reqwest::Client::new();
+    let detail_url = format!("http://127.0.0.1:{admin_port}/v1/runtime/sessions/{session}");
+    let detail: serde_json::Value = client
+        .get(&detail_url)
+        .send()
+        .await
+        .expect("failed to get session detail")
+        .json()
+        .await
+        .expect("session detail was not JSON");
+    assert_eq!(detail["transport"], "ws");
+    assert_eq!(detail["protocol_version"], "2024-11-05");
+    assert_eq!(detail["client_info"]["name"], "integration-test");
+    // The ping is echoed instead of answered, so it stays in flight.
+    assert_eq!(detail["in_flight"], 1);
+    let shared_pid = detail["child_pid"].as_u64().expect("missing child pid");
+
+    let restarted: serde_json::Value = client
+        .post(format!(
+            "http://127.0.0.1:{admin_port}/v1/runtime/child/restart"
+        ))
+        .send()
+        .await
+        .expect("failed to restart child")
+        .json()
+        .await
+        .expect("restart response was not JSON");
+    assert_eq!(
+        restarted["status"], "ok",
+        "unexpected response: {restarted}"
+    );
+
+    let restarted: serde_json::Value = client
+        .post(format!("{detail_url}/restart"))
+        .send()
+        .await
+        .expect("failed to restart session")
+        .json()
+        .await
+        .expect("restart response was not JSON");
+    assert_eq!(
+        restarted["status"], "ok",
+        "unexpected response: {restarted}"
+    );
+    let detail: serde_json::Value = client
+        .get(&detail_url)
+        .send()
+        .await
+        .expect("failed to get session detail")
+        .json()
+        .await
+        .expect("session detail was not JSON");
+    assert_eq!(detail["in_flight"], 0);
+    let dedicated_pid = detail["child_pid"].as_u64().expect("missing child pid");
+    assert_ne!(dedicated_pid, shared_pid);
+
+    let evicted: serde_json::Value = client
+        .delete(&detail_url)
+        .send()
+        .await
+        .expect("failed to evict session")
+        .json()
+        .await
+        .expect("evict response was not JSON");
+    assert_eq!(evicted["status"], "ok", "unexpected response: {evicted}");
+    // The echo child repeats the `notifications/initialized` replayed to it, so skip text.
+    let closed = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            match socket.next().await {
+                Some(Ok(Message::Text(_))) => continue,
+                other => break other,
+            }
+        }
+    })
+    .await
+    .expect("socket was not closed after eviction");
+    match closed {
+        Some(Ok(Message::Close(Some(frame)))) => {
+            assert_eq!(frame.reason, "Session evicted by the runtime admin API")
+        }
+        other => panic!("expected a close frame, got {other:?}"),
+    }
+    let missing = client
+        .get(&detail_url)
+        .send()
+        .await
+        .expect("failed to get session detail");
+    assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+
+    stop_child(&mut child).await;
+}